@@ -0,0 +1,29 @@
+//! Adds par moves and time, worked out from the shortest solution, to every puzzle in a catalogue
+//! that has no par yet. Puzzles that already have par are left alone. The catalogue is rewritten
+//! in place:
+//!
+//!     cargo run --release --example embed_par -- src/puzzles/builtin.txt
+
+use std::{env, fs, process};
+
+use water_sort_solver::puzzles::embed_par;
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: embed_par <catalogue>");
+            process::exit(1);
+        }
+    };
+    let result = fs::read_to_string(&path)
+        .map_err(|err| format!("Unable to read {}: {}", path, err))
+        .and_then(|catalogue| embed_par(&catalogue))
+        .and_then(|catalogue| {
+            fs::write(&path, catalogue).map_err(|err| format!("Unable to write {}: {}", path, err))
+        });
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
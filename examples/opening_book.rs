@@ -0,0 +1,16 @@
+//! Prints the opening book for the built-in puzzles. The output is saved as
+//! `src/opening_book.txt`:
+//!
+//!     cargo run --release --example opening_book > src/opening_book.txt
+
+use water_sort_solver::{opening_book::build_opening_book, puzzles::builtin_puzzles};
+
+fn main() {
+    let games: Vec<_> = builtin_puzzles()
+        .iter()
+        .map(|puzzle| puzzle.to_game())
+        .collect();
+    println!("# Best first moves of the built-in puzzles, as 'fingerprint = difficulty: move'.");
+    println!("# Generated by `cargo run --release --example opening_book`.");
+    print!("{}", build_opening_book(&games));
+}
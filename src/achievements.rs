@@ -0,0 +1,259 @@
+use std::{fmt::Display, fs, str::FromStr};
+
+use crate::{
+    config::home_dir,
+    stats::{LifetimeStats, SolveRecord},
+};
+
+const ACHIEVEMENTS_FILE_NAME: &str = "achievements";
+/// Solves within this many moves of the shortest solution earn `Achievement::NearOptimal`.
+const NEAR_OPTIMAL_MARGIN: usize = 2;
+const BIG_BOARD_TUBES: usize = 16;
+const STREAK_DAYS: usize = 7;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Achievement {
+    NoHints,
+    NearOptimal,
+    BigBoard,
+    WeekStreak,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::NoHints,
+        Achievement::NearOptimal,
+        Achievement::BigBoard,
+        Achievement::WeekStreak,
+    ];
+
+    pub fn description(&self) -> String {
+        match self {
+            Achievement::NoHints => String::from("Solve a puzzle without using a hint"),
+            Achievement::NearOptimal => format!(
+                "Solve a puzzle within {} moves of the shortest solution",
+                NEAR_OPTIMAL_MARGIN
+            ),
+            Achievement::BigBoard => format!("Solve a board with {} tubes", BIG_BOARD_TUBES),
+            Achievement::WeekStreak => format!("Solve a puzzle {} days in a row", STREAK_DAYS),
+        }
+    }
+
+    /// Checks a solve against the achievement. `stats` should already include the solve.
+    fn is_earned_by(&self, solve: &SolveRecord, stats: &LifetimeStats) -> bool {
+        match self {
+            Achievement::NoHints => solve.hints_used == 0,
+            Achievement::NearOptimal => solve
+                .optimal_moves
+                .is_some_and(|optimal| solve.moves <= optimal + NEAR_OPTIMAL_MARGIN),
+            Achievement::BigBoard => solve.tubes >= BIG_BOARD_TUBES,
+            Achievement::WeekStreak => stats.current_streak >= STREAK_DAYS,
+        }
+    }
+}
+
+impl FromStr for Achievement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "no-hints" => Ok(Achievement::NoHints),
+            "near-optimal" => Ok(Achievement::NearOptimal),
+            "big-board" => Ok(Achievement::BigBoard),
+            "week-streak" => Ok(Achievement::WeekStreak),
+            other => Err(format!("Unknown achievement '{}'", other)),
+        }
+    }
+}
+
+impl Display for Achievement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Achievement::NoHints => "no-hints",
+            Achievement::NearOptimal => "near-optimal",
+            Achievement::BigBoard => "big-board",
+            Achievement::WeekStreak => "week-streak",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The achievements a user has unlocked, stored one per line next to the lifetime statistics.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct Achievements {
+    pub unlocked: Vec<Achievement>,
+}
+
+impl Achievements {
+    pub fn parse(contents: &str) -> Result<Achievements, String> {
+        let mut achievements = Achievements::default();
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.parse::<Achievement>() {
+                Ok(achievement) if !achievements.unlocked.contains(&achievement) => {
+                    achievements.unlocked.push(achievement)
+                }
+                Ok(_) => {}
+                Err(err) => return Err(format!("Line {}: {}", line_num + 1, err)),
+            }
+        }
+        Ok(achievements)
+    }
+
+    /// Loads the user's achievements. A missing file means nothing has been unlocked yet.
+    pub fn load() -> Result<Achievements, String> {
+        let path = match home_dir() {
+            Some(dir) => dir.join(ACHIEVEMENTS_FILE_NAME),
+            None => return Ok(Achievements::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Achievements::parse(&contents)
+                .map_err(|err| format!("Unable to read {}: {}", path.display(), err)),
+            Err(_) => Ok(Achievements::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let dir = match home_dir() {
+            Some(dir) => dir,
+            None => {
+                return Err("Unable to find a home directory to save achievements in".to_string())
+            }
+        };
+        let path = dir.join(ACHIEVEMENTS_FILE_NAME);
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, self.to_string()))
+            .map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+    }
+
+    /// Unlocks every achievement earned by the solve, returning the ones that are new.
+    pub fn unlock(&mut self, solve: &SolveRecord, stats: &LifetimeStats) -> Vec<Achievement> {
+        let new: Vec<Achievement> = Achievement::ALL
+            .into_iter()
+            .filter(|achievement| !self.unlocked.contains(achievement))
+            .filter(|achievement| achievement.is_earned_by(solve, stats))
+            .collect();
+        self.unlocked.extend(new.iter().copied());
+        new
+    }
+
+    /// A list of every achievement and whether it is unlocked, for the `stats` command.
+    pub fn summary(&self) -> String {
+        let mut out = format!(
+            "Achievements ({} of {}):\n",
+            self.unlocked.len(),
+            Achievement::ALL.len()
+        );
+        for achievement in Achievement::ALL {
+            let mark = match self.unlocked.contains(&achievement) {
+                true => 'x',
+                false => ' ',
+            };
+            out.push_str(format!("[{}] {}\n", mark, achievement.description()).as_str());
+        }
+        out
+    }
+}
+
+impl Display for Achievements {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for achievement in self.unlocked.iter() {
+            writeln!(f, "{}", achievement)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_unlock() {
+        // (moves, optimal moves, hints used, tubes, current streak), then the achievements that
+        // should be newly unlocked by the solve
+        let tests = [
+            (20, Some(15), 1, 8, 1, vec![]),
+            (17, Some(15), 1, 8, 2, vec![Achievement::NearOptimal]),
+            (
+                30,
+                None,
+                0,
+                16,
+                3,
+                vec![Achievement::NoHints, Achievement::BigBoard],
+            ),
+            (15, Some(15), 0, 16, 6, vec![]),
+            (25, Some(15), 2, 6, 7, vec![Achievement::WeekStreak]),
+        ];
+        let mut achievements = Achievements::default();
+        for (idx, test) in tests.into_iter().enumerate() {
+            let solve = SolveRecord {
+                moves: test.0,
+                optimal_moves: test.1,
+                hints_used: test.2,
+                tubes: test.3,
+                duration: Duration::from_secs(60),
+                day: 100,
+            };
+            let stats = LifetimeStats {
+                current_streak: test.4,
+                ..Default::default()
+            };
+            let result = achievements.unlock(&solve, &stats);
+            assert_eq!(
+                result, test.5,
+                "wrong achievements unlocked by solve {}. Expected: {:?}, got: {:?}",
+                idx, test.5, result
+            );
+        }
+        assert_eq!(
+            achievements.unlocked.len(),
+            Achievement::ALL.len(),
+            "not every achievement was unlocked"
+        );
+    }
+
+    #[test]
+    fn test_parse_achievements() {
+        let achievements = Achievements {
+            unlocked: vec![Achievement::WeekStreak, Achievement::NoHints],
+        };
+        assert_eq!(
+            Achievements::parse(&achievements.to_string()),
+            Ok(achievements.clone()),
+            "achievements did not round trip"
+        );
+        assert_eq!(
+            Achievements::parse("# unlocked\nno-hints\n\nno-hints\n"),
+            Ok(Achievements {
+                unlocked: vec![Achievement::NoHints]
+            }),
+            "duplicate achievements were not ignored"
+        );
+        assert!(
+            Achievements::parse("no-hints\nspeed-run\n").is_err(),
+            "expected an error parsing an unknown achievement"
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let achievements = Achievements {
+            unlocked: vec![Achievement::BigBoard],
+        };
+        assert_eq!(
+            achievements.summary(),
+            "Achievements (1 of 4):\n\
+             [ ] Solve a puzzle without using a hint\n\
+             [ ] Solve a puzzle within 2 moves of the shortest solution\n\
+             [x] Solve a board with 16 tubes\n\
+             [ ] Solve a puzzle 7 days in a row\n"
+        );
+    }
+}
@@ -0,0 +1,839 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    path::Path,
+};
+
+use crate::{
+    game::{Game, Move, MoveRequest},
+    json::Json,
+    known_puzzles::fingerprint,
+    render::board_json,
+    solver::Solver,
+};
+
+/// Reads a board from a JSON puzzle file:
+///
+/// ```json
+/// {
+///     "ruleset": "standard",
+///     "tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []],
+///     "forbidden_pours": [[1, 3]]
+/// }
+/// ```
+///
+/// Tube contents are listed top first, as in interactive setup, and a tube with fewer than four
+/// colours is empty at the top. Forbidden pours use tube numbers starting from 1. Only `tubes` is
+/// required.
+pub fn parse_board(contents: &str) -> Result<Game, String> {
+    let game = read_board(contents)?;
+    let problems = game.check_integrity();
+    if !problems.is_empty() {
+        return Err(format!(
+            "the board is not possible: {}",
+            problems.join(", ")
+        ));
+    }
+    if !game.validate_setup() {
+        return Err(
+            "the board must have two more tubes than colours, with 4 of every colour".to_string(),
+        );
+    }
+    Ok(game)
+}
+
+/// Reads a board from a JSON puzzle file like `parse_board`, but without checking that the board
+/// is possible or has the right number of every colour, so that a board with a mistake in it can
+/// still be looked at and repaired.
+pub fn read_board(contents: &str) -> Result<Game, String> {
+    let json = Json::parse(contents)?;
+    let tubes = match json.get("tubes").and_then(Json::as_array) {
+        Some(tubes) => tubes,
+        None => return Err("expected a \"tubes\" array".to_string()),
+    };
+    if tubes.len() < 4 {
+        return Err("a board must have at least 4 tubes".to_string());
+    }
+    let mut game = Game::default();
+    game.init_tubes(tubes.len());
+    for (idx, tube) in tubes.iter().enumerate() {
+        let colours = tube
+            .as_array()
+            .and_then(|colours| {
+                colours
+                    .iter()
+                    .map(Json::as_str)
+                    .collect::<Option<Vec<&str>>>()
+            })
+            .ok_or_else(|| format!("tube {} must be an array of colours", idx + 1))?;
+        game.init_tube_contents(idx, colours.join(", "));
+    }
+    if let Some(ruleset) = json.get("ruleset") {
+        game.ruleset = match ruleset.as_str() {
+            Some(ruleset) => ruleset.parse()?,
+            None => return Err("the ruleset must be a string".to_string()),
+        };
+    }
+    if let Some(pours) = json.get("forbidden_pours") {
+        let pours = pours
+            .as_array()
+            .ok_or_else(|| "the forbidden pours must be an array".to_string())?;
+        for pour in pours {
+            let tubes: Option<Vec<usize>> = pour
+                .as_array()
+                .and_then(|tubes| tubes.iter().map(Json::as_usize).collect());
+            match tubes.as_deref() {
+                Some([from, to])
+                    if (1..=game.tubes.len()).contains(from)
+                        && (1..=game.tubes.len()).contains(to) =>
+                {
+                    game.forbidden_pours.push((from - 1, to - 1))
+                }
+                _ => return Err("a forbidden pour must be a pair of tube numbers".to_string()),
+            }
+        }
+    }
+    Ok(game)
+}
+
+pub fn load_board(path: &Path) -> Result<Game, String> {
+    fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path.display(), err))
+        .and_then(|contents| {
+            parse_board(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+        })
+}
+
+/// Reads a game from a transcript: a JSON puzzle file of the starting board with the moves that
+/// were played, in order, as `[tube_from, tube_to, quantity]` with tube numbers starting from 1:
+///
+/// ```json
+/// {
+///     "tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []],
+///     "moves": [[1, 3, 3], [2, 3, 1]]
+/// }
+/// ```
+///
+/// A puzzle file without `moves` is a game that has not been started. Every move is checked
+/// against the board the moves before it leave. A move that pours more than the rules allow at
+/// once, as saved with `merge_pours`, is read as the pours between the same two tubes that make it
+/// up.
+pub fn parse_transcript(contents: &str) -> Result<(Game, Vec<Move>), String> {
+    let game = parse_board(contents)?;
+    let json = Json::parse(contents)?;
+    let moves = match json.get("moves") {
+        Some(moves) => moves
+            .as_array()
+            .ok_or_else(|| "the moves must be an array".to_string())?,
+        None => return Ok((game, Vec::new())),
+    };
+    let mut requests = Vec::with_capacity(moves.len());
+    for (idx, a_move) in moves.iter().enumerate() {
+        let numbers: Option<Vec<usize>> = a_move
+            .as_array()
+            .and_then(|numbers| numbers.iter().map(Json::as_usize).collect());
+        match numbers.as_deref() {
+            Some([from, to, quantity]) if *from > 0 && *to > 0 && *quantity > 0 => {
+                requests.push(MoveRequest {
+                    tube_from: from - 1,
+                    tube_to: to - 1,
+                    quantity: *quantity,
+                    colour: None,
+                })
+            }
+            _ => {
+                return Err(format!(
+                    "move {} must be a tube from, a tube to and a quantity",
+                    idx + 1
+                ))
+            }
+        }
+    }
+    let mut board = game.clone();
+    let mut moves = Vec::with_capacity(requests.len());
+    for (idx, request) in requests.into_iter().enumerate() {
+        let mut left = request.quantity;
+        while left > 0 {
+            let a_move = match board.resolve(MoveRequest {
+                quantity: left,
+                ..request.clone()
+            }) {
+                Ok(a_move) if a_move.quantity > 0 => a_move,
+                // Only some of the quantity could be poured, which would not be the game played
+                _ if left < request.quantity => {
+                    return Err(format!(
+                        "move {} can not pour {}",
+                        idx + 1,
+                        request.quantity
+                    ))
+                }
+                Ok(_) => return Err(format!("move {} can not pour anything", idx + 1)),
+                Err(err) => return Err(format!("move {} can not be made: {}", idx + 1, err)),
+            };
+            left -= a_move.quantity;
+            board.make_move(&a_move);
+            moves.push(a_move);
+        }
+    }
+    Ok((game, moves))
+}
+
+pub fn load_transcript(path: &Path) -> Result<(Game, Vec<Move>), String> {
+    fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path.display(), err))
+        .and_then(|contents| {
+            parse_transcript(&contents).map_err(|err| format!("{}: {}", path.display(), err))
+        })
+}
+
+/// Writes a game as a transcript that `parse_transcript` reads back, with the notes on its tubes,
+/// by tube, as a `notes` member that `parse_notes` reads back.
+pub fn transcript_string(start: &Game, moves: &[Move], notes: &BTreeMap<usize, String>) -> String {
+    let mut json = board_json(start);
+    if let Json::Object(members) = &mut json {
+        let moves = moves
+            .iter()
+            .map(|a_move| {
+                Json::Array(vec![
+                    Json::Number((a_move.tube_from + 1) as f64),
+                    Json::Number((a_move.tube_to + 1) as f64),
+                    Json::Number(a_move.quantity as f64),
+                ])
+            })
+            .collect();
+        members.push(("moves".to_string(), Json::Array(moves)));
+        if !notes.is_empty() {
+            let notes = notes
+                .iter()
+                .map(|(tube, note)| {
+                    Json::Array(vec![
+                        Json::Number((tube + 1) as f64),
+                        Json::String(note.clone()),
+                    ])
+                })
+                .collect();
+            members.push(("notes".to_string(), Json::Array(notes)));
+        }
+    }
+    format!("{}\n", json)
+}
+
+/// Reads the notes on the tubes of a saved game, by tube, from its `notes` member of
+/// `[<tube>, "<note>"]` pairs. A file without notes has none.
+pub fn parse_notes(contents: &str, num_of_tubes: usize) -> Result<BTreeMap<usize, String>, String> {
+    let json = Json::parse(contents)?;
+    let notes = match json.get("notes") {
+        Some(notes) => notes
+            .as_array()
+            .ok_or_else(|| "the notes must be an array".to_string())?,
+        None => return Ok(BTreeMap::new()),
+    };
+    let mut parsed = BTreeMap::new();
+    for (idx, note) in notes.iter().enumerate() {
+        let pair = note.as_array().and_then(|pair| match pair {
+            [tube, text] => Some((tube.as_usize()?, text.as_str()?)),
+            _ => None,
+        });
+        match pair {
+            Some((tube, text)) if tube > 0 && tube <= num_of_tubes => {
+                parsed.insert(tube - 1, text.to_string());
+            }
+            _ => {
+                return Err(format!(
+                    "note {} must be a tube from 1 to {} and its text",
+                    idx + 1,
+                    num_of_tubes
+                ))
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// The members a puzzle file or saved game can have. Any other member is ignored.
+const FILE_MEMBERS: [&str; 5] = ["ruleset", "tubes", "forbidden_pours", "moves", "notes"];
+
+/// Checks a puzzle file, or a game saved by `save-game`, as it would be loaded, without solving it:
+/// a description of the board and of any moves played, with a warning for every member that would
+/// be ignored, such as a misspelt one. A file that cannot be loaded is an error.
+pub fn check_report(contents: &str) -> Result<String, String> {
+    let (start, moves) = parse_transcript(contents)?;
+    let mut out = format!(
+        "ok: {} tubes and {} colours, with the {} rules\n",
+        start.tubes.len(),
+        start.colours.len(),
+        start.ruleset
+    );
+    if let Some(pours) = start.forbidden_pours_string() {
+        out.push_str(format!("{}\n", pours).as_str());
+    }
+    out.push_str(
+        format!(
+            "At least {} moves are needed to finish\n",
+            Solver::new(&start).lower_bound()
+        )
+        .as_str(),
+    );
+    if !moves.is_empty() {
+        let mut game = start.clone();
+        for a_move in moves.iter() {
+            game.make_move(a_move);
+        }
+        let result = match game.is_game_complete() {
+            true => String::from("which complete the game"),
+            false => format!(
+                "after which at least {} more are needed",
+                Solver::new(&game).lower_bound()
+            ),
+        };
+        out.push_str(format!("{} moves played, {}\n", moves.len(), result).as_str());
+    }
+    for (tube, note) in parse_notes(contents, start.tubes.len())? {
+        out.push_str(format!("Note on tube {}: {}\n", tube + 1, note).as_str());
+    }
+    if let Ok(Json::Object(members)) = Json::parse(contents) {
+        for (member, _) in members
+            .iter()
+            .filter(|(member, _)| !FILE_MEMBERS.contains(&member.as_str()))
+        {
+            out.push_str(format!("Warning: the member \"{}\" is not used\n", member).as_str());
+        }
+    }
+    Ok(out)
+}
+
+/// The difficulty metrics of one board, as compared by `analyze --compare`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BoardMetrics {
+    pub name: String,
+    pub tubes: usize,
+    pub colours: usize,
+    /// How many moves can be made from the starting board.
+    pub opening_moves: usize,
+    pub lower_bound: usize,
+    /// The length of the shortest solution, if one was found within the search limit.
+    pub optimal_moves: Option<usize>,
+    pub states_searched: Option<usize>,
+    pub difficulty: Option<usize>,
+}
+
+impl BoardMetrics {
+    pub fn measure(name: &str, game: &Game) -> BoardMetrics {
+        let solver = Solver::new(game);
+        let solution = solver.search();
+        BoardMetrics {
+            name: name.to_string(),
+            tubes: game.tubes.len(),
+            colours: game.colours.len(),
+            opening_moves: solver.get_legal_moves().len(),
+            lower_bound: solver.lower_bound(),
+            optimal_moves: solution.as_ref().map(|solution| solution.moves.len()),
+            states_searched: solution.as_ref().map(|solution| solution.states_searched),
+            difficulty: solution.as_ref().map(|solution| solution.difficulty()),
+        }
+    }
+}
+
+/// First moves that lead to the same board once the order of the tubes is ignored, such as
+/// pouring into one empty tube or another. Only one of them is a real choice.
+#[derive(Clone)]
+pub struct OpeningClass {
+    pub moves: Vec<Move>,
+    /// The length of the shortest solution starting with these moves, if one was found.
+    pub solution_moves: Option<usize>,
+}
+
+/// Groups the opening moves of a board by the board they lead to, in the order the first move of
+/// each group is found. Fewer groups than moves means the board has symmetry, and a single group
+/// means the first move is forced.
+pub fn opening_classes(game: &Game) -> Vec<OpeningClass> {
+    let mut keys: Vec<String> = Vec::new();
+    let mut classes: Vec<OpeningClass> = Vec::new();
+    for a_move in Solver::new(game).get_legal_moves() {
+        let mut next = game.clone();
+        next.make_move(&a_move);
+        let (key, _) = fingerprint(&next);
+        match keys.iter().position(|known| *known == key) {
+            Some(idx) => classes[idx].moves.push(a_move),
+            None => {
+                let solution_moves = match Solver::new(&next).search() {
+                    Some(solution) if solution.optimal => Some(solution.moves.len() + 1),
+                    _ => None,
+                };
+                keys.push(key);
+                classes.push(OpeningClass {
+                    moves: vec![a_move],
+                    solution_moves,
+                });
+            }
+        }
+    }
+    classes
+}
+
+/// One line per distinct opening, listing the moves that are the same as it, after a count of the
+/// moves and of the distinct ones.
+pub fn openings_string(classes: &[OpeningClass]) -> String {
+    let moves: usize = classes.iter().map(|class| class.moves.len()).sum();
+    let mut out = format!(
+        "{} opening moves, {} of them distinct\n",
+        moves,
+        classes.len()
+    );
+    for (idx, class) in classes.iter().enumerate() {
+        let solution = match class.solution_moves {
+            Some(moves) => format!("solvable in {} moves", moves),
+            None => String::from("no shortest solution found"),
+        };
+        let moves: Vec<String> = class
+            .moves
+            .iter()
+            .map(|a_move| format!("({})", a_move))
+            .collect();
+        out.push_str(format!("{}: {}, {}\n", idx + 1, moves.join(" = "), solution).as_str());
+    }
+    out
+}
+
+/// How long a game can go on without using an empty tube, for players keeping their empty tubes
+/// for when they are needed. See `empty_tube_advice`.
+#[derive(Clone)]
+pub struct EmptyTubeAdvice {
+    /// The longest line of moves, up to the number asked about, that pours only onto the same
+    /// colour and leaves a board that can still be solved.
+    pub merges: Vec<Move>,
+    /// How many moves were asked about.
+    pub moves: usize,
+    /// Whether the merges complete the game.
+    pub completes: bool,
+    /// How many tubes are empty now.
+    pub empty_tubes: usize,
+}
+
+impl EmptyTubeAdvice {
+    /// Whether an empty tube has to be used within the moves asked about.
+    pub fn needs_empty_tube(&self) -> bool {
+        !self.completes && self.merges.len() < self.moves
+    }
+}
+
+impl Display for EmptyTubeAdvice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line = self
+            .merges
+            .iter()
+            .map(|a_move| a_move.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        let none_free = match self.empty_tubes {
+            0 => ", and none is free",
+            _ => "",
+        };
+        match (self.needs_empty_tube(), self.merges.len()) {
+            (false, _) if self.completes => write!(
+                f,
+                "The game can be finished pouring only onto the same colour: {}",
+                line
+            ),
+            (false, 1) => write!(f, "The next move needs no empty tube: {}", line),
+            (false, moves) => write!(f, "The next {} moves need no empty tube: {}", moves, line),
+            (true, 0) => write!(f, "The next move needs an empty tube{}", none_free),
+            (true, 1) => write!(
+                f,
+                "Only 1 move can be made pouring onto the same colour before an empty tube is needed{}: {}",
+                none_free, line
+            ),
+            (true, moves) => write!(
+                f,
+                "Only {} moves can be made pouring onto the same colour before an empty tube is needed{}: {}",
+                moves, none_free, line
+            ),
+        }
+    }
+}
+
+/// Whether the next `moves` moves of a game can all pour onto the same colour, or an empty tube
+/// has to be used before then. Pouring onto the same colour can leave a board that cannot be
+/// solved, so only lines after which the solver, with the game's preset, still finds a solution
+/// count. The longest such line is given, the first found among lines as long.
+pub fn empty_tube_advice(game: &Game, moves: usize) -> EmptyTubeAdvice {
+    // Every line of merges, in the order found, and the fewest moves each board was reached in
+    let mut lines: Vec<(Game, Vec<Move>)> = Vec::new();
+    let mut fewest: HashMap<Vec<Vec<Option<String>>>, usize> =
+        HashMap::from([(game.state_key(), 0)]);
+    let mut stack = vec![(game.clone(), Vec::new())];
+    while let Some((state, line)) = stack.pop() {
+        if line.len() < moves && !state.is_game_complete() {
+            let merges = Solver::new(&state)
+                .get_possible_moves()
+                .into_iter()
+                .filter(|a_move| state.tubes[a_move.tube_to].get_top_colour().is_some());
+            for a_move in merges.rev() {
+                let mut next = state.clone();
+                next.make_move(&a_move);
+                let key = next.state_key();
+                if fewest
+                    .get(&key)
+                    .is_some_and(|fewest| *fewest <= line.len() + 1)
+                {
+                    continue;
+                }
+                fewest.insert(key, line.len() + 1);
+                let mut next_line = line.clone();
+                next_line.push(a_move);
+                stack.push((next, next_line));
+            }
+        }
+        lines.push((state, line));
+    }
+    // The sort is stable, so the first line found of each length stays first
+    lines.sort_by_key(|(_, line)| std::cmp::Reverse(line.len()));
+    let (state, merges) = lines
+        .into_iter()
+        .find(|(state, line)| {
+            line.is_empty() || state.is_game_complete() || Solver::new(state).search().is_some()
+        })
+        .unwrap_or_else(|| (game.clone(), Vec::new()));
+    EmptyTubeAdvice {
+        merges,
+        moves,
+        completes: state.is_game_complete(),
+        empty_tubes: game
+            .tubes
+            .iter()
+            .filter(|tube| tube.get_top_colour().is_none())
+            .count(),
+    }
+}
+
+/// Lays the metrics of several boards out side by side, one row per board in the order given.
+/// Values that could not be found, because the board was not solved, are shown as `-`.
+pub fn comparison_table(boards: &[BoardMetrics]) -> String {
+    let optional = |value: Option<usize>| match value {
+        Some(value) => value.to_string(),
+        None => String::from("-"),
+    };
+    let mut rows = vec![[
+        "Puzzle",
+        "Tubes",
+        "Colours",
+        "Openings",
+        "Lower bound",
+        "Moves",
+        "States",
+        "Difficulty",
+    ]
+    .map(String::from)
+    .to_vec()];
+    for board in boards {
+        rows.push(vec![
+            board.name.clone(),
+            board.tubes.to_string(),
+            board.colours.to_string(),
+            board.opening_moves.to_string(),
+            board.lower_bound.to_string(),
+            optional(board.optimal_moves),
+            optional(board.states_searched),
+            match board.difficulty {
+                Some(difficulty) => format!("{}/5", difficulty),
+                None => String::from("-"),
+            },
+        ]);
+    }
+    format_table(&rows, 1)
+}
+
+/// Lines up the columns of a table, with the first row as the heading. The first `text_columns`
+/// are left aligned and the rest, which hold numbers, are right aligned.
+pub(crate) fn format_table(rows: &[Vec<String>], text_columns: usize) -> String {
+    let widths: Vec<usize> = (0..rows[0].len())
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+    let mut out = String::new();
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths.iter())
+            .enumerate()
+            .map(|(column, (cell, width))| match column < text_columns {
+                true => format!("{:<width$}", cell, width = width),
+                false => format!("{:>width$}", cell, width = width),
+            })
+            .collect();
+        out.push_str(cells.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_board() {
+        let tests = [
+            (
+                r#"{"tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]}"#,
+                true,
+            ),
+            (
+                r#"{"ruleset": "one-unit", "forbidden_pours": [[1, 4]], "tubes": [["blue"], ["red", "red", "red", "blue"], ["red", "blue", "blue"], []]}"#,
+                true,
+            ),
+            (r#"{"tubes": [["red", "red", "red", "red"], []]}"#, false),
+            (
+                r#"{"tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue"], [], []]}"#,
+                false,
+            ),
+            (
+                r#"{"ruleset": "two-unit", "tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]}"#,
+                false,
+            ),
+            (
+                r#"{"forbidden_pours": [[1, 5]], "tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]}"#,
+                false,
+            ),
+            (r#"{"tubes": [["red", 3], [], [], []]}"#, false),
+            (
+                r#"{"tubes": [["red", "red", "red", "blue", "blue"], ["red", "blue", "blue"], [], []]}"#,
+                false,
+            ),
+            (
+                r#"{"tubes": [["red", "empty", "red", "blue"], ["red", "blue", "blue", "blue"], ["red"], []]}"#,
+                false,
+            ),
+            (r#"["red"]"#, false),
+        ];
+        for test in tests {
+            assert_eq!(
+                parse_board(test.0).is_ok(),
+                test.1,
+                "wrong result parsing {:?}",
+                test.0
+            );
+        }
+        let game = parse_board(tests[1].0).unwrap();
+        assert_eq!(game.forbidden_pours, vec![(0, 3)], "wrong forbidden pours");
+        assert_eq!(
+            game.tubes[0].contents,
+            vec![None, None, None, Some("blue".to_string())],
+            "a short tube should be empty at the top"
+        );
+    }
+
+    #[test]
+    fn test_check_report() {
+        let board =
+            r#""tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]"#;
+        // (file, text expected in the report, or None for an error)
+        let tests = [
+            (
+                format!("{{{}}}", board),
+                Some("ok: 4 tubes and 2 colours, with the standard rules\nAt least 3 moves are needed to finish\n"),
+            ),
+            (
+                format!("{{{}, \"forbidden_pours\": [[1, 3]]}}", board),
+                Some("Forbidden pours: 1 -> 3\n"),
+            ),
+            (
+                format!("{{{}, \"moves\": [[1, 3, 3]]}}", board),
+                Some("1 moves played, after which at least 2 more are needed\n"),
+            ),
+            (
+                format!("{{{}, \"moves\": [[1, 3, 3], [2, 3, 1], [1, 2, 1]]}}", board),
+                Some("3 moves played, which complete the game\n"),
+            ),
+            (
+                format!("{{{}, \"forbiden_pours\": [[1, 3]]}}", board),
+                Some("Warning: the member \"forbiden_pours\" is not used\n"),
+            ),
+            (
+                format!("{{{}, \"notes\": [[3, \"keep for red\"]]}}", board),
+                Some("Note on tube 3: keep for red\n"),
+            ),
+            (format!("{{{}, \"moves\": [[1, 2, 1]]}}", board), None),
+            (format!("{{{}, \"notes\": [[5, \"no such tube\"]]}}", board), None),
+            (String::from(r#"{"tubes": [["red"], [], [], []]}"#), None),
+            (String::from("{"), None),
+        ];
+        for (file, expected) in tests {
+            match (check_report(&file), expected) {
+                (Ok(report), Some(expected)) => assert!(
+                    report.contains(expected),
+                    "expected {:?} in the report of {}, got:\n{}",
+                    expected,
+                    file,
+                    report
+                ),
+                (Err(_), None) => {}
+                (Ok(report), None) => {
+                    panic!("expected an error checking {}, got:\n{}", file, report)
+                }
+                (Err(err), Some(_)) => panic!("unexpected error checking {}: {}", file, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_transcript() {
+        let board =
+            r#""tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]"#;
+        let tests = [
+            (format!("{{{}}}", board), Some(vec![])),
+            (
+                format!("{{{}, \"moves\": [[1, 3, 3], [2, 3, 1]]}}", board),
+                Some(vec![(0, 2, 3), (1, 2, 1)]),
+            ),
+            (format!("{{{}, \"moves\": [[1, 3, 4]]}}", board), None),
+            (format!("{{{}, \"moves\": [[1, 2, 1]]}}", board), None),
+            (format!("{{{}, \"moves\": [[0, 3, 1]]}}", board), None),
+            (format!("{{{}, \"moves\": [[1, 3]]}}", board), None),
+            (format!("{{{}, \"moves\": {{}}}}", board), None),
+        ];
+        for test in tests {
+            let result = parse_transcript(&test.0).ok().map(|(_, moves)| {
+                moves
+                    .iter()
+                    .map(|a_move| (a_move.tube_from, a_move.tube_to, a_move.quantity))
+                    .collect::<Vec<_>>()
+            });
+            assert_eq!(result, test.1, "wrong moves parsed from {}", test.0);
+        }
+
+        let one_unit = format!(
+            "{{\"ruleset\": \"one-unit\", {}, \"moves\": [[1, 3, 3], [2, 3, 1]]}}",
+            board
+        );
+        let (_, moves) = parse_transcript(&one_unit).expect("merged pours should parse");
+        assert_eq!(
+            moves.iter().map(Move::to_string).collect::<Vec<_>>(),
+            [
+                "1 -> 3: red x 1",
+                "1 -> 3: red x 1",
+                "1 -> 3: red x 1",
+                "2 -> 3: red x 1"
+            ],
+            "the merged pours should be poured one unit at a time"
+        );
+
+        let (game, moves) =
+            parse_transcript(&format!("{{{}, \"moves\": [[1, 3, 3], [2, 3, 1]]}}", board))
+                .expect("transcript should parse");
+        let notes = BTreeMap::from([(0, String::from("red first")), (3, String::from("spare"))]);
+        let written = transcript_string(&game, &moves, &notes);
+        let (read_game, read_moves) =
+            parse_transcript(&written).expect("written transcript should parse");
+        assert_eq!(
+            read_game.to_string(),
+            game.to_string(),
+            "the board did not round trip"
+        );
+        assert_eq!(
+            read_moves.iter().map(Move::to_string).collect::<Vec<_>>(),
+            moves.iter().map(Move::to_string).collect::<Vec<_>>(),
+            "the moves did not round trip"
+        );
+        assert_eq!(
+            parse_notes(&written, game.tubes.len()),
+            Ok(notes),
+            "the notes did not round trip"
+        );
+    }
+
+    #[test]
+    fn test_opening_classes() {
+        let game = parse_board(
+            r#"{"tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]}"#,
+        )
+        .unwrap();
+        let classes = opening_classes(&game);
+        // Either top can be poured into either empty tube, and which empty tube does not matter
+        let sizes: Vec<usize> = classes.iter().map(|class| class.moves.len()).collect();
+        assert_eq!(sizes, vec![2, 2], "wrong opening classes");
+        assert_eq!(
+            classes[0].solution_moves,
+            Some(3),
+            "wrong solution length after the first opening"
+        );
+        assert_eq!(
+            openings_string(&classes),
+            format!(
+                "4 opening moves, 2 of them distinct\n\
+                 1: (1 -> 3: red x 3) = (1 -> 4: red x 3), solvable in 3 moves\n\
+                 2: (2 -> 3: red x 1) = (2 -> 4: red x 1), solvable in {} moves\n",
+                classes[1].solution_moves.unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_empty_tube_advice() {
+        // (tubes, the moves asked about, whether an empty tube is needed, the advice)
+        let tests = [
+            (
+                r#"[["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]"#,
+                3,
+                true,
+                "The next move needs an empty tube",
+            ),
+            (
+                r#"[["red"], ["red"], ["red", "red"], ["blue", "blue", "blue", "blue"]]"#,
+                1,
+                false,
+                "The next move needs no empty tube: 1 -> 2: red x 1",
+            ),
+            (
+                r#"[["red"], ["red"], ["red", "red"], ["blue", "blue", "blue", "blue"]]"#,
+                3,
+                false,
+                "The game can be finished pouring only onto the same colour: 1 -> 2: red x 1, 2 -> 3: red x 2",
+            ),
+        ];
+        for (tubes, moves, needed, expected) in tests {
+            let game = parse_board(&format!(r#"{{"tubes": {}}}"#, tubes)).unwrap();
+            let advice = empty_tube_advice(&game, moves);
+            assert_eq!(
+                advice.needs_empty_tube(),
+                needed,
+                "wrong advice for {} within {} moves",
+                tubes,
+                moves
+            );
+            assert_eq!(advice.to_string(), expected, "wrong advice for {}", tubes);
+        }
+    }
+
+    #[test]
+    fn test_comparison_table() {
+        let game = parse_board(
+            r#"{"tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]}"#,
+        )
+        .unwrap();
+        let measured = BoardMetrics::measure("first.json", &game);
+        assert_eq!(measured.optimal_moves, Some(3), "wrong shortest solution");
+        assert_eq!(measured.colours, 2, "wrong number of colours");
+        let unsolved = BoardMetrics {
+            name: String::from("big.json"),
+            tubes: 14,
+            colours: 12,
+            opening_moves: 20,
+            lower_bound: 30,
+            optimal_moves: None,
+            states_searched: None,
+            difficulty: None,
+        };
+        let table = comparison_table(&[measured.clone(), unsolved]);
+        let expected = format!(
+            "Puzzle      Tubes  Colours  Openings  Lower bound  Moves  States  Difficulty\n\
+             first.json      4        2  {:>8}  {:>11}      3  {:>6}  {:>10}\n\
+             big.json       14       12        20           30      -       -           -\n",
+            measured.opening_moves,
+            measured.lower_bound,
+            measured.states_searched.unwrap(),
+            format!("{}/5", measured.difficulty.unwrap())
+        );
+        assert_eq!(table, expected, "wrong comparison table");
+    }
+}
@@ -0,0 +1,1400 @@
+use std::time::Duration;
+
+use crate::{
+    config::MIN_WIDTH,
+    game::Ruleset,
+    generate::PackOptions,
+    rng::os_seed,
+    solver::{Preset, Strategy},
+};
+
+pub const USAGE: &str = "Usage:
+    water_sort_solver [play] [--builtin <number> | --pack <catalogue>] [--rules <ruleset>] [--hints <number>] [--assistant] [--strict] [--blitz <seconds>] [--preset <preset>] [--width <columns>] [--force-colour]
+    water_sort_solver stats --lifetime
+    water_sort_solver analyze --compare <file.json>... [--preset <preset>]
+    water_sort_solver analyze --openings <file.json> [--preset <preset>]
+    water_sort_solver analyze <game.json>
+    water_sort_solver solve <file.json> [--trace] [--preset <preset>] [--strategy <strategy>] [--beam-width <width>] [--optimal]
+    water_sort_solver solve <file.json> --solutions <number> [--preset <preset>]
+    water_sort_solver solve <file.json> --least-volume [--preset <preset>]
+    water_sort_solver solve <file.json> --worksheet <page.html> [--preset <preset>]
+    water_sort_solver solve <file.json> --interactive [--preset <preset>] [--strategy <strategy>]
+    water_sort_solver validate-file <catalogue> [--preset <preset>] [--cache <file>]
+    water_sort_solver compare-strategies [<catalogue>] [--strategies <list>] [--preset <preset>]
+    water_sort_solver import <list.txt> [--first <number>]
+    water_sort_solver generate-pack [--levels <number>] [--colours <number>] [--seed <number>|random] [--threads <number>] [--preset <preset>]
+    water_sort_solver check <file.json>
+    water_sort_solver repair <file.json> [--edits <number>] [--preset <preset>]
+
+Commands:
+    play                 Play a game (the default). Without options the tubes are entered interactively.
+    stats                Show statistics. --lifetime shows the totals across every game played,
+                         and the achievements unlocked.
+    analyze              Analyse puzzle files. --compare shows the difficulty metrics of several
+                         JSON puzzle files side by side, in the order given. --openings shows
+                         which first moves of a puzzle file lead to the same board. Given a
+                         game saved with save-game, steps through its moves with hints and
+                         what-if lines, without changing the saved game.
+    solve                Solve a JSON puzzle file and print the moves. --trace shows how far the
+                         search had got at every depth: the states searched, the fewest moves
+                         that could be left from the most promising board, and the moves pruned
+                         because they led to a board already seen. --strategy searches with
+                         breadth-first, depth-first, a-star, greedy, ida-star, beam or
+                         bidirectional whatever the size of the board. a-star finds the
+                         shortest solution of a large board far faster than breadth-first, and
+                         ida-star does so in less memory. greedy heads straight for a solution,
+                         which is not always the shortest. beam keeps only the most promising
+                         boards of each depth, 500 unless --beam-width gives another number,
+                         for boards too large for any other strategy; its solution is not
+                         always the shortest. bidirectional also finds the shortest solution,
+                         searching breadth first from the board and back from the completed
+                         board until the two meet.
+                         --optimal searches with ida-star and no budget at all, however long
+                         it takes, so the solution is always the shortest. --solutions lists
+                         up to that many shortest solutions, leaving out any that only
+                         interchange two empty tubes or make moves between different tubes in
+                         another order. --least-volume finds the solution that pours the
+                         fewest units of liquid, rather than the fewest moves. --worksheet
+                         writes the board and the moves of its solution, each with a small
+                         diagram of the board after it, to a page of HTML to print.
+                         --interactive steps through the search one state at a time, showing
+                         the states waiting and the lower bound and score the strategy gives
+                         each, with the commands expand, best, frontier, why <state>, run and
+                         quit. It cannot step through ida-star or bidirectional.
+    validate-file        Check every puzzle in a catalogue, in the format of the built-in puzzles,
+                         before publishing it: its tubes and colours, that it can be solved, and
+                         that no two puzzles are the same board. --cache keeps what the solver
+                         learns in a file, so later runs over similar puzzles are faster.
+    compare-strategies   Solve every puzzle of a catalogue, or the built-in puzzles, with each
+                         search strategy and compare the solution lengths, states searched and
+                         times. --strategies takes a comma separated list of breadth-first,
+                         depth-first, a-star, greedy, ida-star, beam and bidirectional, and all
+                         of them are run by default.
+    import               Convert a list of boards in compact code form, one to a line, into a
+                         catalogue, printed in the format of the built-in puzzles. --first
+                         gives the number of the first puzzle, 1 by default.
+    generate-pack        Make a pack of random levels that can all be solved, 20 of 7 colours
+                         unless --levels and --colours give other numbers, and print it as a
+                         catalogue with par, easiest first. The same --seed, 1 by default,
+                         always gives the same pack, and --seed random picks one at random.
+                         The seed is recorded at the top of the catalogue. The levels are made
+                         on one thread for every processor, or as many as --threads gives.
+    check                Check a JSON puzzle file, or a game saved with save-game, without
+                         playing or solving it, and describe the board and the moves played.
+    repair               Suggest fixes for a JSON puzzle file that is not a possible board or
+                         cannot be solved: the fewest segments to change, 2 at most unless
+                         --edits gives another number, for the board to be solved.
+
+Options:
+    --builtin <number>   Start with one of the puzzles shipped with the program.
+    --pack <catalogue>   Play through the levels of a catalogue in order, carrying on from where
+                         the last game of it stopped. The best score of every level is kept,
+                         and the progress command shows how much of the pack is complete.
+    --rules <ruleset>    The pouring rules to play with: standard (the default), one-unit or no-split.
+    --hints <number>     Limit how many times the hint command can be used for the puzzle.
+    --assistant          Ask for confirmation before making a move that looks risky or losing.
+    --strict             Reject a setup with a colour that is not a known name or hex code,
+                         instead of asking whether it is a new colour.
+    --blitz <seconds>    Play against the clock. The puzzle is lost if it is not completed in time.
+    --preset <preset>    How hard the solver works: quick answers straight away but not always
+                         with the shortest solution, balanced (the default) suits most puzzles,
+                         and thorough takes longer and more memory to find the shortest solution
+                         of larger boards.
+    --width <columns>    How wide to draw the board when the tubes are side by side, with
+                         render = columns. The width of the terminal is used by default.
+    --force-colour       Draw the board with render = colour or render = emoji even when NO_COLOR
+                         is set, TERM is dumb or the output is not a terminal, which otherwise
+                         draw it with symbols.";
+
+pub enum Command {
+    Play(PlayOptions),
+    Stats,
+    Compare(Vec<String>, Preset),
+    Openings(String, Preset),
+    /// A saved game to step through.
+    Spectate(String),
+    /// A puzzle file to solve, whether to trace the search, and whether to search without a
+    /// budget for the shortest solution.
+    Solve(String, Preset, Option<Strategy>, bool, bool),
+    /// A puzzle file to step through the search of, and the strategy to search with.
+    Debug(String, Preset, Option<Strategy>),
+    /// A puzzle file to solve pouring the least liquid.
+    LeastVolume(String, Preset),
+    /// A puzzle file to solve, and the HTML file to write the worksheet of its solution to.
+    Worksheet(String, Preset, String),
+    /// A puzzle file, and how many different shortest solutions to list.
+    Solutions(String, Preset, usize),
+    ValidateFile(String, Preset, Option<String>),
+    CompareStrategies(Option<String>, Vec<Strategy>, Preset),
+    /// A puzzle file or saved game to check without solving it.
+    Check(String),
+    /// A puzzle file to repair, and the most segments a repair may change.
+    Repair(String, Preset, usize),
+    /// A list of board codes, and the number of the first puzzle imported from it.
+    Import(String, usize),
+    GeneratePack(PackOptions),
+}
+
+#[derive(Default)]
+pub struct PlayOptions {
+    pub builtin: Option<usize>,
+    /// A catalogue to play through a level at a time.
+    pub pack: Option<String>,
+    pub ruleset: Ruleset,
+    pub hint_limit: Option<usize>,
+    pub assistant: bool,
+    pub strict_input: bool,
+    pub time_limit: Option<Duration>,
+    pub preset: Preset,
+    pub width: Option<usize>,
+    /// Draw the board in colour or emoji, as the settings ask, even when the terminal does not
+    /// seem to support it.
+    pub force_colour: bool,
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Command, String> {
+        let mut args = args.iter().peekable();
+        if let Some(&command) = args.peek() {
+            match command.as_str() {
+                "play" => {
+                    args.next();
+                }
+                "stats" => {
+                    args.next();
+                    return Command::parse_stats(args);
+                }
+                "analyze" => {
+                    args.next();
+                    return Command::parse_analyze(args);
+                }
+                "solve" => {
+                    args.next();
+                    return Command::parse_solve(args);
+                }
+                "validate-file" => {
+                    args.next();
+                    return Command::parse_validate_file(args);
+                }
+                "compare-strategies" => {
+                    args.next();
+                    return Command::parse_compare_strategies(args);
+                }
+                "import" => {
+                    args.next();
+                    return Command::parse_import(args);
+                }
+                "repair" => {
+                    args.next();
+                    return Command::parse_repair(args);
+                }
+                "generate-pack" => {
+                    args.next();
+                    return Command::parse_generate_pack(args);
+                }
+                "check" => {
+                    args.next();
+                    return match (args.next(), args.next()) {
+                        (Some(path), None) if !path.starts_with("--") => {
+                            Ok(Command::Check(path.clone()))
+                        }
+                        (Some(_), Some(arg)) => Err(format!("Unexpected argument '{}'", arg)),
+                        _ => Err("Expected a puzzle file after check".to_string()),
+                    };
+                }
+                arg if arg.starts_with("--") => {}
+                _ => return Err(format!("Unknown command '{}'", command)),
+            }
+        }
+
+        let mut options = PlayOptions::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--builtin" => {
+                    let number = match args.next() {
+                        Some(value) => value,
+                        None => return Err("Expected a puzzle number after --builtin".to_string()),
+                    };
+                    options.builtin = match number.parse::<usize>() {
+                        Ok(number) => Some(number),
+                        Err(_) => {
+                            return Err(format!("Unable to parse {} to a puzzle number", number))
+                        }
+                    };
+                }
+                "--pack" => match args.next() {
+                    Some(path) => options.pack = Some(path.to_string()),
+                    None => return Err("Expected a catalogue after --pack".to_string()),
+                },
+                "--rules" => {
+                    options.ruleset = match args.next() {
+                        Some(value) => value.parse::<Ruleset>()?,
+                        None => return Err("Expected a ruleset after --rules".to_string()),
+                    };
+                }
+                "--hints" => {
+                    let limit = match args.next() {
+                        Some(value) => value,
+                        None => return Err("Expected a number after --hints".to_string()),
+                    };
+                    options.hint_limit = match limit.parse::<usize>() {
+                        Ok(limit) => Some(limit),
+                        Err(_) => return Err(format!("Unable to parse {} to a number", limit)),
+                    };
+                }
+                "--assistant" => options.assistant = true,
+                "--strict" => options.strict_input = true,
+                "--force-colour" | "--force-color" => options.force_colour = true,
+                "--blitz" => {
+                    let seconds = match args.next() {
+                        Some(value) => value,
+                        None => {
+                            return Err("Expected a number of seconds after --blitz".to_string())
+                        }
+                    };
+                    options.time_limit = match seconds.parse::<u64>() {
+                        Ok(seconds) if seconds > 0 => Some(Duration::from_secs(seconds)),
+                        _ => {
+                            return Err(format!(
+                                "Unable to parse {} to a number of seconds",
+                                seconds
+                            ))
+                        }
+                    };
+                }
+                "--preset" => options.preset = parse_preset(args.next())?,
+                "--width" => {
+                    let width = match args.next() {
+                        Some(value) => value,
+                        None => {
+                            return Err("Expected a number of columns after --width".to_string())
+                        }
+                    };
+                    options.width = match width.parse::<usize>() {
+                        Ok(width) if width >= MIN_WIDTH => Some(width),
+                        _ => {
+                            return Err(format!(
+                                "Unable to parse {} to a width of at least {} columns",
+                                width, MIN_WIDTH
+                            ))
+                        }
+                    };
+                }
+                _ => return Err(format!("Unknown option '{}'", arg)),
+            }
+        }
+
+        if options.builtin.is_some() && options.pack.is_some() {
+            return Err("--builtin and --pack cannot be used together".to_string());
+        }
+        Ok(Command::Play(options))
+    }
+
+    fn parse_solve<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+        let mut path = None;
+        let mut preset = Preset::default();
+        let mut strategy = None;
+        let mut trace = false;
+        let mut optimal = false;
+        let mut solutions = None;
+        let mut interactive = false;
+        let mut least_volume = false;
+        let mut beam_width = None;
+        let mut worksheet = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--preset" => preset = parse_preset(args.next())?,
+                "--strategy" => match args.next() {
+                    Some(name) => strategy = Some(name.parse::<Strategy>()?),
+                    None => return Err("Expected a strategy after --strategy".to_string()),
+                },
+                "--beam-width" => {
+                    beam_width = match args.next().map(|width| width.parse::<usize>()) {
+                        Some(Ok(width)) if width > 0 => Some(width),
+                        _ => {
+                            return Err("Expected a number of boards after --beam-width".to_string())
+                        }
+                    }
+                }
+                "--trace" => trace = true,
+                "--optimal" => optimal = true,
+                "--interactive" => interactive = true,
+                "--least-volume" => least_volume = true,
+                "--worksheet" => match args.next() {
+                    Some(page) => worksheet = Some(page.clone()),
+                    None => return Err("Expected a file name after --worksheet".to_string()),
+                },
+                "--solutions" => {
+                    solutions = match args.next().map(|number| number.parse::<usize>()) {
+                        Some(Ok(number)) if number > 0 => Some(number),
+                        _ => {
+                            return Err(
+                                "Expected a number of solutions after --solutions".to_string()
+                            )
+                        }
+                    }
+                }
+                arg if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+                _ if path.is_some() => return Err(format!("Unexpected argument '{}'", arg)),
+                _ => path = Some(arg.clone()),
+            }
+        }
+        if let Some(width) = beam_width {
+            match strategy {
+                Some(Strategy::Beam(_)) => strategy = Some(Strategy::Beam(width)),
+                _ => return Err("--beam-width can only be given with --strategy beam".to_string()),
+            }
+        }
+        if optimal && strategy.is_some() {
+            return Err(
+                "--optimal always searches with ida-star, so --strategy cannot be given with it"
+                    .to_string(),
+            );
+        }
+        if solutions.is_some() && (optimal || trace || strategy.is_some()) {
+            return Err(
+                "--solutions cannot be given with --strategy, --optimal or --trace".to_string(),
+            );
+        }
+        if interactive && (optimal || trace || solutions.is_some()) {
+            return Err(
+                "--interactive cannot be given with --optimal, --trace or --solutions".to_string(),
+            );
+        }
+        if interactive && strategy == Some(Strategy::IdaStar) {
+            return Err(
+                "ida-star searches in passes, which --interactive cannot step through".to_string(),
+            );
+        }
+        if interactive && strategy == Some(Strategy::Bidirectional) {
+            return Err(
+                "bidirectional searches from both ends a depth at a time, which --interactive cannot step through"
+                    .to_string(),
+            );
+        }
+        if least_volume && (optimal || strategy.is_some() || solutions.is_some() || interactive) {
+            return Err(
+                "--least-volume has its own search, so it cannot be given with --strategy, --optimal, --solutions or --interactive"
+                    .to_string(),
+            );
+        }
+        if worksheet.is_some()
+            && (optimal
+                || trace
+                || strategy.is_some()
+                || solutions.is_some()
+                || interactive
+                || least_volume)
+        {
+            return Err(
+                "--worksheet cannot be given with --strategy, --optimal, --trace, --solutions, --least-volume or --interactive"
+                    .to_string(),
+            );
+        }
+        if let (Some(path), Some(page)) = (&path, worksheet) {
+            return Ok(Command::Worksheet(path.clone(), preset, page));
+        }
+        match (path, solutions) {
+            (Some(path), _) if least_volume => Ok(Command::LeastVolume(path, preset)),
+            (Some(path), _) if interactive => Ok(Command::Debug(path, preset, strategy)),
+            (Some(path), Some(count)) => Ok(Command::Solutions(path, preset, count)),
+            (Some(path), None) => Ok(Command::Solve(path, preset, strategy, trace, optimal)),
+            (None, _) => Err("Expected a puzzle file after solve".to_string()),
+        }
+    }
+
+    fn parse_validate_file<'a>(
+        mut args: impl Iterator<Item = &'a String>,
+    ) -> Result<Command, String> {
+        let mut path = None;
+        let mut preset = Preset::default();
+        let mut cache = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--preset" => preset = parse_preset(args.next())?,
+                "--cache" => match args.next() {
+                    Some(file) => cache = Some(file.clone()),
+                    None => return Err("Expected a file after --cache".to_string()),
+                },
+                arg if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+                _ if path.is_some() => return Err(format!("Unexpected argument '{}'", arg)),
+                _ => path = Some(arg.clone()),
+            }
+        }
+        match path {
+            Some(path) => Ok(Command::ValidateFile(path, preset, cache)),
+            None => Err("Expected a catalogue after validate-file".to_string()),
+        }
+    }
+
+    fn parse_import<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+        let mut path = None;
+        let mut first = 1;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--first" => {
+                    first = match args.next().map(|number| number.parse::<usize>()) {
+                        Some(Ok(number)) if number > 0 => number,
+                        _ => return Err("Expected a puzzle number after --first".to_string()),
+                    }
+                }
+                arg if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+                _ if path.is_some() => return Err(format!("Unexpected argument '{}'", arg)),
+                _ => path = Some(arg.clone()),
+            }
+        }
+        match path {
+            Some(path) => Ok(Command::Import(path, first)),
+            None => Err("Expected a list of boards after import".to_string()),
+        }
+    }
+
+    fn parse_repair<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+        let mut path = None;
+        let mut preset = Preset::default();
+        let mut edits = 2;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--preset" => preset = parse_preset(args.next())?,
+                "--edits" => {
+                    edits = match args.next().map(|number| number.parse::<usize>()) {
+                        Some(Ok(number)) if number > 0 => number,
+                        _ => return Err("Expected a number of segments after --edits".to_string()),
+                    }
+                }
+                arg if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+                _ if path.is_some() => return Err(format!("Unexpected argument '{}'", arg)),
+                _ => path = Some(arg.clone()),
+            }
+        }
+        match path {
+            Some(path) => Ok(Command::Repair(path, preset, edits)),
+            None => Err("Expected a puzzle file after repair".to_string()),
+        }
+    }
+
+    fn parse_generate_pack<'a>(
+        mut args: impl Iterator<Item = &'a String>,
+    ) -> Result<Command, String> {
+        let mut options = PackOptions::default();
+        while let Some(arg) = args.next() {
+            let mut number = |expected: &str| match args.next().map(|number| number.parse::<u64>())
+            {
+                Some(Ok(number)) => Ok(number),
+                _ => Err(format!("Expected {} after {}", expected, arg)),
+            };
+            match arg.as_str() {
+                "--preset" => options.preset = parse_preset(args.next())?,
+                "--levels" => match number("a number of levels")? {
+                    0 => return Err("A pack needs at least one level".to_string()),
+                    levels => options.levels = levels as usize,
+                },
+                "--colours" => options.colours = number("a number of colours")? as usize,
+                "--seed" => {
+                    options.seed = match args.next().map(String::as_str) {
+                        Some("random") => os_seed(),
+                        seed => seed
+                            .and_then(|seed| seed.parse::<u64>().ok())
+                            .ok_or_else(|| format!("Expected a seed or random after {}", arg))?,
+                    }
+                }
+                "--threads" => match number("a number of threads")? {
+                    0 => return Err("At least one thread is needed".to_string()),
+                    threads => options.threads = Some(threads as usize),
+                },
+                arg => return Err(format!("Unexpected argument '{}'", arg)),
+            }
+        }
+        Ok(Command::GeneratePack(options))
+    }
+
+    fn parse_compare_strategies<'a>(
+        mut args: impl Iterator<Item = &'a String>,
+    ) -> Result<Command, String> {
+        let mut path = None;
+        let mut strategies = Strategy::ALL.to_vec();
+        let mut preset = Preset::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--preset" => preset = parse_preset(args.next())?,
+                "--strategies" => {
+                    strategies = match args.next() {
+                        Some(list) => {
+                            list.split(',')
+                                .map(str::parse)
+                                .collect::<Result<Vec<Strategy>, String>>()?
+                        }
+                        None => {
+                            return Err(
+                                "Expected a list of strategies after --strategies".to_string()
+                            )
+                        }
+                    }
+                }
+                arg if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+                _ if path.is_some() => return Err(format!("Unexpected argument '{}'", arg)),
+                _ => path = Some(arg.clone()),
+            }
+        }
+        Ok(Command::CompareStrategies(path, strategies, preset))
+    }
+
+    /// Only lifetime statistics are kept, so `--lifetime` is accepted but not required.
+    fn parse_stats<'a>(args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+        for arg in args {
+            match arg.as_str() {
+                "--lifetime" => {}
+                _ => return Err(format!("Unknown option '{}'", arg)),
+            }
+        }
+        Ok(Command::Stats)
+    }
+
+    /// An analysis must be chosen: `--compare` for several files, or `--openings` for one. A file
+    /// on its own is a saved game to step through.
+    fn parse_analyze<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+        let analysis = match args.next().map(String::as_str) {
+            Some(analysis @ ("--compare" | "--openings")) => analysis,
+            Some(arg) if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+            Some(file) => {
+                return match args.next() {
+                    Some(arg) => Err(format!("Unexpected argument '{}'", arg)),
+                    None => Ok(Command::Spectate(file.to_string())),
+                }
+            }
+            None => {
+                return Err(
+                    "Expected --compare, --openings or a saved game after analyze".to_string(),
+                )
+            }
+        };
+        let mut files = Vec::new();
+        let mut preset = Preset::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--preset" => preset = parse_preset(args.next())?,
+                _ => files.push(arg.clone()),
+            }
+        }
+        if analysis == "--openings" {
+            return match &files[..] {
+                [file] => Ok(Command::Openings(file.clone(), preset)),
+                _ => Err("Expected one puzzle file after --openings".to_string()),
+            };
+        }
+        if files.is_empty() {
+            return Err("Expected at least one puzzle file after --compare".to_string());
+        }
+        Ok(Command::Compare(files, preset))
+    }
+}
+
+fn parse_preset(value: Option<&String>) -> Result<Preset, String> {
+    match value {
+        Some(value) => value.parse::<Preset>(),
+        None => Err("Expected a preset after --preset".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::DEFAULT_BEAM_WIDTH;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_ruleset() {
+        let tests: Vec<(Vec<&str>, Option<Ruleset>)> = vec![
+            (vec![], Some(Ruleset::Standard)),
+            (vec!["play", "--rules", "standard"], Some(Ruleset::Standard)),
+            (vec!["play", "--rules", "one-unit"], Some(Ruleset::OneUnit)),
+            (
+                vec!["--builtin", "2", "--rules", "One-Unit"],
+                Some(Ruleset::OneUnit),
+            ),
+            (vec!["play", "--rules"], None),
+            (vec!["play", "--rules", "two-unit"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Play(options)), Some(expected)) => assert_eq!(
+                    options.ruleset, expected,
+                    "wrong ruleset parsed from {:?}. Expected: {:?}, got: {:?}",
+                    test.0, expected, options.ruleset
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a play command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_hint_limit() {
+        let tests: Vec<(Vec<&str>, Option<Option<usize>>)> = vec![
+            (vec![], Some(None)),
+            (vec!["play", "--hints", "3"], Some(Some(3))),
+            (vec!["--builtin", "1", "--hints", "0"], Some(Some(0))),
+            (vec!["play", "--hints"], None),
+            (vec!["play", "--hints", "-1"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Play(options)), Some(expected)) => assert_eq!(
+                    options.hint_limit, expected,
+                    "wrong hint limit parsed from {:?}. Expected: {:?}, got: {:?}",
+                    test.0, expected, options.hint_limit
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a play command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_assistant() {
+        let tests: Vec<(Vec<&str>, bool)> = vec![
+            (vec![], false),
+            (vec!["play", "--assistant"], true),
+            (vec!["--builtin", "1", "--assistant", "--hints", "2"], true),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match Command::parse(&args) {
+                Ok(Command::Play(options)) => assert_eq!(
+                    options.assistant, test.1,
+                    "wrong assistant flag parsed from {:?}",
+                    test.0
+                ),
+                Ok(_) => panic!("expected a play command parsing {:?}", test.0),
+                Err(err) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_force_colour() {
+        let tests: Vec<(Vec<&str>, bool)> = vec![
+            (vec!["play"], false),
+            (vec!["--force-colour"], true),
+            (vec!["play", "--builtin", "2", "--force-color"], true),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match Command::parse(&args) {
+                Ok(Command::Play(options)) => assert_eq!(
+                    options.force_colour, test.1,
+                    "wrong force colour flag parsed from {:?}",
+                    test.0
+                ),
+                Ok(_) => panic!("expected a play command parsing {:?}", test.0),
+                Err(err) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_pack() {
+        let tests: Vec<(Vec<&str>, Option<Option<&str>>)> = vec![
+            (vec!["play"], Some(None)),
+            (vec!["--pack", "pack.txt"], Some(Some("pack.txt"))),
+            (
+                vec!["play", "--pack", "pack.txt", "--hints", "1"],
+                Some(Some("pack.txt")),
+            ),
+            (vec!["play", "--pack"], None),
+            (vec!["play", "--pack", "pack.txt", "--builtin", "2"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Play(options)), Some(expected)) => assert_eq!(
+                    options.pack.as_deref(),
+                    expected,
+                    "wrong pack parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a play command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        let tests: Vec<(Vec<&str>, bool)> = vec![
+            (vec!["play"], false),
+            (vec!["--strict"], true),
+            (vec!["play", "--rules", "no-split", "--strict"], true),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match Command::parse(&args) {
+                Ok(Command::Play(options)) => assert_eq!(
+                    options.strict_input, test.1,
+                    "wrong strict flag parsed from {:?}",
+                    test.0
+                ),
+                Ok(_) => panic!("expected a play command parsing {:?}", test.0),
+                Err(err) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_blitz() {
+        let tests: Vec<(Vec<&str>, Option<Option<u64>>)> = vec![
+            (vec![], Some(None)),
+            (vec!["play", "--blitz", "90"], Some(Some(90))),
+            (vec!["--builtin", "3", "--blitz", "30"], Some(Some(30))),
+            (vec!["play", "--blitz"], None),
+            (vec!["play", "--blitz", "0"], None),
+            (vec!["play", "--blitz", "1m"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Play(options)), Some(expected)) => assert_eq!(
+                    options.time_limit,
+                    expected.map(Duration::from_secs),
+                    "wrong time limit parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a play command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_width() {
+        let tests: Vec<(Vec<&str>, Option<Option<usize>>)> = vec![
+            (vec![], Some(None)),
+            (vec!["play", "--width", "60"], Some(Some(60))),
+            (vec!["--builtin", "3", "--width", "200"], Some(Some(200))),
+            (vec!["play", "--width"], None),
+            (vec!["play", "--width", "10"], None),
+            (vec!["play", "--width", "wide"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Play(options)), Some(expected)) => assert_eq!(
+                    options.width, expected,
+                    "wrong width parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a play command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let tests: Vec<(Vec<&str>, bool)> = vec![
+            (vec!["stats"], true),
+            (vec!["stats", "--lifetime"], true),
+            (vec!["stats", "--weekly"], false),
+            (vec!["play", "stats"], false),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            let result = matches!(Command::parse(&args), Ok(Command::Stats));
+            assert_eq!(
+                result, test.1,
+                "wrong stats command parsed from {:?}. Expected: {}, got: {}",
+                test.0, test.1, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_analyze() {
+        let tests: Vec<(Vec<&str>, Option<Vec<&str>>)> = vec![
+            (
+                vec!["analyze", "--compare", "a.json", "b.json"],
+                Some(vec!["a.json", "b.json"]),
+            ),
+            (vec!["analyze", "--compare", "a.json"], Some(vec!["a.json"])),
+            (vec!["analyze", "--compare"], None),
+            (vec!["analyze"], None),
+            (vec!["analyze", "--summary", "a.json"], None),
+            (
+                vec!["analyze", "--openings", "a.json"],
+                Some(vec!["a.json"]),
+            ),
+            (vec!["analyze", "--openings", "a.json", "b.json"], None),
+            (vec!["analyze", "--openings"], None),
+            (vec!["analyze", "game.json"], Some(vec!["game.json"])),
+            (vec!["analyze", "game.json", "other.json"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Compare(files, _)), Some(expected)) => {
+                    assert_eq!(files, expected, "wrong files parsed from {:?}", test.0)
+                }
+                (Ok(Command::Openings(file, _)), Some(expected))
+                | (Ok(Command::Spectate(file)), Some(expected)) => {
+                    assert_eq!(vec![file], expected, "wrong file parsed from {:?}", test.0)
+                }
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected an analyze command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    /// The catalogue and cache file expected from a validate-file command.
+    type ValidateFileArgs<'a> = (&'a str, Option<&'a str>);
+
+    #[test]
+    fn test_parse_validate_file() {
+        let tests: Vec<(Vec<&str>, Option<ValidateFileArgs>)> = vec![
+            (vec!["validate-file", "pack.txt"], Some(("pack.txt", None))),
+            (
+                vec!["validate-file", "--cache", "boards.txt", "pack.txt"],
+                Some(("pack.txt", Some("boards.txt"))),
+            ),
+            (vec!["validate-file"], None),
+            (vec!["validate-file", "a.txt", "b.txt"], None),
+            (vec!["validate-file", "pack.txt", "--cache"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::ValidateFile(path, _, cache)), Some(expected)) => {
+                    assert_eq!(path, expected.0, "wrong catalogue parsed from {:?}", test.0);
+                    assert_eq!(
+                        cache.as_deref(),
+                        expected.1,
+                        "wrong cache parsed from {:?}",
+                        test.0
+                    );
+                }
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a validate command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    /// The list of boards and first puzzle number expected from an import command.
+    type ImportArgs<'a> = (&'a str, usize);
+
+    #[test]
+    fn test_parse_import() {
+        let tests: Vec<(Vec<&str>, Option<ImportArgs>)> = vec![
+            (vec!["import", "levels.txt"], Some(("levels.txt", 1))),
+            (
+                vec!["import", "--first", "40", "levels.txt"],
+                Some(("levels.txt", 40)),
+            ),
+            (vec!["import"], None),
+            (vec!["import", "a.txt", "b.txt"], None),
+            (vec!["import", "levels.txt", "--first", "0"], None),
+            (vec!["import", "levels.txt", "--first"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Import(path, first)), Some(expected)) => assert_eq!(
+                    (path.as_str(), first),
+                    expected,
+                    "wrong import parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected an import command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_generate_pack() {
+        let tests = vec![
+            (vec!["generate-pack"], Some(PackOptions::default())),
+            (
+                vec![
+                    "generate-pack",
+                    "--levels",
+                    "200",
+                    "--colours",
+                    "5",
+                    "--seed",
+                    "42",
+                    "--threads",
+                    "8",
+                    "--preset",
+                    "thorough",
+                ],
+                Some(PackOptions {
+                    levels: 200,
+                    colours: 5,
+                    seed: 42,
+                    threads: Some(8),
+                    preset: Preset::Thorough,
+                }),
+            ),
+            (vec!["generate-pack", "--levels", "0"], None),
+            (vec!["generate-pack", "--threads", "0"], None),
+            (vec!["generate-pack", "--seed"], None),
+            (vec!["generate-pack", "--seed", "x"], None),
+            (vec!["generate-pack", "pack.txt"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::GeneratePack(options)), Some(expected)) => {
+                    assert_eq!(options, expected, "wrong options parsed from {:?}", test.0)
+                }
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a generate-pack command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+        let random_seed = || match Command::parse(&[
+            String::from("generate-pack"),
+            String::from("--seed"),
+            String::from("random"),
+        ]) {
+            Ok(Command::GeneratePack(options)) => options.seed,
+            _ => panic!("expected a generate-pack command parsing --seed random"),
+        };
+        assert_ne!(
+            random_seed(),
+            random_seed(),
+            "--seed random should pick a different seed every time"
+        );
+    }
+
+    #[test]
+    fn test_parse_repair() {
+        // (arguments, the file and number of edits parsed)
+        let tests = vec![
+            (vec!["repair", "a.json"], Some(("a.json", 2usize))),
+            (
+                vec!["repair", "--edits", "1", "a.json", "--preset", "quick"],
+                Some(("a.json", 1)),
+            ),
+            (vec!["repair"], None),
+            (vec!["repair", "a.json", "b.json"], None),
+            (vec!["repair", "a.json", "--edits", "0"], None),
+            (vec!["repair", "a.json", "--edits"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Repair(path, _, edits)), Some(expected)) => assert_eq!(
+                    (path.as_str(), edits),
+                    expected,
+                    "wrong repair parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a repair command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_compare_strategies() {
+        let tests: Vec<(Vec<&str>, Option<Vec<Strategy>>)> = vec![
+            (vec!["compare-strategies"], Some(Strategy::ALL.to_vec())),
+            (
+                vec![
+                    "compare-strategies",
+                    "pack.txt",
+                    "--strategies",
+                    "depth-first",
+                ],
+                Some(vec![Strategy::DepthFirst]),
+            ),
+            (
+                vec![
+                    "compare-strategies",
+                    "--strategies",
+                    "depth-first,Breadth-First",
+                ],
+                Some(vec![Strategy::DepthFirst, Strategy::BreadthFirst]),
+            ),
+            (
+                vec!["compare-strategies", "--strategies", "best-first"],
+                None,
+            ),
+            (vec!["compare-strategies", "--strategies"], None),
+            (vec!["compare-strategies", "a.txt", "b.txt"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            let strategies = match Command::parse(&args) {
+                Ok(Command::CompareStrategies(_, strategies, _)) => Some(strategies),
+                _ => None,
+            };
+            assert_eq!(
+                strategies, test.1,
+                "wrong strategies parsed from {:?}. Expected: {:?}, got: {:?}",
+                test.0, test.1, strategies
+            );
+        }
+    }
+
+    /// The puzzle file, strategy, trace flag and optimal flag expected from a solve command.
+    type SolveArgs<'a> = (&'a str, Option<Strategy>, bool, bool);
+
+    #[test]
+    fn test_parse_solve() {
+        let tests: Vec<(Vec<&str>, Option<SolveArgs>)> = vec![
+            (
+                vec!["solve", "a.json"],
+                Some(("a.json", None, false, false)),
+            ),
+            (
+                vec!["solve", "--trace", "a.json"],
+                Some(("a.json", None, true, false)),
+            ),
+            (
+                vec!["solve", "a.json", "--preset", "quick", "--trace"],
+                Some(("a.json", None, true, false)),
+            ),
+            (
+                vec!["solve", "a.json", "--strategy", "a-star"],
+                Some(("a.json", Some(Strategy::AStar), false, false)),
+            ),
+            (
+                vec!["solve", "a.json", "--strategy", "beam"],
+                Some((
+                    "a.json",
+                    Some(Strategy::Beam(DEFAULT_BEAM_WIDTH)),
+                    false,
+                    false,
+                )),
+            ),
+            (
+                vec![
+                    "solve",
+                    "a.json",
+                    "--beam-width",
+                    "50",
+                    "--strategy",
+                    "beam",
+                ],
+                Some(("a.json", Some(Strategy::Beam(50)), false, false)),
+            ),
+            (vec!["solve", "a.json", "--beam-width", "50"], None),
+            (
+                vec!["solve", "a.json", "--strategy", "beam", "--beam-width", "0"],
+                None,
+            ),
+            (
+                vec!["solve", "a.json", "--optimal"],
+                Some(("a.json", None, false, true)),
+            ),
+            (
+                vec!["solve", "a.json", "--optimal", "--strategy", "a-star"],
+                None,
+            ),
+            (vec!["solve", "a.json", "--solutions", "0"], None),
+            (vec!["solve", "a.json", "--solutions", "3", "--trace"], None),
+            (vec!["solve", "a.json", "--strategy", "best-first"], None),
+            (vec!["solve", "a.json", "--strategy"], None),
+            (vec!["solve"], None),
+            (vec!["solve", "--trace"], None),
+            (vec!["solve", "a.json", "b.json"], None),
+            (vec!["solve", "a.json", "--verbose"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Solve(file, _, strategy, trace, optimal)), Some(expected)) => {
+                    assert_eq!(
+                        (file.as_str(), strategy, trace, optimal),
+                        expected,
+                        "wrong solve command parsed from {:?}",
+                        test.0
+                    )
+                }
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a solve command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_solutions() {
+        let args: Vec<String> = ["solve", "a.json", "--solutions", "5", "--preset", "quick"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        match Command::parse(&args) {
+            Ok(Command::Solutions(file, preset, count)) => assert_eq!(
+                (file.as_str(), preset, count),
+                ("a.json", Preset::Quick, 5),
+                "wrong solutions command parsed"
+            ),
+            _ => panic!("expected a solutions command parsing {:?}", args),
+        }
+    }
+
+    type DebugArgs<'a> = (&'a str, Option<Strategy>);
+
+    #[test]
+    fn test_parse_interactive() {
+        let tests: Vec<(Vec<&str>, Option<DebugArgs>)> = vec![
+            (
+                vec!["solve", "a.json", "--interactive"],
+                Some(("a.json", None)),
+            ),
+            (
+                vec!["solve", "--interactive", "a.json", "--strategy", "a-star"],
+                Some(("a.json", Some(Strategy::AStar))),
+            ),
+            (
+                vec!["solve", "a.json", "--interactive", "--strategy", "ida-star"],
+                None,
+            ),
+            (
+                vec![
+                    "solve",
+                    "a.json",
+                    "--interactive",
+                    "--strategy",
+                    "bidirectional",
+                ],
+                None,
+            ),
+            (vec!["solve", "a.json", "--interactive", "--trace"], None),
+            (vec!["solve", "a.json", "--interactive", "--optimal"], None),
+            (
+                vec!["solve", "a.json", "--interactive", "--solutions", "2"],
+                None,
+            ),
+            (vec!["solve", "--interactive"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Debug(file, _, strategy)), Some(expected)) => assert_eq!(
+                    (file.as_str(), strategy),
+                    expected,
+                    "wrong interactive solve parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected an interactive solve parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    type LeastVolumeArgs<'a> = (&'a str, Preset);
+
+    #[test]
+    fn test_parse_least_volume() {
+        let tests: Vec<(Vec<&str>, Option<LeastVolumeArgs>)> = vec![
+            (
+                vec!["solve", "a.json", "--least-volume"],
+                Some(("a.json", Preset::Balanced)),
+            ),
+            (
+                vec!["solve", "--least-volume", "a.json", "--preset", "quick"],
+                Some(("a.json", Preset::Quick)),
+            ),
+            (
+                vec!["solve", "a.json", "--least-volume", "--strategy", "a-star"],
+                None,
+            ),
+            (vec!["solve", "a.json", "--least-volume", "--optimal"], None),
+            (
+                vec!["solve", "a.json", "--least-volume", "--interactive"],
+                None,
+            ),
+            (vec!["solve", "--least-volume"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::LeastVolume(file, preset)), Some(expected)) => assert_eq!(
+                    (file.as_str(), preset),
+                    expected,
+                    "wrong least volume solve parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a least volume solve parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    type WorksheetArgs<'a> = (&'a str, Preset, &'a str);
+
+    #[test]
+    fn test_parse_worksheet() {
+        let tests: Vec<(Vec<&str>, Option<WorksheetArgs>)> = vec![
+            (
+                vec!["solve", "a.json", "--worksheet", "a.html"],
+                Some(("a.json", Preset::Balanced, "a.html")),
+            ),
+            (
+                vec![
+                    "solve",
+                    "--worksheet",
+                    "a.html",
+                    "a.json",
+                    "--preset",
+                    "quick",
+                ],
+                Some(("a.json", Preset::Quick, "a.html")),
+            ),
+            (vec!["solve", "a.json", "--worksheet"], None),
+            (
+                vec!["solve", "a.json", "--worksheet", "a.html", "--trace"],
+                None,
+            ),
+            (
+                vec!["solve", "a.json", "--worksheet", "a.html", "--least-volume"],
+                None,
+            ),
+            (vec!["solve", "--worksheet", "a.html"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Worksheet(file, preset, page)), Some(expected)) => assert_eq!(
+                    (file.as_str(), preset, page.as_str()),
+                    expected,
+                    "wrong worksheet parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a worksheet parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_check() {
+        let tests: Vec<(Vec<&str>, Option<&str>)> = vec![
+            (vec!["check", "a.json"], Some("a.json")),
+            (vec!["check"], None),
+            (vec!["check", "--preset"], None),
+            (vec!["check", "a.json", "b.json"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Check(path)), Some(expected)) => {
+                    assert_eq!(path, expected, "wrong file parsed from {:?}", test.0)
+                }
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a check command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_preset() {
+        let tests: Vec<(Vec<&str>, Option<Preset>)> = vec![
+            (vec!["play"], Some(Preset::Balanced)),
+            (vec!["play", "--preset", "quick"], Some(Preset::Quick)),
+            (
+                vec!["analyze", "--compare", "a.json", "--preset", "Thorough"],
+                Some(Preset::Thorough),
+            ),
+            (
+                vec!["validate-file", "--preset", "quick", "pack.txt"],
+                Some(Preset::Quick),
+            ),
+            (vec!["play", "--preset"], None),
+            (
+                vec!["analyze", "--compare", "a.json", "--preset", "fast"],
+                None,
+            ),
+            (vec!["validate-file", "pack.txt", "--preset"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            let preset = match Command::parse(&args) {
+                Ok(Command::Play(options)) => Some(options.preset),
+                Ok(Command::GeneratePack(options)) => Some(options.preset),
+                Ok(Command::Compare(_, preset)) | Ok(Command::ValidateFile(_, preset, _)) => {
+                    Some(preset)
+                }
+                Ok(Command::CompareStrategies(_, _, preset))
+                | Ok(Command::Openings(_, preset))
+                | Ok(Command::Solve(_, preset, _, _, _))
+                | Ok(Command::Solutions(_, preset, _))
+                | Ok(Command::Debug(_, preset, _))
+                | Ok(Command::LeastVolume(_, preset))
+                | Ok(Command::Worksheet(_, preset, _))
+                | Ok(Command::Repair(_, preset, _)) => Some(preset),
+                Ok(Command::Stats)
+                | Ok(Command::Spectate(_))
+                | Ok(Command::Import(..))
+                | Ok(Command::Check(_))
+                | Err(_) => None,
+            };
+            assert_eq!(
+                preset, test.1,
+                "wrong preset parsed from {:?}. Expected: {:?}, got: {:?}",
+                test.0, test.1, preset
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let tests: Vec<(Vec<&str>, Option<Option<usize>>)> = vec![
+            (vec![], Some(None)),
+            (vec!["play"], Some(None)),
+            (vec!["play", "--builtin", "7"], Some(Some(7))),
+            (vec!["--builtin", "3"], Some(Some(3))),
+            (vec!["play", "--builtin"], None),
+            (vec!["play", "--builtin", "seven"], None),
+            (vec!["play", "--unknown"], None),
+            (vec!["unknown"], None),
+        ];
+        for test in tests {
+            let args: Vec<String> = test.0.iter().map(|x| x.to_string()).collect();
+            match (Command::parse(&args), test.1) {
+                (Ok(Command::Play(options)), Some(expected)) => assert_eq!(
+                    options.builtin, expected,
+                    "wrong builtin puzzle parsed from {:?}. Expected: {:?}, got: {:?}",
+                    test.0, expected, options.builtin
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Ok(_), Some(_)) => panic!("expected a play command parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+}
@@ -0,0 +1,679 @@
+use std::{fmt::Display, str::FromStr};
+
+/// A colour as red, green and blue components.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// Display values for the colour names that are commonly used in the games.
+const NAMED_COLOURS: [(&str, Rgb); 18] = [
+    ("red", rgb(230, 40, 40)),
+    ("blue", rgb(40, 80, 230)),
+    ("green", rgb(40, 170, 60)),
+    ("yellow", rgb(245, 220, 40)),
+    ("purple", rgb(130, 50, 180)),
+    ("orange", rgb(250, 140, 20)),
+    ("pink", rgb(245, 130, 190)),
+    ("cyan", rgb(40, 210, 230)),
+    ("teal", rgb(20, 140, 140)),
+    ("grey", rgb(128, 128, 128)),
+    ("gray", rgb(128, 128, 128)),
+    ("brown", rgb(130, 80, 30)),
+    ("lime", rgb(160, 230, 40)),
+    ("navy", rgb(20, 30, 120)),
+    ("magenta", rgb(220, 40, 200)),
+    ("violet", rgb(170, 120, 230)),
+    ("white", rgb(240, 240, 240)),
+    ("black", rgb(20, 20, 20)),
+];
+
+/// The named colours as they are used by the levels of a game, without the alternative spelling
+/// of grey or black, which is rarely a liquid.
+pub const LEVEL_COLOURS: [&str; 16] = [
+    "red", "blue", "green", "yellow", "purple", "orange", "pink", "cyan", "teal", "grey", "brown",
+    "lime", "navy", "magenta", "violet", "white",
+];
+
+/// Softer shades of the named colours, as drawn by the games with a pastel look.
+const PASTEL_COLOURS: [(&str, Rgb); 18] = [
+    ("red", rgb(240, 128, 128)),
+    ("blue", rgb(140, 170, 240)),
+    ("green", rgb(150, 215, 150)),
+    ("yellow", rgb(250, 235, 150)),
+    ("purple", rgb(190, 150, 220)),
+    ("orange", rgb(250, 190, 130)),
+    ("pink", rgb(250, 190, 215)),
+    ("cyan", rgb(160, 230, 240)),
+    ("teal", rgb(120, 190, 185)),
+    ("grey", rgb(190, 190, 190)),
+    ("gray", rgb(190, 190, 190)),
+    ("brown", rgb(190, 150, 120)),
+    ("lime", rgb(200, 240, 150)),
+    ("navy", rgb(110, 120, 180)),
+    ("magenta", rgb(235, 150, 225)),
+    ("violet", rgb(210, 185, 240)),
+    ("white", rgb(250, 250, 250)),
+    ("black", rgb(80, 80, 90)),
+];
+
+/// Bright, fully saturated shades of the named colours, as drawn by the games with a dark
+/// background and glowing liquids.
+const NEON_COLOURS: [(&str, Rgb); 18] = [
+    ("red", rgb(255, 20, 60)),
+    ("blue", rgb(30, 100, 255)),
+    ("green", rgb(60, 255, 60)),
+    ("yellow", rgb(255, 255, 0)),
+    ("purple", rgb(180, 0, 255)),
+    ("orange", rgb(255, 120, 0)),
+    ("pink", rgb(255, 60, 200)),
+    ("cyan", rgb(0, 255, 255)),
+    ("teal", rgb(0, 220, 180)),
+    ("grey", rgb(160, 160, 160)),
+    ("gray", rgb(160, 160, 160)),
+    ("brown", rgb(200, 100, 20)),
+    ("lime", rgb(190, 255, 0)),
+    ("navy", rgb(40, 40, 220)),
+    ("magenta", rgb(255, 0, 255)),
+    ("violet", rgb(200, 100, 255)),
+    ("white", rgb(255, 255, 255)),
+    ("black", rgb(30, 30, 30)),
+];
+
+/// The standard 16 colour ANSI palette, in SGR order.
+const ANSI_COLOURS: [Rgb; 16] = [
+    rgb(0, 0, 0),
+    rgb(205, 0, 0),
+    rgb(0, 205, 0),
+    rgb(205, 205, 0),
+    rgb(0, 0, 238),
+    rgb(205, 0, 205),
+    rgb(0, 205, 205),
+    rgb(229, 229, 229),
+    rgb(127, 127, 127),
+    rgb(255, 0, 0),
+    rgb(0, 255, 0),
+    rgb(255, 255, 0),
+    rgb(92, 92, 255),
+    rgb(255, 0, 255),
+    rgb(0, 255, 255),
+    rgb(255, 255, 255),
+];
+
+/// The coloured circle emoji, with the colours they are usually drawn in.
+const EMOJI_COLOURS: [(&str, Rgb); 9] = [
+    ("\u{1f534}", rgb(221, 46, 68)),
+    ("\u{1f7e0}", rgb(244, 144, 12)),
+    ("\u{1f7e1}", rgb(253, 203, 88)),
+    ("\u{1f7e2}", rgb(120, 177, 89)),
+    ("\u{1f535}", rgb(85, 172, 238)),
+    ("\u{1f7e3}", rgb(170, 142, 214)),
+    ("\u{1f7e4}", rgb(193, 105, 79)),
+    ("\u{26ab}", rgb(49, 55, 61)),
+    ("\u{26aa}", rgb(230, 231, 232)),
+];
+
+const fn rgb(r: u8, g: u8, b: u8) -> Rgb {
+    Rgb { r, g, b }
+}
+
+impl Rgb {
+    /// Parses a `#rrggbb` or `#rgb` hex code.
+    pub fn from_hex(hex: &str) -> Option<Rgb> {
+        let digits = hex.strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let digits: String = match digits.len() {
+            3 => digits.chars().flat_map(|c| [c, c]).collect(),
+            6 => digits.to_string(),
+            _ => return None,
+        };
+        let component = |idx: usize| u8::from_str_radix(&digits[idx..idx + 2], 16).ok();
+        Some(Rgb {
+            r: component(0)?,
+            g: component(2)?,
+            b: component(4)?,
+        })
+    }
+
+    /// The SGR foreground code of the closest colour in the 16 colour ANSI palette.
+    pub fn nearest_ansi(&self) -> u8 {
+        let (idx, _) = ANSI_COLOURS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, ansi)| self.distance(ansi))
+            .expect("ANSI palette should not be empty");
+        match idx {
+            0..=7 => 30 + idx as u8,
+            _ => 90 + (idx - 8) as u8,
+        }
+    }
+
+    /// The closest of the coloured circle emoji.
+    pub fn nearest_emoji(&self) -> &'static str {
+        EMOJI_COLOURS
+            .iter()
+            .min_by_key(|(_, emoji)| self.distance(emoji))
+            .map(|(emoji, _)| *emoji)
+            .expect("emoji palette should not be empty")
+    }
+
+    fn distance(&self, other: &Rgb) -> i32 {
+        let dr = self.r as i32 - other.r as i32;
+        let dg = self.g as i32 - other.g as i32;
+        let db = self.b as i32 - other.b as i32;
+        dr * dr + dg * dg + db * db
+    }
+
+    /// The colour as a `#rrggbb` hex code.
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
+
+/// Normalises a colour as entered by the user: trimmed, lower case, and with short hex codes
+/// expanded so that `#F80` and `#ff8800` are the same colour.
+pub fn normalise_colour(colour: &str) -> String {
+    let colour = colour.trim().to_lowercase();
+    if colour.len() == 4 {
+        if let Some(rgb) = Rgb::from_hex(&colour) {
+            return rgb.to_hex();
+        }
+    }
+    colour
+}
+
+/// Returns the display value of a colour, given either by hex code or by one of the known names.
+pub fn colour_rgb(colour: &str) -> Option<Rgb> {
+    if colour.starts_with('#') {
+        return Rgb::from_hex(colour);
+    }
+    NAMED_COLOURS
+        .iter()
+        .find(|(name, _)| *name == colour)
+        .map(|(_, rgb)| *rgb)
+}
+
+/// A palette of display values for the named colours, so that the board on screen can be drawn
+/// in the same shades as the game being copied. Colours given as hex codes are always drawn as
+/// they are.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Theme {
+    #[default]
+    Classic,
+    Pastel,
+    Neon,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Classic, Theme::Pastel, Theme::Neon];
+
+    /// The display value of a colour in this theme.
+    pub fn rgb(&self, colour: &str) -> Option<Rgb> {
+        let palette = match self {
+            Theme::Classic => return colour_rgb(colour),
+            Theme::Pastel => &PASTEL_COLOURS,
+            Theme::Neon => &NEON_COLOURS,
+        };
+        palette
+            .iter()
+            .find(|(name, _)| *name == colour)
+            .map(|(_, rgb)| *rgb)
+            .or_else(|| colour_rgb(colour))
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "classic" => Ok(Theme::Classic),
+            "pastel" => Ok(Theme::Pastel),
+            "neon" => Ok(Theme::Neon),
+            other => Err(format!(
+                "Unknown theme '{}'. Expected one of: classic, pastel, neon",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Theme::Classic => "classic",
+            Theme::Pastel => "pastel",
+            Theme::Neon => "neon",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Colour names in the languages of the localised versions of the games, with the English names
+/// they stand for, so that a board can be copied from a game that is not in English.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Locale {
+    German,
+    Spanish,
+    French,
+    Italian,
+    Dutch,
+    Portuguese,
+}
+
+const GERMAN_COLOURS: [(&str, &str); 19] = [
+    ("rot", "red"),
+    ("blau", "blue"),
+    ("grün", "green"),
+    ("gruen", "green"),
+    ("gelb", "yellow"),
+    ("lila", "purple"),
+    ("rosa", "pink"),
+    ("türkis", "cyan"),
+    ("tuerkis", "cyan"),
+    ("petrol", "teal"),
+    ("grau", "grey"),
+    ("braun", "brown"),
+    ("hellgrün", "lime"),
+    ("hellgruen", "lime"),
+    ("dunkelblau", "navy"),
+    ("violett", "violet"),
+    ("weiß", "white"),
+    ("weiss", "white"),
+    ("schwarz", "black"),
+];
+
+const SPANISH_COLOURS: [(&str, &str); 16] = [
+    ("rojo", "red"),
+    ("azul", "blue"),
+    ("verde", "green"),
+    ("amarillo", "yellow"),
+    ("morado", "purple"),
+    ("naranja", "orange"),
+    ("rosa", "pink"),
+    ("cian", "cyan"),
+    ("turquesa", "cyan"),
+    ("gris", "grey"),
+    ("marrón", "brown"),
+    ("marron", "brown"),
+    ("azul marino", "navy"),
+    ("violeta", "violet"),
+    ("blanco", "white"),
+    ("negro", "black"),
+];
+
+const FRENCH_COLOURS: [(&str, &str); 14] = [
+    ("rouge", "red"),
+    ("bleu", "blue"),
+    ("vert", "green"),
+    ("jaune", "yellow"),
+    ("pourpre", "purple"),
+    ("rose", "pink"),
+    ("turquoise", "cyan"),
+    ("gris", "grey"),
+    ("marron", "brown"),
+    ("vert clair", "lime"),
+    ("bleu marine", "navy"),
+    ("mauve", "violet"),
+    ("blanc", "white"),
+    ("noir", "black"),
+];
+
+const ITALIAN_COLOURS: [(&str, &str); 14] = [
+    ("rosso", "red"),
+    ("blu", "blue"),
+    ("verde", "green"),
+    ("giallo", "yellow"),
+    ("viola", "purple"),
+    ("arancione", "orange"),
+    ("rosa", "pink"),
+    ("ciano", "cyan"),
+    ("turchese", "cyan"),
+    ("grigio", "grey"),
+    ("marrone", "brown"),
+    ("blu scuro", "navy"),
+    ("bianco", "white"),
+    ("nero", "black"),
+];
+
+const DUTCH_COLOURS: [(&str, &str); 15] = [
+    ("rood", "red"),
+    ("blauw", "blue"),
+    ("groen", "green"),
+    ("geel", "yellow"),
+    ("paars", "purple"),
+    ("oranje", "orange"),
+    ("roze", "pink"),
+    ("cyaan", "cyan"),
+    ("turkoois", "cyan"),
+    ("grijs", "grey"),
+    ("bruin", "brown"),
+    ("limoen", "lime"),
+    ("marineblauw", "navy"),
+    ("wit", "white"),
+    ("zwart", "black"),
+];
+
+const PORTUGUESE_COLOURS: [(&str, &str); 15] = [
+    ("vermelho", "red"),
+    ("azul", "blue"),
+    ("verde", "green"),
+    ("amarelo", "yellow"),
+    ("roxo", "purple"),
+    ("laranja", "orange"),
+    ("rosa", "pink"),
+    ("ciano", "cyan"),
+    ("turquesa", "cyan"),
+    ("cinza", "grey"),
+    ("marrom", "brown"),
+    ("lima", "lime"),
+    ("azul-marinho", "navy"),
+    ("branco", "white"),
+    ("preto", "black"),
+];
+
+impl Locale {
+    pub const ALL: [Locale; 6] = [
+        Locale::German,
+        Locale::Spanish,
+        Locale::French,
+        Locale::Italian,
+        Locale::Dutch,
+        Locale::Portuguese,
+    ];
+
+    /// The English name of a colour named in this language, if it is one of the known names.
+    pub fn translate(&self, colour: &str) -> Option<&'static str> {
+        let names: &[(&str, &str)] = match self {
+            Locale::German => &GERMAN_COLOURS,
+            Locale::Spanish => &SPANISH_COLOURS,
+            Locale::French => &FRENCH_COLOURS,
+            Locale::Italian => &ITALIAN_COLOURS,
+            Locale::Dutch => &DUTCH_COLOURS,
+            Locale::Portuguese => &PORTUGUESE_COLOURS,
+        };
+        names
+            .iter()
+            .find(|(name, _)| *name == colour)
+            .map(|(_, english)| *english)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "de" | "german" => Ok(Locale::German),
+            "es" | "spanish" => Ok(Locale::Spanish),
+            "fr" | "french" => Ok(Locale::French),
+            "it" | "italian" => Ok(Locale::Italian),
+            "nl" | "dutch" => Ok(Locale::Dutch),
+            "pt" | "portuguese" => Ok(Locale::Portuguese),
+            other => Err(format!(
+                "Unknown locale '{}'. Expected one of: de, es, fr, it, nl, pt",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let code = match self {
+            Locale::German => "de",
+            Locale::Spanish => "es",
+            Locale::French => "fr",
+            Locale::Italian => "it",
+            Locale::Dutch => "nl",
+            Locale::Portuguese => "pt",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+/// The colour names that are read as other colours while the tubes are entered: those of the
+/// chosen locales, and any names of the user's own.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct ColourNames {
+    pub locales: Vec<Locale>,
+    /// Names of the user's own, with the colour each stands for. These are looked at before the
+    /// locales.
+    pub aliases: Vec<(String, String)>,
+}
+
+impl ColourNames {
+    /// The colour a normalised colour name stands for. The known colour names and hex codes are
+    /// always themselves, so a name such as `violet`, which is purple in French, is not changed.
+    pub fn translate(&self, colour: String) -> String {
+        if colour.starts_with('#') || colour_rgb(&colour).is_some() {
+            return colour;
+        }
+        if let Some((_, alias)) = self.aliases.iter().find(|(name, _)| *name == colour) {
+            return alias.clone();
+        }
+        self.locales
+            .iter()
+            .find_map(|locale| locale.translate(&colour))
+            .map_or(colour, String::from)
+    }
+}
+
+/// Suggests what an unknown colour was meant to be: the closest of the known colour names and
+/// `others`, if it is within a couple of typos.
+pub fn suggest_colour<'a>(
+    colour: &str,
+    others: impl Iterator<Item = &'a String>,
+) -> Option<String> {
+    let names = NAMED_COLOURS.iter().map(|(name, _)| name.to_string());
+    names
+        .chain(others.cloned())
+        .filter(|candidate| candidate != colour)
+        .map(|candidate| (edit_distance(colour, &candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2 && *distance < colour.chars().count())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between two strings: how many characters need to be inserted,
+/// removed or changed to turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let change = previous[j] + usize::from(a_char != *b_char);
+            current.push(change.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_hex() {
+        let tests = [
+            ("#ff8800", Some(rgb(255, 136, 0))),
+            ("#FF8800", Some(rgb(255, 136, 0))),
+            ("#f80", Some(rgb(255, 136, 0))),
+            ("#000000", Some(rgb(0, 0, 0))),
+            ("ff8800", None),
+            ("#ff880", None),
+            ("#gg8800", None),
+            ("#", None),
+        ];
+        for test in tests {
+            let result = Rgb::from_hex(test.0);
+            assert_eq!(
+                result, test.1,
+                "wrong colour parsed from {}. Expected: {:?}, got: {:?}",
+                test.0, test.1, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalise_colour() {
+        let tests = [
+            ("  Red ", "red"),
+            ("#FF8800", "#ff8800"),
+            ("#F80", "#ff8800"),
+            ("#f8", "#f8"),
+            ("teal", "teal"),
+        ];
+        for test in tests {
+            let result = normalise_colour(test.0);
+            assert_eq!(
+                result, test.1,
+                "wrong normalised colour for {:?}. Expected: {}, got: {}",
+                test.0, test.1, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_translate_colour() {
+        let names = ColourNames {
+            locales: vec![Locale::German, Locale::French],
+            aliases: vec![(String::from("rot"), String::from("pink"))],
+        };
+        let tests = [
+            ("rot", "pink"),
+            ("blau", "blue"),
+            ("grün", "green"),
+            ("rouge", "red"),
+            ("bleu marine", "navy"),
+            ("violet", "violet"),
+            ("#ff8800", "#ff8800"),
+            ("azul", "azul"),
+            ("empty", "empty"),
+        ];
+        for test in tests {
+            let result = names.translate(String::from(test.0));
+            assert_eq!(
+                result, test.1,
+                "wrong colour for {:?}. Expected: {}, got: {}",
+                test.0, test.1, result
+            );
+        }
+        for locale in Locale::ALL {
+            assert_eq!(
+                locale.to_string().parse::<Locale>(),
+                Ok(locale),
+                "{} should parse to itself",
+                locale
+            );
+        }
+    }
+
+    #[test]
+    fn test_nearest_emoji() {
+        let tests = [
+            ("red", "\u{1f534}"),
+            ("navy", "\u{26ab}"),
+            ("cyan", "\u{1f535}"),
+            ("#ff8800", "\u{1f7e0}"),
+            ("white", "\u{26aa}"),
+        ];
+        for test in tests {
+            let result = colour_rgb(test.0).unwrap().nearest_emoji();
+            assert_eq!(
+                result, test.1,
+                "wrong emoji for {}. Expected: {}, got: {}",
+                test.0, test.1, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_theme() {
+        let tests = [
+            (Theme::Classic, "red", Some(rgb(230, 40, 40))),
+            (Theme::Pastel, "red", Some(rgb(240, 128, 128))),
+            (Theme::Neon, "cyan", Some(rgb(0, 255, 255))),
+            (Theme::Neon, "#ff8800", Some(rgb(255, 136, 0))),
+            (Theme::Pastel, "mauve", None),
+        ];
+        for test in tests {
+            let result = test.0.rgb(test.1);
+            assert_eq!(
+                result, test.2,
+                "wrong display value for {} in the {} theme. Expected: {:?}, got: {:?}",
+                test.1, test.0, test.2, result
+            );
+        }
+        for theme in Theme::ALL {
+            assert_eq!(
+                theme.to_string().parse::<Theme>(),
+                Ok(theme),
+                "theme did not round trip"
+            );
+            assert!(
+                NAMED_COLOURS
+                    .iter()
+                    .all(|(name, _)| theme.rgb(name).is_some()),
+                "the {} theme is missing a named colour",
+                theme
+            );
+        }
+        assert!(
+            "dark".parse::<Theme>().is_err(),
+            "expected an unknown theme"
+        );
+    }
+
+    #[test]
+    fn test_suggest_colour() {
+        let others = [String::from("mauve"), String::from("#ff8800")];
+        let tests = [
+            ("ornge", Some("orange")),
+            ("bleu", Some("blue")),
+            ("mauv", Some("mauve")),
+            ("gren", Some("green")),
+            ("rd", Some("red")),
+            ("xyzzy", None),
+            ("ab", None),
+        ];
+        for test in tests {
+            let result = suggest_colour(test.0, others.iter());
+            assert_eq!(
+                result.as_deref(),
+                test.1,
+                "wrong suggestion for {}. Expected: {:?}, got: {:?}",
+                test.0,
+                test.1,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_nearest_ansi() {
+        let tests = [
+            ("red", 31),
+            ("#ff0000", 91),
+            ("blue", 94),
+            ("#ffffff", 97),
+            ("black", 30),
+            ("#ffee00", 93),
+        ];
+        for test in tests {
+            let result = colour_rgb(test.0).unwrap().nearest_ansi();
+            assert_eq!(
+                result, test.1,
+                "wrong ANSI colour for {}. Expected: {}, got: {}",
+                test.0, test.1, result
+            );
+        }
+        assert!(colour_rgb("mauve").is_none(), "found a value for mauve");
+    }
+}
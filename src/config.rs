@@ -0,0 +1,357 @@
+use std::{env, fs, path::PathBuf};
+
+use crate::{
+    colour::{colour_rgb, normalise_colour, ColourNames, Locale, Theme},
+    render::RenderMode,
+};
+
+/// Overrides the directory the settings and other per-user files are kept in.
+pub const HOME_ENV_VAR: &str = "WATER_SORT_SOLVER_HOME";
+const CONFIG_FILE_NAME: &str = "config";
+const DEFAULT_MAX_TUBES: usize = 64;
+const DEFAULT_PAGE_ROWS: usize = 20;
+/// The width boards are drawn to when neither the settings nor the terminal give one.
+const DEFAULT_WIDTH: usize = 80;
+/// The narrowest a board can be drawn, so that at least one tube fits on every row.
+pub const MIN_WIDTH: usize = 20;
+
+/// Per-user settings, read from a file of `key = value` lines.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct Config {
+    pub render_mode: RenderMode,
+    pub patterns: bool,
+    /// The shades the named colours are drawn in.
+    pub theme: Theme,
+    /// Colour names read as other colours while the tubes are entered, for copying a board from a
+    /// game in another language.
+    pub colour_names: ColourNames,
+    /// Ask for confirmation before making a move that looks risky or losing.
+    pub assistant: bool,
+    /// Ask for confirmation before pouring into an empty tube when a merge is available instead.
+    pub confirm_wasteful: bool,
+    /// Refuse moves that only pour a tube holding a single colour into an empty tube, rather than
+    /// warning about them.
+    pub reject_null_moves: bool,
+    /// Keep the solutions of solved puzzles, so that boards seen before are solved instantly.
+    pub known_puzzles: bool,
+    /// Reject a setup holding a colour that is not one of the known names or a hex code, rather
+    /// than asking whether it is a new colour, so a typo can not slip into a scripted setup.
+    pub strict_input: bool,
+    /// Log every rejected input, to see which input formats users try.
+    pub debug_log: bool,
+    /// Show a lower bound on the number of moves left after every move.
+    pub show_estimate: bool,
+    /// Show how many segments of every colour are sorted after every move.
+    pub show_progress: bool,
+    /// Allow the commands for developing the game, such as `setstate`, which are always allowed
+    /// in a debug build.
+    pub debug_commands: bool,
+    /// Whether to show how hard a board looks once it has been entered, if not the default of
+    /// showing it.
+    pub show_difficulty: Option<bool>,
+    /// The largest number of tubes a board can be set up with, if not the default.
+    pub max_tubes: Option<usize>,
+    /// How many tubes are shown at a time before waiting for Enter, if not the default. 0 shows
+    /// every tube at once.
+    pub page_rows: Option<usize>,
+    /// How many columns wide the board is drawn when the tubes are side by side, if not the width
+    /// of the terminal.
+    pub width: Option<usize>,
+    /// Whether to show consecutive pours between the same two tubes as one move in the moves
+    /// listed at the end of a game and in saved games, if not the default of merging them. The
+    /// history still keeps every pour, to undo one at a time.
+    pub merge_pours: Option<bool>,
+    /// The most moves kept in a game's history, or None to keep them all. The oldest moves are
+    /// dropped first.
+    pub history_limit: Option<usize>,
+}
+
+impl Config {
+    pub fn parse(contents: &str) -> Result<Config, String> {
+        let mut config = Config::default();
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("Line {}: expected 'key = value'", line_num + 1)),
+            };
+            match key {
+                "render" => {
+                    config.render_mode = match value.parse::<RenderMode>() {
+                        Ok(mode) => mode,
+                        Err(err) => return Err(format!("Line {}: {}", line_num + 1, err)),
+                    }
+                }
+                "patterns" => config.patterns = parse_bool(value, line_num)?,
+                "theme" => {
+                    config.theme = value
+                        .parse::<Theme>()
+                        .map_err(|err| format!("Line {}: {}", line_num + 1, err))?
+                }
+                "locales" => {
+                    config.colour_names.locales = value
+                        .split(',')
+                        .map(|locale| locale.parse::<Locale>())
+                        .collect::<Result<Vec<Locale>, String>>()
+                        .map_err(|err| format!("Line {}: {}", line_num + 1, err))?
+                }
+                "colour_alias" => {
+                    let alias = parse_alias(value)
+                        .map_err(|err| format!("Line {}: {}", line_num + 1, err))?;
+                    config.colour_names.aliases.push(alias);
+                }
+                "assistant" => config.assistant = parse_bool(value, line_num)?,
+                "confirm_wasteful" => config.confirm_wasteful = parse_bool(value, line_num)?,
+                "reject_null_moves" => config.reject_null_moves = parse_bool(value, line_num)?,
+                "strict_input" => config.strict_input = parse_bool(value, line_num)?,
+                "debug_log" => config.debug_log = parse_bool(value, line_num)?,
+                "show_estimate" => config.show_estimate = parse_bool(value, line_num)?,
+                "show_progress" => config.show_progress = parse_bool(value, line_num)?,
+                "debug_commands" => config.debug_commands = parse_bool(value, line_num)?,
+                "show_difficulty" => config.show_difficulty = Some(parse_bool(value, line_num)?),
+                "known_puzzles" => config.known_puzzles = parse_bool(value, line_num)?,
+                "merge_pours" => config.merge_pours = Some(parse_bool(value, line_num)?),
+                "max_tubes" => match parse_number(value, line_num)? {
+                    max if max >= 4 => config.max_tubes = Some(max),
+                    _ => {
+                        return Err(format!(
+                            "Line {}: max_tubes must be at least 4",
+                            line_num + 1
+                        ))
+                    }
+                },
+                "page_rows" => config.page_rows = Some(parse_number(value, line_num)?),
+                "width" => match parse_number(value, line_num)? {
+                    width if width >= MIN_WIDTH => config.width = Some(width),
+                    _ => {
+                        return Err(format!(
+                            "Line {}: width must be at least {}",
+                            line_num + 1,
+                            MIN_WIDTH
+                        ))
+                    }
+                },
+                "history_limit" => match value {
+                    "unlimited" => config.history_limit = None,
+                    value => match parse_number(value, line_num)? {
+                        limit if limit > 0 => config.history_limit = Some(limit),
+                        _ => {
+                            return Err(format!(
+                                "Line {}: history_limit must be at least 1",
+                                line_num + 1
+                            ))
+                        }
+                    },
+                },
+                _ => return Err(format!("Line {}: unknown setting '{}'", line_num + 1, key)),
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn max_tubes(&self) -> usize {
+        self.max_tubes.unwrap_or(DEFAULT_MAX_TUBES)
+    }
+
+    pub fn show_difficulty(&self) -> bool {
+        self.show_difficulty.unwrap_or(true)
+    }
+
+    pub fn merge_pours(&self) -> bool {
+        self.merge_pours.unwrap_or(true)
+    }
+
+    pub fn debug_commands(&self) -> bool {
+        cfg!(debug_assertions) || self.debug_commands
+    }
+
+    pub fn page_rows(&self) -> usize {
+        self.page_rows.unwrap_or(DEFAULT_PAGE_ROWS)
+    }
+
+    /// The width set in the settings, or else the width of the terminal, which shells pass on
+    /// through `COLUMNS`.
+    pub fn width(&self) -> usize {
+        if let Some(width) = self.width {
+            return width;
+        }
+        match env::var("COLUMNS").map(|columns| columns.trim().parse::<usize>()) {
+            Ok(Ok(width)) if width >= MIN_WIDTH => width,
+            _ => DEFAULT_WIDTH,
+        }
+    }
+
+    /// Loads the user's settings. A missing settings file just gives the defaults.
+    pub fn load() -> Result<Config, String> {
+        let path = match home_dir() {
+            Some(dir) => dir.join(CONFIG_FILE_NAME),
+            None => return Ok(Config::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => Config::parse(&contents)
+                .map_err(|err| format!("Unable to read {}: {}", path.display(), err)),
+            Err(_) => Ok(Config::default()),
+        }
+    }
+}
+
+/// The directory holding the per-user files, `~/.water_sort_solver` unless overridden.
+pub fn home_dir() -> Option<PathBuf> {
+    if let Ok(dir) = env::var(HOME_ENV_VAR) {
+        return Some(PathBuf::from(dir));
+    }
+    env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(".water_sort_solver"))
+}
+
+/// Parses a colour name of the user's own, such as `lachs: pink`, to the name and the colour it
+/// stands for, which must be a known colour name or a hex code.
+fn parse_alias(value: &str) -> Result<(String, String), String> {
+    let (name, colour) = match value.split_once(':') {
+        Some((name, colour)) => (normalise_colour(name), normalise_colour(colour)),
+        None => return Err(String::from("expected 'colour_alias = <name>: <colour>'")),
+    };
+    if name.is_empty() {
+        return Err(String::from("a colour alias needs a name"));
+    }
+    if colour_rgb(&colour).is_none() {
+        return Err(format!(
+            "'{}' is not a known colour name or hex code",
+            colour
+        ));
+    }
+    Ok((name, colour))
+}
+
+fn parse_bool(value: &str, line_num: usize) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(true),
+        "false" | "no" | "off" => Ok(false),
+        _ => Err(format!(
+            "Line {}: expected true or false, got '{}'",
+            line_num + 1,
+            value
+        )),
+    }
+}
+
+fn parse_number(value: &str, line_num: usize) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(number) => Ok(number),
+        Err(_) => Err(format!(
+            "Line {}: unable to parse {} to a number",
+            line_num + 1,
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let tests: Vec<(&str, Option<Config>)> = vec![
+            ("", Some(Config::default())),
+            (
+                "# comment\nrender = symbols\n\npatterns = yes\n",
+                Some(Config {
+                    render_mode: RenderMode::Symbols,
+                    patterns: true,
+                    ..Default::default()
+                }),
+            ),
+            ("render=names\npatterns=off", Some(Config::default())),
+            (
+                "assistant = true\nconfirm_wasteful = yes\nreject_null_moves = on\nknown_puzzles = on\nstrict_input = true\ndebug_log = true\nshow_estimate = true\nshow_progress = true\ndebug_commands = true",
+                Some(Config {
+                    assistant: true,
+                    confirm_wasteful: true,
+                    reject_null_moves: true,
+                    known_puzzles: true,
+                    strict_input: true,
+                    debug_log: true,
+                    show_estimate: true,
+                    show_progress: true,
+                    debug_commands: true,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "max_tubes = 40\npage_rows = 0\nwidth = 120\nrender = columns",
+                Some(Config {
+                    render_mode: RenderMode::Columns,
+                    max_tubes: Some(40),
+                    page_rows: Some(0),
+                    width: Some(120),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "history_limit = 200\nmerge_pours = no",
+                Some(Config {
+                    history_limit: Some(200),
+                    merge_pours: Some(false),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "render = colour\ntheme = Neon",
+                Some(Config {
+                    render_mode: RenderMode::Colour,
+                    theme: Theme::Neon,
+                    ..Default::default()
+                }),
+            ),
+            (
+                "show_difficulty = off",
+                Some(Config {
+                    show_difficulty: Some(false),
+                    ..Default::default()
+                }),
+            ),
+            (
+                "locales = de, ES\ncolour_alias = Lachs: pink\ncolour_alias = ocre: #C80",
+                Some(Config {
+                    colour_names: ColourNames {
+                        locales: vec![Locale::German, Locale::Spanish],
+                        aliases: vec![
+                            (String::from("lachs"), String::from("pink")),
+                            (String::from("ocre"), String::from("#cc8800")),
+                        ],
+                    },
+                    ..Default::default()
+                }),
+            ),
+            ("history_limit = unlimited", Some(Config::default())),
+            ("history_limit = 0", None),
+            ("max_tubes = 3", None),
+            ("page_rows = many", None),
+            ("width = 10", None),
+            ("assistant = sometimes", None),
+            ("render = pictures", None),
+            ("theme = dark", None),
+            ("locales = de, klingon", None),
+            ("colour_alias = lachs", None),
+            ("colour_alias = lachs: salmon", None),
+            ("patterns = maybe", None),
+            ("colour = blue", None),
+            ("render symbols", None),
+        ];
+        for test in tests {
+            match (Config::parse(test.0), test.1) {
+                (Ok(config), Some(expected)) => {
+                    assert_eq!(config, expected, "wrong settings parsed from {:?}", test.0)
+                }
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+}
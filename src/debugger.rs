@@ -0,0 +1,364 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use crate::{
+    game::{Game, Move},
+    search::{
+        AStarSearch, BeamSearch, BreadthFirstSearch, DepthFirstSearch, GreedySearch, SearchNode,
+        SolverStrategy,
+    },
+    solver::{state_hash, Strategy},
+};
+
+/// The most states `frontier` lists.
+const FRONTIER_LINES: usize = 20;
+
+/// What has become of a state the search has reached.
+enum Status {
+    /// Waiting in the frontier to be looked at.
+    Waiting,
+    /// Looked at, and the states one move on given to the strategy. The number is its place in
+    /// the order states were looked at, from 1.
+    Expanded(usize),
+    /// Not given to the strategy, for the reason given.
+    Pruned(String),
+    /// Reached again in fewer moves before being looked at, and so passed over.
+    Skipped,
+}
+
+/// A state the search has reached, numbered by its place in `SearchDebugger::records`.
+struct Record {
+    state: Game,
+    parent: Option<usize>,
+    last_move: Option<Move>,
+    depth: usize,
+    bound: usize,
+    score: i64,
+    status: Status,
+}
+
+/// Steps through a search one state at a time, showing the frontier of states waiting and the
+/// score and lower bound the strategy gives each of them, for developing new heuristics. The
+/// search follows the same rules as `Solver::search_using`. Every state reached is numbered, from
+/// 0 for the board being solved.
+pub struct SearchDebugger<R: BufRead, W: Write> {
+    stdin: R,
+    stdout: W,
+    strategy: Box<dyn SolverStrategy>,
+    max_states: usize,
+    records: Vec<Record>,
+    /// The number of the latest record of every state reached.
+    ids: HashMap<u64, usize>,
+    /// The fewest moves every state has been reached in.
+    fewest: HashMap<u64, usize>,
+    states_searched: usize,
+    finished: bool,
+}
+
+impl<R: BufRead, W: Write> SearchDebugger<R, W> {
+    /// Returns None for IDA*, which searches in passes, and for a bidirectional search, which
+    /// searches from both ends a depth at a time, as neither can be stepped through one state at
+    /// a time.
+    pub fn new(stdin: R, stdout: W, game: &Game, strategy: Strategy) -> Option<Self> {
+        let mut strategy: Box<dyn SolverStrategy> = match strategy {
+            Strategy::BreadthFirst => Box::<BreadthFirstSearch>::default(),
+            Strategy::DepthFirst => Box::<DepthFirstSearch>::default(),
+            Strategy::AStar => Box::<AStarSearch>::default(),
+            Strategy::Greedy => Box::<GreedySearch>::default(),
+            Strategy::Beam(width) => Box::new(BeamSearch::new(width)),
+            Strategy::IdaStar | Strategy::Bidirectional => return None,
+        };
+        let start = SearchNode::new(game.clone(), 0);
+        let hash = state_hash(&start.state);
+        let record = Record {
+            state: game.clone(),
+            parent: None,
+            last_move: None,
+            depth: 0,
+            bound: start.bound,
+            score: strategy.evaluate(&start),
+            status: Status::Waiting,
+        };
+        let max_states = strategy.budget(&game.preset.settings());
+        strategy.push(start);
+        Some(SearchDebugger {
+            stdin,
+            stdout,
+            strategy,
+            max_states,
+            records: vec![record],
+            ids: HashMap::from([(hash, 0)]),
+            fewest: HashMap::from([(hash, 0)]),
+            states_searched: 0,
+            finished: false,
+        })
+    }
+
+    /// Reads commands until `quit` or the end of the input.
+    pub fn run(&mut self) -> io::Result<()> {
+        writeln!(
+            self.stdout,
+            "Enter expand [<count>] to look at the next state, best to see it, frontier to list the states waiting, why <state> to explain one, run to search to the end, or quit."
+        )?;
+        writeln!(self.stdout, "State 0 is the board being solved:")?;
+        write!(self.stdout, "{}", self.records[0].state)?;
+        loop {
+            write!(self.stdout, "> ")?;
+            self.stdout.flush()?;
+            let mut input = String::new();
+            if self.stdin.read_line(&mut input)? == 0 {
+                return Ok(());
+            }
+            let (command, args) = match input.trim().split_once(' ') {
+                Some((command, args)) => (command, args.trim()),
+                None => (input.trim(), ""),
+            };
+            match command {
+                "expand" | "" => {
+                    let count = match args {
+                        "" => Some(1),
+                        count => count.parse::<usize>().ok(),
+                    };
+                    match count {
+                        Some(count) => {
+                            for _ in 0..count {
+                                if !self.expand()? {
+                                    break;
+                                }
+                            }
+                        }
+                        None => writeln!(self.stdout, "Usage: expand [<count>]")?,
+                    }
+                }
+                "run" => while self.expand()? {},
+                "best" => self.best()?,
+                "frontier" => self.frontier()?,
+                "why" => match args.parse::<usize>() {
+                    Ok(id) if id < self.records.len() => self.why(id)?,
+                    Ok(id) => writeln!(self.stdout, "There is no state {}", id)?,
+                    Err(_) => writeln!(self.stdout, "Usage: why <state>")?,
+                },
+                "quit" => return Ok(()),
+                command => writeln!(self.stdout, "Unknown command '{}'", command)?,
+            }
+        }
+    }
+
+    /// Looks at the next state, as the solver would. Returns false once the search has finished.
+    fn expand(&mut self) -> io::Result<bool> {
+        if self.finished {
+            writeln!(self.stdout, "The search has finished")?;
+            return Ok(false);
+        }
+        let node = loop {
+            let node = match self.strategy.pop() {
+                Some(node) => node,
+                None => {
+                    self.finished = true;
+                    writeln!(
+                        self.stdout,
+                        "There are no states left to look at, so the board cannot be solved"
+                    )?;
+                    return Ok(false);
+                }
+            };
+            if self.states_searched == self.max_states {
+                self.finished = true;
+                writeln!(
+                    self.stdout,
+                    "The budget of {} states has been used",
+                    self.max_states
+                )?;
+                return Ok(false);
+            }
+            if self
+                .fewest
+                .get(&state_hash(&node.state))
+                .is_some_and(|fewest| *fewest < node.depth)
+            {
+                continue;
+            }
+            break node;
+        };
+        self.states_searched += 1;
+        let id = self.ids[&state_hash(&node.state)];
+        self.records[id].status = Status::Expanded(self.states_searched);
+        writeln!(self.stdout, "Expanded {}", self.describe(id))?;
+        if node.state.is_game_complete() {
+            self.finished = true;
+            writeln!(
+                self.stdout,
+                "State {} completes the game in {} moves: {}",
+                id,
+                node.depth,
+                self.path(id)
+                    .iter()
+                    .map(|a_move| a_move.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )?;
+            return Ok(false);
+        }
+        for next in self.strategy.next_states(&node) {
+            let hash = state_hash(&next.state);
+            let last_move = next.state.moves.get(&next.state.current_move).cloned();
+            let fewest = self.fewest.get(&hash).copied();
+            if self.strategy.should_prune(&next, fewest) {
+                let reason = match (fewest, self.ids.get(&hash)) {
+                    (Some(fewest), Some(seen)) => {
+                        format!("reached before in {} moves as state {}", fewest, seen)
+                    }
+                    _ => String::from("not followed by the strategy"),
+                };
+                let after = last_move.as_ref().map_or(String::new(), Move::to_string);
+                writeln!(self.stdout, "  Pruned {}: {}", after, reason)?;
+                if fewest.is_none() {
+                    self.records.push(Record {
+                        state: next.state.clone(),
+                        parent: Some(id),
+                        last_move,
+                        depth: next.depth,
+                        bound: next.bound,
+                        score: self.strategy.evaluate(&next),
+                        status: Status::Pruned(reason),
+                    });
+                }
+                continue;
+            }
+            if let Some(seen) = self.ids.get(&hash) {
+                // Reached again in fewer moves, so the search will pass the earlier record over
+                self.records[*seen].status = Status::Skipped;
+            }
+            self.records.push(Record {
+                state: next.state.clone(),
+                parent: Some(id),
+                last_move,
+                depth: next.depth,
+                bound: next.bound,
+                score: self.strategy.evaluate(&next),
+                status: Status::Waiting,
+            });
+            let next_id = self.records.len() - 1;
+            self.ids.insert(hash, next_id);
+            self.fewest.insert(hash, next.depth);
+            writeln!(self.stdout, "  Added {}", self.describe(next_id))?;
+            self.strategy.push(next);
+        }
+        Ok(true)
+    }
+
+    /// Shows the state the strategy would look at next.
+    fn best(&mut self) -> io::Result<()> {
+        let id = match self.waiting().first() {
+            Some(id) => *id,
+            None => return writeln!(self.stdout, "There are no states waiting"),
+        };
+        writeln!(self.stdout, "Next is {}:", self.describe(id))?;
+        write!(self.stdout, "{}", self.records[id].state)
+    }
+
+    fn frontier(&mut self) -> io::Result<()> {
+        let ids = self.waiting();
+        writeln!(
+            self.stdout,
+            "{} states waiting, after {} looked at:",
+            ids.len(),
+            self.states_searched
+        )?;
+        for id in ids.iter().take(FRONTIER_LINES) {
+            writeln!(self.stdout, "  {}", self.describe(*id))?;
+        }
+        if ids.len() > FRONTIER_LINES {
+            writeln!(self.stdout, "  ... and {} more", ids.len() - FRONTIER_LINES)?;
+        }
+        Ok(())
+    }
+
+    /// Explains how a state was reached, how the strategy scored it and what became of it.
+    fn why(&mut self, id: usize) -> io::Result<()> {
+        let path = self.path(id);
+        match path.is_empty() {
+            true => writeln!(self.stdout, "State 0 is the board being solved.")?,
+            false => writeln!(
+                self.stdout,
+                "State {} was reached in {} moves: {}.",
+                id,
+                path.len(),
+                path.iter()
+                    .map(|a_move| a_move.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )?,
+        }
+        let record = &self.records[id];
+        writeln!(
+            self.stdout,
+            "It could be finished in {} moves or more, and the strategy scores it {}.",
+            record.bound, record.score
+        )?;
+        match &record.status {
+            Status::Waiting => {
+                let place = self.waiting().iter().position(|waiting| *waiting == id);
+                match place {
+                    Some(place) => writeln!(
+                        self.stdout,
+                        "It is waiting to be looked at, number {} in the frontier.",
+                        place + 1
+                    )?,
+                    None => writeln!(self.stdout, "It is waiting to be looked at.")?,
+                }
+            }
+            Status::Expanded(order) => {
+                writeln!(self.stdout, "It was the state looked at number {}.", order)?
+            }
+            Status::Pruned(reason) => writeln!(self.stdout, "It was pruned: {}.", reason)?,
+            Status::Skipped => writeln!(
+                self.stdout,
+                "It is passed over, as it was reached again in fewer moves."
+            )?,
+        }
+        let state = self.records[id].state.to_string();
+        write!(self.stdout, "{}", state)
+    }
+
+    /// The states in the frontier, next first, leaving out those reached again in fewer moves.
+    fn waiting(&self) -> Vec<usize> {
+        self.strategy
+            .frontier()
+            .iter()
+            .filter(|node| self.fewest[&state_hash(&node.state)] == node.depth)
+            .map(|node| self.ids[&state_hash(&node.state)])
+            .collect()
+    }
+
+    /// One line about a state, such as `state 3 after 1 -> 3: red x 3 (depth 1, lower bound 2,
+    /// score 3)`.
+    fn describe(&self, id: usize) -> String {
+        let record = &self.records[id];
+        let after = match &record.last_move {
+            Some(a_move) => format!(" after {}", a_move),
+            None => String::new(),
+        };
+        format!(
+            "state {}{} (depth {}, lower bound {}, score {})",
+            id, after, record.depth, record.bound, record.score
+        )
+    }
+
+    /// The moves that reached a state from the board being solved.
+    fn path(&self, id: usize) -> Vec<Move> {
+        let mut path = Vec::new();
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let record = &self.records[id];
+            if let Some(a_move) = &record.last_move {
+                path.push(a_move.clone());
+            }
+            current = record.parent;
+        }
+        path.reverse();
+        path
+    }
+}
@@ -1,10 +1,22 @@
 use itertools::Itertools;
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::{min, Reverse},
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Display,
+    str::FromStr,
+    time::Duration,
 };
 
-use crate::{tube::Tube, TUBE_SIZE};
+use crate::{
+    colour::normalise_colour,
+    solver::{Preset, Solver},
+    stats::format_duration,
+    tube::Tube,
+    TUBE_SIZE,
+};
+
+/// How many moves ahead `classify_move` looks.
+const SAFETY_LOOKAHEAD: usize = 4;
 
 #[derive(Default, Clone)]
 pub struct Game {
@@ -12,6 +24,161 @@ pub struct Game {
     pub moves: HashMap<usize, Move>,
     pub current_move: usize,
     pub colours: HashSet<String>,
+    pub ruleset: Ruleset,
+    pub hint_limit: Option<usize>,
+    /// In blitz mode, how long the player has to complete the puzzle.
+    pub time_limit: Option<Duration>,
+    pub stats: GameStats,
+    /// Pours that are never allowed, as `(tube_from, tube_to)`, for games where the tubes are
+    /// connected by valves rather than poured freely.
+    pub forbidden_pours: Vec<(usize, usize)>,
+    pub par: Par,
+    /// The search settings used whenever the game is solved.
+    pub preset: Preset,
+    /// The most moves kept in `moves`, or None to keep every move. Once the limit is reached the
+    /// oldest move is dropped for every new one, though `current_move` still counts them all.
+    pub history_limit: Option<usize>,
+}
+
+/// Target values for a puzzle to measure a solve against.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Par {
+    pub moves: Option<usize>,
+    pub time: Option<Duration>,
+}
+
+impl Par {
+    pub fn is_set(&self) -> bool {
+        self.moves.is_some() || self.time.is_some()
+    }
+}
+
+impl Display for Par {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(moves) = self.moves {
+            parts.push(format!("{} moves", moves));
+        }
+        if let Some(time) = self.time {
+            parts.push(format_duration(time));
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Counters kept while a game is played, used for the end-of-game rating.
+#[derive(Default, Clone)]
+pub struct GameStats {
+    pub hints_used: usize,
+}
+
+impl GameStats {
+    /// Rates a completed game out of 3 stars. Every hint used costs a star.
+    pub fn stars(&self) -> usize {
+        3 - min(self.hints_used, 2)
+    }
+
+    pub fn rating_string(&self) -> String {
+        let stars = self.stars();
+        let hints = match self.hints_used {
+            1 => "1 hint used".to_string(),
+            hints => format!("{} hints used", hints),
+        };
+        format!("{}{} ({})", "★".repeat(stars), "☆".repeat(3 - stars), hints)
+    }
+}
+
+/// The rules deciding how much liquid a single pour moves.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ruleset {
+    /// Pour as much of the top colour as there is space for.
+    #[default]
+    Standard,
+    /// Every pour moves exactly one segment, as in some ball sort games.
+    OneUnit,
+    /// A pour must move the whole top block, so it is illegal if there is not enough space.
+    NoSplit,
+}
+
+impl Ruleset {
+    /// Returns the quantity a pour of the top block would move, given how many segments of the
+    /// top colour there are and how much space there is in the destination tube. Returns None if
+    /// the pour is not allowed at all.
+    pub fn pour_quantity(&self, block_size: usize, space: usize) -> Option<usize> {
+        let quantity = match self {
+            Ruleset::Standard => min(block_size, space),
+            Ruleset::OneUnit => min(1, min(block_size, space)),
+            Ruleset::NoSplit if block_size <= space => block_size,
+            Ruleset::NoSplit => 0,
+        };
+        match quantity {
+            0 => None,
+            quantity => Some(quantity),
+        }
+    }
+
+    pub fn is_valid_quantity(&self, quantity: usize, block_size: usize) -> bool {
+        match self {
+            Ruleset::Standard => quantity > 0,
+            Ruleset::OneUnit => quantity == 1,
+            Ruleset::NoSplit => quantity == block_size,
+        }
+    }
+}
+
+/// How safe a move is, judged by looking a few moves ahead.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveSafety {
+    /// The move reduces the number of blocks, or the game can be completed soon afterwards.
+    Safe,
+    /// Nothing obviously good or bad could be found within the lookahead.
+    Risky,
+    /// Every line after the move gets stuck, so the game can no longer be completed.
+    Losing,
+}
+
+enum Lookahead {
+    Completes,
+    DeadEnd,
+    Unknown,
+}
+
+impl Display for MoveSafety {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MoveSafety::Safe => "safe",
+            MoveSafety::Risky => "risky",
+            MoveSafety::Losing => "losing",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Ruleset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "standard" => Ok(Ruleset::Standard),
+            "one-unit" => Ok(Ruleset::OneUnit),
+            "no-split" => Ok(Ruleset::NoSplit),
+            other => Err(format!(
+                "Unknown ruleset '{}'. Expected one of: standard, one-unit, no-split",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for Ruleset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Ruleset::Standard => "standard",
+            Ruleset::OneUnit => "one-unit",
+            Ruleset::NoSplit => "no-split",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 impl Game {
@@ -28,12 +195,182 @@ impl Game {
 
     pub fn init_tube_contents(&mut self, tube_num: usize, contents: String) {
         self.tubes[tube_num] = Tube::from_string(contents, tube_num);
-        let colours: HashSet<String> = self.tubes[tube_num]
-            .contents
+        self.refresh_colours();
+    }
+
+    /// Sets one segment of a tube, counted from the top, to a colour or to empty.
+    pub fn set_segment(&mut self, tube_num: usize, segment: usize, colour: Option<String>) {
+        self.tubes[tube_num].contents[segment] = colour;
+        self.refresh_colours();
+    }
+
+    /// Inserts a new tube at the given position, moving the tubes after it along by one.
+    pub fn insert_tube(&mut self, tube_num: usize, contents: String) {
+        self.tubes
+            .insert(tube_num, Tube::from_string(contents, tube_num));
+        self.renumber_tubes();
+        self.refresh_colours();
+    }
+
+    /// Removes the tube at the given position, moving the tubes after it back by one.
+    pub fn remove_tube(&mut self, tube_num: usize) {
+        self.tubes.remove(tube_num);
+        self.renumber_tubes();
+        self.refresh_colours();
+    }
+
+    /// Puts the tubes in a new order, where `order[new_position]` is the current position of the
+    /// tube to move there. The moves made so far and the forbidden pours are renumbered to match.
+    pub fn permute_tubes(&mut self, order: &[usize]) -> Result<(), String> {
+        let mut sorted = order.to_vec();
+        sorted.sort();
+        if sorted != (0..self.tubes.len()).collect::<Vec<usize>>() {
+            return Err(format!(
+                "A new order must list every tube from 1 to {} exactly once",
+                self.tubes.len()
+            ));
+        }
+        let mut position = vec![0; order.len()];
+        for (new_idx, old_idx) in order.iter().enumerate() {
+            position[*old_idx] = new_idx;
+        }
+        self.tubes = order.iter().map(|idx| self.tubes[*idx].clone()).collect();
+        self.renumber_tubes();
+        for a_move in self.moves.values_mut() {
+            a_move.tube_from = position[a_move.tube_from];
+            a_move.tube_to = position[a_move.tube_to];
+        }
+        for (from, to) in self.forbidden_pours.iter_mut() {
+            *from = position[*from];
+            *to = position[*to];
+        }
+        Ok(())
+    }
+
+    /// Renames a colour everywhere it appears: in the tubes, the moves made so far and the colours
+    /// of the game. The new name can not already be in the game, as that would merge two colours.
+    pub fn rename_colour(&mut self, old: &str, new: &str) -> Result<(), String> {
+        if !self.colours.contains(old) {
+            return Err(format!("There is no {} in this game", old));
+        }
+        if new.is_empty() || new == "empty" {
+            return Err(format!("'{}' can not be used as a colour", new));
+        }
+        if self.colours.contains(new) {
+            return Err(format!("There is already {} in this game", new));
+        }
+        self.relabel_colours(&HashMap::from([(old.to_string(), new.to_string())]))
+    }
+
+    /// Renames several colours at once, each `map` key becoming its value, so colours can be
+    /// swapped or the whole board recoloured. Colours not in the map keep their names. Every key
+    /// must be a colour of the game, and no two colours can end up with the same name.
+    pub fn relabel_colours(&mut self, map: &HashMap<String, String>) -> Result<(), String> {
+        for (old, new) in map.iter().sorted() {
+            if !self.colours.contains(old) {
+                return Err(format!("There is no {} in this game", old));
+            }
+            if new.is_empty() || new == "empty" {
+                return Err(format!("'{}' can not be used as a colour", new));
+            }
+        }
+        let relabel = |colour: &String| map.get(colour).unwrap_or(colour).clone();
+        let mut relabelled: HashMap<String, &String> = HashMap::new();
+        for colour in self.colours.iter().sorted() {
+            if let Some(other) = relabelled.insert(relabel(colour), colour) {
+                return Err(format!(
+                    "{} and {} would both become {}",
+                    other,
+                    colour,
+                    relabel(colour)
+                ));
+            }
+        }
+        for colour in self
+            .tubes
+            .iter_mut()
+            .flat_map(|tube| tube.contents.iter_mut().flatten())
+        {
+            *colour = relabel(colour);
+        }
+        for a_move in self.moves.values_mut() {
+            a_move.colour = relabel(&a_move.colour);
+        }
+        self.refresh_colours();
+        Ok(())
+    }
+
+    fn renumber_tubes(&mut self) {
+        for (idx, tube) in self.tubes.iter_mut().enumerate() {
+            tube.tube_number = idx;
+        }
+    }
+
+    // Rebuild the colours from every tube, so re-entering or removing a tube does not leave
+    // behind the colours it used to contain.
+    fn refresh_colours(&mut self) {
+        self.colours = self
+            .tubes
             .iter()
-            .filter_map(|x| x.clone())
+            .flat_map(|tube| tube.contents.iter().filter_map(|x| x.clone()))
             .collect();
-        self.colours.extend(colours);
+    }
+
+    /// Returns every pair of non-empty tubes with exactly the same contents, which during setup
+    /// usually means a tube was entered twice by mistake.
+    pub fn find_identical_tubes(&self) -> Vec<(usize, usize)> {
+        let mut identical = Vec::new();
+        for (first_idx, first) in self.tubes.iter().enumerate() {
+            if first.contents.iter().all(|x| x.is_none()) {
+                continue;
+            }
+            for (second_idx, second) in self.tubes.iter().enumerate().skip(first_idx + 1) {
+                if first.contents == second.contents {
+                    identical.push((first_idx, second_idx));
+                }
+            }
+        }
+        identical
+    }
+
+    /// Looks for states that no real game could be in, as found in files edited by hand: a tube
+    /// holding more than `TUBE_SIZE` segments, a colour with an empty segment below it (liquid
+    /// settles to the bottom, so the empty segments of a tube are always at the top), and a colour
+    /// whose segments could not fill a whole number of tubes. Returns a description of each
+    /// problem, so an empty list means the board is possible.
+    pub fn check_integrity(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut colour_counts: HashMap<&String, usize> = HashMap::new();
+        for tube in self.tubes.iter() {
+            if tube.contents.len() > TUBE_SIZE {
+                problems.push(format!(
+                    "tube {} has {} segments, a tube holds {}",
+                    tube.tube_number + 1,
+                    tube.contents.len(),
+                    TUBE_SIZE
+                ));
+            }
+            if tube.has_gaps() {
+                let floating = tube.contents.iter().flatten().next().unwrap();
+                problems.push(format!(
+                    "tube {} has {} floating above an empty segment",
+                    tube.tube_number + 1,
+                    floating
+                ));
+            }
+            for colour in tube.contents.iter().flatten() {
+                *colour_counts.entry(colour).or_insert(0) += 1;
+            }
+        }
+        for (colour, count) in colour_counts.into_iter().sorted() {
+            if !count.is_multiple_of(TUBE_SIZE) {
+                problems.push(format!(
+                    "colour {} has {} segments, which is not a multiple of {}",
+                    colour, count, TUBE_SIZE
+                ));
+            }
+        }
+        problems
     }
 
     pub fn validate_setup(&self) -> bool {
@@ -59,11 +396,155 @@ impl Game {
     }
 
     pub fn validate_move(&self, a_move: &Move) -> bool {
+        if self.is_pour_forbidden(a_move.tube_from, a_move.tube_to) {
+            return false;
+        }
         let from_tube = &self.tubes[a_move.tube_from];
+        let block_size = match from_tube.get_top_colour() {
+            Some(top_colour) => top_colour.block_size,
+            None => return false,
+        };
+        if !self.ruleset.is_valid_quantity(a_move.quantity, block_size) {
+            return false;
+        }
         let to_tube = &self.tubes[a_move.tube_to];
         from_tube.is_valid_move_from(a_move) && to_tube.is_valid_move_to(a_move)
     }
 
+    /// Turns a move the player asked for into a move on this board: the colour is the one on top
+    /// of the tube poured from, and a quantity larger than can be poured is reduced to as much as
+    /// can. This is the same for every frontend, so only the parsing of input differs between
+    /// them.
+    pub fn resolve(&self, request: MoveRequest) -> Result<Move, MoveError> {
+        for tube in [request.tube_from, request.tube_to] {
+            if tube >= self.tubes.len() {
+                return Err(MoveError::NoSuchTube(tube));
+            }
+        }
+        if request.tube_from == request.tube_to {
+            return Err(MoveError::SameTube);
+        }
+        if self.is_pour_forbidden(request.tube_from, request.tube_to) {
+            return Err(MoveError::ForbiddenPour(request.tube_from, request.tube_to));
+        }
+        let top = self.tubes[request.tube_from]
+            .get_top_colour()
+            .ok_or(MoveError::EmptyTube(request.tube_from))?;
+        if let Some(colour) = request.colour {
+            if colour != top.colour {
+                return Err(MoveError::NotOnTop(colour, request.tube_from));
+            }
+        }
+        let to_tube = &self.tubes[request.tube_to];
+        let space = to_tube
+            .contents
+            .iter()
+            .filter(|cell| cell.is_none())
+            .count();
+        if space == 0 {
+            return Err(MoveError::FullTube(request.tube_to));
+        }
+        if let Some(to_top) = to_tube.get_top_colour() {
+            if to_top.colour != top.colour {
+                return Err(MoveError::WrongColour(request.tube_to));
+            }
+        }
+        let most = self
+            .ruleset
+            .pour_quantity(top.block_size, space)
+            .ok_or(MoveError::Quantity(self.ruleset))?;
+        let a_move = Move {
+            tube_from: request.tube_from,
+            tube_to: request.tube_to,
+            colour: top.colour,
+            quantity: min(request.quantity, most),
+            played_at: None,
+        };
+        match self.validate_move(&a_move) {
+            true => Ok(a_move),
+            false => Err(MoveError::Quantity(self.ruleset)),
+        }
+    }
+
+    /// Resolves moves to be made one after another, each on the board the ones before it leave.
+    /// If any of them cannot be made, the error says which, counting from 0, and the board is
+    /// left as it is.
+    pub fn resolve_sequence(
+        &self,
+        requests: &[MoveRequest],
+    ) -> Result<Vec<Move>, (usize, MoveError)> {
+        let mut state = self.clone();
+        let mut moves = Vec::with_capacity(requests.len());
+        for (idx, request) in requests.iter().enumerate() {
+            let a_move = state.resolve(request.clone()).map_err(|err| (idx, err))?;
+            state.make_move(&a_move);
+            moves.push(a_move);
+        }
+        Ok(moves)
+    }
+
+    pub fn is_pour_forbidden(&self, tube_from: usize, tube_to: usize) -> bool {
+        self.forbidden_pours.contains(&(tube_from, tube_to))
+    }
+
+    /// Whether the game can not go on: it is not complete, and no pour at all can be made, counting
+    /// the pours the solver never tries, such as a tube of one colour into an empty tube.
+    pub fn is_stuck(&self) -> bool {
+        if self.is_game_complete() {
+            return false;
+        }
+        let tubes = 0..self.tubes.len();
+        !tubes
+            .clone()
+            .cartesian_product(tubes)
+            .filter(|(from, to)| from != to)
+            .any(|(tube_from, tube_to)| {
+                self.resolve(MoveRequest {
+                    tube_from,
+                    tube_to,
+                    quantity: TUBE_SIZE,
+                    colour: None,
+                })
+                .is_ok()
+            })
+    }
+
+    /// Whether a tube is the one poured from or into in any forbidden pour.
+    pub fn is_in_forbidden_pour(&self, idx: usize) -> bool {
+        self.forbidden_pours
+            .iter()
+            .any(|(from, to)| *from == idx || *to == idx)
+    }
+
+    /// Whether pouring from `tube_from` into `tube_to` would only move a tube that holds a single
+    /// colour into an empty tube. The board is the same afterwards apart from which tube is empty,
+    /// so such a pour never makes progress. A tube in a forbidden pour is not interchangeable with
+    /// the others, so a pour from or into one can always make progress.
+    pub fn is_null_progress(&self, tube_from: usize, tube_to: usize) -> bool {
+        if self.is_in_forbidden_pour(tube_from) || self.is_in_forbidden_pour(tube_to) {
+            return false;
+        }
+        let from = &self.tubes[tube_from];
+        let holds_one_block = match from.get_top_colour() {
+            Some(top) => top.pos + top.block_size == from.contents.len(),
+            None => false,
+        };
+        holds_one_block && self.tubes[tube_to].get_top_colour().is_none()
+    }
+
+    /// Lists the forbidden pours, numbered as they are displayed, or None if every pour is allowed.
+    pub fn forbidden_pours_string(&self) -> Option<String> {
+        if self.forbidden_pours.is_empty() {
+            return None;
+        }
+        let pours: Vec<String> = self
+            .forbidden_pours
+            .iter()
+            .map(|(from, to)| format!("{} -> {}", from + 1, to + 1))
+            .collect();
+        Some(format!("Forbidden pours: {}", pours.join(", ")))
+    }
+
     pub fn make_move(&mut self, a_move: &Move) {
         if !self.validate_move(a_move) {
             return;
@@ -72,6 +553,154 @@ impl Game {
         self.tubes[a_move.tube_to].pour_to(a_move);
         self.current_move += 1;
         self.moves.insert(self.current_move, a_move.clone());
+        if let Some(limit) = self.history_limit {
+            // Only the move that has just fallen out of the history can be left over it
+            self.moves.remove(&self.current_move.saturating_sub(limit));
+        }
+    }
+
+    /// Takes back the last move made, pouring the liquid back where it came from, and returns it.
+    /// Returns None if no move has been made or the last one is no longer in the history.
+    pub fn undo_move(&mut self) -> Option<Move> {
+        let a_move = self.moves.remove(&self.current_move)?;
+        let back = Move {
+            tube_from: a_move.tube_to,
+            tube_to: a_move.tube_from,
+            ..a_move.clone()
+        };
+        self.tubes[back.tube_from].pour_from(&back);
+        self.tubes[back.tube_to].pour_to(&back);
+        self.current_move -= 1;
+        Some(a_move)
+    }
+
+    /// Whether every move made since the game was set up is still in `moves`.
+    pub fn has_full_history(&self) -> bool {
+        self.moves.len() == self.current_move
+    }
+
+    /// Returns how many hints are left, or None if hints are not limited.
+    pub fn hints_remaining(&self) -> Option<usize> {
+        self.hint_limit
+            .map(|limit| limit.saturating_sub(self.stats.hints_used))
+    }
+
+    /// Records that a hint has been given. Returns false, without recording anything, if the hint
+    /// limit has already been reached.
+    pub fn use_hint(&mut self) -> bool {
+        if self.hints_remaining() == Some(0) {
+            return false;
+        }
+        self.stats.hints_used += 1;
+        true
+    }
+
+    /// Returns a merge onto a tube of the same colour that could be made instead of the given move,
+    /// if the given move pours into an empty tube. Filling an empty tube while a merge is available
+    /// usually wastes the space.
+    pub fn find_merge_instead(&self, a_move: &Move) -> Option<Move> {
+        if self.tubes[a_move.tube_to].get_top_colour().is_some() {
+            return None;
+        }
+        Solver::new(self)
+            .get_legal_moves()
+            .into_iter()
+            .find(|possible_move| {
+                possible_move.tube_from == a_move.tube_from
+                    && self.tubes[possible_move.tube_to].get_top_colour().is_some()
+            })
+    }
+
+    /// Classifies a move by looking a few moves ahead of the position it leads to. Invalid moves
+    /// are classified as losing, as they cannot be played at all.
+    pub fn classify_move(&self, a_move: &Move) -> MoveSafety {
+        if !self.validate_move(a_move) {
+            return MoveSafety::Losing;
+        }
+        let mut after = self.clone();
+        after.make_move(a_move);
+        let mut path = Vec::new();
+        match after.lookahead(SAFETY_LOOKAHEAD, &mut path) {
+            Lookahead::Completes => MoveSafety::Safe,
+            Lookahead::DeadEnd => MoveSafety::Losing,
+            Lookahead::Unknown if after.get_number_of_blocks() < self.get_number_of_blocks() => {
+                MoveSafety::Safe
+            }
+            Lookahead::Unknown => MoveSafety::Risky,
+        }
+    }
+
+    /// Searches up to `depth` moves ahead. `path` holds the states on the way to this one, so that
+    /// lines going round in circles count as dead ends.
+    fn lookahead(&self, depth: usize, path: &mut Vec<Vec<Vec<Option<String>>>>) -> Lookahead {
+        if self.is_game_complete() {
+            return Lookahead::Completes;
+        }
+        let key = self.state_key();
+        if path.contains(&key) {
+            return Lookahead::DeadEnd;
+        }
+        let possible_moves = Solver::new(self).get_legal_moves();
+        if possible_moves.is_empty() {
+            return Lookahead::DeadEnd;
+        }
+        if depth == 0 {
+            return Lookahead::Unknown;
+        }
+        path.push(key);
+        let mut result = Lookahead::DeadEnd;
+        for possible_move in possible_moves.iter() {
+            let mut next = self.clone();
+            next.make_move(possible_move);
+            match next.lookahead(depth - 1, path) {
+                Lookahead::Completes => {
+                    result = Lookahead::Completes;
+                    break;
+                }
+                Lookahead::Unknown => result = Lookahead::Unknown,
+                Lookahead::DeadEnd => {}
+            }
+        }
+        path.pop();
+        result
+    }
+
+    /// Returns an iterator over the distinct states that can be reached from this one in at most
+    /// `limit` moves, nearest first. The current state itself is not included, and every state
+    /// keeps the moves that lead to it.
+    pub fn reachable_states(&self, limit: usize) -> ReachableStates {
+        ReachableStates {
+            queue: VecDeque::from([(self.clone(), 0)]),
+            seen: HashSet::from([self.state_key()]),
+            limit,
+            pruned: 0,
+        }
+    }
+
+    /// The contents of every tube, which identifies the state of the game.
+    pub fn state_key(&self) -> Vec<Vec<Option<String>>> {
+        self.tubes
+            .iter()
+            .map(|tube| tube.contents.clone())
+            .collect()
+    }
+
+    /// Like `state_key`, but the same for every state that differs only in the order of its
+    /// tubes, which are the same state for solving. A tube in a forbidden pour is set apart by the
+    /// pour, so those tubes keep their order, before the rest sorted by their contents. The key
+    /// is only for comparing states, as its tubes are no longer numbered as in the game.
+    pub fn canonical_key(&self) -> Vec<Vec<Option<String>>> {
+        let (fixed, mut free): (Vec<_>, Vec<_>) = self
+            .tubes
+            .iter()
+            .enumerate()
+            .partition(|(idx, _)| self.is_in_forbidden_pour(*idx));
+        free.sort_by(|(_, first), (_, second)| first.contents.cmp(&second.contents));
+        fixed
+            .into_iter()
+            .chain(free)
+            .map(|(_, tube)| tube.contents.clone())
+            .collect()
     }
 
     pub fn is_game_complete(&self) -> bool {
@@ -80,14 +709,92 @@ impl Game {
             .all(|tube| tube.is_tube_all_same_contents())
     }
 
+    /// Lists the moves in order. Moves played interactively also show when they were made and
+    /// how long they took.
     pub fn get_all_moves_string(&self) -> String {
-        let mut all_moves = String::new();
+        self.moves_string(false)
+    }
+
+    /// Lists the moves like `get_all_moves_string`, but with consecutive pours between the same
+    /// two tubes on one line, numbered by the moves it holds, such as `3-5 : (1 -> 2: red x 3)`.
+    /// The moves themselves are not changed, so each can still be undone on its own.
+    pub fn get_merged_moves_string(&self) -> String {
+        self.moves_string(true)
+    }
+
+    fn moves_string(&self, merge: bool) -> String {
+        let taken: HashMap<usize, Duration> = self.move_times().into_iter().collect();
+        // The first and last move numbers of each line, its move and how long its moves took
+        let mut lines: Vec<(usize, usize, Move, Option<Duration>)> = Vec::new();
         for (move_num, a_move) in self.moves.iter().sorted_by_key(|x| x.0) {
-            all_moves.push_str(format!("{} : ({})\n", move_num, a_move).as_str());
+            let took = taken.get(move_num).copied();
+            match lines.last_mut() {
+                Some((_, last, line_move, line_took))
+                    if merge && *last + 1 == *move_num && line_move.continues(a_move) =>
+                {
+                    *last = *move_num;
+                    line_move.quantity += a_move.quantity;
+                    line_move.played_at = a_move.played_at;
+                    *line_took = line_took.zip(took).map(|(before, took)| before + took);
+                }
+                _ => lines.push((*move_num, *move_num, a_move.clone(), took)),
+            }
+        }
+        let mut all_moves = String::new();
+        for (first, last, a_move, took) in lines {
+            let numbers = match first == last {
+                true => first.to_string(),
+                false => format!("{}-{}", first, last),
+            };
+            let line = match (a_move.played_at, took) {
+                (Some(played_at), Some(took)) => format!(
+                    "{} : ({}) at {}, took {}\n",
+                    numbers,
+                    a_move,
+                    format_duration(played_at),
+                    format_duration(took)
+                ),
+                _ => format!("{} : ({})\n", numbers, a_move),
+            };
+            all_moves.push_str(line.as_str());
         }
         all_moves
     }
 
+    /// How long the player took over each move, by move number, counted from the move before or
+    /// from the start of play. Moves without a timestamp, or whose move before is no longer in
+    /// the history, are left out.
+    pub fn move_times(&self) -> Vec<(usize, Duration)> {
+        let mut previous = Some((0, Duration::ZERO));
+        let mut times = Vec::new();
+        for (move_num, a_move) in self.moves.iter().sorted_by_key(|x| x.0) {
+            if let (Some(played_at), Some((previous_num, previous_at))) =
+                (a_move.played_at, previous)
+            {
+                if *move_num == previous_num + 1 {
+                    times.push((*move_num, played_at.saturating_sub(previous_at)));
+                }
+            }
+            previous = a_move.played_at.map(|played_at| (*move_num, played_at));
+        }
+        times
+    }
+
+    /// Sums up how quickly the moves were played, or None if none of them have a timestamp.
+    pub fn pace_string(&self) -> Option<String> {
+        let times = self.move_times();
+        let (slowest, longest) = times
+            .iter()
+            .max_by_key(|(move_num, took)| (*took, Reverse(*move_num)))?;
+        let total: Duration = times.iter().map(|(_, took)| *took).sum();
+        Some(format!(
+            "Pace: {} a move on average. The slowest was move {}, which took {}",
+            format_duration(total / times.len() as u32),
+            slowest,
+            format_duration(*longest)
+        ))
+    }
+
     pub fn print_colour(&self, requested_colour: &str) -> String {
         let mut requested_colour = requested_colour.to_string();
         match self.colours.contains(&requested_colour) {
@@ -146,12 +853,107 @@ impl Display for Game {
     }
 }
 
+/// A breadth first walk over the states reachable from a game. See `Game::reachable_states`.
+pub struct ReachableStates {
+    queue: VecDeque<(Game, usize)>,
+    seen: HashSet<Vec<Vec<Option<String>>>>,
+    limit: usize,
+    pruned: usize,
+}
+
+impl ReachableStates {
+    /// How many moves so far have led to a state that was already seen, and so were not followed.
+    pub fn pruned(&self) -> usize {
+        self.pruned
+    }
+}
+
+impl Iterator for ReachableStates {
+    type Item = Game;
+
+    fn next(&mut self) -> Option<Game> {
+        while let Some((game, depth)) = self.queue.pop_front() {
+            if depth < self.limit {
+                for possible_move in Solver::new(&game).get_legal_moves() {
+                    let mut next = game.clone();
+                    next.make_move(&possible_move);
+                    match self.seen.insert(next.state_key()) {
+                        true => self.queue.push_back((next, depth + 1)),
+                        false => self.pruned += 1,
+                    }
+                }
+            }
+            // Only the starting state is at depth 0, and it is not one of the reachable states.
+            if depth > 0 {
+                return Some(game);
+            }
+        }
+        None
+    }
+}
+
 #[derive(Clone)]
 pub struct Move {
     pub tube_from: usize,
     pub tube_to: usize,
     pub colour: String,
     pub quantity: usize,
+    /// How long after the start of play the move was made. Only moves played interactively have
+    /// one, not those found by the solver.
+    pub played_at: Option<Duration>,
+}
+
+impl Move {
+    /// Whether `next` pours more of the same colour between the same two tubes, so that the two
+    /// can be shown as one move.
+    pub fn continues(&self, next: &Move) -> bool {
+        self.tube_from == next.tube_from
+            && self.tube_to == next.tube_to
+            && self.colour == next.colour
+    }
+}
+
+/// Merges every run of consecutive pours between the same two tubes into one move of their total
+/// quantity, made when the last of them was, such as a colour poured one unit at a time under the
+/// one-unit rules.
+pub fn merge_pours(moves: &[Move]) -> Vec<Move> {
+    merge_pour_runs(moves)
+        .into_iter()
+        .map(|(a_move, _)| a_move)
+        .collect()
+}
+
+/// Merges pours as `merge_pours` does, with the number of pours each merged move is made of.
+fn merge_pour_runs(moves: &[Move]) -> Vec<(Move, usize)> {
+    let mut merged: Vec<(Move, usize)> = Vec::with_capacity(moves.len());
+    for a_move in moves {
+        match merged.last_mut() {
+            Some((last, pours)) if last.continues(a_move) => {
+                last.quantity += a_move.quantity;
+                last.played_at = a_move.played_at;
+                *pours += 1;
+            }
+            _ => merged.push((a_move.clone(), 1)),
+        }
+    }
+    merged
+}
+
+/// Lists moves one to a line, numbered from 1, with every run of pours between the same two
+/// tubes merged into one line numbered by the moves it holds, such as `  1-3: 1 -> 3: red x 3`,
+/// as a solution is shown.
+pub fn numbered_moves_string(moves: &[Move]) -> String {
+    let mut lines = String::new();
+    let mut first = 1;
+    for (a_move, pours) in merge_pour_runs(moves) {
+        let numbers = match pours {
+            1 => first.to_string(),
+            pours => format!("{}-{}", first, first + pours - 1),
+        };
+        lines.push_str(&format!("{:>3}: {}\n", numbers, a_move));
+        first += pours;
+    }
+    lines
 }
 
 impl Display for Move {
@@ -168,34 +970,146 @@ impl Display for Move {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// A move as the player asked for it, before it is checked against the board. Tubes are numbered
+/// from 0. Parse one from the `<tube_from> <tube_to> <quantity>` form, the `<tube_from>>
+/// <tube_to>` form that pours as much as can be poured, or the `<tube_from> <tube_to> <colour>`
+/// form that pours as much of that colour as can be poured, with tubes numbered from 1 as they
+/// are displayed, and turn it into a `Move` with `Game::resolve`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct MoveRequest {
+    pub tube_from: usize,
+    pub tube_to: usize,
+    pub quantity: usize,
+    /// The colour that must be on top of `tube_from`, when the move was asked for by colour.
+    pub colour: Option<String>,
+}
 
-    #[test]
-    fn test_init_game() {
-        let mut game = Game::default();
-        game.init_tubes(4);
-        game.init_tube_contents(0, String::from("red, blue, green, red"));
-        game.init_tube_contents(1, String::from("green, blue, red, purple"));
+impl FromStr for MoveRequest {
+    type Err = String;
 
-        let expected = Game {
-            tubes: vec![
-                Tube::from_string(String::from("red, blue, green, red"), 0),
-                Tube::from_string(String::from("green, blue, red, purple"), 1),
-                Tube::from_string_vec(vec![None; 4], 2),
-                Tube::from_string_vec(vec![None; 4], 3),
-            ],
-            moves: HashMap::new(),
-            current_move: 0,
-            colours: HashSet::from([
-                "red".to_string(),
-                "green".to_string(),
-                "blue".to_string(),
-                "purple".to_string(),
-            ]),
-        };
-        test_all_tubes(&game.tubes, &expected.tubes);
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // "<tube_from>><tube_to>" pours as much as it can, which resolve reduces to what fits
+        if let Some((tube_from, tube_to)) = s.split_once('>') {
+            return format!("{} {} {}", tube_from.trim(), tube_to.trim(), usize::MAX).parse();
+        }
+        // A move string should be of the format "<tube_from> <tube_to> <quantity>" (i.e. space delimited)
+        let string_parts: Vec<&str> = s.trim().split(' ').collect();
+        if string_parts.len() != 3 {
+            return Err(
+                "Move must be in the format\"<tube_from> <tube_to> <quantity>\"".to_string(),
+            );
+        }
+        let tube_from = match string_parts[0].parse::<usize>() {
+            Ok(entry) if entry > 0 => entry - 1,
+            _ => return Err("Expected a tube number for the 'from tube' value".to_string()),
+        };
+        let tube_to = match string_parts[1].parse::<usize>() {
+            Ok(entry) if entry > 0 => entry - 1,
+            _ => return Err("Expected a tube number for the 'to tube' value".to_string()),
+        };
+        // Players often think in colours rather than counts, so "3 5 red" pours all the red
+        let (quantity, colour) = match string_parts[2].parse::<usize>() {
+            Ok(entry) if entry > 0 => (entry, None),
+            _ if string_parts[2].starts_with(|c: char| c.is_alphabetic() || c == '#') => {
+                (usize::MAX, Some(normalise_colour(string_parts[2])))
+            }
+            _ => {
+                return Err(
+                    "Expected a positive integer or a colour for the 'quantity' value".to_string(),
+                )
+            }
+        };
+        Ok(MoveRequest {
+            tube_from,
+            tube_to,
+            quantity,
+            colour,
+        })
+    }
+}
+
+/// Parses moves separated by commas, such as `1>3, 3>5`, to be made one after another. The error
+/// says which move could not be parsed, counting from 1.
+pub fn parse_move_sequence(s: &str) -> Result<Vec<MoveRequest>, String> {
+    s.split(',')
+        .enumerate()
+        .map(|(idx, part)| {
+            part.parse::<MoveRequest>()
+                .map_err(|err| format!("move {}: {}", idx + 1, err))
+        })
+        .collect()
+}
+
+/// Why a `MoveRequest` cannot be made on the board. Tubes are numbered from 0.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MoveError {
+    NoSuchTube(usize),
+    SameTube,
+    EmptyTube(usize),
+    ForbiddenPour(usize, usize),
+    FullTube(usize),
+    /// The colour on top of the destination tube is not the colour being poured.
+    WrongColour(usize),
+    /// The colour the move was asked for by is not on top of the source tube.
+    NotOnTop(String, usize),
+    /// The ruleset does not allow a pour of the quantity asked for.
+    Quantity(Ruleset),
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::NoSuchTube(tube) => write!(f, "there is no tube {}", tube + 1),
+            MoveError::SameTube => write!(f, "a tube cannot pour into itself"),
+            MoveError::EmptyTube(tube) => write!(f, "tube {} is empty", tube + 1),
+            MoveError::ForbiddenPour(from, to) => {
+                write!(f, "tube {} may never pour into tube {}", from + 1, to + 1)
+            }
+            MoveError::FullTube(tube) => write!(f, "tube {} is full", tube + 1),
+            MoveError::WrongColour(tube) => {
+                write!(f, "the colour on top of tube {} does not match", tube + 1)
+            }
+            MoveError::NotOnTop(colour, tube) => {
+                write!(f, "{} is not on top of tube {}", colour, tube + 1)
+            }
+            MoveError::Quantity(ruleset) => {
+                write!(f, "the {} rules do not allow that quantity", ruleset)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{assert_move_eq, assert_tubes_eq};
+
+    use super::*;
+
+    #[test]
+    fn test_init_game() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, blue, green, red"));
+        game.init_tube_contents(1, String::from("green, blue, red, purple"));
+
+        let expected = Game {
+            tubes: vec![
+                Tube::from_string(String::from("red, blue, green, red"), 0),
+                Tube::from_string(String::from("green, blue, red, purple"), 1),
+                Tube::from_string_vec(vec![None; 4], 2),
+                Tube::from_string_vec(vec![None; 4], 3),
+            ],
+            moves: HashMap::new(),
+            current_move: 0,
+            colours: HashSet::from([
+                "red".to_string(),
+                "green".to_string(),
+                "blue".to_string(),
+                "purple".to_string(),
+            ]),
+            ..Default::default()
+        };
+        assert_tubes_eq(&game.tubes, &expected.tubes);
         assert_eq!(
             game.current_move, 0,
             "current move wrong value. Expected = {}, got = {}",
@@ -209,6 +1123,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reinit_tube_contents() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, blue, blue"));
+        game.init_tube_contents(1, String::from("blue, blue, rde, red"));
+        game.init_tube_contents(1, String::from("blue, blue, red, red"));
+        let expected = HashSet::from(["red".to_string(), "blue".to_string()]);
+        assert_eq!(
+            game.colours, expected,
+            "Colours hashset is not the same. Expected = {:?}, got = {:?}",
+            expected, game.colours
+        );
+        assert!(game.validate_setup(), "re-entered setup should be valid");
+    }
+
+    #[test]
+    fn test_insert_and_remove_tube() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, blue, blue"));
+        game.init_tube_contents(1, String::from("green, green"));
+        game.insert_tube(1, String::from("blue, blue, red, red"));
+        let expected = vec![
+            Tube::from_string(String::from("red, red, blue, blue"), 0),
+            Tube::from_string(String::from("blue, blue, red, red"), 1),
+            Tube::from_string(String::from("green, green"), 2),
+            Tube::from_string(String::from(""), 3),
+            Tube::from_string(String::from(""), 4),
+        ];
+        assert_tubes_eq(&game.tubes, &expected);
+
+        game.remove_tube(2);
+        let expected = vec![
+            Tube::from_string(String::from("red, red, blue, blue"), 0),
+            Tube::from_string(String::from("blue, blue, red, red"), 1),
+            Tube::from_string(String::from(""), 2),
+            Tube::from_string(String::from(""), 3),
+        ];
+        assert_tubes_eq(&game.tubes, &expected);
+        let expected_colours = HashSet::from(["red".to_string(), "blue".to_string()]);
+        assert_eq!(
+            game.colours, expected_colours,
+            "Colours hashset is not the same. Expected = {:?}, got = {:?}",
+            expected_colours, game.colours
+        );
+    }
+
+    #[test]
+    fn test_find_identical_tubes() {
+        let num_of_tubes: usize = 6;
+        let tests = [
+            (
+                vec![
+                    String::from("red, blue, green, red"),
+                    String::from("blue, red, green, blue"),
+                    String::from("green, red, blue, green"),
+                ],
+                Vec::new(),
+            ),
+            (
+                vec![
+                    String::from("red, blue, green, red"),
+                    String::from("blue, red, green, blue"),
+                    String::from("red, blue, green, red"),
+                ],
+                vec![(0, 2)],
+            ),
+            (
+                vec![
+                    String::from("red, blue"),
+                    String::from("red, blue"),
+                    String::from("red, blue"),
+                    String::from("green"),
+                ],
+                vec![(0, 1), (0, 2), (1, 2)],
+            ),
+        ];
+        for test in tests {
+            let mut game = Game::default();
+            game.init_tubes(num_of_tubes);
+            for (idx, init_tube) in test.0.into_iter().enumerate() {
+                game.init_tube_contents(idx, init_tube);
+            }
+            let result = game.find_identical_tubes();
+            assert_eq!(
+                result, test.1,
+                "wrong identical tubes found. Expected: {:?}, got: {:?}",
+                test.1, result
+            );
+        }
+    }
+
     #[test]
     fn test_post_setup_validation() {
         let num_of_tubes: usize = 4;
@@ -282,6 +1289,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 false,
             ),
@@ -296,6 +1304,7 @@ mod tests {
                     tube_to: 2,
                     colour: "blue".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 true,
             ),
@@ -310,6 +1319,7 @@ mod tests {
                     tube_to: 2,
                     colour: "blue".to_string(),
                     quantity: 3,
+                    played_at: None,
                 },
                 false,
             ),
@@ -324,6 +1334,7 @@ mod tests {
                     tube_to: 2,
                     colour: "blue".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 false,
             ),
@@ -338,6 +1349,7 @@ mod tests {
                     tube_to: 3,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 true,
             ),
@@ -352,6 +1364,7 @@ mod tests {
                     tube_to: 3,
                     colour: "red".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 true,
             ),
@@ -366,6 +1379,7 @@ mod tests {
                     tube_to: 3,
                     colour: "red".to_string(),
                     quantity: 3,
+                    played_at: None,
                 },
                 false,
             ),
@@ -385,6 +1399,111 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ruleset_move_validation() {
+        let num_of_tubes: usize = 4;
+        let tubes = [
+            String::from("red, red, blue, blue"),
+            String::from("blue, blue, red, red"),
+            String::from("red, red"),
+        ];
+        let tests: Vec<(Ruleset, Move, bool)> = vec![
+            (
+                Ruleset::OneUnit,
+                Move {
+                    tube_from: 0,
+                    tube_to: 3,
+                    colour: "red".to_string(),
+                    quantity: 1,
+                    played_at: None,
+                },
+                true,
+            ),
+            (
+                Ruleset::OneUnit,
+                Move {
+                    tube_from: 0,
+                    tube_to: 3,
+                    colour: "red".to_string(),
+                    quantity: 2,
+                    played_at: None,
+                },
+                false,
+            ),
+            (
+                Ruleset::OneUnit,
+                Move {
+                    tube_from: 1,
+                    tube_to: 0,
+                    colour: "blue".to_string(),
+                    quantity: 1,
+                    played_at: None,
+                },
+                false,
+            ),
+            (
+                Ruleset::NoSplit,
+                Move {
+                    tube_from: 0,
+                    tube_to: 3,
+                    colour: "red".to_string(),
+                    quantity: 2,
+                    played_at: None,
+                },
+                true,
+            ),
+            (
+                Ruleset::NoSplit,
+                Move {
+                    tube_from: 0,
+                    tube_to: 3,
+                    colour: "red".to_string(),
+                    quantity: 1,
+                    played_at: None,
+                },
+                false,
+            ),
+            (
+                Ruleset::NoSplit,
+                Move {
+                    tube_from: 0,
+                    tube_to: 2,
+                    colour: "red".to_string(),
+                    quantity: 2,
+                    played_at: None,
+                },
+                true,
+            ),
+            (
+                Ruleset::Standard,
+                Move {
+                    tube_from: 0,
+                    tube_to: 3,
+                    colour: "red".to_string(),
+                    quantity: 1,
+                    played_at: None,
+                },
+                true,
+            ),
+        ];
+        for test in tests {
+            let mut game = Game {
+                ruleset: test.0,
+                ..Default::default()
+            };
+            game.init_tubes(num_of_tubes);
+            for (idx, init_tube) in tubes.iter().enumerate() {
+                game.init_tube_contents(idx, init_tube.clone());
+            }
+            let val_res = game.validate_move(&test.1);
+            assert_eq!(
+                val_res, test.2,
+                "{} validation incorrect for move: {}. Expected = {}, got = {}",
+                test.0, test.1, test.2, val_res
+            );
+        }
+    }
+
     #[test]
     fn test_single_move() {
         // All of these tests performed on games with 4 tubes
@@ -401,6 +1520,7 @@ mod tests {
                     tube_to: 2,
                     colour: "blue".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 Game {
                     tubes: vec![
@@ -443,6 +1563,7 @@ mod tests {
                             tube_to: 2,
                             colour: "blue".to_string(),
                             quantity: 1,
+                            played_at: None,
                         },
                     )]),
                     current_move: 1,
@@ -450,6 +1571,7 @@ mod tests {
                         .into_iter()
                         .map(|x| x.to_string())
                         .collect(),
+                    ..Default::default()
                 },
             ),
             (
@@ -463,6 +1585,7 @@ mod tests {
                     tube_to: 3,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 Game {
                     tubes: vec![
@@ -500,6 +1623,7 @@ mod tests {
                             tube_to: 3,
                             colour: "red".to_string(),
                             quantity: 1,
+                            played_at: None,
                         },
                     )]),
                     current_move: 1,
@@ -507,6 +1631,7 @@ mod tests {
                         .into_iter()
                         .map(|x| x.to_string())
                         .collect(),
+                    ..Default::default()
                 },
             ),
             (
@@ -520,6 +1645,7 @@ mod tests {
                     tube_to: 3,
                     colour: "red".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 Game {
                     tubes: vec![
@@ -562,6 +1688,7 @@ mod tests {
                             tube_to: 3,
                             colour: "red".to_string(),
                             quantity: 2,
+                            played_at: None,
                         },
                     )]),
                     current_move: 1,
@@ -569,6 +1696,7 @@ mod tests {
                         .into_iter()
                         .map(|x| x.to_string())
                         .collect(),
+                    ..Default::default()
                 },
             ),
         ];
@@ -580,7 +1708,7 @@ mod tests {
                 game.init_tube_contents(idx, init_tube);
             }
             game.make_move(&test.1);
-            test_all_tubes(&game.tubes, &test.2.tubes);
+            assert_tubes_eq(&game.tubes, &test.2.tubes);
             assert_eq!(
                 game.current_move, 1,
                 "current move not expected value. Expected = 1, got = {}",
@@ -593,7 +1721,7 @@ mod tests {
                 game.moves.len()
             );
             match game.moves.get(&1_usize) {
-                Some(move1) => test_move(move1, &test.1),
+                Some(move1) => assert_move_eq(move1, &test.1),
                 None => panic!("Did not find move 1"),
             }
         }
@@ -616,6 +1744,7 @@ mod tests {
                     moves: HashMap::new(),
                     current_move: 0,
                     colours: HashSet::from(["red".to_string()]),
+                    ..Default::default()
                 },
                 true,
             ),
@@ -633,6 +1762,7 @@ mod tests {
                     moves: HashMap::new(),
                     current_move: 0,
                     colours: HashSet::from(["red".to_string(), "blue".to_string()]),
+                    ..Default::default()
                 },
                 false,
             ),
@@ -645,6 +1775,7 @@ mod tests {
                     moves: HashMap::new(),
                     current_move: 0,
                     colours: HashSet::new(),
+                    ..Default::default()
                 },
                 true,
             ),
@@ -657,6 +1788,7 @@ mod tests {
                     moves: HashMap::new(),
                     current_move: 0,
                     colours: HashSet::from(["red".to_string()]),
+                    ..Default::default()
                 },
                 false,
             ),
@@ -685,6 +1817,7 @@ mod tests {
                     moves: HashMap::new(),
                     current_move: 0,
                     colours: HashSet::from(["red".to_string(), "blue".to_string()]),
+                    ..Default::default()
                 },
                 false,
             ),
@@ -700,52 +1833,949 @@ mod tests {
         }
     }
 
-    fn test_all_tubes(result: &[Tube], expected: &[Tube]) {
+    #[test]
+    fn test_forbidden_move_validation() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, blue, blue, blue"));
+        game.init_tube_contents(1, String::from("red, red, red, blue"));
+        game.forbidden_pours = vec![(0, 2), (2, 0)];
+        // (tube_from, tube_to, quantity, expected validity)
+        let tests = [(0, 2, 1, false), (0, 3, 1, true), (1, 2, 3, true)];
+        for test in tests {
+            let a_move = Move {
+                tube_from: test.0,
+                tube_to: test.1,
+                colour: String::from("red"),
+                quantity: test.2,
+                played_at: None,
+            };
+            let result = game.validate_move(&a_move);
+            assert_eq!(
+                result, test.3,
+                "wrong validation for move {} with pours {:?} forbidden",
+                a_move, game.forbidden_pours
+            );
+        }
         assert_eq!(
-            result.len(),
-            expected.len(),
-            "different number of tubes. Expected = {}, got = {}",
-            result.len(),
-            expected.len()
+            game.forbidden_pours_string(),
+            Some(String::from("Forbidden pours: 1 -> 3, 3 -> 1"))
         );
-        for (idx, expected_tube) in expected.iter().enumerate() {
-            test_tube(&result[idx], expected_tube);
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let board = |contents: [&str; 4], forbidden_pours: Vec<(usize, usize)>| {
+            let mut game = Game::default();
+            game.init_tubes(4);
+            for (idx, tube) in contents.iter().enumerate() {
+                game.init_tube_contents(idx, tube.to_string());
+            }
+            game.forbidden_pours = forbidden_pours;
+            game
+        };
+        // (first board, second board, forbidden pours, whether they have the same key)
+        let tests = [
+            (
+                ["red, blue", "blue, red", "", ""],
+                ["", "blue, red", "", "red, blue"],
+                vec![],
+                true,
+            ),
+            (
+                ["red, blue", "blue, red", "", ""],
+                ["blue, red", "red, blue", "", ""],
+                vec![],
+                true,
+            ),
+            (
+                ["red, blue", "blue, red", "", ""],
+                ["red, blue", "red, blue", "", ""],
+                vec![],
+                false,
+            ),
+            (
+                ["red, blue", "blue, red", "", ""],
+                ["blue, red", "red, blue", "", ""],
+                vec![(0, 2)],
+                false,
+            ),
+            (
+                ["red, blue", "blue, red", "", ""],
+                ["red, blue", "", "", "blue, red"],
+                vec![(0, 2)],
+                true,
+            ),
+        ];
+        for test in tests {
+            let first = board(test.0, test.2.clone());
+            let second = board(test.1, test.2.clone());
+            assert_eq!(
+                first.canonical_key() == second.canonical_key(),
+                test.3,
+                "wrong comparison of {:?} and {:?} with pours {:?} forbidden",
+                test.0,
+                test.1,
+                test.2
+            );
         }
     }
 
-    fn test_tube(test_result: &Tube, expected: &Tube) {
+    #[test]
+    fn test_find_merge_instead() {
+        let mut game = Game::default();
+        game.init_tubes(5);
+        game.init_tube_contents(0, String::from("red, blue, blue, blue"));
+        game.init_tube_contents(1, String::from("red, red, blue, blue"));
+        game.init_tube_contents(2, String::from("red"));
+        // (tube_from, tube_to, quantity, expected tube_to of the merge instead)
+        let tests = [
+            (0, 3, 1, Some(2)),
+            (1, 4, 2, Some(2)),
+            (0, 2, 1, None),
+            (2, 3, 1, None),
+        ];
+        for test in tests {
+            let a_move = Move {
+                tube_from: test.0,
+                tube_to: test.1,
+                colour: String::from("red"),
+                quantity: test.2,
+                played_at: None,
+            };
+            let result = game.find_merge_instead(&a_move).map(|merge| merge.tube_to);
+            assert_eq!(
+                result, test.3,
+                "wrong merge found instead of move {}. Expected: {:?}, got: {:?}",
+                a_move, test.3, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_stuck() {
+        // (tubes, forbidden pours, expected)
+        let tests = [
+            (
+                vec!["red, red, red, blue", "blue, blue, blue, red", "", ""],
+                vec![],
+                false,
+            ),
+            (
+                vec![
+                    "red, red, red, blue",
+                    "blue, blue, blue, red",
+                    "green, green, green, yellow",
+                    "yellow, yellow, yellow, green",
+                ],
+                vec![],
+                true,
+            ),
+            (
+                vec!["red, red, red, red", "blue, blue, blue, blue", "", ""],
+                vec![],
+                false,
+            ),
+            // Only forbidden pours are left
+            (
+                vec!["red, red, red, blue", "blue, blue, blue, red", "", ""],
+                vec![(0, 2), (0, 3), (1, 2), (1, 3)],
+                true,
+            ),
+            // Only a tube of one colour can be poured, into an empty tube
+            (
+                vec!["green, green", "red, red, green, green", "red, red", "", ""],
+                vec![(1, 2), (1, 3), (1, 4)],
+                false,
+            ),
+        ];
+        for test in tests {
+            let mut game = Game::default();
+            game.init_tubes(test.0.len());
+            for (idx, tube) in test.0.iter().enumerate() {
+                game.init_tube_contents(idx, tube.to_string());
+            }
+            game.forbidden_pours = test.1.clone();
+            assert_eq!(
+                game.is_stuck(),
+                test.2,
+                "wrong result for {:?} with forbidden pours {:?}",
+                test.0,
+                test.1
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_null_progress() {
+        let mut game = Game::default();
+        game.init_tubes(5);
+        game.init_tube_contents(0, String::from("red, red"));
+        game.init_tube_contents(1, String::from("blue, red, red"));
+        game.init_tube_contents(2, String::from("blue, blue, blue, blue"));
+        game.init_tube_contents(3, String::from("blue"));
+        // (forbidden pours, tube_from, tube_to, expected)
+        let tests = [
+            (vec![], 0, 4, true),
+            (vec![], 2, 4, true),
+            (vec![], 1, 4, false),
+            (vec![], 3, 1, false),
+            (vec![], 4, 0, false),
+            // A tube in a forbidden pour can not be swapped for another
+            (vec![(2, 3)], 2, 4, false),
+            (vec![(1, 4)], 0, 4, false),
+            (vec![(2, 3)], 0, 4, true),
+        ];
+        for test in tests {
+            game.forbidden_pours = test.0.clone();
+            assert_eq!(
+                game.is_null_progress(test.1, test.2),
+                test.3,
+                "wrong result for pouring tube {} into tube {} with forbidden pours {:?}",
+                test.1 + 1,
+                test.2 + 1,
+                test.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_rename_colour() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, teal, teal, teal"));
+        game.init_tube_contents(1, String::from("red, red, red, teal"));
+        game.make_move(&Move {
+            tube_from: 1,
+            tube_to: 2,
+            colour: String::from("red"),
+            quantity: 3,
+            played_at: None,
+        });
+        game.make_move(&Move {
+            tube_from: 1,
+            tube_to: 3,
+            colour: String::from("teal"),
+            quantity: 1,
+            played_at: None,
+        });
+
+        game.rename_colour("teal", "cyan")
+            .expect("rename should be valid");
+        let expected = [
+            Tube::from_string(String::from("red, cyan, cyan, cyan"), 0),
+            Tube::from_string(String::from(""), 1),
+            Tube::from_string(String::from("red, red, red"), 2),
+            Tube::from_string(String::from("cyan"), 3),
+        ];
+        assert_tubes_eq(&game.tubes, &expected);
         assert_eq!(
-            test_result.contents, expected.contents,
-            "tube contents are not the same. Expected = {}, got = {}",
-            expected, test_result
+            game.moves[&1].colour, "red",
+            "other moves should be unchanged"
         );
+        assert_eq!(game.moves[&2].colour, "cyan", "moves were not renamed");
         assert_eq!(
-            test_result.tube_number, expected.tube_number,
-            "tube number not the same. Expected = {}, got = {}",
-            expected.tube_number, test_result.tube_number
+            game.colours,
+            HashSet::from(["red".to_string(), "cyan".to_string()]),
+            "colours were not renamed"
         );
+
+        for (old, new) in [("teal", "blue"), ("cyan", "red"), ("cyan", "empty")] {
+            assert!(
+                game.clone().rename_colour(old, new).is_err(),
+                "expected an error renaming {} to {}",
+                old,
+                new
+            );
+        }
     }
 
-    fn test_move(test_result: &Move, expected: &Move) {
+    #[test]
+    fn test_relabel_colours() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, blue, blue, blue"));
+        game.init_tube_contents(1, String::from("red, red, red, blue"));
+        game.make_move(&Move {
+            tube_from: 0,
+            tube_to: 3,
+            colour: String::from("red"),
+            quantity: 1,
+            played_at: None,
+        });
+        let map = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(old, new)| (old.to_string(), new.to_string()))
+                .collect()
+        };
+
+        game.relabel_colours(&map(&[("red", "blue"), ("blue", "red")]))
+            .expect("swapping colours should be allowed");
+        let expected = [
+            Tube::from_string(String::from("red, red, red"), 0),
+            Tube::from_string(String::from("blue, blue, blue, red"), 1),
+            Tube::from_string(String::from(""), 2),
+            Tube::from_string(String::from("blue"), 3),
+        ];
+        assert_tubes_eq(&game.tubes, &expected);
+        assert_eq!(game.moves[&1].colour, "blue", "moves were not relabelled");
         assert_eq!(
-            test_result.tube_from, expected.tube_from,
-            "Move.tube_from different. Expected = {}, got = {}",
-            expected.tube_from, test_result.tube_from
+            game.colours,
+            HashSet::from(["red".to_string(), "blue".to_string()]),
+            "wrong colours after swapping"
+        );
+
+        let tests = [
+            map(&[("green", "pink")]),
+            map(&[("red", "blue")]),
+            map(&[("red", "pink"), ("blue", "pink")]),
+            map(&[("red", "")]),
+        ];
+        for test in tests {
+            let mut relabelled = game.clone();
+            assert!(
+                relabelled.relabel_colours(&test).is_err(),
+                "expected an error relabelling with {:?}",
+                test
+            );
+            assert_eq!(
+                relabelled.state_key(),
+                game.state_key(),
+                "a failed relabelling should leave the board as it was"
+            );
+        }
+    }
+
+    #[test]
+    fn test_permute_tubes() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, blue, blue, blue"));
+        game.init_tube_contents(1, String::from("red, red, red, blue"));
+        game.forbidden_pours = vec![(1, 2)];
+        game.make_move(&Move {
+            tube_from: 0,
+            tube_to: 3,
+            colour: String::from("red"),
+            quantity: 1,
+            played_at: None,
+        });
+
+        game.permute_tubes(&[3, 2, 1, 0])
+            .expect("order should be valid");
+        let expected = [
+            Tube::from_string(String::from("red"), 0),
+            Tube::from_string(String::from(""), 1),
+            Tube::from_string(String::from("red, red, red, blue"), 2),
+            Tube::from_string(String::from("blue, blue, blue"), 3),
+        ];
+        assert_tubes_eq(&game.tubes, &expected);
+        assert_move_eq(
+            &game.moves[&1],
+            &Move {
+                tube_from: 3,
+                tube_to: 0,
+                colour: String::from("red"),
+                quantity: 1,
+                played_at: None,
+            },
         );
         assert_eq!(
-            test_result.tube_to, expected.tube_to,
-            "Move.tube_to different. Expected = {}, got = {}",
-            expected.tube_to, test_result.tube_to
+            game.forbidden_pours,
+            vec![(2, 1)],
+            "forbidden pours were not renumbered"
         );
+
+        for order in [vec![0, 1, 2], vec![0, 1, 1, 2], vec![0, 1, 2, 4]] {
+            assert!(
+                game.permute_tubes(&order).is_err(),
+                "expected an error permuting with {:?}",
+                order
+            );
+        }
+    }
+
+    #[test]
+    fn test_hint_limit() {
+        let tests: Vec<(Option<usize>, usize, Vec<bool>, &str)> = vec![
+            (None, 4, vec![true, true, true, true], "★☆☆ (4 hints used)"),
+            (
+                Some(3),
+                4,
+                vec![true, true, true, false],
+                "★☆☆ (3 hints used)",
+            ),
+            (Some(1), 2, vec![true, false], "★★☆ (1 hint used)"),
+            (Some(0), 1, vec![false], "★★★ (0 hints used)"),
+        ];
+        for test in tests {
+            let mut game = Game {
+                hint_limit: test.0,
+                ..Default::default()
+            };
+            let results: Vec<bool> = (0..test.1).map(|_| game.use_hint()).collect();
+            assert_eq!(
+                results, test.2,
+                "wrong hint results for limit {:?}. Expected: {:?}, got: {:?}",
+                test.0, test.2, results
+            );
+            assert_eq!(
+                game.hints_remaining(),
+                test.0.map(|_| 0),
+                "hints remaining wrong for limit {:?}",
+                test.0
+            );
+            assert_eq!(
+                game.stats.rating_string(),
+                test.3,
+                "wrong rating. Expected: {}, got: {}",
+                test.3,
+                game.stats.rating_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_move() {
+        let tests: Vec<(usize, Vec<String>, Move, MoveSafety)> = vec![
+            (
+                4,
+                vec![
+                    String::from("red, blue, blue, blue"),
+                    String::from("red, red, red, blue"),
+                ],
+                Move {
+                    tube_from: 0,
+                    tube_to: 2,
+                    colour: "red".to_string(),
+                    quantity: 1,
+                    played_at: None,
+                },
+                MoveSafety::Safe,
+            ),
+            (
+                6,
+                vec![
+                    String::from(""),
+                    String::from("green"),
+                    String::from("red, blue, yellow"),
+                    String::from("yellow, red, red, red"),
+                    String::from("yellow, green, green, green"),
+                    String::from("yellow, blue, blue, blue"),
+                ],
+                Move {
+                    tube_from: 2,
+                    tube_to: 0,
+                    colour: "red".to_string(),
+                    quantity: 1,
+                    played_at: None,
+                },
+                MoveSafety::Losing,
+            ),
+            (
+                5,
+                vec![
+                    String::from("red, green, blue, red"),
+                    String::from("red, green, blue, red"),
+                    String::from("blue, green, blue, green"),
+                ],
+                Move {
+                    tube_from: 0,
+                    tube_to: 3,
+                    colour: "red".to_string(),
+                    quantity: 1,
+                    played_at: None,
+                },
+                MoveSafety::Risky,
+            ),
+        ];
+        for test in tests {
+            let mut game = Game::default();
+            game.init_tubes(test.0);
+            for (idx, init_tube) in test.1.into_iter().enumerate() {
+                game.init_tube_contents(idx, init_tube);
+            }
+            let result = game.classify_move(&test.2);
+            assert_eq!(
+                result, test.3,
+                "wrong classification for move {}. Expected: {}, got: {}",
+                test.2, test.3, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_reachable_states() {
+        let tests: Vec<(usize, Vec<String>, usize, usize)> = vec![
+            (
+                4,
+                vec![
+                    String::from("red, blue, blue, blue"),
+                    String::from("red, red, red, blue"),
+                ],
+                0,
+                0,
+            ),
+            (
+                4,
+                vec![
+                    String::from("red, blue, blue, blue"),
+                    String::from("red, red, red, blue"),
+                ],
+                1,
+                4,
+            ),
+            (
+                4,
+                vec![
+                    String::from("red, red, red, red"),
+                    String::from("blue, blue, blue, blue"),
+                ],
+                5,
+                0,
+            ),
+        ];
+        for test in tests {
+            let mut game = Game::default();
+            game.init_tubes(test.0);
+            for (idx, init_tube) in test.1.into_iter().enumerate() {
+                game.init_tube_contents(idx, init_tube);
+            }
+            let states: Vec<Game> = game.reachable_states(test.2).collect();
+            assert_eq!(
+                states.len(),
+                test.3,
+                "wrong number of states reachable within {} moves. Expected: {}, got: {}",
+                test.2,
+                test.3,
+                states.len()
+            );
+            let keys: HashSet<Vec<Vec<Option<String>>>> =
+                states.iter().map(|state| state.state_key()).collect();
+            assert_eq!(
+                keys.len(),
+                states.len(),
+                "reachable states are not distinct"
+            );
+            assert!(
+                !keys.contains(&game.state_key()),
+                "the starting state was returned as reachable"
+            );
+            for state in states.iter() {
+                assert!(
+                    state.current_move >= 1 && state.current_move <= test.2,
+                    "state reached in {} moves, outside the limit of {}",
+                    state.current_move,
+                    test.2
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_reachable_states_complete_search() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, blue, blue, blue"));
+        game.init_tube_contents(1, String::from("red, red, red, blue"));
+        let states: Vec<Game> = game.reachable_states(usize::MAX).collect();
+        assert!(
+            states.iter().any(|state| state.is_game_complete()),
+            "no complete state is reachable"
+        );
+        let nearest = states.iter().map(|state| state.current_move);
+        assert!(
+            nearest.tuple_windows().all(|(a, b)| a <= b),
+            "states are not returned nearest first"
+        );
+    }
+
+    #[test]
+    fn test_move_times() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+        let moves = [
+            (0, 2, "red", 3, Some(4)),
+            (1, 2, "red", 1, Some(16)),
+            (0, 1, "blue", 1, Some(20)),
+        ];
+        for (tube_from, tube_to, colour, quantity, played_at) in moves {
+            game.make_move(&Move {
+                tube_from,
+                tube_to,
+                colour: colour.to_string(),
+                quantity,
+                played_at: played_at.map(Duration::from_secs),
+            });
+        }
+        let times = game.move_times();
+        let expected = [1, 2, 3]
+            .into_iter()
+            .zip([4, 12, 4].map(Duration::from_secs));
+        assert!(times.into_iter().eq(expected), "wrong move times");
         assert_eq!(
-            test_result.colour, expected.colour,
-            "Move.colour different. Expected = {}, got = {}",
-            expected.colour, test_result.colour
+            game.get_all_moves_string(),
+            "1 : (1 -> 3: red x 3) at 4s, took 4s\n\
+             2 : (2 -> 3: red x 1) at 16s, took 12s\n\
+             3 : (1 -> 2: blue x 1) at 20s, took 4s\n"
         );
         assert_eq!(
-            test_result.quantity, expected.quantity,
-            "Move.colour different. Expected = {}, got = {}",
-            expected.quantity, test_result.quantity
+            game.pace_string().as_deref(),
+            Some("Pace: 6s a move on average. The slowest was move 2, which took 12s")
         );
+        let unplayed = Game::default();
+        assert_eq!(unplayed.pace_string(), None, "no moves should have no pace");
+    }
+
+    #[test]
+    fn test_undo_move() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+        let start = game.clone();
+        assert!(game.undo_move().is_none(), "there is no move to undo");
+        let moves = [(0, 2, "red", 3), (1, 2, "red", 1), (1, 0, "blue", 3)];
+        let mut boards = vec![game.clone()];
+        for (tube_from, tube_to, colour, quantity) in moves {
+            game.make_move(&Move {
+                tube_from,
+                tube_to,
+                colour: colour.to_string(),
+                quantity,
+                played_at: None,
+            });
+            boards.push(game.clone());
+        }
+        for (idx, (tube_from, tube_to, _, _)) in moves.iter().enumerate().rev() {
+            let undone = game.undo_move().expect("the move should be undone");
+            assert_eq!(
+                (undone.tube_from, undone.tube_to),
+                (*tube_from, *tube_to),
+                "wrong move undone"
+            );
+            assert_tubes_eq(&game.tubes, &boards[idx].tubes);
+            assert_eq!(game.current_move, idx, "wrong move number after undo");
+        }
+        assert_tubes_eq(&game.tubes, &start.tubes);
+        assert!(game.moves.is_empty(), "every move should have been undone");
+
+        let mut limited = Game {
+            history_limit: Some(1),
+            ..start.clone()
+        };
+        for (tube_from, tube_to, colour, quantity) in &moves[..2] {
+            limited.make_move(&Move {
+                tube_from: *tube_from,
+                tube_to: *tube_to,
+                colour: colour.to_string(),
+                quantity: *quantity,
+                played_at: None,
+            });
+        }
+        assert!(limited.undo_move().is_some(), "the last move is kept");
+        assert!(
+            limited.undo_move().is_none(),
+            "a move dropped from the history cannot be undone"
+        );
+    }
+
+    #[test]
+    fn test_merge_pours() {
+        let mut game = Game {
+            ruleset: Ruleset::OneUnit,
+            ..Default::default()
+        };
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+        let moves = [
+            (0, 2, "red", Some(2)),
+            (0, 2, "red", Some(3)),
+            (0, 2, "red", Some(5)),
+            (1, 2, "red", Some(9)),
+            (0, 1, "blue", None),
+        ];
+        for (tube_from, tube_to, colour, played_at) in moves {
+            game.make_move(&Move {
+                tube_from,
+                tube_to,
+                colour: colour.to_string(),
+                quantity: 1,
+                played_at: played_at.map(Duration::from_secs),
+            });
+        }
+        assert_eq!(
+            game.get_merged_moves_string(),
+            "1-3 : (1 -> 3: red x 3) at 5s, took 5s\n\
+             4 : (2 -> 3: red x 1) at 9s, took 4s\n\
+             5 : (1 -> 2: blue x 1)\n"
+        );
+        assert_eq!(game.moves.len(), 5, "the moves should not be changed");
+        let all: Vec<Move> = (1..=5).map(|idx| game.moves[&idx].clone()).collect();
+        let merged: Vec<String> = merge_pours(&all).iter().map(Move::to_string).collect();
+        assert_eq!(
+            merged,
+            ["1 -> 3: red x 3", "2 -> 3: red x 1", "1 -> 2: blue x 1"],
+            "wrong pours merged"
+        );
+        assert_eq!(
+            numbered_moves_string(&all),
+            "1-3: 1 -> 3: red x 3\n  4: 2 -> 3: red x 1\n  5: 1 -> 2: blue x 1\n",
+            "wrong numbered moves"
+        );
+    }
+
+    #[test]
+    fn test_check_integrity() {
+        let tests = [
+            (vec!["red, red, blue, blue", "blue, blue, red, red"], vec![]),
+            (
+                vec!["red, empty, blue, blue", "blue, blue, red, red", "red"],
+                vec!["tube 1 has red floating above an empty segment"],
+            ),
+            (
+                vec!["red, red, red, red, blue", "blue, blue, blue"],
+                vec!["tube 1 has 5 segments, a tube holds 4"],
+            ),
+            (
+                vec!["red, red, blue", "blue, blue, red, red"],
+                vec!["colour blue has 3 segments, which is not a multiple of 4"],
+            ),
+            (vec!["red, red, red, red", "red, red, red, red"], vec![]),
+        ];
+        for test in tests {
+            let mut game = Game::default();
+            game.init_tubes(4);
+            for (idx, tube) in test.0.iter().enumerate() {
+                game.init_tube_contents(idx, tube.to_string());
+            }
+            let problems = game.check_integrity();
+            assert_eq!(
+                problems, test.1,
+                "wrong problems for {:?}. Expected: {:?}, got: {:?}",
+                test.0, test.1, problems
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_move_request() {
+        let tests = [
+            ("1 3 2", Some((0, 2, 2))),
+            (" 10 1 4 ", Some((9, 0, 4))),
+            ("1 3", None),
+            ("1 3 2 4", None),
+            ("one 3 2", None),
+            ("0 3 2", None),
+            ("1 3 0", None),
+            ("1 -3 2", None),
+            ("1>3", Some((0, 2, usize::MAX))),
+            (" 2 > 10 ", Some((1, 9, usize::MAX))),
+            (">3", None),
+            ("1>3 2", None),
+        ];
+        for test in tests {
+            let result = test.0.parse::<MoveRequest>().ok();
+            let expected = test.1.map(|(tube_from, tube_to, quantity)| MoveRequest {
+                tube_from,
+                tube_to,
+                quantity,
+                colour: None,
+            });
+            assert_eq!(result, expected, "wrong request parsed from {:?}", test.0);
+        }
+        let tests = [
+            ("3 5 red", Some((2, 4, "red"))),
+            ("1 2 Blue", Some((0, 1, "blue"))),
+            ("1 2 #F80", Some((0, 1, "#ff8800"))),
+            ("1 2 -red", None),
+        ];
+        for test in tests {
+            let result = test.0.parse::<MoveRequest>().ok();
+            let expected = test.1.map(|(tube_from, tube_to, colour)| MoveRequest {
+                tube_from,
+                tube_to,
+                quantity: usize::MAX,
+                colour: Some(colour.to_string()),
+            });
+            assert_eq!(result, expected, "wrong request parsed from {:?}", test.0);
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut game = Game::default();
+        game.init_tubes(5);
+        game.init_tube_contents(0, String::from("red, red, blue, blue"));
+        game.init_tube_contents(1, String::from("blue, red, red"));
+        game.init_tube_contents(2, String::from("red, blue, blue, red"));
+        game.forbidden_pours.push((2, 4));
+        let tests = [
+            ((0, 3, 2), Ruleset::Standard, Ok(("red", 2))),
+            ((0, 3, 4), Ruleset::Standard, Ok(("red", 2))),
+            ((0, 1, 1), Ruleset::Standard, Err(MoveError::WrongColour(1))),
+            ((1, 0, 1), Ruleset::Standard, Err(MoveError::FullTube(0))),
+            ((3, 0, 1), Ruleset::Standard, Err(MoveError::EmptyTube(3))),
+            ((0, 5, 1), Ruleset::Standard, Err(MoveError::NoSuchTube(5))),
+            ((0, 0, 1), Ruleset::Standard, Err(MoveError::SameTube)),
+            (
+                (2, 4, 1),
+                Ruleset::Standard,
+                Err(MoveError::ForbiddenPour(2, 4)),
+            ),
+            ((0, 3, 2), Ruleset::OneUnit, Ok(("red", 1))),
+            ((0, 3, 2), Ruleset::NoSplit, Ok(("red", 2))),
+            (
+                (0, 3, 1),
+                Ruleset::NoSplit,
+                Err(MoveError::Quantity(Ruleset::NoSplit)),
+            ),
+        ];
+        for test in tests {
+            game.ruleset = test.1;
+            let ((tube_from, tube_to, quantity), expected) = (test.0, test.2);
+            let result = game
+                .resolve(MoveRequest {
+                    tube_from,
+                    tube_to,
+                    quantity,
+                    colour: None,
+                })
+                .map(|a_move| (a_move.colour, a_move.quantity));
+            let expected = expected.map(|(colour, quantity)| (colour.to_string(), quantity));
+            assert_eq!(
+                result, expected,
+                "wrong resolution of {:?} with the {} rules",
+                test.0, test.1
+            );
+        }
+        game.ruleset = Ruleset::Standard;
+        let by_colour = |input: &str| {
+            game.resolve(input.parse::<MoveRequest>().unwrap())
+                .map(|a_move| (a_move.colour, a_move.quantity))
+        };
+        assert_eq!(
+            by_colour("1 4 red"),
+            Ok((String::from("red"), 2)),
+            "the whole red block should be poured"
+        );
+        assert_eq!(
+            by_colour("1 4 blue"),
+            Err(MoveError::NotOnTop(String::from("blue"), 0)),
+            "blue is not on top of tube 1"
+        );
+    }
+
+    #[test]
+    fn test_resolve_sequence() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+        let tests = [
+            ("1>3, 2>3, 2 1 3", Ok(vec![(0, 2, 3), (1, 2, 1), (1, 0, 3)])),
+            ("1>3,3>4", Ok(vec![(0, 2, 3), (2, 3, 3)])),
+            ("1>3, 1>3", Err(1)),
+            ("1>3, 4>1", Err(1)),
+            ("3>1, 1>3", Err(0)),
+        ];
+        for test in tests {
+            let requests = parse_move_sequence(test.0).expect("moves should parse");
+            let result = game.resolve_sequence(&requests).map(|moves| {
+                moves
+                    .iter()
+                    .map(|a_move| (a_move.tube_from, a_move.tube_to, a_move.quantity))
+                    .collect::<Vec<(usize, usize, usize)>>()
+            });
+            assert_eq!(
+                result.map_err(|(idx, _)| idx),
+                test.1,
+                "wrong moves resolved from {:?}",
+                test.0
+            );
+        }
+        assert_eq!(
+            game.current_move, 0,
+            "resolving moves should not change the board"
+        );
+
+        let tests = [
+            ("1>3, 2 3 1", Ok(2)),
+            ("1>3", Ok(1)),
+            ("1>3, three", Err("move 2: ")),
+            (",1>3", Err("move 1: ")),
+        ];
+        for test in tests {
+            let result = parse_move_sequence(test.0).map(|requests| requests.len());
+            match (result, test.1) {
+                (Ok(len), Ok(expected)) => assert_eq!(
+                    len, expected,
+                    "wrong number of moves parsed from {:?}",
+                    test.0
+                ),
+                (Err(err), Err(expected)) => assert!(
+                    err.starts_with(expected),
+                    "wrong error parsing {:?}: {}",
+                    test.0,
+                    err
+                ),
+                (result, _) => panic!("unexpected result parsing {:?}: {:?}", test.0, result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_history_limit() {
+        let moves = [
+            (0, 2, "red", 3, 4),
+            (1, 2, "red", 1, 16),
+            (0, 1, "blue", 1, 20),
+        ];
+        let tests = [
+            (None, vec![1, 2, 3], vec![1, 2, 3]),
+            (Some(5), vec![1, 2, 3], vec![1, 2, 3]),
+            (Some(2), vec![2, 3], vec![3]),
+            (Some(1), vec![3], vec![]),
+        ];
+        for test in tests {
+            let mut game = Game::default();
+            game.init_tubes(4);
+            game.init_tube_contents(0, String::from("red, red, red, blue"));
+            game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+            game.history_limit = test.0;
+            for (tube_from, tube_to, colour, quantity, played_at) in moves {
+                game.make_move(&Move {
+                    tube_from,
+                    tube_to,
+                    colour: colour.to_string(),
+                    quantity,
+                    played_at: Some(Duration::from_secs(played_at)),
+                });
+            }
+            let kept: Vec<usize> = game.moves.keys().copied().sorted().collect();
+            assert_eq!(
+                kept, test.1,
+                "wrong moves kept with a limit of {:?}",
+                test.0
+            );
+            let timed: Vec<usize> = game.move_times().iter().map(|(num, _)| *num).collect();
+            assert_eq!(
+                timed, test.2,
+                "wrong moves timed with a limit of {:?}",
+                test.0
+            );
+            assert_eq!(game.current_move, 3, "every move should still be counted");
+            assert_eq!(
+                game.has_full_history(),
+                test.1.len() == 3,
+                "wrong full history with a limit of {:?}",
+                test.0
+            );
+            assert!(game.is_game_complete(), "the moves should still be made");
+        }
     }
 }
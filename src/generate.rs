@@ -0,0 +1,254 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    game::Par,
+    puzzles::{Puzzle, PAR_SECONDS_PER_MOVE},
+    self_play::{random_boards, MAX_COLOURS},
+    solver::{Preset, Solver},
+};
+
+/// How many random boards are tried for a level before giving up on it. Most random boards of a
+/// few colours can be solved, so this is only reached when the preset's budget is far too small.
+const MAX_ATTEMPTS: u64 = 1_000;
+
+/// What `generate_pack` makes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PackOptions {
+    pub levels: usize,
+    pub colours: usize,
+    /// The seed every level's seed is derived from, so the same seed always gives the same pack.
+    pub seed: u64,
+    /// How many levels are made at once, or None for one for every processor.
+    pub threads: Option<usize>,
+    /// The budgets within which every level must be solved.
+    pub preset: Preset,
+}
+
+impl Default for PackOptions {
+    fn default() -> Self {
+        PackOptions {
+            levels: 20,
+            colours: 7,
+            seed: 1,
+            threads: None,
+            preset: Preset::default(),
+        }
+    }
+}
+
+/// A level made, with how many states its solution took to find, to order the pack by.
+struct Level {
+    tubes: Vec<String>,
+    moves: usize,
+    states_searched: usize,
+}
+
+/// The seed of one level of a pack, mixed from the seed of the pack and the level's place in it
+/// (SplitMix64), so that every level is made the same whichever thread makes it and whenever.
+pub fn level_seed(seed: u64, level: usize) -> u64 {
+    let mut z = seed.wrapping_add((level as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Makes a pack of random levels that can all be solved within the budgets of the preset, and
+/// calibrates it: every level is given par from its solution, and the levels are numbered from 1
+/// in order of how much searching they took, so that the pack gets harder as it goes. The levels
+/// are made on several threads at once, each from its own seed, so the pack is the same however
+/// many threads made it.
+pub fn generate_pack(options: &PackOptions) -> Result<Vec<Puzzle>, String> {
+    if !(2..=MAX_COLOURS).contains(&options.colours) {
+        return Err(format!(
+            "A level needs between 2 and {} colours",
+            MAX_COLOURS
+        ));
+    }
+    let threads = options
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()))
+        .clamp(1, options.levels.max(1));
+    let next = AtomicUsize::new(0);
+    let levels: Mutex<Vec<Option<Level>>> = Mutex::new((0..options.levels).map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, Ordering::Relaxed);
+                if idx >= options.levels {
+                    break;
+                }
+                let level = generate_level(options, level_seed(options.seed, idx));
+                levels.lock().expect("no thread should panic")[idx] = level;
+            });
+        }
+    });
+    let mut levels = levels
+        .into_inner()
+        .expect("no thread should panic")
+        .into_iter()
+        .enumerate()
+        .map(|(idx, level)| {
+            level.ok_or_else(|| {
+                format!(
+                    "No board that could be solved was found for level {} in {} tries",
+                    idx + 1,
+                    MAX_ATTEMPTS
+                )
+            })
+        })
+        .collect::<Result<Vec<Level>, String>>()?;
+    // The sort is stable, so levels that took the same search keep the order of their seeds
+    levels.sort_by_key(|level| (level.states_searched, level.moves));
+    Ok(levels
+        .into_iter()
+        .enumerate()
+        .map(|(idx, level)| Puzzle {
+            number: idx + 1,
+            name: format!("Level {}", idx + 1),
+            tubes: level.tubes,
+            forbidden_pours: Vec::new(),
+            par: Par {
+                moves: Some(level.moves),
+                time: Some(Duration::from_secs(
+                    level.moves as u64 * PAR_SECONDS_PER_MOVE,
+                )),
+            },
+        })
+        .collect())
+}
+
+/// Tries random boards from the level's seed until one can be solved within the preset's budgets.
+fn generate_level(options: &PackOptions, seed: u64) -> Option<Level> {
+    (0..MAX_ATTEMPTS).find_map(|attempt| {
+        let mut game = random_boards(1, options.colours, level_seed(seed, attempt as usize))
+            .pop()
+            .expect("one board should be made");
+        game.preset = options.preset;
+        let solution = Solver::new(&game).search()?;
+        Some(Level {
+            tubes: game
+                .tubes
+                .iter()
+                .map(|tube| match tube.contents.iter().all(Option::is_none) {
+                    true => String::from("empty"),
+                    false => tube.contents_string(),
+                })
+                .collect(),
+            moves: solution.moves.len(),
+            states_searched: solution.states_searched,
+        })
+    })
+}
+
+/// The pack as a catalogue in the format of the built-in puzzles. It starts with a comment
+/// recording the seed and the options it was made with, which make the same pack again.
+pub fn pack_catalogue(options: &PackOptions, puzzles: &[Puzzle]) -> String {
+    let mut catalogue = format!(
+        "# Made by generate-pack --levels {} --colours {} --seed {} --preset {}\n",
+        options.levels, options.colours, options.seed, options.preset
+    );
+    for puzzle in puzzles {
+        catalogue.push('\n');
+        catalogue.push_str(&format!("[{}] {}\n", puzzle.number, puzzle.name));
+        if let (Some(moves), Some(time)) = (puzzle.par.moves, puzzle.par.time) {
+            catalogue.push_str(&format!(
+                "par moves {}\npar time {}\n",
+                moves,
+                time.as_secs()
+            ));
+        }
+        for tube in puzzle.tubes.iter() {
+            catalogue.push_str(tube);
+            catalogue.push('\n');
+        }
+    }
+    catalogue
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles::parse_catalogue;
+
+    #[test]
+    fn test_generate_pack() {
+        let options = PackOptions {
+            levels: 6,
+            colours: 4,
+            seed: 3,
+            threads: Some(3),
+            preset: Preset::Quick,
+        };
+        let pack = generate_pack(&options).expect("the pack should be made");
+        assert_eq!(pack.len(), 6, "wrong number of levels");
+        for puzzle in pack.iter() {
+            let mut game = puzzle.to_game();
+            game.preset = options.preset;
+            let moves = Solver::new(&game)
+                .solve()
+                .unwrap_or_else(|| panic!("level {} should be solved", puzzle.number))
+                .len();
+            assert_eq!(
+                puzzle.par.moves,
+                Some(moves),
+                "wrong par for level {}",
+                puzzle.number
+            );
+        }
+        let catalogue = pack_catalogue(&options, &pack);
+        assert!(
+            catalogue.starts_with(
+                "# Made by generate-pack --levels 6 --colours 4 --seed 3 --preset quick\n"
+            ),
+            "the catalogue should record its seed:\n{}",
+            catalogue
+        );
+        for threads in [1, 4] {
+            let other = generate_pack(&PackOptions {
+                threads: Some(threads),
+                ..options
+            })
+            .expect("the pack should be made");
+            assert_eq!(
+                pack_catalogue(&options, &other),
+                catalogue,
+                "the pack should not depend on the {} threads that made it",
+                threads
+            );
+        }
+        let parsed = parse_catalogue(&catalogue).expect("the catalogue should be read back");
+        assert_eq!(
+            parsed
+                .iter()
+                .map(|puzzle| puzzle.tubes.clone())
+                .collect::<Vec<_>>(),
+            pack.iter()
+                .map(|puzzle| puzzle.tubes.clone())
+                .collect::<Vec<_>>(),
+            "the catalogue should read back as the same levels"
+        );
+        assert_ne!(
+            pack_catalogue(
+                &options,
+                &generate_pack(&PackOptions { seed: 4, ..options }).unwrap()
+            ),
+            catalogue,
+            "a different seed should give a different pack"
+        );
+        assert!(
+            generate_pack(&PackOptions {
+                colours: 1,
+                ..options
+            })
+            .is_err(),
+            "a level needs two colours"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::config::home_dir;
+
+const INPUT_LOG_FILE_NAME: &str = "input.log";
+
+/// What the user was asked for when they entered a rejected input.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputKind {
+    TubeCount,
+    Ruleset,
+    Tube,
+    Setup,
+    Move,
+}
+
+impl Display for InputKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            InputKind::TubeCount => "tube-count",
+            InputKind::Ruleset => "ruleset",
+            InputKind::Tube => "tube",
+            InputKind::Setup => "setup",
+            InputKind::Move => "move",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An opt-in log of every input that was rejected, with the reason, so the input formats users
+/// actually try can be supported. Each entry is one tab separated line of the time, the kind of
+/// input, the input itself and the error.
+pub struct InputLog {
+    path: Option<PathBuf>,
+}
+
+impl InputLog {
+    /// Opens the log in the per-user directory, or a log that records nothing if it is disabled.
+    pub fn open(enabled: bool) -> InputLog {
+        InputLog {
+            path: match enabled {
+                true => home_dir().map(|dir| dir.join(INPUT_LOG_FILE_NAME)),
+                false => None,
+            },
+        }
+    }
+
+    /// Records a rejected input. Failing to write the log never interrupts the game, so errors
+    /// are ignored.
+    pub fn record(&self, kind: InputKind, input: &str, error: &str) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+            let _ = writeln!(file, "{}", format_entry(time, kind, input, error));
+        }
+    }
+}
+
+/// Formats one log entry, escaping the input and error so that every entry stays on one line.
+pub fn format_entry(time: u64, kind: InputKind, input: &str, error: &str) -> String {
+    format!(
+        "{}\t{}\t{}\t{}",
+        time,
+        kind,
+        escape(input.trim_end_matches(['\r', '\n'])),
+        escape(error)
+    )
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_entry() {
+        let tests = [
+            (
+                InputKind::Move,
+                "1 to 3\n",
+                "Move must be in the format \"<tube_from> <tube_to> <quantity>\"",
+                "1700000000\tmove\t1 to 3\tMove must be in the format \"<tube_from> <tube_to> <quantity>\"",
+            ),
+            (
+                InputKind::TubeCount,
+                "six\r\n",
+                "Unable to parse six to a number",
+                "1700000000\ttube-count\tsix\tUnable to parse six to a number",
+            ),
+            (
+                InputKind::Tube,
+                "red\tblue \\ green",
+                "line one\nline two",
+                "1700000000\ttube\tred\\tblue \\\\ green\tline one\\nline two",
+            ),
+        ];
+        for test in tests {
+            let result = format_entry(1700000000, test.0, test.1, test.2);
+            assert_eq!(
+                result, test.3,
+                "wrong log entry for {:?}. Expected: {:?}, got: {:?}",
+                test.1, test.3, result
+            );
+        }
+    }
+}
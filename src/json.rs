@@ -0,0 +1,292 @@
+use std::{fmt::Display, iter::Peekable, str::Chars};
+
+/// A parsed JSON value. Only as much of JSON is supported as puzzle files need, which is
+/// everything apart from exponents in numbers and `\u` escapes outside the basic plane.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members are kept in the order they were written.
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut chars = text.chars().peekable();
+        let value = parse_value(&mut chars)?;
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected '{}' after the end of the value", c)),
+        }
+    }
+
+    /// Returns the member with the given key, if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(member, _)| member == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if it is a whole number that is not negative.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(value) if *value >= 0.0 && value.fract() == 0.0 => Some(*value as usize),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the value as compact JSON, on one line.
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(value) => write!(f, "{}", value),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (idx, (key, value)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ": {}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, value: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+}
+
+fn expect(chars: &mut Peekable<Chars>, expected: char) -> Result<(), String> {
+    match chars.next() {
+        Some(c) if c == expected => Ok(()),
+        Some(c) => Err(format!("expected '{}', got '{}'", expected, c)),
+        None => Err(format!("expected '{}', got the end of the file", expected)),
+    }
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    skip_whitespace(chars);
+    match chars.peek() {
+        Some('{') => parse_object(chars),
+        Some('[') => parse_array(chars),
+        Some('"') => Ok(Json::String(parse_string(chars)?)),
+        Some(c) if *c == '-' || c.is_ascii_digit() => parse_number(chars),
+        Some(c) if c.is_ascii_alphabetic() => {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphabetic()) {
+                word.push(c);
+            }
+            match word.as_str() {
+                "null" => Ok(Json::Null),
+                "true" => Ok(Json::Bool(true)),
+                "false" => Ok(Json::Bool(false)),
+                _ => Err(format!("unexpected '{}'", word)),
+            }
+        }
+        Some(c) => Err(format!("unexpected '{}'", c)),
+        None => Err("expected a value, got the end of the file".to_string()),
+    }
+}
+
+fn parse_object(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    expect(chars, '{')?;
+    let mut members = Vec::new();
+    skip_whitespace(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Ok(Json::Object(members));
+    }
+    loop {
+        skip_whitespace(chars);
+        let key = parse_string(chars)?;
+        skip_whitespace(chars);
+        expect(chars, ':')?;
+        members.push((key, parse_value(chars)?));
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => {}
+            Some('}') => return Ok(Json::Object(members)),
+            Some(c) => return Err(format!("expected ',' or '}}', got '{}'", c)),
+            None => return Err("unterminated object".to_string()),
+        }
+    }
+}
+
+fn parse_array(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    expect(chars, '[')?;
+    let mut values = Vec::new();
+    skip_whitespace(chars);
+    if chars.next_if_eq(&']').is_some() {
+        return Ok(Json::Array(values));
+    }
+    loop {
+        values.push(parse_value(chars)?);
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => {}
+            Some(']') => return Ok(Json::Array(values)),
+            Some(c) => return Err(format!("expected ',' or ']', got '{}'", c)),
+            None => return Err("unterminated array".to_string()),
+        }
+    }
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    expect(chars, '"')?;
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(value),
+            Some('\\') => {
+                let escaped = match chars.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('/') => '/',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('u') => {
+                        let code: String = chars.by_ref().take(4).collect();
+                        u32::from_str_radix(&code, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid escape '\\u{}'", code))?
+                    }
+                    Some(c) => return Err(format!("invalid escape '\\{}'", c)),
+                    None => return Err("unterminated string".to_string()),
+                };
+                value.push(escaped);
+            }
+            Some(c) => value.push(c),
+            None => return Err("unterminated string".to_string()),
+        }
+    }
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| *c == '-' || *c == '.' || c.is_ascii_digit()) {
+        number.push(c);
+    }
+    match number.parse::<f64>() {
+        Ok(value) => Ok(Json::Number(value)),
+        Err(_) => Err(format!("unable to parse {} to a number", number)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let tests = [
+            ("null", Some(Json::Null)),
+            (" true ", Some(Json::Bool(true))),
+            ("-1.5", Some(Json::Number(-1.5))),
+            (
+                "\"a \\\"b\\\"\\n\\u0041\"",
+                Some(Json::String("a \"b\"\nA".to_string())),
+            ),
+            (
+                "{\"tubes\": [[\"red\"], []], \"name\": \"x\"}",
+                Some(Json::Object(vec![
+                    (
+                        "tubes".to_string(),
+                        Json::Array(vec![
+                            Json::Array(vec![Json::String("red".to_string())]),
+                            Json::Array(vec![]),
+                        ]),
+                    ),
+                    ("name".to_string(), Json::String("x".to_string())),
+                ])),
+            ),
+            ("{}", Some(Json::Object(vec![]))),
+            ("[1, 2,]", None),
+            ("{\"a\" 1}", None),
+            ("\"open", None),
+            ("[1] 2", None),
+            ("nil", None),
+            ("", None),
+        ];
+        for test in tests {
+            let result = Json::parse(test.0).ok();
+            assert_eq!(result, test.1, "wrong value parsed from {:?}", test.0);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let value = Json::Object(vec![
+            ("name".to_string(), Json::String("a \"b\"\n".to_string())),
+            (
+                "tubes".to_string(),
+                Json::Array(vec![Json::Number(1.0), Json::Number(2.5), Json::Null]),
+            ),
+            ("empty".to_string(), Json::Object(vec![])),
+            ("ok".to_string(), Json::Bool(true)),
+        ]);
+        let result = value.to_string();
+        assert_eq!(
+            result,
+            "{\"name\": \"a \\\"b\\\"\\n\", \"tubes\": [1, 2.5, null], \"empty\": {}, \"ok\": true}"
+        );
+        assert_eq!(Json::parse(&result), Ok(value), "value did not round trip");
+    }
+}
@@ -0,0 +1,345 @@
+use std::{collections::HashMap, fmt::Display, fs};
+
+use crate::{
+    config::home_dir,
+    game::{Game, Move},
+    solver::Solver,
+};
+
+const KNOWN_PUZZLES_FILE_NAME: &str = "known_puzzles";
+
+/// A puzzle that has been solved before. The moves are stored against the tubes in fingerprint
+/// order, so that the same board entered with its tubes in a different order still matches.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KnownPuzzle {
+    pub difficulty: usize,
+    /// Every move as `(tube_from, tube_to, quantity)`.
+    pub moves: Vec<(usize, usize, usize)>,
+}
+
+/// Previously computed solutions, looked up by the fingerprint of the starting board. Stored as
+/// `fingerprint = difficulty: moves` lines next to the settings.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct KnownPuzzles {
+    pub puzzles: HashMap<String, KnownPuzzle>,
+}
+
+/// Identifies a board regardless of the order its tubes were entered in. Returns the fingerprint
+/// along with the order of the tubes it was taken in.
+pub fn fingerprint(game: &Game) -> (String, Vec<usize>) {
+    let tubes: Vec<String> = game
+        .tubes
+        .iter()
+        .map(|tube| {
+            tube.contents
+                .iter()
+                .map(|cell| cell.as_deref().unwrap_or(""))
+                .collect::<Vec<&str>>()
+                .join(",")
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..tubes.len()).collect();
+    order.sort_by(|a, b| tubes[*a].cmp(&tubes[*b]));
+    let mut board = game.ruleset.to_string();
+    for idx in order.iter() {
+        board.push('|');
+        board.push_str(&tubes[*idx]);
+    }
+    let position = positions(&order);
+    let mut forbidden: Vec<(usize, usize)> = game
+        .forbidden_pours
+        .iter()
+        .map(|(from, to)| (position[*from], position[*to]))
+        .collect();
+    forbidden.sort();
+    for (from, to) in forbidden {
+        board.push_str(format!("|{}>{}", from, to).as_str());
+    }
+    (format!("{:016x}", fnv1a(board.as_bytes())), order)
+}
+
+/// The inverse of a fingerprint order: the position in the fingerprint of every tube.
+pub(crate) fn positions(order: &[usize]) -> Vec<usize> {
+    let mut position = vec![0; order.len()];
+    for (fingerprint_idx, tube_idx) in order.iter().enumerate() {
+        position[*tube_idx] = fingerprint_idx;
+    }
+    position
+}
+
+/// The 64-bit FNV-1a hash, used as it gives the same fingerprint on every platform and release.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+impl KnownPuzzles {
+    pub fn parse(contents: &str) -> Result<KnownPuzzles, String> {
+        let mut known = KnownPuzzles::default();
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("Line {}: expected 'key = value'", line_num + 1)),
+            };
+            let puzzle = parse_known_puzzle(value)
+                .map_err(|err| format!("Line {}: {}", line_num + 1, err))?;
+            known.puzzles.insert(key.to_string(), puzzle);
+        }
+        Ok(known)
+    }
+
+    /// Loads the known puzzles. A missing file just means no puzzles are known yet.
+    pub fn load() -> Result<KnownPuzzles, String> {
+        let path = match home_dir() {
+            Some(dir) => dir.join(KNOWN_PUZZLES_FILE_NAME),
+            None => return Ok(KnownPuzzles::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => KnownPuzzles::parse(&contents)
+                .map_err(|err| format!("Unable to read {}: {}", path.display(), err)),
+            Err(_) => Ok(KnownPuzzles::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let dir = match home_dir() {
+            Some(dir) => dir,
+            None => {
+                return Err("Unable to find a home directory to save known puzzles in".to_string())
+            }
+        };
+        let path = dir.join(KNOWN_PUZZLES_FILE_NAME);
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, self.to_string()))
+            .map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+    }
+
+    /// Returns the stored solution for the game, with the moves renumbered to its tubes. A stored
+    /// solution that no longer completes the game is ignored.
+    pub fn lookup(&self, game: &Game) -> Option<(Vec<Move>, usize)> {
+        let (key, order) = fingerprint(game);
+        let known = self.puzzles.get(&key)?;
+        let mut replay = game.clone();
+        let mut moves = Vec::new();
+        for stored in known.moves.iter() {
+            let a_move = stored_move(&replay, &order, stored)?;
+            replay.make_move(&a_move);
+            moves.push(a_move);
+        }
+        if !replay.is_game_complete() {
+            return None;
+        }
+        Some((moves, known.difficulty))
+    }
+
+    /// Returns just the first move of the stored solution for the game, renumbered to its tubes.
+    pub fn first_move(&self, game: &Game) -> Option<Move> {
+        let (key, order) = fingerprint(game);
+        let stored = self.puzzles.get(&key)?.moves.first()?;
+        stored_move(game, &order, stored)
+    }
+
+    pub fn insert(&mut self, game: &Game, moves: &[Move], difficulty: usize) {
+        let (key, order) = fingerprint(game);
+        let position = positions(&order);
+        let moves = moves
+            .iter()
+            .map(|a_move| {
+                (
+                    position[a_move.tube_from],
+                    position[a_move.tube_to],
+                    a_move.quantity,
+                )
+            })
+            .collect();
+        self.puzzles.insert(key, KnownPuzzle { difficulty, moves });
+    }
+
+    /// Solves the game, using the stored solution if the board has been solved before. New
+    /// solutions are added to the known puzzles. Returns the moves and the difficulty.
+    pub fn solve(&mut self, game: &Game) -> Option<(Vec<Move>, usize)> {
+        if let Some(known) = self.lookup(game) {
+            return Some(known);
+        }
+        let solution = Solver::new(game).search()?;
+        let difficulty = solution.difficulty();
+        // Only the shortest solutions are worth keeping for next time
+        if solution.optimal {
+            self.insert(game, &solution.moves, difficulty);
+        }
+        Some((solution.moves, difficulty))
+    }
+}
+
+/// Turns a stored move into a move on the game, given the fingerprint order of its tubes. Returns
+/// None if the move cannot be made.
+pub(crate) fn stored_move(
+    game: &Game,
+    order: &[usize],
+    stored: &(usize, usize, usize),
+) -> Option<Move> {
+    let (from, to, quantity) = *stored;
+    let tube_from = *order.get(from)?;
+    let a_move = Move {
+        tube_from,
+        tube_to: *order.get(to)?,
+        colour: game.tubes[tube_from].get_top_colour()?.colour,
+        quantity,
+        played_at: None,
+    };
+    match game.validate_move(&a_move) {
+        true => Some(a_move),
+        false => None,
+    }
+}
+
+fn parse_known_puzzle(value: &str) -> Result<KnownPuzzle, String> {
+    let (difficulty, moves) = match value.split_once(':') {
+        Some((difficulty, moves)) => (difficulty.trim(), moves.trim()),
+        None => return Err("expected 'difficulty: moves'".to_string()),
+    };
+    let difficulty = match difficulty.parse::<usize>() {
+        Ok(difficulty) => difficulty,
+        Err(_) => return Err(format!("unable to parse {} to a difficulty", difficulty)),
+    };
+    let mut parsed = Vec::new();
+    for a_move in moves.split_whitespace() {
+        parsed.push(parse_stored_move(a_move)?);
+    }
+    Ok(KnownPuzzle {
+        difficulty,
+        moves: parsed,
+    })
+}
+
+/// Parses a stored move written as `from>toxquantity`.
+pub(crate) fn parse_stored_move(a_move: &str) -> Result<(usize, usize, usize), String> {
+    let invalid = || format!("unable to parse {} to a move", a_move);
+    let (tubes, quantity) = a_move.split_once('x').ok_or_else(invalid)?;
+    let (from, to) = tubes.split_once('>').ok_or_else(invalid)?;
+    let number = |value: &str| value.parse::<usize>().map_err(|_| invalid());
+    Ok((number(from)?, number(to)?, number(quantity)?))
+}
+
+impl Display for KnownPuzzles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys: Vec<&String> = self.puzzles.keys().collect();
+        keys.sort();
+        for key in keys {
+            let known = &self.puzzles[key];
+            let moves: Vec<String> = known
+                .moves
+                .iter()
+                .map(|(from, to, quantity)| format!("{}>{}x{}", from, to, quantity))
+                .collect();
+            writeln!(f, "{} = {}: {}", key, known.difficulty, moves.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::Ruleset, testing::initialise_game};
+
+    use super::*;
+
+    #[test]
+    fn test_fingerprint() {
+        let game = initialise_game(&["red, red, red, blue", "red, blue, blue, blue"], 4);
+        let tests = [
+            (
+                vec!["", "red, blue, blue, blue", "", "red, red, red, blue"],
+                true,
+            ),
+            (vec!["red, blue, blue, blue", "red, red, red, blue"], true),
+            (vec!["red, red, blue, blue", "red, red, blue, blue"], false),
+            (
+                vec!["red, red, red, blue", "", "red, blue, blue, blue"],
+                true,
+            ),
+        ];
+        let (expected, _) = fingerprint(&game);
+        for test in tests {
+            let other = initialise_game(&test.0, 4);
+            let (result, _) = fingerprint(&other);
+            assert_eq!(
+                result == expected,
+                test.1,
+                "wrong fingerprint match for {:?}",
+                test.0
+            );
+        }
+        let mut one_unit = game.clone();
+        one_unit.ruleset = Ruleset::OneUnit;
+        assert_ne!(
+            fingerprint(&one_unit).0,
+            expected,
+            "the ruleset is not part of the fingerprint"
+        );
+        let mut forbidden = game.clone();
+        forbidden.forbidden_pours = vec![(1, 2)];
+        assert_ne!(
+            fingerprint(&forbidden).0,
+            expected,
+            "the forbidden pours are not part of the fingerprint"
+        );
+    }
+
+    #[test]
+    fn test_solve_and_lookup() {
+        let game = initialise_game(&["red, red, red, blue", "red, blue, blue, blue"], 4);
+        let mut known = KnownPuzzles::default();
+        assert!(known.lookup(&game).is_none(), "found an unknown puzzle");
+        let (moves, difficulty) = known.solve(&game).expect("puzzle should be solvable");
+        assert_eq!(moves.len(), 3, "wrong solution length");
+
+        // The same board with the tubes in a different order uses the stored solution.
+        let reordered =
+            initialise_game(&["", "red, blue, blue, blue", "", "red, red, red, blue"], 4);
+        let (reordered_moves, reordered_difficulty) = known
+            .lookup(&reordered)
+            .expect("reordered puzzle should be known");
+        assert_eq!(reordered_difficulty, difficulty, "wrong difficulty");
+        let mut replay = reordered.clone();
+        for a_move in reordered_moves.iter() {
+            assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+            replay.make_move(a_move);
+        }
+        assert!(
+            replay.is_game_complete(),
+            "stored solution does not complete the game"
+        );
+
+        let reloaded = KnownPuzzles::parse(&known.to_string()).expect("should parse");
+        assert_eq!(reloaded, known, "known puzzles did not round trip");
+    }
+
+    #[test]
+    fn test_parse_known_puzzles() {
+        let tests = [
+            ("# solved\n0123456789abcdef = 2: 0>2x1 1>2x3\n", true),
+            ("0123456789abcdef = 1:\n", true),
+            ("0123456789abcdef = 2 0>2x1\n", false),
+            ("0123456789abcdef = hard: 0>2x1\n", false),
+            ("0123456789abcdef = 2: 0-2x1\n", false),
+            ("0123456789abcdef = 2: 0>2\n", false),
+        ];
+        for test in tests {
+            assert_eq!(
+                KnownPuzzles::parse(test.0).is_ok(),
+                test.1,
+                "wrong result parsing {:?}",
+                test.0
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+pub mod achievements;
+pub mod analyze;
+pub mod cli;
+pub mod colour;
+pub mod config;
+pub mod debugger;
+pub mod game;
+pub mod generate;
+pub mod input_log;
+pub mod json;
+pub mod known_puzzles;
+pub mod opening_book;
+pub mod pack_progress;
+pub mod prelude;
+pub mod puzzles;
+pub mod render;
+pub mod repair;
+pub mod repl;
+pub mod review;
+pub mod rng;
+pub mod rollout;
+pub mod saved_setup;
+pub mod search;
+pub mod self_play;
+pub mod simulate;
+pub mod solutions;
+pub mod solver;
+pub mod spectate;
+pub mod stats;
+pub mod strategies;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod transpositions;
+pub mod tube;
+pub mod validate;
+pub mod verify;
+pub mod worksheet;
+
+pub const TUBE_SIZE: usize = 4;
@@ -1,22 +1,652 @@
-use std::io;
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::Path,
+    process,
+};
 
-use crate::{game::Game, repl::Repl};
+use water_sort_solver::{
+    achievements::Achievements,
+    analyze::{
+        check_report, comparison_table, load_board, load_transcript, opening_classes,
+        openings_string, read_board, BoardMetrics,
+    },
+    cli::{Command, PlayOptions, USAGE},
+    config::Config,
+    debugger::SearchDebugger,
+    game::{numbered_moves_string, Game},
+    generate::{generate_pack, pack_catalogue, PackOptions},
+    pack_progress::{LevelScore, PackProgress},
+    puzzles::{self, parse_catalogue, Puzzle},
+    render::{render_game, terminal_supports_colour, RenderMode},
+    repair::repair_board,
+    repl::Repl,
+    search::LeastVolumeSearch,
+    solutions::distinct_solutions,
+    solver::{OptimalSolver, Preset, Solution, Solver, Strategy},
+    spectate::Spectator,
+    stats::LifetimeStats,
+    strategies::{compare_strategies, strategy_table},
+    transpositions::TranspositionTable,
+    validate::{report_string, validate_catalogue},
+    worksheet::worksheet_html,
+};
 
-pub mod game;
-pub mod repl;
-pub mod solver;
-pub mod tube;
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match Command::parse(&args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            eprintln!("{USAGE}");
+            process::exit(1);
+        }
+    };
+    match command {
+        Command::Play(options) => play(options),
+        Command::Stats => show_stats(),
+        Command::Compare(files, preset) => compare(&files, preset),
+        Command::Openings(file, preset) => openings(&file, preset),
+        Command::Spectate(file) => spectate(&file),
+        Command::Solve(file, preset, strategy, trace, optimal) => {
+            solve(&file, preset, strategy, trace, optimal)
+        }
+        Command::LeastVolume(file, preset) => solve_least_volume(&file, preset),
+        Command::Worksheet(file, preset, page) => write_worksheet(&file, preset, &page),
+        Command::Debug(file, preset, strategy) => debug_search(&file, preset, strategy),
+        Command::Solutions(file, preset, count) => list_solutions(&file, preset, count),
+        Command::CompareStrategies(path, strategies, preset) => {
+            run_strategies(path.as_deref(), &strategies, preset)
+        }
+        Command::ValidateFile(path, preset, cache) => {
+            validate_file(&path, preset, cache.as_deref())
+        }
+        Command::Import(path, first) => import(&path, first),
+        Command::GeneratePack(options) => generate(&options),
+        Command::Check(path) => check(&path),
+        Command::Repair(path, preset, edits) => repair(&path, preset, edits),
+    }
+}
 
-pub const TUBE_SIZE: usize = 4;
+/// The most fixes `repair` lists.
+const REPAIRS_SHOWN: usize = 10;
 
-fn main() {
+fn repair(path: &str, preset: Preset, edits: usize) {
+    let mut game = match fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path, err))
+        .and_then(|contents| read_board(&contents).map_err(|err| format!("{}: {}", path, err)))
+    {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    game.preset = preset;
+    let repairs = repair_board(&game, edits, REPAIRS_SHOWN);
+    match repairs.first() {
+        None => {
+            eprintln!("Error: Changing at most {edits} of the segments of {path} does not give a board that can be solved");
+            process::exit(1);
+        }
+        Some(repair) if repair.edits.is_empty() => {
+            println!(
+                "{path}: the board needs no repair, as it can be solved in {} moves",
+                repair.moves
+            );
+            return;
+        }
+        Some(repair) => {
+            let mut problems = game.check_integrity();
+            if problems.is_empty() && !game.validate_setup() {
+                problems.push(String::from(
+                    "it must have two more tubes than colours, with 4 of every colour",
+                ));
+            }
+            let problems = match problems.is_empty() {
+                true => String::from("it cannot be solved"),
+                false => problems.join(", "),
+            };
+            let segments = match repair.edits.len() {
+                1 => String::from("1 segment"),
+                edits => format!("{edits} segments"),
+            };
+            println!(
+                "{path}: {problems}. Fixes changing {segments}, the first {REPAIRS_SHOWN} found at most:"
+            );
+        }
+    }
+    for (idx, repair) in repairs.iter().enumerate() {
+        let edits: Vec<String> = repair.edits.iter().map(|edit| edit.to_string()).collect();
+        println!(
+            "{:>3}: {} (solved in {} moves)",
+            idx + 1,
+            edits.join(", "),
+            repair.moves
+        );
+    }
+}
+
+fn check(path: &str) {
+    match fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path, err))
+        .and_then(|contents| check_report(&contents).map_err(|err| format!("{}: {}", path, err)))
+    {
+        Ok(report) => print!("{path}: {report}"),
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    }
+}
+
+fn import(path: &str, first: usize) {
+    match fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path, err))
+        .and_then(|list| puzzles::import_codes(&list, first))
+    {
+        Ok(catalogue) => print!("{catalogue}"),
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    }
+}
+
+fn generate(options: &PackOptions) {
+    match generate_pack(options) {
+        Ok(pack) => print!("{}", pack_catalogue(options, &pack)),
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    }
+}
+
+fn validate_file(path: &str, preset: Preset, cache: Option<&str>) {
+    let loaded = match cache {
+        Some(cache) => TranspositionTable::load(Path::new(cache)),
+        None => Ok(TranspositionTable::default()),
+    };
+    let mut table = match loaded {
+        Ok(table) => table,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    let reports = match fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path, err))
+        .and_then(|catalogue| validate_catalogue(&catalogue, preset, &mut table))
+    {
+        Ok(reports) => reports,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    if let Some(cache) = cache {
+        if let Err(err) = table.save(Path::new(cache)) {
+            eprintln!("Warning: {err}");
+        }
+    }
+    print!("{}", report_string(&reports));
+    if reports.iter().any(|report| !report.problems.is_empty()) {
+        process::exit(1);
+    }
+}
+
+fn run_strategies(path: Option<&str>, strategies: &[Strategy], preset: Preset) {
+    let puzzles = match path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}", path, err))
+            .and_then(|catalogue| puzzles::parse_catalogue(&catalogue)),
+        None => Ok(puzzles::builtin_puzzles()),
+    };
+    match puzzles {
+        Ok(puzzles) => {
+            let runs = compare_strategies(&puzzles, strategies, preset);
+            print!("{}", strategy_table(&runs, strategies));
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    }
+}
+
+fn openings(file: &str, preset: Preset) {
+    match load_board(Path::new(file)) {
+        Ok(mut game) => {
+            game.preset = preset;
+            print!("{}", openings_string(&opening_classes(&game)));
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    }
+}
+
+fn spectate(file: &str) {
+    let (start, moves) = match load_transcript(Path::new(file)) {
+        Ok(transcript) => transcript,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Warning: {err}. Using the default settings.");
+            Config::default()
+        }
+    };
+    let stdin = io::stdin();
+    let mut spectator = Spectator::new(stdin.lock(), io::stdout(), start, moves, &config);
+    if let Err(err) = spectator.run() {
+        eprintln!("Error: {err}");
+        process::exit(1);
+    }
+}
+
+fn solve(file: &str, preset: Preset, strategy: Option<Strategy>, trace: bool, optimal: bool) {
+    let mut game = match load_board(Path::new(file)) {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    game.preset = preset;
+    if let Some(reason) = Solver::new(&game).why_unsolvable() {
+        eprintln!("Error: The puzzle cannot be solved: {reason}");
+        process::exit(1);
+    }
+    let solution = match optimal {
+        true => {
+            let solver = OptimalSolver::new(&game);
+            match trace {
+                true => solver.search_traced(&mut |step| println!("{step}")),
+                false => solver.search(),
+            }
+        }
+        false => {
+            let solver = Solver::new(&game);
+            let strategy = strategy.unwrap_or_else(|| solver.strategy());
+            match trace {
+                true => solver.search_traced_with(strategy, &mut |step| println!("{step}")),
+                false => {
+                    // A long search keeps a line of progress on stderr, cleared once it is done
+                    let mut reported = false;
+                    let solution = solver.search_with_progress(strategy, &mut |progress| {
+                        eprint!("\rSearching: {progress}");
+                        reported = true;
+                    });
+                    if reported {
+                        eprint!("\r\x1b[2K");
+                    }
+                    solution
+                }
+            }
+        }
+    };
+    let solution = match (solution, optimal) {
+        (Some(solution), _) => solution,
+        (None, true) => {
+            eprintln!("Error: The puzzle cannot be solved");
+            process::exit(1);
+        }
+        (None, false) => {
+            eprintln!("Error: No solution was found within the limits of the {preset} preset");
+            process::exit(1);
+        }
+    };
+    print_solution(&solution);
+}
+
+fn write_worksheet(file: &str, preset: Preset, page: &str) {
+    let mut game = match load_board(Path::new(file)) {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    game.preset = preset;
+    if let Some(reason) = Solver::new(&game).why_unsolvable() {
+        eprintln!("Error: The puzzle cannot be solved: {reason}");
+        process::exit(1);
+    }
+    let solution = match Solver::new(&game).search() {
+        Some(solution) => solution,
+        None => {
+            eprintln!("Error: No solution was found within the limits of the {preset} preset");
+            process::exit(1);
+        }
+    };
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Warning: {err}. Using the default settings.");
+            Config::default()
+        }
+    };
+    let html = worksheet_html(&game, &solution.moves, config.theme);
+    match fs::write(page, html) {
+        Ok(_) => println!(
+            "Worksheet of a {} move solution written to {}",
+            solution.moves.len(),
+            page
+        ),
+        Err(err) => {
+            eprintln!("Error: Unable to write {}: {}", page, err);
+            process::exit(1);
+        }
+    }
+}
+
+fn solve_least_volume(file: &str, preset: Preset) {
+    let mut game = match load_board(Path::new(file)) {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    game.preset = preset;
+    match Solver::new(&game).search_using(&mut LeastVolumeSearch::default()) {
+        Some(solution) => print_solution(&solution),
+        None => {
+            eprintln!("Error: No solution was found within the limits of the {preset} preset");
+            process::exit(1);
+        }
+    }
+}
+
+fn print_solution(solution: &Solution) {
+    let shortest = match solution.optimal {
+        true => "",
+        false => ", though it might not be the shortest",
+    };
+    println!(
+        "Solved in {} moves pouring {} units, after searching {} states and skipping {} already seen{}:",
+        solution.moves.len(),
+        solution.volume(),
+        solution.states_searched,
+        solution.duplicates,
+        shortest
+    );
+    print!("{}", numbered_moves_string(&solution.moves));
+}
+
+fn debug_search(file: &str, preset: Preset, strategy: Option<Strategy>) {
+    let mut game = match load_board(Path::new(file)) {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    game.preset = preset;
+    let strategy = strategy.unwrap_or_else(|| Solver::new(&game).strategy());
+    println!("Stepping through a {strategy} search of {file}.");
+    let stdin = io::stdin();
+    match SearchDebugger::new(stdin.lock(), io::stdout(), &game, strategy) {
+        Some(mut debugger) => {
+            if let Err(err) = debugger.run() {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            }
+        }
+        None => {
+            eprintln!("Error: {strategy} cannot be stepped through");
+            process::exit(1);
+        }
+    }
+}
+
+fn list_solutions(file: &str, preset: Preset, count: usize) {
+    let mut game = match load_board(Path::new(file)) {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    game.preset = preset;
+    let set = match distinct_solutions(&game, count) {
+        Some(set) => set,
+        None => {
+            eprintln!("Error: No solution was found within the limits of the {preset} preset");
+            process::exit(1);
+        }
+    };
+    let moves = set.solutions.first().map_or(0, Vec::len);
+    let found = match set.complete {
+        true => "",
+        false => ", though there might be more",
+    };
+    println!(
+        "{} different shortest solutions of {} moves{}. {} more only reordered or interchanged tubes.",
+        set.solutions.len(),
+        moves,
+        found,
+        set.duplicates
+    );
+    for (idx, solution) in set.solutions.iter().enumerate() {
+        println!("\nSolution {}:", idx + 1);
+        print!("{}", numbered_moves_string(solution));
+    }
+}
+
+fn compare(files: &[String], preset: Preset) {
+    let mut boards = Vec::new();
+    for file in files {
+        match load_board(Path::new(file)) {
+            Ok(mut game) => {
+                game.preset = preset;
+                boards.push(BoardMetrics::measure(file, &game))
+            }
+            Err(err) => {
+                eprintln!("Error: {err}");
+                process::exit(1);
+            }
+        }
+    }
+    print!("{}", comparison_table(&boards));
+}
+
+fn show_stats() {
+    match LifetimeStats::load().and_then(|stats| Ok((stats, Achievements::load()?))) {
+        Ok((stats, achievements)) => {
+            print!("Lifetime statistics:\n{}", stats.summary());
+            print!("\n{}", achievements.summary());
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    }
+}
+
+/// Sets up a puzzle to be played with the options, showing its par and starting board.
+fn puzzle_game(puzzle: &Puzzle, options: &PlayOptions, config: &Config) -> Game {
+    if puzzle.par.is_set() {
+        println!("Par: {}", puzzle.par);
+    }
+    let mut game = puzzle.to_game();
+    game.ruleset = options.ruleset;
+    game.hint_limit = options.hint_limit;
+    game.time_limit = options.time_limit;
+    game.preset = options.preset;
+    game.history_limit = config.history_limit;
+    println!("Starting state of the game:");
+    println!("{}", render_game(&game, config));
+    game
+}
+
+/// Plays the levels of a catalogue one after another, from the level the last game of it
+/// stopped on, keeping the best score of every level completed. Stops when a level is left
+/// unfinished, the player chooses to, or every level has been completed.
+fn play_pack(path: &str, options: &PlayOptions, config: Config) {
+    let puzzles = match fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read {}: {}", path, err))
+        .and_then(|catalogue| parse_catalogue(&catalogue))
+    {
+        Ok(puzzles) if puzzles.is_empty() => {
+            eprintln!("Error: There are no levels in {}", path);
+            process::exit(1);
+        }
+        Ok(puzzles) => puzzles,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            process::exit(1);
+        }
+    };
+    // The same pack is found again from wherever it is played
+    let key = fs::canonicalize(path).map_or(path.to_string(), |path| path.display().to_string());
+    let mut all_progress = PackProgress::load().unwrap_or_else(|err| {
+        eprintln!("Warning: {err}. Starting the pack afresh.");
+        PackProgress::default()
+    });
+    let mut progress = all_progress.packs.get(&key).cloned().unwrap_or_default();
+    let mut level = progress
+        .current
+        .filter(|current| puzzles.iter().any(|puzzle| puzzle.number == *current))
+        .unwrap_or(puzzles[0].number);
+    loop {
+        let (place, puzzle) = puzzles
+            .iter()
+            .enumerate()
+            .find(|(_, puzzle)| puzzle.number == level)
+            .expect("the level should be in the pack");
+        progress.current = Some(level);
+        println!("Level {} of {}: {}", place + 1, puzzles.len(), puzzle.name);
+        let game = puzzle_game(puzzle, options, &config);
+        let mut repl = Repl::new(io::stdin().lock(), io::stdout(), game, config.clone());
+        repl.set_pack_report(progress.report(&puzzles));
+        repl.play();
+        let score = repl.completed_game().map(|game| LevelScore {
+            moves: game.current_move,
+            stars: game.stats.stars(),
+        });
+        drop(repl);
+        let next = match score {
+            Some(score) => {
+                if progress.record(level, score) {
+                    println!("New best for level {}: {}", level, score);
+                }
+                progress.next_level(&puzzles, level)
+            }
+            None => Some(level),
+        };
+        progress.current = next;
+        all_progress.packs.insert(key.clone(), progress.clone());
+        if let Err(err) = all_progress.save() {
+            eprintln!("Warning: {err}");
+        }
+        print!("{}", progress.report(&puzzles));
+        let next = match (score, next) {
+            (_, None) => {
+                println!("You have completed every level of the pack!");
+                return;
+            }
+            (None, Some(_)) => {
+                println!("Your place in the pack is saved. Play it again to carry on.");
+                return;
+            }
+            (Some(_), Some(next)) => next,
+        };
+        print!(
+            "Press Enter to play level {}, or enter quit to stop: ",
+            next
+        );
+        io::stdout().flush().expect("should have flushed stdout");
+        let mut answer = String::new();
+        if io::stdin()
+            .read_line(&mut answer)
+            .map_or(true, |read| read == 0)
+            || answer.trim() == "quit"
+        {
+            return;
+        }
+        level = next;
+    }
+}
+
+fn play(options: PlayOptions) {
     println!("Welcome to Water Sorter Solver!");
+    let mut config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Warning: {err}. Using the default settings.");
+            Config::default()
+        }
+    };
+    if options.assistant {
+        config.assistant = true;
+    }
+    if options.strict_input {
+        config.strict_input = true;
+    }
+    if let Some(width) = options.width {
+        config.width = Some(width);
+    }
+    if config.render_mode.needs_colour() && !options.force_colour && !terminal_supports_colour() {
+        eprintln!(
+            "Note: The terminal does not show colour, so the board is drawn with symbols instead of render = {}. Add --force-colour to keep it.",
+            config.render_mode
+        );
+        config.render_mode = RenderMode::Symbols;
+    }
+    if let Some(number) = options.builtin {
+        let puzzle = match puzzles::get_builtin_puzzle(number) {
+            Some(puzzle) => puzzle,
+            None => {
+                eprintln!(
+                    "Error: There is no built-in puzzle {number}. The available puzzles are:"
+                );
+                for puzzle in puzzles::builtin_puzzles() {
+                    eprintln!("    {}: {}", puzzle.number, puzzle.name);
+                }
+                process::exit(1);
+            }
+        };
+        println!(
+            "Starting built-in puzzle {}: {}",
+            puzzle.number, puzzle.name
+        );
+        let game = puzzle_game(&puzzle, &options, &config);
+        let mut repl = Repl::new(io::stdin().lock(), io::stdout(), game, config);
+        repl.play();
+        return;
+    }
+    if let Some(path) = &options.pack {
+        play_pack(path, &options, config);
+        return;
+    }
+
     println!("Starting a new game...");
-    let game = Game::default();
-    let mut repl = Repl::new(io::stdin(), io::stdout(), game);
+    let game = Game {
+        ruleset: options.ruleset,
+        hint_limit: options.hint_limit,
+        time_limit: options.time_limit,
+        preset: options.preset,
+        history_limit: config.history_limit,
+        ..Default::default()
+    };
+    let mut repl = Repl::new(io::stdin().lock(), io::stdout(), game, config);
     let mut initialized = false;
     while !initialized {
         initialized = repl.start();
+        if repl.setup_saved() {
+            return;
+        }
+        if repl.setup_rejected() {
+            process::exit(1);
+        }
     }
     repl.play();
 }
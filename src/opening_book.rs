@@ -0,0 +1,104 @@
+use crate::{
+    game::{Game, Move},
+    known_puzzles::KnownPuzzles,
+    solver::Solver,
+};
+
+/// The best first moves of common boards, generated by `cargo run --example opening_book`.
+const OPENING_BOOK: &str = include_str!("opening_book.txt");
+/// Only boards up to this size are kept in the book, as larger boards are rarely repeated.
+pub const MAX_BOOK_TUBES: usize = 8;
+
+/// The embedded opening book. Entries use the same format as the known puzzles, keeping only the
+/// first move of each solution.
+pub fn opening_book() -> KnownPuzzles {
+    KnownPuzzles::parse(OPENING_BOOK).expect("opening book should be well formed")
+}
+
+/// Looks up the best first move for the game in the opening book.
+pub fn book_move(game: &Game) -> Option<Move> {
+    if game.tubes.len() > MAX_BOOK_TUBES {
+        return None;
+    }
+    opening_book().first_move(game)
+}
+
+/// Solves every board that fits in the book, keeping the first move of each solution.
+pub fn build_opening_book(games: &[Game]) -> KnownPuzzles {
+    let mut book = KnownPuzzles::default();
+    for game in games.iter() {
+        if game.tubes.len() > MAX_BOOK_TUBES {
+            continue;
+        }
+        if let Some(solution) = Solver::new(game).search() {
+            let first_move = &solution.moves[..solution.moves.len().min(1)];
+            book.insert(game, first_move, solution.difficulty());
+        }
+    }
+    book
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzles::builtin_puzzles;
+
+    use super::*;
+
+    #[test]
+    fn test_book_covers_builtin_puzzles() {
+        for puzzle in builtin_puzzles() {
+            let game = puzzle.to_game();
+            if game.tubes.len() > MAX_BOOK_TUBES {
+                continue;
+            }
+            let book_move = book_move(&game).unwrap_or_else(|| {
+                panic!(
+                    "no valid book move for built-in puzzle {} ({})",
+                    puzzle.number, puzzle.name
+                )
+            });
+            assert!(
+                game.validate_move(&book_move),
+                "invalid book move {} for built-in puzzle {}",
+                book_move,
+                puzzle.number
+            );
+        }
+    }
+
+    #[test]
+    fn test_book_move_is_optimal() {
+        // Only the small boards are checked, to keep the test quick.
+        for puzzle in builtin_puzzles().into_iter().take(4) {
+            let game = puzzle.to_game();
+            let optimal = Solver::new(&game)
+                .solve()
+                .expect("should be solvable")
+                .len();
+            let mut after = game.clone();
+            after.make_move(&book_move(&game).expect("should have a book move"));
+            let remaining = Solver::new(&after)
+                .solve()
+                .expect("should be solvable")
+                .len();
+            assert_eq!(
+                remaining + 1,
+                optimal,
+                "book move for built-in puzzle {} is not on a shortest solution",
+                puzzle.number
+            );
+        }
+    }
+
+    #[test]
+    fn test_book_ignores_large_boards() {
+        let mut game = Game::default();
+        game.init_tubes(MAX_BOOK_TUBES + 1);
+        game.init_tube_contents(0, String::from("red, blue, blue, blue"));
+        game.init_tube_contents(1, String::from("red, red, red, blue"));
+        assert!(
+            book_move(&game).is_none(),
+            "found a book move for a large board"
+        );
+    }
+}
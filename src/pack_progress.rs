@@ -0,0 +1,326 @@
+use std::{cmp::Reverse, collections::BTreeMap, fmt::Display, fs};
+
+use crate::{config::home_dir, puzzles::Puzzle};
+
+const PROGRESS_FILE_NAME: &str = "pack_progress";
+/// The most stars a level can be rated, as in `GameStats::stars`.
+const MAX_STARS: usize = 3;
+
+/// How well a level of a pack was completed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LevelScore {
+    pub moves: usize,
+    pub stars: usize,
+}
+
+impl LevelScore {
+    /// Whether this is a better score than `other`: more stars, or as many in fewer moves.
+    pub fn beats(&self, other: &LevelScore) -> bool {
+        (self.stars, Reverse(self.moves)) > (other.stars, Reverse(other.moves))
+    }
+}
+
+impl Display for LevelScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stars = self.stars.min(MAX_STARS);
+        write!(
+            f,
+            "{} moves {}{}",
+            self.moves,
+            "★".repeat(stars),
+            "☆".repeat(MAX_STARS - stars)
+        )
+    }
+}
+
+/// How far through one pack a player has got: the level they are on and the best score of every
+/// level they have completed, both by the number of the level in the catalogue.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct Progress {
+    /// The level to play next, or None to start from the first.
+    pub current: Option<usize>,
+    pub best: BTreeMap<usize, LevelScore>,
+}
+
+impl Progress {
+    /// Records a completed level, keeping the better of its scores. Returns whether the score is
+    /// a new best.
+    pub fn record(&mut self, level: usize, score: LevelScore) -> bool {
+        match self.best.get(&level) {
+            Some(best) if !score.beats(best) => false,
+            _ => {
+                self.best.insert(level, score);
+                true
+            }
+        }
+    }
+
+    /// The level to play after `level`: the next one not yet completed, looking from the one
+    /// after it to the end of the pack and then from the start. None once every level has been
+    /// completed.
+    pub fn next_level(&self, puzzles: &[Puzzle], level: usize) -> Option<usize> {
+        let place = puzzles
+            .iter()
+            .position(|puzzle| puzzle.number == level)
+            .map_or(0, |place| place + 1);
+        puzzles[place..]
+            .iter()
+            .chain(puzzles[..place].iter())
+            .map(|puzzle| puzzle.number)
+            .find(|number| !self.best.contains_key(number))
+    }
+
+    /// How much of the pack has been completed, with the best score of every level completed,
+    /// such as `2 of 20 levels completed (10%), on level 3`.
+    pub fn report(&self, puzzles: &[Puzzle]) -> String {
+        let completed: Vec<&Puzzle> = puzzles
+            .iter()
+            .filter(|puzzle| self.best.contains_key(&puzzle.number))
+            .collect();
+        let mut report = format!(
+            "{} of {} levels completed ({}%)",
+            completed.len(),
+            puzzles.len(),
+            // Rounded down, so that a pack is not shown complete before it is
+            completed.len() * 100 / puzzles.len().max(1)
+        );
+        if let Some(current) = self.current {
+            report.push_str(&format!(", on level {}", current));
+        }
+        report.push('\n');
+        for puzzle in completed {
+            report.push_str(&format!(
+                "  {}: {} - {}\n",
+                puzzle.number, puzzle.name, self.best[&puzzle.number]
+            ));
+        }
+        report
+    }
+}
+
+/// The progress through every pack played, by the path of its catalogue, stored alongside the
+/// settings. Each pack is a `[path]` line followed by `current = <level>` and a
+/// `<level> = <moves> moves, <stars> stars` line for every level completed.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct PackProgress {
+    pub packs: BTreeMap<String, Progress>,
+}
+
+impl PackProgress {
+    pub fn parse(contents: &str) -> Result<PackProgress, String> {
+        let mut progress = PackProgress::default();
+        let mut pack: Option<&mut Progress> = None;
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(path) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                pack = Some(progress.packs.entry(path.to_string()).or_default());
+                continue;
+            }
+            let pack = match pack.as_mut() {
+                Some(pack) => pack,
+                None => return Err(format!("Line {}: expected '[<pack>]'", line_num + 1)),
+            };
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("Line {}: expected 'key = value'", line_num + 1)),
+            };
+            let number = |value: &str| {
+                value.trim().parse::<usize>().map_err(|_| {
+                    format!(
+                        "Line {}: unable to parse {} to a number",
+                        line_num + 1,
+                        value.trim()
+                    )
+                })
+            };
+            if key == "current" {
+                pack.current = Some(number(value)?);
+                continue;
+            }
+            let level = number(key)?;
+            let score = value
+                .split_once(',')
+                .and_then(|(moves, stars)| {
+                    Some((
+                        moves.trim().strip_suffix("moves")?,
+                        stars.trim().strip_suffix("stars")?,
+                    ))
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "Line {}: expected '<moves> moves, <stars> stars', got '{}'",
+                        line_num + 1,
+                        value
+                    )
+                })?;
+            pack.best.insert(
+                level,
+                LevelScore {
+                    moves: number(score.0)?,
+                    stars: number(score.1)?,
+                },
+            );
+        }
+        Ok(progress)
+    }
+
+    /// Loads the progress through every pack. A missing file means no pack has been played yet.
+    pub fn load() -> Result<PackProgress, String> {
+        let path = match home_dir() {
+            Some(dir) => dir.join(PROGRESS_FILE_NAME),
+            None => return Ok(PackProgress::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => PackProgress::parse(&contents)
+                .map_err(|err| format!("Unable to read {}: {}", path.display(), err)),
+            Err(_) => Ok(PackProgress::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let dir = match home_dir() {
+            Some(dir) => dir,
+            None => {
+                return Err("Unable to find a home directory to save pack progress in".to_string())
+            }
+        };
+        let path = dir.join(PROGRESS_FILE_NAME);
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, self.to_string()))
+            .map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+    }
+}
+
+impl Display for PackProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (path, progress) in self.packs.iter() {
+            writeln!(f, "[{}]", path)?;
+            if let Some(current) = progress.current {
+                writeln!(f, "current = {}", current)?;
+            }
+            for (level, score) in progress.best.iter() {
+                writeln!(
+                    f,
+                    "{} = {} moves, {} stars",
+                    level, score.moves, score.stars
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzles::builtin_puzzles;
+
+    fn score(moves: usize, stars: usize) -> LevelScore {
+        LevelScore { moves, stars }
+    }
+
+    #[test]
+    fn test_parse_pack_progress() {
+        let tests = [
+            ("", Some(PackProgress::default())),
+            (
+                "[packs/easy.txt]\ncurrent = 3\n1 = 14 moves, 3 stars\n2 = 20 moves, 1 stars\n\n# another\n[hard.txt]\n",
+                Some(PackProgress {
+                    packs: BTreeMap::from([
+                        (
+                            String::from("packs/easy.txt"),
+                            Progress {
+                                current: Some(3),
+                                best: BTreeMap::from([(1, score(14, 3)), (2, score(20, 1))]),
+                            },
+                        ),
+                        (String::from("hard.txt"), Progress::default()),
+                    ]),
+                }),
+            ),
+            ("current = 3\n", None),
+            ("[easy.txt]\ncurrent = three\n", None),
+            ("[easy.txt]\n1 = 14 moves\n", None),
+            ("[easy.txt]\n1 = many moves, 3 stars\n", None),
+            ("[easy.txt]\ncurrent 3\n", None),
+        ];
+        for test in tests {
+            let result = PackProgress::parse(test.0).ok();
+            assert_eq!(result, test.1, "wrong progress parsed from {:?}", test.0);
+            if let Some(progress) = result {
+                assert_eq!(
+                    PackProgress::parse(&progress.to_string()).ok(),
+                    Some(progress),
+                    "progress did not round trip"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let mut progress = Progress::default();
+        // (score recorded for level 1, whether it is a new best, the best afterwards)
+        let tests = [
+            (score(20, 2), true, score(20, 2)),
+            (score(25, 2), false, score(20, 2)),
+            (score(18, 2), true, score(18, 2)),
+            (score(30, 3), true, score(30, 3)),
+            (score(10, 1), false, score(30, 3)),
+        ];
+        for test in tests {
+            assert_eq!(
+                progress.record(1, test.0),
+                test.1,
+                "wrong new best for {:?}",
+                test.0
+            );
+            assert_eq!(progress.best[&1], test.2, "wrong best after {:?}", test.0);
+        }
+    }
+
+    #[test]
+    fn test_next_level_and_report() {
+        let puzzles: Vec<Puzzle> = builtin_puzzles().into_iter().take(4).collect();
+        let mut progress = Progress::default();
+        assert_eq!(
+            progress.next_level(&puzzles, 1),
+            Some(2),
+            "wrong next level"
+        );
+        progress.record(2, score(4, 3));
+        progress.record(3, score(9, 2));
+        // (level just completed, the next level)
+        let tests = [(1, Some(4)), (4, Some(1)), (3, Some(4))];
+        for test in tests {
+            assert_eq!(
+                progress.next_level(&puzzles, test.0),
+                test.1,
+                "wrong level after {}",
+                test.0
+            );
+        }
+        progress.current = Some(4);
+        assert_eq!(
+            progress.report(&puzzles),
+            format!(
+                "2 of 4 levels completed (50%), on level 4\n  2: {} - 4 moves ★★★\n  3: {} - 9 moves ★★☆\n",
+                puzzles[1].name, puzzles[2].name
+            ),
+            "wrong report"
+        );
+        progress.record(1, score(3, 3));
+        progress.record(4, score(8, 3));
+        assert_eq!(
+            progress.next_level(&puzzles, 4),
+            None,
+            "there should be no level left"
+        );
+    }
+}
@@ -0,0 +1,36 @@
+//! The types most programs using the library need, for a single import:
+//!
+//! ```
+//! use water_sort_solver::prelude::*;
+//!
+//! let mut game = Game::default();
+//! game.init_tubes(4);
+//! game.init_tube_contents(0, String::from("red, red, red, blue"));
+//! game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+//! game.preset = Preset::Quick;
+//! let solution = Solver::new(&game).search().expect("puzzle should be solvable");
+//! assert!(!solution.moves.is_empty());
+//! ```
+//!
+//! How hard the solver works is set with a `Preset` on the game, which bundles the
+//! `SearchSettings`. `Solver::with_limits` puts `SearchLimits` on time and states searched, and
+//! `Solver::try_solve` then says whether a board was solved, cannot be solved, with an
+//! `UnsolvableReason`, or was given up on.
+//! `Solver::solve_with_stats` returns the `SolveStats` of a search next to its solution.
+//! `minimum_moves_lower_bound` is the fewest moves a board could still take, for a search of its
+//! own. `verify_solution` checks moves from anywhere against a board, with a `VerifyError` saying
+//! why they are not a solution.
+
+pub use crate::{
+    colour::Rgb,
+    game::{Game, Move, MoveError, MoveRequest, Ruleset},
+    search::minimum_moves_lower_bound,
+    simulate::{simulate, Outcome, Trajectory},
+    solver::{
+        Preset, SearchLimits, SearchSettings, Solution, SolveOutcome, SolveStats, Solver, Strategy,
+        UnsolvableReason,
+    },
+    tube::Tube,
+    verify::{verify_solution, VerifyError},
+    TUBE_SIZE,
+};
@@ -0,0 +1,501 @@
+use std::time::Duration;
+
+use crate::{
+    colour::LEVEL_COLOURS,
+    game::{Game, Par},
+    solver::Solver,
+    TUBE_SIZE,
+};
+
+const BUILTIN_CATALOGUE: &str = include_str!("puzzles/builtin.txt");
+/// The par time allowed for every move of the shortest solution.
+pub(crate) const PAR_SECONDS_PER_MOVE: u64 = 6;
+
+#[derive(Clone)]
+pub struct Puzzle {
+    pub number: usize,
+    pub name: String,
+    pub tubes: Vec<String>,
+    /// Pours the puzzle does not allow, as `(tube_from, tube_to)` counted from 0.
+    pub forbidden_pours: Vec<(usize, usize)>,
+    pub par: Par,
+}
+
+impl Puzzle {
+    pub fn to_game(&self) -> Game {
+        let mut game = Game::default();
+        game.init_tubes(self.tubes.len());
+        for (idx, tube) in self.tubes.iter().enumerate() {
+            game.init_tube_contents(idx, tube.clone());
+        }
+        game.forbidden_pours = self.forbidden_pours.clone();
+        game.par = self.par;
+        game
+    }
+
+    /// Works out par from the shortest solution: its number of moves, with a few seconds for each
+    /// of them. Returns None if the puzzle could not be solved.
+    pub fn compute_par(&self) -> Option<Par> {
+        let moves = Solver::new(&self.to_game()).solve()?.len();
+        Some(Par {
+            moves: Some(moves),
+            time: Some(Duration::from_secs(moves as u64 * PAR_SECONDS_PER_MOVE)),
+        })
+    }
+}
+
+pub fn builtin_puzzles() -> Vec<Puzzle> {
+    parse_catalogue(BUILTIN_CATALOGUE).expect("built-in catalogue should be well formed")
+}
+
+pub fn get_builtin_puzzle(number: usize) -> Option<Puzzle> {
+    builtin_puzzles()
+        .into_iter()
+        .find(|puzzle| puzzle.number == number)
+}
+
+pub fn parse_catalogue(catalogue: &str) -> Result<Vec<Puzzle>, String> {
+    let mut puzzles: Vec<Puzzle> = Vec::new();
+    for (line_num, line) in catalogue.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let (number, name) = match header.split_once(']') {
+                Some(parts) => parts,
+                None => return Err(format!("Line {}: unterminated puzzle header", line_num + 1)),
+            };
+            let number = match number.trim().parse::<usize>() {
+                Ok(number) => number,
+                Err(_) => {
+                    return Err(format!(
+                        "Line {}: expected a puzzle number in the header",
+                        line_num + 1
+                    ))
+                }
+            };
+            if puzzles.iter().any(|puzzle| puzzle.number == number) {
+                return Err(format!(
+                    "Line {}: puzzle number {} is used more than once",
+                    line_num + 1,
+                    number
+                ));
+            }
+            puzzles.push(Puzzle {
+                number,
+                name: name.trim().to_string(),
+                tubes: Vec::new(),
+                forbidden_pours: Vec::new(),
+                par: Par::default(),
+            });
+            continue;
+        }
+        if let Some(par) = line.strip_prefix("par ") {
+            let puzzle = match puzzles.last_mut() {
+                Some(puzzle) => puzzle,
+                None => {
+                    return Err(format!(
+                        "Line {}: par found before the first puzzle header",
+                        line_num + 1
+                    ))
+                }
+            };
+            parse_par(par, &mut puzzle.par)
+                .map_err(|err| format!("Line {}: {}", line_num + 1, err))?;
+            continue;
+        }
+        if let Some(pour) = line.strip_prefix("forbid ") {
+            let pour = parse_forbidden_pour(pour)
+                .map_err(|err| format!("Line {}: {}", line_num + 1, err))?;
+            match puzzles.last_mut() {
+                Some(puzzle) => puzzle.forbidden_pours.push(pour),
+                None => {
+                    return Err(format!(
+                        "Line {}: forbidden pour found before the first puzzle header",
+                        line_num + 1
+                    ))
+                }
+            }
+            continue;
+        }
+        match puzzles.last_mut() {
+            Some(puzzle) => puzzle.tubes.push(line.to_string()),
+            None => {
+                return Err(format!(
+                    "Line {}: tube contents found before the first puzzle header",
+                    line_num + 1
+                ))
+            }
+        }
+    }
+
+    for puzzle in puzzles.iter() {
+        for (from, to) in puzzle.forbidden_pours.iter() {
+            if *from >= puzzle.tubes.len() || *to >= puzzle.tubes.len() {
+                return Err(format!(
+                    "Puzzle {}: forbidden pour {} -> {} refers to a tube that does not exist",
+                    puzzle.number,
+                    from + 1,
+                    to + 1
+                ));
+            }
+        }
+    }
+
+    Ok(puzzles)
+}
+
+/// Parses a `moves <number>` or `time <seconds>` par value into `par`.
+fn parse_par(value: &str, par: &mut Par) -> Result<(), String> {
+    let (kind, number) = match value.trim().split_once(' ') {
+        Some((kind, number)) => (kind, number.trim()),
+        None => {
+            return Err(format!(
+                "expected 'par moves <number>' or 'par time <seconds>', got '{}'",
+                value
+            ))
+        }
+    };
+    let number = match number.parse::<u64>() {
+        Ok(number) => number,
+        Err(_) => return Err(format!("unable to parse {} to a number", number)),
+    };
+    match kind {
+        "moves" => par.moves = Some(number as usize),
+        "time" => par.time = Some(Duration::from_secs(number)),
+        _ => return Err(format!("unknown par '{}'. Expected moves or time", kind)),
+    }
+    Ok(())
+}
+
+/// Adds par lines, computed from the shortest solution, to every puzzle in the catalogue that has
+/// no par yet. Everything else in the catalogue, including comments, is kept as it is.
+pub fn embed_par(catalogue: &str) -> Result<String, String> {
+    let puzzles = parse_catalogue(catalogue)?;
+    let mut puzzles = puzzles.iter();
+    let mut out = String::new();
+    for line in catalogue.lines() {
+        out.push_str(line);
+        out.push('\n');
+        if !line.trim().starts_with('[') {
+            continue;
+        }
+        let puzzle = puzzles
+            .next()
+            .expect("every header should be a parsed puzzle");
+        if puzzle.par.is_set() {
+            continue;
+        }
+        let par = match puzzle.compute_par() {
+            Some(par) => par,
+            None => return Err(format!("Puzzle {} could not be solved", puzzle.number)),
+        };
+        if let (Some(moves), Some(time)) = (par.moves, par.time) {
+            out.push_str(format!("par moves {}\npar time {}\n", moves, time.as_secs()).as_str());
+        }
+    }
+    Ok(out)
+}
+
+/// Converts a list of boards in compact code form, one to a line, into a catalogue with the
+/// puzzles numbered from `first_number`. A code lists the tubes separated by `/`, each tube as one
+/// symbol for every segment, top first, with `-` or nothing for an empty tube. The code may be
+/// followed by the name of the level:
+///
+/// ```text
+/// RRRB/RBBB/-/- First Pour
+/// ```
+///
+/// Symbols stand for colours, which are given the usual colour names in the order they first
+/// appear on the board. Blank lines and lines starting with `#` are skipped. Only the shape of each
+/// board is checked, so the catalogue should be checked with `validate-file` before it is used.
+pub fn import_codes(list: &str, first_number: usize) -> Result<String, String> {
+    let mut catalogue = String::new();
+    let mut number = first_number;
+    for (line_num, line) in list.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (code, name) = match line.split_once(char::is_whitespace) {
+            Some((code, name)) => (code, name.trim()),
+            None => (line, ""),
+        };
+        let tubes =
+            parse_board_code(code).map_err(|err| format!("Line {}: {}", line_num + 1, err))?;
+        if !catalogue.is_empty() {
+            catalogue.push('\n');
+        }
+        match name {
+            "" => catalogue.push_str(&format!("[{}] Level {}\n", number, number)),
+            name => catalogue.push_str(&format!("[{}] {}\n", number, name)),
+        }
+        for tube in tubes {
+            catalogue.push_str(&tube);
+            catalogue.push('\n');
+        }
+        number += 1;
+    }
+    Ok(catalogue)
+}
+
+/// Converts one board in compact code form, as read by `import_codes`, into the contents of its
+/// tubes, as written in a catalogue.
+pub fn parse_board_code(code: &str) -> Result<Vec<String>, String> {
+    let mut symbols: Vec<char> = Vec::new();
+    let mut tubes = Vec::new();
+    for (idx, tube) in code.split('/').enumerate() {
+        let tube = if tube == "-" { "" } else { tube };
+        if tube.chars().count() > TUBE_SIZE {
+            return Err(format!(
+                "tube {} has more than {} segments",
+                idx + 1,
+                TUBE_SIZE
+            ));
+        }
+        let mut colours = Vec::with_capacity(TUBE_SIZE);
+        for symbol in tube.chars() {
+            if !symbol.is_alphanumeric() {
+                return Err(format!("unexpected '{}' in tube {}", symbol, idx + 1));
+            }
+            let colour = match symbols.iter().position(|known| *known == symbol) {
+                Some(colour) => colour,
+                None => {
+                    symbols.push(symbol);
+                    symbols.len() - 1
+                }
+            };
+            match LEVEL_COLOURS.get(colour) {
+                Some(colour) => colours.push(*colour),
+                None => {
+                    return Err(format!(
+                        "a board can have at most {} colours",
+                        LEVEL_COLOURS.len()
+                    ))
+                }
+            }
+        }
+        if colours.is_empty() {
+            tubes.push(String::from("empty"));
+        } else {
+            tubes.push(colours.join(", "));
+        }
+    }
+    Ok(tubes)
+}
+
+/// Parses a forbidden pour written as `<tube_from> -> <tube_to>`, with the tubes counted from 1.
+fn parse_forbidden_pour(pour: &str) -> Result<(usize, usize), String> {
+    let (from, to) = match pour.split_once("->") {
+        Some(parts) => parts,
+        None => {
+            return Err(format!(
+                "expected 'forbid <tube_from> -> <tube_to>', got '{}'",
+                pour
+            ))
+        }
+    };
+    let tube = |value: &str| match value.trim().parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number - 1),
+        _ => Err(format!("unable to parse {} to a tube number", value.trim())),
+    };
+    let (from, to) = (tube(from)?, tube(to)?);
+    if from == to {
+        return Err(format!(
+            "a tube cannot pour into itself: {} -> {}",
+            from + 1,
+            to + 1
+        ));
+    }
+    Ok((from, to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_puzzles_are_valid() {
+        let puzzles = builtin_puzzles();
+        assert!(!puzzles.is_empty(), "built-in catalogue is empty");
+        for puzzle in puzzles.iter() {
+            let game = puzzle.to_game();
+            assert!(
+                game.validate_setup(),
+                "built-in puzzle {} ({}) has an invalid setup",
+                puzzle.number,
+                puzzle.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_builtin_puzzle() {
+        let puzzle = get_builtin_puzzle(7).expect("should have found built-in puzzle 7");
+        assert_eq!(
+            puzzle.number, 7,
+            "wrong puzzle returned. Expected: 7, got: {}",
+            puzzle.number
+        );
+        assert!(get_builtin_puzzle(0).is_none(), "found a puzzle numbered 0");
+    }
+
+    #[test]
+    fn test_parse_catalogue() {
+        // (number, name, number of tubes) for each expected puzzle, or None if parsing should fail
+        type Expected<'a> = Option<Vec<(usize, &'a str, usize)>>;
+        let tests: Vec<(&str, Expected)> = vec![
+            (
+                "# comment\n[1] One\nred, blue\nblue, red\n\nempty\nempty\n[2]   Two  \nred\n",
+                Some(vec![(1, "One", 4), (2, "Two", 1)]),
+            ),
+            ("", Some(Vec::new())),
+            ("red, blue\n[1] One\n", None),
+            ("[one] One\nred\n", None),
+            ("[1 One\nred\n", None),
+            ("[1] One\nred\n[1] Again\nblue\n", None),
+        ];
+        for test in tests {
+            let result = parse_catalogue(test.0);
+            match test.1 {
+                Some(expected) => {
+                    let puzzles = result.unwrap_or_else(|err| {
+                        panic!("unexpected error parsing {:?}: {}", test.0, err)
+                    });
+                    assert_eq!(
+                        puzzles.len(),
+                        expected.len(),
+                        "wrong number of puzzles. Expected: {}, got: {}",
+                        expected.len(),
+                        puzzles.len()
+                    );
+                    for (puzzle, (number, name, num_of_tubes)) in puzzles.iter().zip(expected) {
+                        assert_eq!(puzzle.number, number, "puzzle number is wrong");
+                        assert_eq!(puzzle.name, name, "puzzle name is wrong");
+                        assert_eq!(
+                            puzzle.tubes.len(),
+                            num_of_tubes,
+                            "wrong number of tubes. Expected: {}, got: {}",
+                            num_of_tubes,
+                            puzzle.tubes.len()
+                        );
+                    }
+                }
+                None => assert!(result.is_err(), "expected an error parsing {:?}", test.0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_par() {
+        let tests: Vec<(&str, Option<Par>)> = vec![
+            (
+                "[1] Par\npar moves 12\nred\npar time 90\n",
+                Some(Par {
+                    moves: Some(12),
+                    time: Some(Duration::from_secs(90)),
+                }),
+            ),
+            (
+                "[1] Moves only\nred\npar moves 3\n",
+                Some(Par {
+                    moves: Some(3),
+                    time: None,
+                }),
+            ),
+            ("[1] None\nred\n", Some(Par::default())),
+            ("par moves 3\n[1] Early\nred\n", None),
+            ("[1] Bad\npar moves lots\n", None),
+            ("[1] Bad\npar speed 3\n", None),
+            ("[1] Bad\npar 3\n", None),
+        ];
+        for test in tests {
+            match (parse_catalogue(test.0), test.1) {
+                (Ok(puzzles), Some(expected)) => assert_eq!(
+                    puzzles[0].par, expected,
+                    "wrong par parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_embed_par() {
+        let catalogue = "# comment\n[1] First\nred, red, red, blue\nred, blue, blue, blue\nempty\nempty\n\n[2] Set\npar moves 9\nblue, blue, blue, blue\nred, red, red, red\nempty\nempty\n";
+        let expected = "# comment\n[1] First\npar moves 3\npar time 18\nred, red, red, blue\nred, blue, blue, blue\nempty\nempty\n\n[2] Set\npar moves 9\nblue, blue, blue, blue\nred, red, red, red\nempty\nempty\n";
+        let result = embed_par(catalogue).expect("catalogue should be valid");
+        assert_eq!(result, expected, "wrong catalogue with par embedded");
+        assert!(
+            embed_par("[1] Stuck\nred, blue, red, blue\nblue, red, blue, red\nred, blue, red, blue\nblue, red, blue, red\n").is_err(),
+            "expected an error embedding par for an unsolvable puzzle"
+        );
+    }
+
+    #[test]
+    fn test_import_codes() {
+        let tests: Vec<(&str, Option<&str>)> =
+            vec![
+            (
+                "# a dump\nRRRB/RBBB/-/- First Pour\n\nab/ba//aabb\n",
+                Some("[3] First Pour\nred, red, red, blue\nred, blue, blue, blue\nempty\nempty\n\n\
+                      [4] Level 4\nred, blue\nblue, red\nempty\nred, red, blue, blue\n"),
+            ),
+            ("", Some("")),
+            ("RRRRR/-/-\n", None),
+            ("RR,R/-\n", None),
+            ("0123/4567/89ab/cdef/ghij/-/-\n", None),
+        ];
+        for test in tests {
+            let result = import_codes(test.0, 3);
+            match test.1 {
+                Some(expected) => {
+                    let catalogue = result.unwrap_or_else(|err| {
+                        panic!("unexpected error importing {:?}: {}", test.0, err)
+                    });
+                    assert_eq!(catalogue, expected, "wrong catalogue from {:?}", test.0);
+                    assert!(
+                        parse_catalogue(&catalogue).is_ok(),
+                        "the catalogue should parse"
+                    );
+                }
+                None => assert!(result.is_err(), "expected an error importing {:?}", test.0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_forbidden_pours() {
+        // The forbidden pours of the first puzzle, or None if parsing should fail
+        type Expected = Option<Vec<(usize, usize)>>;
+        let tests: Vec<(&str, Expected)> = vec![
+            (
+                "[1] Valves\nred, blue\nforbid 2 -> 1\nblue, red\nempty\nforbid 1->4\nempty\n",
+                Some(vec![(1, 0), (0, 3)]),
+            ),
+            ("[1] Open\nred\nblue\nempty\nempty\n", Some(vec![])),
+            ("forbid 1 -> 2\n[1] Early\nred\n", None),
+            ("[1] Missing\nred\nblue\nforbid 1 -> 3\n", None),
+            ("[1] Itself\nred\nblue\nforbid 2 -> 2\n", None),
+            ("[1] Zero\nred\nblue\nforbid 0 -> 1\n", None),
+            ("[1] Arrow\nred\nblue\nforbid 1 2\n", None),
+        ];
+        for test in tests {
+            let result = parse_catalogue(test.0);
+            match (result, test.1) {
+                (Ok(puzzles), Some(expected)) => assert_eq!(
+                    puzzles[0].forbidden_pours, expected,
+                    "wrong forbidden pours parsed from {:?}",
+                    test.0
+                ),
+                (Err(_), None) => {}
+                (Ok(_), None) => panic!("expected an error parsing {:?}", test.0),
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
+    }
+}
@@ -0,0 +1,1104 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    fmt::Display,
+    io::{self, IsTerminal},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+
+use crate::{colour::Theme, config::Config, game::Game, json::Json, tube::Tube, TUBE_SIZE};
+
+const EMPTY_SYMBOL: char = '.';
+/// Spaces between the tubes when they are drawn side by side.
+const COLUMN_GAP: usize = 2;
+/// Put either side of the segments of the focused colour, when they are not drawn in colour.
+const FOCUS_MARKER: char = '*';
+/// Drawn in each colour by the colour legend.
+const SWATCH: &str = "\u{2588}\u{2588}\u{2588}";
+/// Fill patterns paired with the symbols, so colours can be told apart at a glance.
+const PATTERNS: [&str; 8] = ["//", "::", "==", "##", "++", "~~", "**", "\\\\"];
+const EMPTY_EMOJI: &str = "\u{25cc}";
+/// Drawn for colours without a known display value, which have no nearest emoji.
+const UNKNOWN_EMOJI: &str = "\u{2754}";
+/// Filled in for colours without a known display value in SVG.
+const UNKNOWN_FILL: &str = "#cccccc";
+const SVG_TUBE_WIDTH: usize = 40;
+const SVG_SEGMENT_HEIGHT: usize = 30;
+const SVG_GAP: usize = 10;
+/// Room below the tubes for their numbers.
+const SVG_LABEL_HEIGHT: usize = 20;
+
+/// How the contents of the tubes are shown.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenderMode {
+    /// Every segment is shown by its colour name.
+    #[default]
+    Names,
+    /// Every colour is shown by its own letter, with a key, for players who find colour names
+    /// or colours hard to tell apart.
+    Symbols,
+    /// Every segment is shown by its colour name, drawn in that colour.
+    Colour,
+    /// Every segment is shown by the nearest coloured circle emoji, for sharing a board in chat.
+    Emoji,
+    /// The tubes are drawn upright and side by side, like in the game, wrapping onto more rows
+    /// when they do not fit across the terminal.
+    Columns,
+    /// A picture of the board.
+    Svg,
+    /// The JSON puzzle format read by `analyze`.
+    Json,
+}
+
+/// How many colours the terminal can show.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColourDepth {
+    TrueColour,
+    Ansi,
+}
+
+impl ColourDepth {
+    /// Terminals advertise 24-bit colour support through `COLORTERM`.
+    pub fn detect() -> ColourDepth {
+        match env::var("COLORTERM") {
+            Ok(value) if value == "truecolor" || value == "24bit" => ColourDepth::TrueColour,
+            _ => ColourDepth::Ansi,
+        }
+    }
+}
+
+/// Whether the output can show colour and emoji: not when `NO_COLOR` is set to anything, when
+/// `TERM` is `dumb`, or when the output is not a terminal, such as when it is piped to a file.
+pub fn terminal_supports_colour() -> bool {
+    supports_colour(
+        env::var("NO_COLOR").ok().as_deref(),
+        env::var("TERM").ok().as_deref(),
+        io::stdout().is_terminal(),
+    )
+}
+
+fn supports_colour(no_colour: Option<&str>, term: Option<&str>, is_terminal: bool) -> bool {
+    is_terminal && no_colour.is_none_or(str::is_empty) && term != Some("dumb")
+}
+
+impl RenderMode {
+    /// Whether the mode draws the board in terminal colours or emoji, which are unreadable
+    /// without colour support.
+    pub fn needs_colour(&self) -> bool {
+        matches!(self, RenderMode::Colour | RenderMode::Emoji)
+    }
+}
+
+impl FromStr for RenderMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "names" => Ok(RenderMode::Names),
+            "symbols" => Ok(RenderMode::Symbols),
+            "colour" | "color" => Ok(RenderMode::Colour),
+            "emoji" => Ok(RenderMode::Emoji),
+            "columns" => Ok(RenderMode::Columns),
+            "svg" => Ok(RenderMode::Svg),
+            "json" => Ok(RenderMode::Json),
+            other => Err(format!(
+                "Unknown render mode '{}'. Expected one of: names, symbols, colour, emoji, columns, svg, json",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for RenderMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            RenderMode::Names => "names",
+            RenderMode::Symbols => "symbols",
+            RenderMode::Colour => "colour",
+            RenderMode::Emoji => "emoji",
+            RenderMode::Columns => "columns",
+            RenderMode::Svg => "svg",
+            RenderMode::Json => "json",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Draws a board in one output style. The REPL shows the board through a renderer and `export`
+/// writes one to a file, so a new style only needs a new implementation.
+pub trait Renderer {
+    /// Draws the board, with every segment of the `focus` colour highlighted where the style
+    /// allows it.
+    fn render(&self, game: &Game, focus: Option<&str>) -> String;
+
+    /// Whether the output starts with one line per tube, so that it can be split into pages.
+    fn is_line_per_tube(&self) -> bool {
+        true
+    }
+}
+
+/// Shows every segment by its colour name.
+pub struct NamesRenderer;
+
+/// Shows every colour by its own letter, optionally with a fill pattern, followed by a key.
+pub struct SymbolsRenderer {
+    pub patterns: bool,
+}
+
+/// Shows every segment by its colour name, drawn in that colour.
+pub struct ColourRenderer {
+    pub depth: ColourDepth,
+    pub theme: Theme,
+}
+
+/// Shows every segment by the nearest coloured circle emoji, followed by a key.
+pub struct EmojiRenderer {
+    pub theme: Theme,
+}
+
+/// Draws every tube upright, top segment first, with the tubes side by side and their numbers
+/// below. The tubes wrap onto as many rows as it takes to keep within `width` columns.
+pub struct ColumnsRenderer {
+    pub width: usize,
+}
+
+/// Draws the board as an SVG picture, with the colour names as tooltips.
+pub struct SvgRenderer {
+    pub theme: Theme,
+}
+
+/// Writes the board in the JSON puzzle format, so that it can be read back by `analyze`.
+pub struct JsonRenderer;
+
+/// The renderer for a render mode, set up from the user's settings.
+pub fn renderer(mode: RenderMode, config: &Config) -> Box<dyn Renderer> {
+    match mode {
+        RenderMode::Names => Box::new(NamesRenderer),
+        RenderMode::Symbols => Box::new(SymbolsRenderer {
+            patterns: config.patterns,
+        }),
+        RenderMode::Colour => Box::new(ColourRenderer {
+            depth: ColourDepth::detect(),
+            theme: config.theme,
+        }),
+        RenderMode::Emoji => Box::new(EmojiRenderer {
+            theme: config.theme,
+        }),
+        RenderMode::Columns => Box::new(ColumnsRenderer {
+            width: config.width(),
+        }),
+        RenderMode::Svg => Box::new(SvgRenderer {
+            theme: config.theme,
+        }),
+        RenderMode::Json => Box::new(JsonRenderer),
+    }
+}
+
+/// Gives every colour a distinct symbol, preferring a letter from its name.
+pub fn colour_symbols(colours: &HashSet<String>) -> HashMap<String, char> {
+    let mut symbols: HashMap<String, char> = HashMap::new();
+    let mut used: HashSet<char> = HashSet::from([EMPTY_SYMBOL]);
+    for colour in colours.iter().sorted() {
+        let mut candidates = colour
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .map(|c| c.to_ascii_uppercase())
+            .chain('A'..='Z')
+            .chain('0'..='9')
+            .chain('a'..='z');
+        let symbol = candidates.find(|c| !used.contains(c)).unwrap_or('?');
+        used.insert(symbol);
+        symbols.insert(colour.clone(), symbol);
+    }
+    symbols
+}
+
+pub fn render_game(game: &Game, config: &Config) -> String {
+    render_game_focused(game, config, None)
+}
+
+/// Renders the game like `render_game`, with every segment of the `focus` colour highlighted so
+/// it can be picked out across a large board.
+pub fn render_game_focused(game: &Game, config: &Config, focus: Option<&str>) -> String {
+    renderer(config.render_mode, config).render(game, focus)
+}
+
+/// Renders the game with `renderer`, split into pages of at most `rows` tubes so that a large
+/// board does not scroll off the terminal. The lines after the tubes, such as the key, are kept
+/// with the last page. A `rows` of 0, or a renderer that does not draw a line per tube, puts the
+/// whole board on one page.
+pub fn render_pages(
+    game: &Game,
+    renderer: &dyn Renderer,
+    focus: Option<&str>,
+    rows: usize,
+) -> Vec<String> {
+    let board = renderer.render(game, focus);
+    let lines: Vec<&str> = board.lines().collect();
+    let tubes = game.tubes.len().min(lines.len());
+    if rows == 0 || tubes <= rows || !renderer.is_line_per_tube() {
+        return vec![board];
+    }
+    let mut pages: Vec<String> = lines[..tubes]
+        .chunks(rows)
+        .map(|page| page.iter().map(|line| format!("{}\n", line)).collect())
+        .collect();
+    if let Some(last) = pages.last_mut() {
+        for line in lines[tubes..].iter() {
+            last.push_str(format!("{}\n", line).as_str());
+        }
+    }
+    pages
+}
+
+/// Wraps the name of a colour in the escape codes to draw it in that colour. Colours without a
+/// known display value in the theme are left as they are.
+pub fn paint_colour(colour: &str, depth: ColourDepth, theme: Theme) -> String {
+    paint(colour, colour, depth, theme)
+}
+
+/// Wraps `text` in the escape codes to draw it in `colour`.
+fn paint(text: &str, colour: &str, depth: ColourDepth, theme: Theme) -> String {
+    match (theme.rgb(colour), depth) {
+        (Some(rgb), ColourDepth::TrueColour) => {
+            format!("\x1b[38;2;{};{};{}m{}\x1b[0m", rgb.r, rgb.g, rgb.b, text)
+        }
+        (Some(rgb), ColourDepth::Ansi) => format!("\x1b[{}m{}\x1b[0m", rgb.nearest_ansi(), text),
+        (None, _) => text.to_string(),
+    }
+}
+
+/// Lists every colour in the game with its symbol, a swatch of it, how many of its segments are
+/// not yet in a solved tube, and the tubes it is in. Colours without a known display value get a
+/// blank swatch.
+pub fn render_colour_legend(game: &Game, depth: ColourDepth, theme: Theme) -> String {
+    let symbols = colour_symbols(&game.colours);
+    let name_width = game
+        .colours
+        .iter()
+        .map(|colour| colour.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::from("Colours:\n");
+    for (colour, symbol) in symbols.iter().sorted_by_key(|x| x.1) {
+        let swatch = match theme.rgb(colour) {
+            Some(_) => paint(SWATCH, colour, depth, theme),
+            None => " ".repeat(SWATCH.chars().count()),
+        };
+        let unsolved: usize = game
+            .tubes
+            .iter()
+            .filter(|tube| !tube.is_tube_all_same_contents())
+            .map(|tube| {
+                tube.contents
+                    .iter()
+                    .filter(|cell| cell.as_ref() == Some(colour))
+                    .count()
+            })
+            .sum();
+        let unsolved = match unsolved {
+            0 => String::from("solved"),
+            count => format!("{} unsolved", count),
+        };
+        let tubes: Vec<String> = game
+            .tubes
+            .iter()
+            .filter(|tube| {
+                tube.contents
+                    .iter()
+                    .any(|cell| cell.as_ref() == Some(colour))
+            })
+            .map(|tube| (tube.tube_number + 1).to_string())
+            .collect();
+        let tube_label = match tubes.len() {
+            1 => "tube",
+            _ => "tubes",
+        };
+        out.push_str(
+            format!(
+                "{}  {:<width$}  {}  {:<10}  {} {}\n",
+                symbol,
+                colour,
+                swatch,
+                unsolved,
+                tube_label,
+                tubes.join(", "),
+                width = name_width
+            )
+            .as_str(),
+        );
+    }
+    out
+}
+
+/// One line of how far every colour is from being sorted: how many of its segments are in tubes
+/// holding nothing but that colour, out of all of them, such as `blue 2/4, red 4/4 ✔`. A colour
+/// whose segments are all in one tube is ticked.
+pub fn render_colour_progress(game: &Game) -> String {
+    game.colours
+        .iter()
+        .sorted()
+        .map(|colour| {
+            let count = |tube: &Tube| {
+                tube.contents
+                    .iter()
+                    .filter(|cell| cell.as_ref() == Some(colour))
+                    .count()
+            };
+            let total: usize = game.tubes.iter().map(count).sum();
+            let sorted_tubes: Vec<usize> = game
+                .tubes
+                .iter()
+                .filter(|tube| tube.contents.iter().flatten().all(|cell| cell == colour))
+                .map(count)
+                .filter(|count| *count > 0)
+                .collect();
+            let sorted: usize = sorted_tubes.iter().sum();
+            match sorted == total && sorted_tubes.len() == 1 {
+                true => format!("{} {}/{} \u{2714}", colour, sorted, total),
+                false => format!("{} {}/{}", colour, sorted, total),
+            }
+        })
+        .join(", ")
+}
+
+/// Draws every tube on its own line, using `render_cell` for each segment.
+/// The tube numbers are padded to the same width so that the tubes line up on large boards.
+fn render_tubes(game: &Game, render_cell: impl Fn(&Option<String>) -> String) -> String {
+    let width = game.tubes.len().to_string().len();
+    let mut out = String::new();
+    for tube in game.tubes.iter() {
+        let cells: Vec<String> = tube.contents.iter().map(&render_cell).collect();
+        out.push_str(
+            format!(
+                "{:>width$}: ({})\n",
+                tube.tube_number + 1,
+                cells.join(", "),
+                width = width
+            )
+            .as_str(),
+        );
+    }
+    out
+}
+
+/// Marks a segment of the focused colour with asterisks, for output without escape codes.
+fn mark(cell: String, colour: &str, focus: Option<&str>) -> String {
+    match focus == Some(colour) {
+        true => format!("{}{}{}", FOCUS_MARKER, cell, FOCUS_MARKER),
+        false => cell,
+    }
+}
+
+/// Adds the forbidden pours, if there are any, below a board drawn as text.
+fn with_forbidden_pours(mut out: String, game: &Game) -> String {
+    if let Some(forbidden) = game.forbidden_pours_string() {
+        out.push_str(format!("{}\n", forbidden).as_str());
+    }
+    out
+}
+
+impl Renderer for NamesRenderer {
+    fn render(&self, game: &Game, focus: Option<&str>) -> String {
+        let out = render_tubes(game, |cell| match cell {
+            Some(colour) => mark(colour.clone(), colour, focus),
+            None => String::from("empty"),
+        });
+        with_forbidden_pours(out, game)
+    }
+}
+
+impl Renderer for ColourRenderer {
+    fn render(&self, game: &Game, focus: Option<&str>) -> String {
+        let out = render_tubes(game, |cell| match cell {
+            Some(colour) if focus == Some(colour.as_str()) => {
+                format!("\x1b[7m{}", paint_colour(colour, self.depth, self.theme))
+            }
+            Some(colour) => paint_colour(colour, self.depth, self.theme),
+            None => String::from("empty"),
+        });
+        with_forbidden_pours(out, game)
+    }
+}
+
+impl Renderer for SymbolsRenderer {
+    fn render(&self, game: &Game, focus: Option<&str>) -> String {
+        let symbols = colour_symbols(&game.colours);
+        let pattern_for = |colour: &String| -> String {
+            if !self.patterns {
+                return String::new();
+            }
+            let idx = game
+                .colours
+                .iter()
+                .sorted()
+                .position(|x| x == colour)
+                .unwrap_or(0);
+            PATTERNS[idx % PATTERNS.len()].to_string()
+        };
+        let mut out = render_tubes(game, |cell| match cell {
+            Some(colour) => {
+                let symbol = symbols.get(colour).copied().unwrap_or('?');
+                mark(format!("{}{}", symbol, pattern_for(colour)), colour, focus)
+            }
+            None => match self.patterns {
+                true => EMPTY_SYMBOL.to_string().repeat(3),
+                false => EMPTY_SYMBOL.to_string(),
+            },
+        });
+        let key: Vec<String> = symbols
+            .iter()
+            .sorted_by_key(|x| x.1)
+            .map(|(colour, symbol)| format!("{}{} = {}", symbol, pattern_for(colour), colour))
+            .collect();
+        out.push_str(format!("Key: {}\n", key.join(", ")).as_str());
+        with_forbidden_pours(out, game)
+    }
+}
+
+impl Renderer for ColumnsRenderer {
+    fn render(&self, game: &Game, focus: Option<&str>) -> String {
+        let cells: Vec<Vec<String>> = game
+            .tubes
+            .iter()
+            .map(|tube| {
+                tube.contents
+                    .iter()
+                    .map(|cell| match cell {
+                        Some(colour) => mark(colour.clone(), colour, focus),
+                        None => EMPTY_SYMBOL.to_string(),
+                    })
+                    .collect()
+            })
+            .collect();
+        let cell_width = cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.chars().count())
+            .chain([game.tubes.len().to_string().len()])
+            .max()
+            .unwrap_or(1);
+        let height = cells.iter().map(Vec::len).max().unwrap_or(0);
+        let per_row = ((self.width + COLUMN_GAP) / (cell_width + COLUMN_GAP)).max(1);
+        let line = |texts: Vec<&str>| -> String {
+            let line = texts
+                .iter()
+                .map(|text| format!("{:^width$}", text, width = cell_width))
+                .join(&" ".repeat(COLUMN_GAP));
+            format!("{}\n", line.trim_end())
+        };
+        let mut rows: Vec<String> = Vec::new();
+        for (tubes, cells) in game.tubes.chunks(per_row).zip(cells.chunks(per_row)) {
+            let mut row = String::new();
+            for segment in 0..height {
+                // Shorter tubes are lined up at the bottom with the others
+                row.push_str(&line(
+                    cells
+                        .iter()
+                        .map(|tube| match (segment + tube.len()).checked_sub(height) {
+                            Some(idx) => tube[idx].as_str(),
+                            None => "",
+                        })
+                        .collect(),
+                ));
+            }
+            let numbers: Vec<String> = tubes
+                .iter()
+                .map(|tube| (tube.tube_number + 1).to_string())
+                .collect();
+            row.push_str(&line(numbers.iter().map(String::as_str).collect()));
+            rows.push(row);
+        }
+        with_forbidden_pours(rows.join("\n"), game)
+    }
+
+    fn is_line_per_tube(&self) -> bool {
+        false
+    }
+}
+
+impl Renderer for EmojiRenderer {
+    fn render(&self, game: &Game, focus: Option<&str>) -> String {
+        let emoji = |colour: &str| match self.theme.rgb(colour) {
+            Some(rgb) => rgb.nearest_emoji(),
+            None => UNKNOWN_EMOJI,
+        };
+        let mut out = render_tubes(game, |cell| match cell {
+            Some(colour) => mark(emoji(colour).to_string(), colour, focus),
+            None => EMPTY_EMOJI.to_string(),
+        });
+        // Several colours can share the nearest emoji, so the key is needed to tell them apart
+        let key: Vec<String> = game
+            .colours
+            .iter()
+            .sorted()
+            .map(|colour| format!("{} = {}", emoji(colour), colour))
+            .collect();
+        out.push_str(format!("Key: {}\n", key.join(", ")).as_str());
+        with_forbidden_pours(out, game)
+    }
+}
+
+impl Renderer for SvgRenderer {
+    fn render(&self, game: &Game, focus: Option<&str>) -> String {
+        let width = game.tubes.len() * (SVG_TUBE_WIDTH + SVG_GAP) + SVG_GAP;
+        let height = SVG_GAP + TUBE_SIZE * SVG_SEGMENT_HEIGHT + SVG_LABEL_HEIGHT;
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        );
+        for tube in game.tubes.iter() {
+            let x = SVG_GAP + tube.tube_number * (SVG_TUBE_WIDTH + SVG_GAP);
+            for (idx, cell) in tube.contents.iter().enumerate() {
+                let colour = match cell {
+                    Some(colour) => colour,
+                    None => continue,
+                };
+                let fill = self
+                    .theme
+                    .rgb(colour)
+                    .map(|rgb| rgb.to_hex())
+                    .unwrap_or_else(|| UNKNOWN_FILL.to_string());
+                let stroke = match focus == Some(colour.as_str()) {
+                    true => " stroke=\"black\" stroke-width=\"3\"",
+                    false => "",
+                };
+                out.push_str(
+                    format!(
+                        "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"{}><title>{}</title></rect>\n",
+                        x,
+                        SVG_GAP + idx * SVG_SEGMENT_HEIGHT,
+                        SVG_TUBE_WIDTH,
+                        SVG_SEGMENT_HEIGHT,
+                        fill,
+                        stroke,
+                        escape_xml(colour)
+                    )
+                    .as_str(),
+                );
+            }
+            out.push_str(
+                format!(
+                    "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"black\"/>\n",
+                    x,
+                    SVG_GAP,
+                    SVG_TUBE_WIDTH,
+                    TUBE_SIZE * SVG_SEGMENT_HEIGHT
+                )
+                .as_str(),
+            );
+            out.push_str(
+                format!(
+                    "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                    x + SVG_TUBE_WIDTH / 2,
+                    height - SVG_GAP / 2,
+                    tube.tube_number + 1
+                )
+                .as_str(),
+            );
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    fn is_line_per_tube(&self) -> bool {
+        false
+    }
+}
+
+impl Renderer for JsonRenderer {
+    fn render(&self, game: &Game, _focus: Option<&str>) -> String {
+        // Focus is not part of a puzzle, so it is left out
+        format!("{}\n", board_json(game))
+    }
+
+    fn is_line_per_tube(&self) -> bool {
+        false
+    }
+}
+
+/// The board in the JSON puzzle format.
+pub(crate) fn board_json(game: &Game) -> Json {
+    let tubes = game
+        .tubes
+        .iter()
+        .map(|tube| {
+            Json::Array(
+                tube.contents
+                    .iter()
+                    .flatten()
+                    .map(|colour| Json::String(colour.clone()))
+                    .collect(),
+            )
+        })
+        .collect();
+    let mut members = vec![
+        (
+            "ruleset".to_string(),
+            Json::String(game.ruleset.to_string()),
+        ),
+        ("tubes".to_string(), Json::Array(tubes)),
+    ];
+    if !game.forbidden_pours.is_empty() {
+        let pours = game
+            .forbidden_pours
+            .iter()
+            .map(|(from, to)| {
+                Json::Array(vec![
+                    Json::Number((from + 1) as f64),
+                    Json::Number((to + 1) as f64),
+                ])
+            })
+            .collect();
+        members.push(("forbidden_pours".to_string(), Json::Array(pours)));
+    }
+    Json::Object(members)
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colour_symbols() {
+        let tests = [
+            (
+                vec!["red", "blue", "green"],
+                vec![("red", 'R'), ("blue", 'B'), ("green", 'G')],
+            ),
+            (
+                vec!["blue", "brown", "black"],
+                vec![("black", 'B'), ("blue", 'L'), ("brown", 'R')],
+            ),
+            (
+                vec!["a", "aa", "aaa"],
+                vec![("a", 'A'), ("aa", 'B'), ("aaa", 'C')],
+            ),
+        ];
+        for test in tests {
+            let colours: HashSet<String> = test.0.iter().map(|x| x.to_string()).collect();
+            let symbols = colour_symbols(&colours);
+            for (colour, expected) in test.1 {
+                assert_eq!(
+                    symbols.get(colour),
+                    Some(&expected),
+                    "wrong symbol for {}. Expected: {}, got: {:?}",
+                    colour,
+                    expected,
+                    symbols.get(colour)
+                );
+            }
+            let unique: HashSet<&char> = symbols.values().collect();
+            assert_eq!(unique.len(), symbols.len(), "symbols are not unique");
+        }
+    }
+
+    #[test]
+    fn test_paint_colour() {
+        let tests = [
+            (
+                "#ff8800",
+                ColourDepth::TrueColour,
+                Theme::Classic,
+                "\x1b[38;2;255;136;0m#ff8800\x1b[0m",
+            ),
+            (
+                "#ff8800",
+                ColourDepth::Ansi,
+                Theme::Classic,
+                "\x1b[33m#ff8800\x1b[0m",
+            ),
+            (
+                "red",
+                ColourDepth::Ansi,
+                Theme::Classic,
+                "\x1b[31mred\x1b[0m",
+            ),
+            (
+                "red",
+                ColourDepth::TrueColour,
+                Theme::Pastel,
+                "\x1b[38;2;240;128;128mred\x1b[0m",
+            ),
+            (
+                "#ff8800",
+                ColourDepth::TrueColour,
+                Theme::Neon,
+                "\x1b[38;2;255;136;0m#ff8800\x1b[0m",
+            ),
+            ("mauve", ColourDepth::TrueColour, Theme::Neon, "mauve"),
+        ];
+        for test in tests {
+            let result = paint_colour(test.0, test.1, test.2);
+            assert_eq!(
+                result, test.3,
+                "wrong painted colour for {} with {:?} in the {} theme. Expected: {:?}, got: {:?}",
+                test.0, test.1, test.2, test.3, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_colour_legend() {
+        let mut game = Game::default();
+        game.init_tubes(5);
+        game.init_tube_contents(0, String::from("red, red, blue, blue"));
+        game.init_tube_contents(1, String::from("blue, blue, red, red"));
+        game.init_tube_contents(2, String::from("mauve, mauve, mauve, mauve"));
+        let tests = [
+            (
+                ColourDepth::Ansi,
+                "Colours:\n\
+                 B  blue   \x1b[94m\u{2588}\u{2588}\u{2588}\x1b[0m  4 unsolved  tubes 1, 2\n\
+                 M  mauve       solved      tube 3\n\
+                 R  red    \x1b[31m\u{2588}\u{2588}\u{2588}\x1b[0m  4 unsolved  tubes 1, 2\n",
+            ),
+            (
+                ColourDepth::TrueColour,
+                "Colours:\n\
+                 B  blue   \x1b[38;2;40;80;230m\u{2588}\u{2588}\u{2588}\x1b[0m  4 unsolved  tubes 1, 2\n\
+                 M  mauve       solved      tube 3\n\
+                 R  red    \x1b[38;2;230;40;40m\u{2588}\u{2588}\u{2588}\x1b[0m  4 unsolved  tubes 1, 2\n",
+            ),
+        ];
+        for test in tests {
+            let result = render_colour_legend(&game, test.0, Theme::Classic);
+            assert_eq!(
+                result, test.1,
+                "wrong colour legend with {:?}. Expected: {:?}, got: {:?}",
+                test.0, test.1, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_supports_colour() {
+        // (NO_COLOR, TERM, whether the output is a terminal, whether colour is shown)
+        let tests = [
+            (None, Some("xterm-256color"), true, true),
+            (Some(""), Some("xterm"), true, true),
+            (None, None, true, true),
+            (Some("1"), Some("xterm"), true, false),
+            (None, Some("dumb"), true, false),
+            (None, Some("xterm"), false, false),
+        ];
+        for test in tests {
+            assert_eq!(
+                supports_colour(test.0, test.1, test.2),
+                test.3,
+                "wrong colour support with NO_COLOR {:?}, TERM {:?} and a terminal {}",
+                test.0,
+                test.1,
+                test.2
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_colour_progress() {
+        // (tubes, progress)
+        let tests = [
+            (
+                vec![
+                    "red, red, red, red",
+                    "blue, blue",
+                    "green, blue, green, blue",
+                    "green, green",
+                ],
+                "blue 2/4, green 2/4, red 4/4 \u{2714}",
+            ),
+            (
+                vec!["red, red", "red, red", "blue, blue, blue, blue", ""],
+                "blue 4/4 \u{2714}, red 4/4",
+            ),
+            (
+                vec!["red, blue, red, blue", "blue, red, blue, red", "", ""],
+                "blue 0/4, red 0/4",
+            ),
+        ];
+        for test in tests {
+            let mut game = Game::default();
+            game.init_tubes(test.0.len());
+            for (idx, contents) in test.0.iter().enumerate() {
+                game.init_tube_contents(idx, contents.to_string());
+            }
+            assert_eq!(
+                render_colour_progress(&game),
+                test.1,
+                "wrong progress for {:?}",
+                test.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_pages() {
+        let mut game = Game::default();
+        game.init_tubes(12);
+        for idx in 0..10 {
+            game.init_tube_contents(idx, format!("c{0}, c{0}, c{0}, c{0}", idx));
+        }
+        let config = Config {
+            render_mode: RenderMode::Symbols,
+            ..Default::default()
+        };
+        let tests = [
+            (0, vec![12]),
+            (12, vec![12]),
+            (5, vec![5, 5, 2]),
+            (8, vec![8, 4]),
+        ];
+        for test in tests {
+            let pages = render_pages(
+                &game,
+                renderer(RenderMode::Symbols, &config).as_ref(),
+                None,
+                test.0,
+            );
+            let tubes: Vec<usize> = pages
+                .iter()
+                .map(|page| page.lines().filter(|line| line.contains(": (")).count())
+                .collect();
+            assert_eq!(
+                tubes, test.1,
+                "wrong tubes per page for {} rows. Expected: {:?}, got: {:?}",
+                test.0, test.1, tubes
+            );
+            assert!(
+                pages.last().unwrap().contains("Key: "),
+                "the key should be on the last page"
+            );
+        }
+        let pages = render_pages(
+            &game,
+            renderer(RenderMode::Symbols, &config).as_ref(),
+            None,
+            0,
+        );
+        assert!(
+            pages[0].starts_with(" 1: (") && pages[0].contains("\n12: ("),
+            "tube numbers should be padded to the same width"
+        );
+    }
+
+    #[test]
+    fn test_render_game_focused() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, blue, blue"));
+        game.init_tube_contents(1, String::from("blue, blue, red"));
+        let tests = [
+            (
+                RenderMode::Names,
+                Some("red"),
+                "1: (*red*, *red*, blue, blue)\n2: (empty, blue, blue, *red*)\n3: (empty, empty, empty, empty)\n4: (empty, empty, empty, empty)\n",
+            ),
+            (
+                RenderMode::Symbols,
+                Some("blue"),
+                "1: (R, R, *B*, *B*)\n2: (., *B*, *B*, R)\n3: (., ., ., .)\n4: (., ., ., .)\nKey: B = blue, R = red\n",
+            ),
+            (
+                RenderMode::Names,
+                Some("green"),
+                "1: (red, red, blue, blue)\n2: (empty, blue, blue, red)\n3: (empty, empty, empty, empty)\n4: (empty, empty, empty, empty)\n",
+            ),
+        ];
+        for test in tests {
+            let config = Config {
+                render_mode: test.0,
+                ..Default::default()
+            };
+            let result = render_game_focused(&game, &config, test.1);
+            assert_eq!(
+                result, test.2,
+                "wrong {} rendering focused on {:?}. Expected:\n{}\ngot:\n{}",
+                test.0, test.1, test.2, result
+            );
+        }
+        assert_eq!(
+            ColourRenderer {
+                depth: ColourDepth::Ansi,
+                theme: Theme::Classic,
+            }
+            .render(&game, Some("red"))
+            .lines()
+            .next(),
+            Some("1: (\x1b[7m\x1b[31mred\x1b[0m, \x1b[7m\x1b[31mred\x1b[0m, \x1b[94mblue\x1b[0m, \x1b[94mblue\x1b[0m)")
+        );
+    }
+
+    #[test]
+    fn test_render_game() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, blue, blue"));
+        game.init_tube_contents(1, String::from("blue, blue, red"));
+        let tests = [
+            (
+                RenderMode::Names,
+                false,
+                format!("{}", game),
+            ),
+            (
+                RenderMode::Symbols,
+                false,
+                "1: (R, R, B, B)\n2: (., B, B, R)\n3: (., ., ., .)\n4: (., ., ., .)\nKey: B = blue, R = red\n".to_string(),
+            ),
+            (
+                RenderMode::Symbols,
+                true,
+                "1: (R::, R::, B//, B//)\n2: (..., B//, B//, R::)\n3: (..., ..., ..., ...)\n4: (..., ..., ..., ...)\nKey: B// = blue, R:: = red\n".to_string(),
+            ),
+        ];
+        for test in tests {
+            let config = Config {
+                render_mode: test.0,
+                patterns: test.1,
+                ..Default::default()
+            };
+            let result = render_game(&game, &config);
+            assert_eq!(
+                result, test.2,
+                "wrong {} rendering. Expected:\n{}\ngot:\n{}",
+                test.0, test.2, result
+            );
+        }
+    }
+
+    #[test]
+    fn test_emoji_renderer() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, mauve, mauve"));
+        game.init_tube_contents(1, String::from("mauve, mauve, red"));
+        game.forbidden_pours.push((0, 3));
+        let result = EmojiRenderer {
+            theme: Theme::Classic,
+        }
+        .render(&game, Some("red"));
+        assert_eq!(
+            result,
+            "1: (*\u{1f534}*, *\u{1f534}*, \u{2754}, \u{2754})\n\
+             2: (\u{25cc}, \u{2754}, \u{2754}, *\u{1f534}*)\n\
+             3: (\u{25cc}, \u{25cc}, \u{25cc}, \u{25cc})\n\
+             4: (\u{25cc}, \u{25cc}, \u{25cc}, \u{25cc})\n\
+             Key: \u{2754} = mauve, \u{1f534} = red\n\
+             Forbidden pours: 1 -> 4\n",
+            "wrong emoji rendering"
+        );
+    }
+
+    #[test]
+    fn test_columns_renderer() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+        let tests = [
+            (
+                80,
+                "red   red    .     .\n\
+                 red   blue   .     .\n\
+                 red   blue   .     .\n\
+                 blue  blue   .     .\n \
+                 1     2     3     4\n",
+            ),
+            (
+                20,
+                "red   red    .\n\
+                 red   blue   .\n\
+                 red   blue   .\n\
+                 blue  blue   .\n \
+                 1     2     3\n\
+                 \n \
+                 .\n \
+                 .\n \
+                 .\n \
+                 .\n \
+                 4\n",
+            ),
+        ];
+        for test in tests {
+            let result = ColumnsRenderer { width: test.0 }.render(&game, None);
+            assert_eq!(result, test.1, "wrong rendering at a width of {}", test.0);
+            assert!(
+                result.lines().all(|line| line.chars().count() <= test.0),
+                "a line is wider than {}:\n{}",
+                test.0,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_svg_renderer() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, blue, blue"));
+        game.init_tube_contents(1, String::from("blue, blue, red, <x>"));
+        let renderer = SvgRenderer {
+            theme: Theme::Classic,
+        };
+        let result = renderer.render(&game, Some("blue"));
+        let tests = [
+            ("<svg ", 1),
+            ("</svg>", 1),
+            ("fill=\"none\"", 4),
+            ("<title>red</title>", 3),
+            ("stroke-width=\"3\"", 4),
+            ("<title>&lt;x&gt;</title>", 1),
+            ("fill=\"#cccccc\"", 1),
+            ("text-anchor", 4),
+        ];
+        for test in tests {
+            assert_eq!(
+                result.matches(test.0).count(),
+                test.1,
+                "wrong number of {:?} in:\n{}",
+                test.0,
+                result
+            );
+        }
+        assert_eq!(
+            render_pages(&game, &renderer, None, 2).len(),
+            1,
+            "an SVG should not be split into pages"
+        );
+    }
+
+    #[test]
+    fn test_json_renderer() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue"));
+        game.init_tube_contents(2, String::from("blue"));
+        game.ruleset = crate::game::Ruleset::NoSplit;
+        game.forbidden_pours.push((0, 3));
+        let result = JsonRenderer.render(&game, None);
+        assert_eq!(
+            result,
+            "{\"ruleset\": \"no-split\", \"tubes\": [[\"red\", \"red\", \"red\", \"blue\"], [\"red\", \"blue\", \"blue\"], [\"blue\"], []], \"forbidden_pours\": [[1, 4]]}\n",
+            "wrong JSON rendering"
+        );
+        let parsed = crate::analyze::parse_board(&result).expect("rendered JSON should parse");
+        assert_eq!(parsed.ruleset, game.ruleset, "ruleset did not round trip");
+        assert_eq!(
+            parsed.forbidden_pours, game.forbidden_pours,
+            "forbidden pours did not round trip"
+        );
+        for (parsed, tube) in parsed.tubes.iter().zip(game.tubes.iter()) {
+            assert_eq!(parsed.contents, tube.contents, "tube did not round trip");
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::{
+    game::Game,
+    solver::{SolveOutcome, Solver},
+};
+
+/// A change to one segment of a board.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SegmentEdit {
+    pub tube: usize,
+    /// The segment changed, counted from the top of the tube from 0.
+    pub segment: usize,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl Display for SegmentEdit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = |colour: &Option<String>| colour.clone().unwrap_or(String::from("empty"));
+        write!(
+            f,
+            "tube {} segment {} from the top: {} -> {}",
+            self.tube + 1,
+            self.segment + 1,
+            name(&self.from),
+            name(&self.to)
+        )
+    }
+}
+
+/// Edits that make a board valid and solvable, with the board they leave.
+#[derive(Clone)]
+pub struct Repair {
+    pub edits: Vec<SegmentEdit>,
+    pub board: Game,
+    /// How many moves the repaired board is solved in.
+    pub moves: usize,
+}
+
+/// Finds the fewest segment edits, up to `max_edits`, that make a board possible, with the right
+/// number of every colour, and solvable within the budget of the game's preset. Most mistakes in
+/// copying a board are a single segment given the wrong colour, so one edit is tried before two.
+/// Every segment can be changed to any colour the board has or to empty. Up to `limit` repairs
+/// with the fewest edits are given, in the order of the tubes and segments they change, or a
+/// single repair with no edits if the board needs none. None are given if no repair was found.
+pub fn repair_board(game: &Game, max_edits: usize, limit: usize) -> Vec<Repair> {
+    if let Some(moves) = solved_in(game) {
+        return vec![Repair {
+            edits: Vec::new(),
+            board: game.clone(),
+            moves,
+        }];
+    }
+    let segments: Vec<(usize, usize)> = game
+        .tubes
+        .iter()
+        .enumerate()
+        .flat_map(|(tube, contents)| {
+            (0..contents.contents.len()).map(move |segment| (tube, segment))
+        })
+        .collect();
+    let mut values: Vec<Option<String>> = game.colours.iter().cloned().sorted().map(Some).collect();
+    values.push(None);
+    for edits in 1..=max_edits {
+        let mut repairs = Vec::new();
+        for chosen in segments.iter().combinations(edits) {
+            let choices = chosen.iter().map(|(tube, segment)| {
+                let from = &game.tubes[*tube].contents[*segment];
+                values
+                    .iter()
+                    .filter(move |value| *value != from)
+                    .map(move |to| SegmentEdit {
+                        tube: *tube,
+                        segment: *segment,
+                        from: from.clone(),
+                        to: to.clone(),
+                    })
+            });
+            for edits in choices.multi_cartesian_product() {
+                let mut board = game.clone();
+                for edit in edits.iter() {
+                    board.set_segment(edit.tube, edit.segment, edit.to.clone());
+                }
+                if let Some(moves) = solved_in(&board) {
+                    repairs.push(Repair {
+                        edits,
+                        board,
+                        moves,
+                    });
+                    if repairs.len() == limit {
+                        return repairs;
+                    }
+                }
+            }
+        }
+        if !repairs.is_empty() {
+            return repairs;
+        }
+    }
+    Vec::new()
+}
+
+/// How many moves a board is solved in, or None if it is not possible, does not have the right
+/// number of every colour or was not solved.
+fn solved_in(board: &Game) -> Option<usize> {
+    if !board.check_integrity().is_empty() || !board.validate_setup() {
+        return None;
+    }
+    match Solver::new(board).try_solve() {
+        SolveOutcome::Solved(moves) => Some(moves.len()),
+        SolveOutcome::Unsolvable(_) | SolveOutcome::LimitReached => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::read_board;
+
+    use super::*;
+
+    #[test]
+    fn test_repair_board() {
+        // (tubes, the edits of every repair found)
+        let tests = [
+            (
+                r#"[["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]"#,
+                vec![vec![]],
+            ),
+            (
+                r#"[["rde", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]"#,
+                vec![vec!["tube 1 segment 1 from the top: rde -> red"]],
+            ),
+            (
+                r#"[["red", "red", "red", "blue"], ["red", "blue", "red", "blue"], [], []]"#,
+                vec![
+                    vec!["tube 1 segment 1 from the top: red -> blue"],
+                    vec!["tube 1 segment 2 from the top: red -> blue"],
+                    vec!["tube 1 segment 3 from the top: red -> blue"],
+                    vec!["tube 2 segment 1 from the top: red -> blue"],
+                    vec!["tube 2 segment 3 from the top: red -> blue"],
+                ],
+            ),
+            (
+                r#"[["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], ["green"], [], []]"#,
+                vec![],
+            ),
+        ];
+        for (tubes, expected) in tests {
+            let game = read_board(&format!(r#"{{"tubes": {}}}"#, tubes)).unwrap();
+            let repairs: Vec<Vec<String>> = repair_board(&game, 1, 10)
+                .iter()
+                .map(|repair| repair.edits.iter().map(SegmentEdit::to_string).collect())
+                .collect();
+            assert_eq!(repairs, expected, "wrong repairs of {}", tubes);
+        }
+    }
+
+    #[test]
+    fn test_repair_board_two_edits() {
+        let game = read_board(
+            r#"{"tubes": [["red", "rde", "red", "blue"], ["red", "blue", "bleu", "blue"], [], []]}"#,
+        )
+        .unwrap();
+        assert!(
+            repair_board(&game, 1, 10).is_empty(),
+            "one edit cannot fix two mistakes"
+        );
+        let repairs = repair_board(&game, 2, 10);
+        assert!(
+            repairs.iter().all(|repair| repair.edits.len() == 2),
+            "every repair should take two edits"
+        );
+        let board = read_board(
+            r#"{"tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []]}"#,
+        )
+        .unwrap();
+        assert!(
+            repairs
+                .iter()
+                .any(|repair| repair.board.state_key() == board.state_key() && repair.moves == 3),
+            "a repair should give back the board without the mistakes"
+        );
+    }
+}
@@ -1,62 +1,191 @@
-use std::io::{Stdin, Stdout, Write};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, Write},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
 
 use crate::{
-    game::{Game, Move},
+    achievements::Achievements,
+    analyze::{empty_tube_advice, transcript_string},
+    colour::{colour_rgb, normalise_colour, suggest_colour, Theme},
+    config::Config,
+    game::{
+        merge_pours, numbered_moves_string, parse_move_sequence, Game, Move, MoveRequest,
+        MoveSafety, Ruleset,
+    },
+    input_log::{InputKind, InputLog},
+    known_puzzles::{fingerprint, KnownPuzzles},
+    puzzles::parse_board_code,
+    render::{
+        render_colour_legend, render_colour_progress, render_pages, renderer, ColourDepth,
+        RenderMode, Renderer,
+    },
+    review::{review_moves, review_string},
+    rollout::RolloutEvaluator,
+    saved_setup::SavedSetup,
+    solver::{Solution, Solver},
+    stats::{current_day, format_duration, LifetimeStats, SolveRecord},
+    transpositions::TranspositionTable,
+    tube::Tube,
     TUBE_SIZE,
 };
 
 const FLUSH_ERR_MSG: &str = "should have flushed stdout";
 const ERR_MSG_WRITE_ERR_MSG: &str = "should have written an error message";
+/// How many moves ahead `empty-tube` looks when it is not given a number.
+const EMPTY_TUBE_MOVES: usize = 3;
 
-pub struct Repl {
-    stdin: Stdin,
-    stdout: Stdout,
+/// The interactive game, reading the player's input from `stdin` and writing to `stdout`. These
+/// are the terminal when playing, and can be any reader and writer, such as a script of input and
+/// a buffer, to drive a whole session from a test.
+pub struct Repl<R: BufRead, W: Write> {
+    stdin: R,
+    stdout: W,
     current_state: Game,
+    config: Config,
+    input_log: InputLog,
+    /// The colour picked out by the `focus` command.
+    focus: Option<String>,
+    /// Draws the board in the render mode from the settings.
+    renderer: Box<dyn Renderer>,
+    /// The lines left by the `branch` command, the main line first. Moves are always played on
+    /// `current_state`, so `back` returns to the last of these without it being disturbed.
+    branches: Vec<Game>,
+    /// Set when setup is left with the `save` command, to be resumed later.
+    setup_saved: bool,
+    /// Set when a tube entered with strict input holds an unknown colour, which ends setup.
+    setup_rejected: bool,
+    /// Every board along the shortest solutions found this session, so that a board met again,
+    /// such as the boards of the shortest solution when `compare` replays the game, is not
+    /// searched again. A hint on one of these boards suggests the move of that solution.
+    solutions: TranspositionTable,
+    /// Set once the board has been replaced with `setstate`, after which a solve is not counted in
+    /// the statistics.
+    state_set: bool,
+    /// How far through the pack being played the player is, shown by `progress`.
+    pack_report: Option<String>,
+    /// The player's notes on the tubes, by tube, shown with the board and saved with the game.
+    notes: BTreeMap<usize, String>,
+    /// A board reached in play being solved on another thread while the player thinks. Its
+    /// shortest solution is added to `solutions`, so that `solve` later in the game can follow it
+    /// rather than search.
+    analysis: Option<(Game, JoinHandle<Option<Solution>>)>,
 }
 
-impl Repl {
-    pub fn new(stdin: Stdin, stdout: Stdout, init_state: Game) -> Repl {
+impl<R: BufRead, W: Write> Repl<R, W> {
+    pub fn new(stdin: R, stdout: W, init_state: Game, config: Config) -> Repl<R, W> {
         Repl {
             current_state: init_state,
             stdin,
             stdout,
+            input_log: InputLog::open(config.debug_log),
+            renderer: renderer(config.render_mode, &config),
+            config,
+            focus: None,
+            branches: Vec::new(),
+            setup_saved: false,
+            setup_rejected: false,
+            solutions: TranspositionTable::default(),
+            state_set: false,
+            pack_report: None,
+            notes: BTreeMap::new(),
+            analysis: None,
         }
     }
 
     pub fn start(&mut self) -> bool {
-        loop {
-            write!(self.stdout, "Enter the total number of tubes in the game: ")
-                .expect("error writing prompt string");
+        let resumed = match self.resume_setup() {
+            Ok(resumed) => resumed,
+            Err(_) => return false,
+        };
+        let (num_of_tubes, mut idx) = match resumed {
+            Some(resumed) => resumed,
+            None => match self.read_board_size() {
+                Some(num_of_tubes) => (num_of_tubes, 0),
+                None => return false,
+            },
+        };
+        writeln!(
+            self.stdout,
+            "Enter each tube's contents, or 'insert <tube>' / 'remove <tube>' to fix a tube that was skipped or entered twice, or 'save' to carry on later."
+        )
+        .expect(ERR_MSG_WRITE_ERR_MSG);
+        while idx < num_of_tubes {
+            write!(self.stdout, "Enter the initial state of tube {}: ", idx + 1)
+                .expect(FLUSH_ERR_MSG);
             self.stdout.flush().expect(FLUSH_ERR_MSG);
             let mut input = String::new();
             if let Err(e) = self.stdin.read_line(&mut input) {
                 writeln!(self.stdout, "Error: {e}").expect(ERR_MSG_WRITE_ERR_MSG);
                 return false;
             }
-            let num_of_tubes = match input.trim().parse::<usize>() {
-                Ok(tube_num) => tube_num,
-                Err(_) => {
-                    match writeln!(self.stdout, "Unable to parse {} to a number", input) {
+            if input.trim().eq_ignore_ascii_case("save") {
+                match self.save_setup(idx) {
+                    Ok(true) => return false,
+                    Ok(false) => continue,
+                    Err(_) => return false,
+                }
+            }
+            match SetupCommand::parse(&input, idx) {
+                Some(Ok(SetupCommand::Insert(pos))) => {
+                    let contents = match self.prompt_tube(&format!(
+                        "Enter the initial state of the tube to insert at position {}: ",
+                        pos + 1
+                    )) {
+                        Ok(contents) => contents,
                         Err(_) => return false,
-                        Ok(_) => continue,
                     };
+                    // The last tube has not been entered yet, so it makes way for the new one
+                    self.current_state.remove_tube(num_of_tubes - 1);
+                    self.current_state.insert_tube(pos, contents);
+                    idx += 1;
                 }
-            };
-            self.current_state.init_tubes(num_of_tubes);
-            for idx in 0..num_of_tubes {
-                write!(self.stdout, "Enter the initial state of tube {}: ", idx + 1)
-                    .expect(FLUSH_ERR_MSG);
-                self.stdout.flush().expect(FLUSH_ERR_MSG);
-                let mut input = String::new();
-                if let Err(e) = self.stdin.read_line(&mut input) {
-                    writeln!(self.stdout, "Error: {e}").expect(ERR_MSG_WRITE_ERR_MSG);
+                Some(Ok(SetupCommand::Remove(pos))) => {
+                    self.current_state.remove_tube(pos);
+                    self.current_state
+                        .insert_tube(num_of_tubes - 1, String::new());
+                    idx -= 1;
+                }
+                Some(Ok(SetupCommand::RenameColour(old, new))) => {
+                    if self.rename_colour(&old, &new).is_err() {
+                        return false;
+                    }
+                }
+                Some(Err(err)) => {
+                    self.input_log.record(InputKind::Tube, &input, &err);
+                    if writeln!(self.stdout, "{}", err).is_err() {
+                        return false;
+                    }
+                }
+                None => match self.check_tube(&input) {
+                    Ok(Some(contents)) => {
+                        self.current_state.init_tube_contents(idx, contents);
+                        idx += 1;
+                    }
+                    Ok(None) if self.setup_rejected => return false,
+                    Ok(None) => {}
+                    Err(_) => return false,
+                },
+            }
+        }
+        if resumed.is_some() {
+            if let Err(err) = SavedSetup::clear() {
+                if writeln!(self.stdout, "Warning: {err}").is_err() {
                     return false;
                 }
-                self.current_state.init_tube_contents(idx, input);
             }
-            break;
+        }
+        if self.check_identical_tubes().is_err() {
+            return false;
         }
         if !self.current_state.validate_setup() {
+            self.input_log.record(
+                InputKind::Setup,
+                &format!("{}", self.current_state),
+                "Initial setup is incorrect",
+            );
             match writeln!(
                 self.stdout,
                 "Error: Initial setup is incorrect. Please try again."
@@ -70,15 +199,40 @@ impl Repl {
             Ok(_) => {}
             Err(_) => return false,
         };
-        match writeln!(self.stdout, "{}", self.current_state) {
-            Ok(_) => {}
-            Err(_) => return false,
-        };
+        if self.show_board().is_err() {
+            return false;
+        }
+        if self.config.show_difficulty() && self.show_difficulty().is_err() {
+            return false;
+        }
         true
     }
     pub fn play(&mut self) {
+        let mut start_state = self.current_state.clone();
+        if let Some(best) = LifetimeStats::load().ok().and_then(|stats| {
+            stats
+                .personal_bests
+                .get(&fingerprint(&start_state).0)
+                .copied()
+        }) {
+            if writeln!(self.stdout, "Personal best: {}", best).is_err() {
+                return;
+            }
+        }
+        let started = Instant::now();
         let mut is_complete = false;
         while !is_complete {
+            // Reading a move waits for the player however long they take, so the clock is checked
+            // at every prompt and again once a move is entered, rather than while waiting
+            if let Some(limit) = self.current_state.time_limit {
+                let remaining = limit.saturating_sub(started.elapsed());
+                if remaining.is_zero() {
+                    let _ = self.record_blitz_loss();
+                    return;
+                }
+                write!(self.stdout, "[{} left] ", format_duration(remaining))
+                    .expect("error writing move prompt string");
+            }
             write!(
                     self.stdout,
                     "Enter a move in the format (without quotes): \"<tube_from> <tube_to> <quantity>\": "
@@ -90,6 +244,17 @@ impl Repl {
                 writeln!(self.stdout, "Error: {e}").expect(ERR_MSG_WRITE_ERR_MSG);
                 continue;
             }
+            // A move counts from when it was entered, so time spent in the prompts that follow
+            // does not count against it
+            let played_at = started.elapsed();
+            if self
+                .current_state
+                .time_limit
+                .is_some_and(|limit| played_at > limit)
+            {
+                let _ = self.record_blitz_loss();
+                return;
+            }
             input = input.trim().to_string();
             match input.as_str() {
                 "restart" => {
@@ -100,61 +265,226 @@ impl Repl {
                     is_complete = true;
                     continue;
                 }
+                "hint" => {
+                    if self.hint().is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                "solve" => {
+                    if self.solve().is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                command if command.starts_with("empty-tube") => {
+                    if self.empty_tube(&command["empty-tube".len()..]).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                "colours" | "colors" => {
+                    if self.colours().is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                command if command.starts_with("focus") => {
+                    if self.focus(&command["focus".len()..]).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                command if command.starts_with("theme") => {
+                    if self.theme(&command["theme".len()..]).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                command if command.starts_with("save-game") => {
+                    if self
+                        .save_game(&start_state, &command["save-game".len()..])
+                        .is_err()
+                    {
+                        return;
+                    }
+                    continue;
+                }
+                command if command.starts_with("export") => {
+                    if self.export(&command["export".len()..]).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                "progress" => {
+                    let report = match &self.pack_report {
+                        Some(report) => report.clone(),
+                        None => String::from(
+                            "This board is not part of a pack. Play one with play --pack <catalogue>.\n",
+                        ),
+                    };
+                    if write!(self.stdout, "{}", report).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                "branch" => {
+                    if self.branch().is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                "back" => {
+                    if self.back().is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                "undo" => {
+                    if self.undo().is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                command
+                    if command.starts_with("rename-colour")
+                        || command.starts_with("rename-color") =>
+                {
+                    let args = command.split_once(' ').map_or("", |(_, args)| args);
+                    if self.rename(args).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                command if command.starts_with("setstate") => {
+                    match self.set_state(&command["setstate".len()..]) {
+                        Ok(true) => start_state = self.current_state.clone(),
+                        Ok(false) => {}
+                        Err(_) => return,
+                    }
+                    continue;
+                }
+                command if command.starts_with("note") => {
+                    if self.note(&command["note".len()..]).is_err() {
+                        return;
+                    }
+                    continue;
+                }
+                command if command.starts_with("relabel") => {
+                    match self.relabel(&command["relabel".len()..]) {
+                        // The moves are renumbered, so they have to be replayed from a board
+                        // numbered the same way
+                        Ok(Some(order)) => {
+                            let _ = start_state.permute_tubes(&order);
+                        }
+                        Ok(None) => {}
+                        Err(_) => return,
+                    }
+                    continue;
+                }
                 _ => {}
             }
-            let move_input = match MoveInput::parse_move(input, &self.current_state) {
+            let moves = match self.read_moves(&input, played_at) {
+                Ok(moves) => moves,
                 Err(err) => {
-                    match writeln!(self.stdout, "Unable to parse move: {}", err) {
+                    self.input_log.record(InputKind::Move, &input, &err);
+                    match writeln!(self.stdout, "{}", err) {
                         Ok(_) => continue,
                         Err(_) => return,
                     };
                 }
-                Ok(move_in) => move_in,
             };
-            let tube_from = match self
-                .current_state
-                .tubes
-                .get((move_input.tube_from - 1) as usize)
-            {
-                Some(tube) => tube.to_owned(),
-                None => {
-                    match writeln!(
-                        self.stdout,
-                        "Error: Unable to find 'from tube' {}",
-                        move_input.tube_from - 1
-                    ) {
-                        Ok(_) => continue,
+            // The checks are about a single pour, so several moves entered together are made as
+            // they were written
+            if let [this_move] = moves.as_slice() {
+                if self
+                    .current_state
+                    .is_null_progress(this_move.tube_from, this_move.tube_to)
+                {
+                    let warning = format!(
+                        "{} only moves the {} into another empty tube, which makes no progress",
+                        this_move, this_move.colour
+                    );
+                    if self.config.reject_null_moves {
+                        let err = format!("Move rejected: {}", warning);
+                        self.input_log.record(InputKind::Move, &input, &err);
+                        match writeln!(self.stdout, "{}", err) {
+                            Ok(_) => continue,
+                            Err(_) => return,
+                        };
+                    }
+                    match writeln!(self.stdout, "Warning: {}", warning) {
+                        Ok(_) => {}
                         Err(_) => return,
                     };
                 }
-            };
-            let from_colour = match tube_from.get_top_colour() {
-                Some(col) => col.colour,
-                None => {
-                    match writeln!(self.stdout, "Move is invalid") {
-                        Ok(_) => continue,
+                if self.config.confirm_wasteful {
+                    match self.confirm_wasteful_move(this_move) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
                         Err(_) => return,
-                    };
+                    }
                 }
-            };
-            let this_move = Move {
-                tube_from: (move_input.tube_from - 1) as usize,
-                tube_to: (move_input.tube_to - 1) as usize,
-                quantity: move_input.quantity as usize,
-                colour: from_colour,
-            };
-            if self.current_state.validate_move(&this_move) {
-                self.current_state.make_move(&this_move);
-                match writeln!(self.stdout, "After move: {}:", &this_move) {
+                if self.config.assistant {
+                    match self.confirm_move(this_move) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(_) => return,
+                    }
+                }
+            }
+            for a_move in moves.iter() {
+                self.current_state.make_move(a_move);
+                match writeln!(self.stdout, "After move: {}:", a_move) {
+                    Ok(_) => {}
+                    Err(_) => return,
+                };
+            }
+            self.analyse_in_background();
+            if self.show_board().is_err() {
+                return;
+            }
+            if let Some(par_moves) = self.current_state.par.moves {
+                match writeln!(
+                    self.stdout,
+                    "Moves: {} (par {})",
+                    self.current_state.current_move, par_moves
+                ) {
+                    Ok(_) => {}
+                    Err(_) => return,
+                };
+            }
+            if self.config.show_estimate && !self.current_state.is_game_complete() {
+                let estimate = match Solver::new(&self.current_state).lower_bound() {
+                    1 => String::from("At least 1 more move needed"),
+                    moves => format!("At least {} more moves needed", moves),
+                };
+                match writeln!(self.stdout, "{}", estimate) {
                     Ok(_) => {}
                     Err(_) => return,
                 };
-                match writeln!(self.stdout, "{}", self.current_state) {
+            }
+            if self.config.show_progress {
+                match writeln!(
+                    self.stdout,
+                    "Sorted: {}",
+                    render_colour_progress(&self.current_state)
+                ) {
                     Ok(_) => {}
                     Err(_) => return,
                 };
-            } else {
-                match writeln!(self.stdout, "Move is invalid") {
+            }
+            if self.current_state.is_stuck() {
+                match self.stuck() {
+                    Ok(true) => continue,
+                    Ok(false) | Err(_) => return,
+                }
+            }
+            if self.current_state.is_game_complete() && !self.branches.is_empty() {
+                match writeln!(
+                    self.stdout,
+                    "This line completes the puzzle. Enter back to return to where it branched."
+                ) {
                     Ok(_) => continue,
                     Err(_) => return,
                 };
@@ -168,57 +498,1238 @@ impl Repl {
                     Ok(_) => {}
                     Err(_) => return,
                 };
-                match writeln!(self.stdout, "{}", self.current_state.get_all_moves_string()) {
+                let moves = match self.config.merge_pours() {
+                    true => self.current_state.get_merged_moves_string(),
+                    false => self.current_state.get_all_moves_string(),
+                };
+                match writeln!(self.stdout, "{}", moves) {
                     Ok(_) => {}
                     Err(_) => return,
                 };
+                match writeln!(
+                    self.stdout,
+                    "Rating: {}",
+                    self.current_state.stats.rating_string()
+                ) {
+                    Ok(_) => {}
+                    Err(_) => return,
+                };
+                if self.current_state.par.is_set() {
+                    let par = self.current_state.par;
+                    match writeln!(
+                        self.stdout,
+                        "Par: {}. You took {} moves in {}.",
+                        par,
+                        self.current_state.current_move,
+                        format_duration(started.elapsed())
+                    ) {
+                        Ok(_) => {}
+                        Err(_) => return,
+                    };
+                }
+                if let Some(pace) = self.current_state.pace_string() {
+                    match writeln!(self.stdout, "{}", pace) {
+                        Ok(_) => {}
+                        Err(_) => return,
+                    };
+                }
+                if !self.state_set && self.record_solve(&start_state, started.elapsed()).is_err() {
+                    return;
+                }
+                if self.offer_comparison(&start_state).is_err() {
+                    return;
+                }
             }
         }
     }
-}
 
-struct MoveInput {
-    tube_from: i32,
-    tube_to: i32,
-    quantity: i32,
-}
+    /// Offers to carry on with a setup saved part way through, entering the tubes it holds.
+    /// Returns the number of tubes of the board and how many have been entered, or None to start
+    /// a new board.
+    fn resume_setup(&mut self) -> io::Result<Option<(usize, usize)>> {
+        let setup = match SavedSetup::load() {
+            Ok(Some(setup)) => setup,
+            Ok(None) => return Ok(None),
+            Err(err) => {
+                writeln!(self.stdout, "Warning: {err}. Starting a new board.")?;
+                return Ok(None);
+            }
+        };
+        let answer = self.prompt(&format!(
+            "A board of {} tubes was saved with {} entered. Carry on entering it? [Y/n]: ",
+            setup.num_of_tubes,
+            setup.tubes.len()
+        ))?;
+        if matches!(answer.trim().to_lowercase().as_str(), "n" | "no") {
+            if let Err(err) = SavedSetup::clear() {
+                writeln!(self.stdout, "Warning: {err}")?;
+            }
+            return Ok(None);
+        }
+        self.current_state.ruleset = setup.ruleset;
+        self.current_state.init_tubes(setup.num_of_tubes);
+        for (idx, contents) in setup.tubes.iter().enumerate() {
+            self.current_state.init_tube_contents(idx, contents.clone());
+        }
+        writeln!(
+            self.stdout,
+            "Carrying on from tube {} with the {} rules.",
+            setup.tubes.len() + 1,
+            setup.ruleset
+        )?;
+        Ok(Some((setup.num_of_tubes, setup.tubes.len())))
+    }
+
+    /// Saves the `entered` tubes, so that the board can be finished the next time a new game is
+    /// started. Returns whether it was saved; if not, entering the tubes carries on.
+    fn save_setup(&mut self, entered: usize) -> io::Result<bool> {
+        let setup = SavedSetup {
+            num_of_tubes: self.current_state.tubes.len(),
+            ruleset: self.current_state.ruleset,
+            tubes: self.current_state.tubes[..entered]
+                .iter()
+                .map(Tube::contents_string)
+                .collect(),
+        };
+        if let Err(err) = setup.save() {
+            writeln!(self.stdout, "Error: {err}. The tubes were not saved.")?;
+            return Ok(false);
+        }
+        self.setup_saved = true;
+        writeln!(
+            self.stdout,
+            "Saved the first {} of {} tubes. Start a new game to carry on from tube {}.",
+            entered,
+            self.current_state.tubes.len(),
+            entered + 1
+        )?;
+        Ok(true)
+    }
+
+    /// Sets what `progress` shows, when the board is a level of a pack.
+    pub fn set_pack_report(&mut self, report: String) {
+        self.pack_report = Some(report);
+    }
+
+    /// The game, once play has ended with it completed. A board completed on a branch, or after
+    /// `setstate` replaced it, is not the board that was set, so it does not count.
+    pub fn completed_game(&self) -> Option<&Game> {
+        (self.current_state.is_game_complete() && self.branches.is_empty() && !self.state_set)
+            .then_some(&self.current_state)
+    }
+
+    /// Whether setup ended with the tubes being saved for later, rather than with a board to play.
+    pub fn setup_saved(&self) -> bool {
+        self.setup_saved
+    }
+
+    /// Whether setup ended because strict input was given a colour it does not know, so it should
+    /// not be started again.
+    pub fn setup_rejected(&self) -> bool {
+        self.setup_rejected
+    }
+
+    /// Asks for the number of tubes and the ruleset of a new board, until both are valid. Returns
+    /// None if the input can not be read.
+    fn read_board_size(&mut self) -> Option<usize> {
+        loop {
+            write!(self.stdout, "Enter the total number of tubes in the game: ")
+                .expect("error writing prompt string");
+            self.stdout.flush().expect(FLUSH_ERR_MSG);
+            let mut input = String::new();
+            if let Err(e) = self.stdin.read_line(&mut input) {
+                writeln!(self.stdout, "Error: {e}").expect(ERR_MSG_WRITE_ERR_MSG);
+                return None;
+            }
+            let num_of_tubes = match input.trim().parse::<usize>() {
+                Ok(tube_num) if tube_num > self.config.max_tubes() => {
+                    let err = format!(
+                        "At most {} tubes are supported. Set max_tubes in the settings to allow more",
+                        self.config.max_tubes()
+                    );
+                    self.input_log.record(InputKind::TubeCount, &input, &err);
+                    match writeln!(self.stdout, "{}", err) {
+                        Err(_) => return None,
+                        Ok(_) => continue,
+                    };
+                }
+                Ok(tube_num) if tube_num >= 4 => tube_num,
+                Ok(_) => {
+                    let err = "A game needs at least 4 tubes";
+                    self.input_log.record(InputKind::TubeCount, &input, err);
+                    match writeln!(self.stdout, "{}", err) {
+                        Err(_) => return None,
+                        Ok(_) => continue,
+                    };
+                }
+                Err(_) => {
+                    let err = format!("Unable to parse {} to a number", input.trim());
+                    self.input_log.record(InputKind::TubeCount, &input, &err);
+                    match writeln!(self.stdout, "{}", err) {
+                        Err(_) => return None,
+                        Ok(_) => continue,
+                    };
+                }
+            };
+            write!(
+                self.stdout,
+                "Enter the ruleset (standard, one-unit or no-split), or leave blank for {}: ",
+                self.current_state.ruleset
+            )
+            .expect("error writing prompt string");
+            self.stdout.flush().expect(FLUSH_ERR_MSG);
+            let mut input = String::new();
+            if let Err(e) = self.stdin.read_line(&mut input) {
+                writeln!(self.stdout, "Error: {e}").expect(ERR_MSG_WRITE_ERR_MSG);
+                return None;
+            }
+            if !input.trim().is_empty() {
+                self.current_state.ruleset = match input.parse::<Ruleset>() {
+                    Ok(ruleset) => ruleset,
+                    Err(err) => {
+                        self.input_log.record(InputKind::Ruleset, &input, &err);
+                        match writeln!(self.stdout, "{}", err) {
+                            Err(_) => return None,
+                            Ok(_) => continue,
+                        };
+                    }
+                };
+            }
+            self.current_state.init_tubes(num_of_tubes);
+            return Some(num_of_tubes);
+        }
+    }
+
+    /// Asks the user to confirm any tubes with identical contents, as these are usually a
+    /// transcription error. Tubes that were not intended to be identical are entered again.
+    fn check_identical_tubes(&mut self) -> io::Result<()> {
+        let mut confirmed: Vec<(usize, usize)> = Vec::new();
+        loop {
+            let (first, second) = match self
+                .current_state
+                .find_identical_tubes()
+                .into_iter()
+                .find(|pair| !confirmed.contains(pair))
+            {
+                Some(pair) => pair,
+                None => return Ok(()),
+            };
+            let answer = self.prompt(&format!(
+                "Tubes {} and {} are identical — is that intentional? [y/N]: ",
+                first + 1,
+                second + 1
+            ))?;
+            if matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                confirmed.push((first, second));
+                continue;
+            }
+            let contents =
+                self.prompt_tube(&format!("Enter the initial state of tube {}: ", second + 1))?;
+            self.current_state.init_tube_contents(second, contents);
+        }
+    }
+
+    /// Reads one move, or several separated by commas such as `1>3, 3>5`. Several moves are
+    /// checked together, each on the board the ones before it leave, and if any of them cannot be
+    /// made none of them are.
+    fn read_moves(&self, input: &str, played_at: Duration) -> Result<Vec<Move>, String> {
+        let moves = match input.contains(',') {
+            false => {
+                let request = input
+                    .parse::<MoveRequest>()
+                    .map_err(|err| format!("Unable to parse move: {}", err))?;
+                let a_move = self
+                    .current_state
+                    .resolve(request)
+                    .map_err(|err| format!("Move is invalid: {}", err))?;
+                vec![a_move]
+            }
+            true => {
+                let requests =
+                    parse_move_sequence(input).map_err(|err| format!("Unable to parse {}", err))?;
+                self.current_state
+                    .resolve_sequence(&requests)
+                    .map_err(|(idx, err)| {
+                        format!(
+                            "Move {} is invalid: {}. None of the moves were made",
+                            idx + 1,
+                            err
+                        )
+                    })?
+            }
+        };
+        Ok(moves
+            .into_iter()
+            .map(|a_move| Move {
+                played_at: Some(played_at),
+                ..a_move
+            })
+            .collect())
+    }
+
+    /// Asks the user to confirm a move into an empty tube if the same colour could be merged
+    /// instead.
+    fn confirm_wasteful_move(&mut self, a_move: &Move) -> io::Result<bool> {
+        let merge = match self.current_state.find_merge_instead(a_move) {
+            Some(merge) => merge,
+            None => return Ok(true),
+        };
+        let answer = self.prompt(&format!(
+            "Tube {} is empty, but the {} could be poured onto tube {} instead. Pour {} anyway? [y/N]: ",
+            a_move.tube_to + 1,
+            merge.colour,
+            merge.tube_to + 1,
+            a_move
+        ))?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+
+    /// Checks how safe a move is, and asks the user to confirm it if it looks risky or losing.
+    fn confirm_move(&mut self, a_move: &Move) -> io::Result<bool> {
+        let warning = match self.current_state.classify_move(a_move) {
+            MoveSafety::Safe => return Ok(true),
+            MoveSafety::Risky => "Careful: this pour looks risky",
+            MoveSafety::Losing => "Careful: after this pour the game can no longer be completed",
+        };
+        let answer = self.prompt(&format!("{}. Pour {} anyway? [y/N]: ", warning, a_move))?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
 
-impl MoveInput {
-    fn parse_move(move_string: String, game: &Game) -> Result<MoveInput, String> {
-        // A move string should be of the format "<tube_from> <tube_to> <quantity>" (i.e. space delimited)
-        let string_parts: Vec<&str> = move_string.split(' ').collect();
-        if string_parts.len() != 3 {
-            return Err(
-                "Move must be in the format\"<tube_from> <tube_to> <quantity>\"".to_string(),
+    /// Adds the completed game to the lifetime statistics, comparing it to the shortest solution
+    /// from where the game started, and announces any achievements it unlocks.
+    fn record_solve(&mut self, start_state: &Game, duration: Duration) -> io::Result<()> {
+        let moves = self.current_state.current_move - start_state.current_move;
+        let optimal_moves = self.shortest_solution(start_state)?;
+        if let Some(optimal_moves) = optimal_moves {
+            writeln!(
+                self.stdout,
+                "You took {} moves. The shortest solution takes {}.",
+                moves, optimal_moves
+            )?;
+        }
+        let mut stats = match LifetimeStats::load() {
+            Ok(stats) => stats,
+            Err(err) => {
+                return writeln!(self.stdout, "Warning: {err}. Statistics were not updated.")
+            }
+        };
+        let solve = SolveRecord {
+            moves,
+            optimal_moves,
+            hints_used: self.current_state.stats.hints_used,
+            tubes: self.current_state.tubes.len(),
+            duration,
+            day: current_day(),
+        };
+        stats.record_solve(&solve);
+        // Times are kept to the second, so a new best must be a whole second faster
+        let time = Duration::from_secs(duration.as_secs());
+        let previous = stats.record_personal_best(&fingerprint(start_state).0, moves, time);
+        if let Err(err) = stats.save() {
+            writeln!(self.stdout, "Warning: {err}")?;
+        }
+        if let Some(previous) = previous {
+            if moves < previous.moves {
+                writeln!(
+                    self.stdout,
+                    "New personal best: {} moves, beating {}!",
+                    moves, previous.moves
+                )?;
+            }
+            if time < previous.time {
+                writeln!(
+                    self.stdout,
+                    "New personal best time: {}, beating {}!",
+                    format_duration(time),
+                    format_duration(previous.time)
+                )?;
+            }
+        }
+        let mut achievements = match Achievements::load() {
+            Ok(achievements) => achievements,
+            Err(err) => {
+                return writeln!(
+                    self.stdout,
+                    "Warning: {err}. Achievements were not updated."
+                )
+            }
+        };
+        let unlocked = achievements.unlock(&solve, &stats);
+        if unlocked.is_empty() {
+            return Ok(());
+        }
+        for achievement in unlocked {
+            writeln!(
+                self.stdout,
+                "Achievement unlocked: {}!",
+                achievement.description()
+            )?;
+        }
+        if let Err(err) = achievements.save() {
+            writeln!(self.stdout, "Warning: {err}")?;
+        }
+        Ok(())
+    }
+
+    /// Offers to compare the player's moves against the shortest solution once they have won.
+    fn offer_comparison(&mut self, start_state: &Game) -> io::Result<()> {
+        let answer = self.prompt(
+            "Enter 'compare' to see where your moves left the shortest solution, or press Enter to finish: ",
+        )?;
+        if answer.trim() != "compare" {
+            return Ok(());
+        }
+        if !self.current_state.has_full_history() {
+            return writeln!(
+                self.stdout,
+                "The earliest moves are no longer in the history, so they cannot be compared. Raise history_limit in the settings to keep them."
+            );
+        }
+        let moves: Vec<Move> = (start_state.current_move + 1..=self.current_state.current_move)
+            .filter_map(|idx| self.current_state.moves.get(&idx).cloned())
+            .collect();
+        write!(
+            self.stdout,
+            "{}",
+            review_string(&review_moves(start_state, &moves, &mut self.solutions))
+        )
+    }
+
+    /// Ends a blitz mode puzzle that ran out of time, counting it as lost in the statistics.
+    fn record_blitz_loss(&mut self) -> io::Result<()> {
+        writeln!(self.stdout, "Time is up! The puzzle is lost.")?;
+        let mut stats = match LifetimeStats::load() {
+            Ok(stats) => stats,
+            Err(err) => {
+                return writeln!(self.stdout, "Warning: {err}. Statistics were not updated.")
+            }
+        };
+        stats.record_blitz_loss();
+        if let Err(err) = stats.save() {
+            writeln!(self.stdout, "Warning: {err}")?;
+        }
+        Ok(())
+    }
+
+    /// Finds the length of the shortest solution, checking the known puzzles first if they are
+    /// turned on.
+    fn shortest_solution(&mut self, start_state: &Game) -> io::Result<Option<usize>> {
+        // Large boards are searched depth first, which does not give the shortest solution
+        if Solver::new(start_state).searches_depth_first() {
+            return Ok(None);
+        }
+        if !self.config.known_puzzles {
+            return Ok(self
+                .solutions
+                .solve(start_state)
+                .map(|solution| solution.moves.len()));
+        }
+        let mut known = match KnownPuzzles::load() {
+            Ok(known) => known,
+            Err(err) => {
+                writeln!(self.stdout, "Warning: {err}. Known puzzles were not used.")?;
+                KnownPuzzles::default()
+            }
+        };
+        let (solution, difficulty) = match known.solve(start_state) {
+            Some(solved) => solved,
+            None => return Ok(None),
+        };
+        writeln!(self.stdout, "Difficulty: {}/5", difficulty)?;
+        if let Err(err) = known.save() {
+            writeln!(self.stdout, "Warning: {err}")?;
+        }
+        Ok(Some(solution.len()))
+    }
+
+    /// Gauges how hard a newly entered board is from how much the solver has to search to solve
+    /// it. The solution is kept for the rest of the session, so it is not searched for again at
+    /// the end of the game.
+    fn show_difficulty(&mut self) -> io::Result<()> {
+        let solution = match self.solutions.solve(&self.current_state) {
+            Some(solution) => solution,
+            None => {
+                return writeln!(
+                    self.stdout,
+                    "The solver could not find a solution, so this puzzle might not be possible."
+                )
+            }
+        };
+        // A depth first search gives a solution, but not always the shortest
+        let optimal = match solution.optimal {
+            true => "\u{2248}",
+            false => "\u{2264}",
+        };
+        writeln!(
+            self.stdout,
+            "This looks like a {} puzzle (optimal {} {} moves)",
+            solution.difficulty_name(),
+            optimal,
+            solution.moves.len()
+        )
+    }
+
+    /// Shows the board a page at a time, waiting for Enter between pages.
+    fn show_board(&mut self) -> io::Result<()> {
+        let rows = self.config.page_rows();
+        let pages = render_pages(
+            &self.current_state,
+            self.renderer.as_ref(),
+            self.focus.as_deref(),
+            rows,
+        );
+        let tubes = self.current_state.tubes.len();
+        for (idx, page) in pages.iter().enumerate() {
+            write!(self.stdout, "{}", page)?;
+            if idx + 1 < pages.len() {
+                self.prompt(&format!(
+                    "-- Tubes {}-{} of {}. Press Enter for more --",
+                    idx * rows + 1,
+                    (idx + 1) * rows,
+                    tubes
+                ))?;
+            }
+        }
+        if !self.notes.is_empty() {
+            writeln!(self.stdout, "Notes:")?;
+            for (tube, note) in self.notes.iter() {
+                writeln!(self.stdout, "  {}: {}", tube + 1, note)?;
+            }
+        }
+        writeln!(self.stdout)
+    }
+
+    /// Highlights every segment of a colour, or clears the highlight with `focus off` or no colour.
+    fn focus(&mut self, colour: &str) -> io::Result<()> {
+        let colour = normalise_colour(colour);
+        if colour.is_empty() || colour == "off" {
+            self.focus = None;
+            return writeln!(self.stdout, "Focus cleared");
+        }
+        if !self.current_state.colours.contains(&colour) {
+            return writeln!(self.stdout, "There is no {} in this game", colour);
+        }
+        self.focus = Some(colour);
+        self.show_board()
+    }
+
+    /// Says whether the next moves can all pour onto the same colour, or an empty tube will have
+    /// to be used, so that the empty tubes can be kept for when they are needed.
+    fn empty_tube(&mut self, moves: &str) -> io::Result<()> {
+        let moves = match moves.trim() {
+            "" => EMPTY_TUBE_MOVES,
+            moves => match moves.parse::<usize>() {
+                Ok(moves) if moves > 0 => moves,
+                _ => return writeln!(self.stdout, "Usage: empty-tube [<moves>]"),
+            },
+        };
+        writeln!(
+            self.stdout,
+            "{}",
+            empty_tube_advice(&self.current_state, moves)
+        )
+    }
+
+    /// Shows a live inventory of the colours in the game.
+    fn colours(&mut self) -> io::Result<()> {
+        write!(
+            self.stdout,
+            "{}",
+            render_colour_legend(
+                &self.current_state,
+                ColourDepth::detect(),
+                self.config.theme
+            )
+        )
+    }
+
+    /// Switches the shades the colours are drawn in for the rest of the session, or shows the
+    /// current theme and the others when none is given.
+    fn theme(&mut self, name: &str) -> io::Result<()> {
+        if name.trim().is_empty() {
+            let themes = Theme::ALL.map(|theme| theme.to_string()).join(", ");
+            return writeln!(
+                self.stdout,
+                "The theme is {}. Themes: {}",
+                self.config.theme, themes
             );
         }
-        let tube_from = match string_parts[0].parse::<i32>() {
-            Ok(entry) => entry,
-            Err(_) => return Err("Expected an integer for the 'from tube' value".to_string()),
+        self.config.theme = match name.parse::<Theme>() {
+            Ok(theme) => theme,
+            Err(err) => return writeln!(self.stdout, "{}", err),
+        };
+        self.renderer = renderer(self.config.render_mode, &self.config);
+        writeln!(self.stdout, "Using the {} theme", self.config.theme)?;
+        self.show_board()
+    }
+
+    /// Writes the board to a file in any render mode, such as an SVG picture, or a JSON puzzle
+    /// file that can be read back by `analyze`.
+    fn export(&mut self, args: &str) -> io::Result<()> {
+        let (mode, path) = match args.trim().split_once(' ') {
+            Some((mode, path)) => (mode, path.trim()),
+            None => return writeln!(self.stdout, "Usage: export <mode> <path>"),
         };
-        let tube_to = match string_parts[1].parse::<i32>() {
-            Ok(entry) => entry,
-            Err(_) => return Err("Expected an integer for the 'to tube' value".to_string()),
+        let mode = match mode.parse::<RenderMode>() {
+            Ok(mode) => mode,
+            Err(err) => return writeln!(self.stdout, "{}", err),
+        };
+        let board = renderer(mode, &self.config).render(&self.current_state, None);
+        match fs::write(path, board) {
+            Ok(_) => writeln!(self.stdout, "Board exported to {}", path),
+            Err(err) => writeln!(
+                self.stdout,
+                "Unable to export the board to {}: {}",
+                path, err
+            ),
+        }
+    }
+
+    /// Writes the starting board and the moves played so far to a transcript, which `analyze
+    /// <file>` can step through later.
+    fn save_game(&mut self, start_state: &Game, path: &str) -> io::Result<()> {
+        let path = path.trim();
+        if path.is_empty() {
+            return writeln!(self.stdout, "Usage: save-game <path>");
+        }
+        if !self.current_state.has_full_history() {
+            return writeln!(
+                self.stdout,
+                "The earliest moves are no longer in the history, so the game cannot be saved. Raise history_limit in the settings to keep them."
+            );
+        }
+        let mut moves: Vec<Move> = (start_state.current_move + 1..=self.current_state.current_move)
+            .filter_map(|idx| self.current_state.moves.get(&idx).cloned())
+            .collect();
+        if self.config.merge_pours() {
+            moves = merge_pours(&moves);
+        }
+        match fs::write(path, transcript_string(start_state, &moves, &self.notes)) {
+            Ok(_) => writeln!(self.stdout, "Game saved to {}", path),
+            Err(err) => writeln!(self.stdout, "Unable to save the game to {}: {}", path, err),
+        }
+    }
+
+    /// Forks the game into an exploratory line, leaving the line it was on to return to with
+    /// `back`. Branches can be made from other branches.
+    fn branch(&mut self) -> io::Result<()> {
+        self.branches.push(self.current_state.clone());
+        writeln!(
+            self.stdout,
+            "Exploring branch {} from move {}. Enter back to return.",
+            self.branches.len(),
+            self.current_state.current_move
+        )
+    }
+
+    /// Takes back the last move and shows the board. Returns whether there was a move to take back.
+    fn undo(&mut self) -> io::Result<bool> {
+        match self.current_state.undo_move() {
+            Some(a_move) => {
+                writeln!(self.stdout, "Took back {}:", a_move)?;
+                self.show_board()?;
+                Ok(true)
+            }
+            None if self.current_state.current_move == 0 => {
+                writeln!(self.stdout, "There is no move to take back")?;
+                Ok(false)
+            }
+            None => {
+                writeln!(
+                    self.stdout,
+                    "The last move is no longer in the history, so it cannot be taken back. Raise history_limit in the settings to keep it."
+                )?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Deals with a board that is not complete but has no legal moves left, which would otherwise
+    /// leave the player entering moves that can never be made. The player can take back the last
+    /// move, or else the puzzle is lost and counted so in the statistics. On a branch the line it
+    /// was made from can still be returned to, so nothing is lost. Returns whether play goes on.
+    fn stuck(&mut self) -> io::Result<bool> {
+        writeln!(self.stdout, "Stuck: no legal moves remain.")?;
+        if !self.branches.is_empty() {
+            writeln!(
+                self.stdout,
+                "Enter undo to take back the last move, or back to return to where it branched."
+            )?;
+            return Ok(true);
+        }
+        let answer = self
+            .prompt("Enter 'undo' to take back the last move, or press Enter to end the game: ")?;
+        if answer.trim() == "undo" && self.undo()? {
+            return Ok(true);
+        }
+        writeln!(self.stdout, "The puzzle is lost.")?;
+        let mut stats = match LifetimeStats::load() {
+            Ok(stats) => stats,
+            Err(err) => {
+                writeln!(self.stdout, "Warning: {err}. Statistics were not updated.")?;
+                return Ok(false);
+            }
         };
-        let quantity = match string_parts[2].parse::<i32>() {
-            Ok(entry) => entry,
-            Err(_) => return Err("Expected an integer for the 'quantity' value".to_string()),
+        stats.record_stuck_loss();
+        if let Err(err) = stats.save() {
+            writeln!(self.stdout, "Warning: {err}")?;
+        }
+        Ok(false)
+    }
+
+    /// Abandons the current branch and returns to the line it was made from, as it was left.
+    /// Hints used on the branch still count against the game.
+    fn back(&mut self) -> io::Result<()> {
+        let stats = self.current_state.stats.clone();
+        self.current_state = match self.branches.pop() {
+            Some(line) => line,
+            None => return writeln!(self.stdout, "There is no branch to go back from"),
         };
+        self.current_state.stats = stats;
+        match self.branches.len() {
+            0 => writeln!(
+                self.stdout,
+                "Back on the main line at move {}:",
+                self.current_state.current_move
+            )?,
+            branch => writeln!(
+                self.stdout,
+                "Back on branch {} at move {}:",
+                branch, self.current_state.current_move
+            )?,
+        }
+        self.show_board()
+    }
+
+    /// Handles a `rename-colour` command during play, then shows the board.
+    fn rename(&mut self, args: &str) -> io::Result<()> {
+        match parse_rename_colour(args) {
+            Ok((old, new)) => {
+                if self.rename_colour(&old, &new)? {
+                    self.show_board()?;
+                }
+                Ok(())
+            }
+            Err(err) => writeln!(self.stdout, "{}", err),
+        }
+    }
 
-        if tube_from < 1 || tube_from > game.tubes.len() as i32 {
-            return Err("Unexpected 'tube from' number".to_string());
+    /// Renames a colour in the game and in every branch of it, for when a colour was entered
+    /// under a different name from the one the game being copied uses. Returns whether it was
+    /// renamed.
+    fn rename_colour(&mut self, old: &str, new: &str) -> io::Result<bool> {
+        if let Err(err) = self.current_state.rename_colour(old, new) {
+            writeln!(self.stdout, "Unable to rename {}: {}", old, err)?;
+            return Ok(false);
         }
-        if tube_to < 1 || tube_to > game.tubes.len() as i32 {
-            return Err("Unexpected 'tube to' number".to_string());
+        for line in self.branches.iter_mut() {
+            let _ = line.rename_colour(old, new);
         }
-        if quantity < 1 || quantity > TUBE_SIZE as i32 {
-            return Err("Unexpected 'quantity' number".to_string());
+        if self.focus.as_deref() == Some(old) {
+            self.focus = Some(new.to_string());
         }
+        writeln!(self.stdout, "Renamed {} to {}", old, new)?;
+        Ok(true)
+    }
 
-        Ok(MoveInput {
-            tube_from,
-            tube_to,
-            quantity,
-        })
+    /// Renumbers the tubes to match the layout of the game being copied, along with the lines left
+    /// by `branch`, then shows the board. Returns the new order, for the board the game started
+    /// from to be renumbered too, or None if the tubes were not relabelled.
+    fn relabel(&mut self, args: &str) -> io::Result<Option<Vec<usize>>> {
+        let order = match parse_relabel(args, self.current_state.tubes.len()) {
+            Ok(order) => order,
+            Err(err) => {
+                writeln!(self.stdout, "Unable to relabel the tubes: {}", err)?;
+                return Ok(None);
+            }
+        };
+        if let Err(err) = self.current_state.permute_tubes(&order) {
+            writeln!(self.stdout, "Unable to relabel the tubes: {}", err)?;
+            return Ok(None);
+        }
+        // The lines to go back to are numbered the same way, so they are still the same game
+        for line in self.branches.iter_mut() {
+            let _ = line.permute_tubes(&order);
+        }
+        // The notes follow their tubes to their new positions
+        self.notes = std::mem::take(&mut self.notes)
+            .into_iter()
+            .filter_map(|(tube, note)| Some((order.iter().position(|old| *old == tube)?, note)))
+            .collect();
+        writeln!(self.stdout, "Tubes relabelled:")?;
+        self.show_board()?;
+        Ok(Some(order))
+    }
+
+    /// Attaches a note to a tube, such as `note 5 "keep for green"`, shown with the board until it
+    /// is cleared with `note <tube>` and no text.
+    fn note(&mut self, args: &str) -> io::Result<()> {
+        match parse_note(args, self.current_state.tubes.len()) {
+            Ok((tube, Some(note))) => {
+                self.notes.insert(tube, note);
+                writeln!(self.stdout, "Note added to tube {}:", tube + 1)?;
+            }
+            Ok((tube, None)) => {
+                if self.notes.remove(&tube).is_none() {
+                    return writeln!(self.stdout, "Tube {} has no note", tube + 1);
+                }
+                writeln!(self.stdout, "Note on tube {} cleared:", tube + 1)?;
+            }
+            Err(err) => return writeln!(self.stdout, "Unable to add the note: {}", err),
+        }
+        self.show_board()
+    }
+
+    /// Replaces the board with one given as a compact code, as read by `import`, to jump straight
+    /// to a position while developing the game. The rules and limits of the game are kept, and
+    /// the moves so far are forgotten. Only allowed in a debug build or with `debug_commands` in
+    /// the settings. Returns whether the board was replaced.
+    fn set_state(&mut self, code: &str) -> io::Result<bool> {
+        if !self.config.debug_commands() {
+            writeln!(
+                self.stdout,
+                "setstate is only available in a debug build or with debug_commands = true in the settings"
+            )?;
+            return Ok(false);
+        }
+        let code = code.trim();
+        if code.is_empty() {
+            writeln!(self.stdout, "Usage: setstate <code>")?;
+            return Ok(false);
+        }
+        let tubes = match parse_board_code(code) {
+            Ok(tubes) if tubes.len() < 4 => {
+                writeln!(
+                    self.stdout,
+                    "Unable to set the board: a game needs at least 4 tubes"
+                )?;
+                return Ok(false);
+            }
+            Ok(tubes) => tubes,
+            Err(err) => {
+                writeln!(self.stdout, "Unable to set the board: {}", err)?;
+                return Ok(false);
+            }
+        };
+        let mut state = Game {
+            ruleset: self.current_state.ruleset,
+            hint_limit: self.current_state.hint_limit,
+            time_limit: self.current_state.time_limit,
+            stats: self.current_state.stats.clone(),
+            preset: self.current_state.preset,
+            history_limit: self.current_state.history_limit,
+            ..Default::default()
+        };
+        state.init_tubes(tubes.len());
+        for (idx, contents) in tubes.into_iter().enumerate() {
+            state.init_tube_contents(idx, contents);
+        }
+        if !state.validate_setup() {
+            writeln!(
+                self.stdout,
+                "Unable to set the board: it needs {} of every colour and two more tubes than colours",
+                TUBE_SIZE
+            )?;
+            return Ok(false);
+        }
+        self.current_state = state;
+        self.focus = None;
+        self.notes.clear();
+        self.state_set = true;
+        writeln!(self.stdout, "Board set:")?;
+        self.show_board()?;
+        Ok(true)
+    }
+
+    /// Asks for the contents of a tube until they pass `check_tube`.
+    fn prompt_tube(&mut self, prompt: &str) -> io::Result<String> {
+        loop {
+            let input = self.prompt(prompt)?;
+            if let Some(contents) = self.check_tube(&input)? {
+                return Ok(contents);
+            }
+            if self.setup_rejected {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "unknown colour with strict input",
+                ));
+            }
+        }
+    }
+
+    /// Checks the contents of a tube as entered during setup, for unknown colours and then for
+    /// gaps. Returns the contents with any fixes the user accepted, or None if the tube should be
+    /// re-entered.
+    fn check_tube(&mut self, input: &str) -> io::Result<Option<String>> {
+        match self.resolve_unknown_colours(input)? {
+            Some(contents) => self.settle_gaps(contents),
+            None => Ok(None),
+        }
+    }
+
+    /// Offers to let the colours of a tube settle to the bottom if one was entered above an empty
+    /// segment, which can not happen in a real game. Returns the settled contents, or None if the
+    /// tube should be re-entered.
+    fn settle_gaps(&mut self, contents: String) -> io::Result<Option<String>> {
+        let mut tube = Tube::from_string(contents.clone(), 0);
+        if !tube.has_gaps() {
+            return Ok(Some(contents));
+        }
+        tube.settle();
+        let settled = tube.contents_string();
+        let answer = self.prompt(&format!(
+            "A colour is above an empty segment, but liquid settles to the bottom of a tube. Settle it as '{}'? (y)es / (r)e-enter: ",
+            settled
+        ))?;
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => Ok(Some(settled)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Checks the contents of a tube for colours that are not known names, hex codes or already in
+    /// the game, which are usually typos. Colour names of the configured locales and aliases are
+    /// first read as the colours they stand for. For each one the user can take the suggested colour,
+    /// add it as a new colour, or re-enter the tube. Returns the contents with any suggestions
+    /// taken, or None if the tube should be re-entered. With strict input an unknown colour is
+    /// not offered any of these, and rejects the setup instead.
+    fn resolve_unknown_colours(&mut self, input: &str) -> io::Result<Option<String>> {
+        let mut colours: Vec<String> = input
+            .trim()
+            .split(',')
+            .map(|colour| self.config.colour_names.translate(normalise_colour(colour)))
+            .collect();
+        for idx in 0..colours.len() {
+            let colour = colours[idx].clone();
+            if colour.is_empty()
+                || colour == "empty"
+                || colour_rgb(&colour).is_some()
+                || self.current_state.colours.contains(&colour)
+                || colours[..idx].contains(&colour)
+            {
+                continue;
+            }
+            let suggestion = suggest_colour(&colour, self.current_state.colours.iter());
+            if self.config.strict_input {
+                let mut err = format!("unknown colour '{}'", colour);
+                if let Some(suggestion) = suggestion {
+                    err.push_str(format!(" (did you mean '{}'?)", suggestion).as_str());
+                }
+                self.input_log.record(InputKind::Tube, input, &err);
+                writeln!(
+                    self.stdout,
+                    "Error: {}. Only the known colour names and hex codes can be used with strict input.",
+                    err
+                )?;
+                self.setup_rejected = true;
+                return Ok(None);
+            }
+            let prompt = match &suggestion {
+                Some(suggestion) => format!(
+                    "Unknown colour '{}': did you mean '{}'? (y)es / (a)dd as new colour / (r)e-enter: ",
+                    colour, suggestion
+                ),
+                None => format!(
+                    "Unknown colour '{}': (a)dd as new colour / (r)e-enter: ",
+                    colour
+                ),
+            };
+            let answer = self.prompt(&prompt)?;
+            match (answer.trim().to_lowercase().as_str(), suggestion) {
+                ("y" | "yes", Some(suggestion)) => colours[idx] = suggestion,
+                ("a" | "add", _) => {}
+                _ => return Ok(None),
+            }
+        }
+        Ok(Some(colours.join(", ")))
+    }
+
+    fn prompt(&mut self, prompt: &str) -> io::Result<String> {
+        write!(self.stdout, "{}", prompt)?;
+        self.stdout.flush()?;
+        let mut input = String::new();
+        self.stdin.read_line(&mut input)?;
+        Ok(input)
+    }
+
+    /// Starts solving the board on another thread, unless it is already known or another board is
+    /// still being solved, in which case the board is left for the `solve` command to search.
+    fn analyse_in_background(&mut self) {
+        self.collect_analysis(false);
+        if self.analysis.is_some()
+            || self.current_state.is_game_complete()
+            || self.solutions.best_move(&self.current_state).is_some()
+        {
+            return;
+        }
+        let game = self.current_state.clone();
+        let board = game.clone();
+        self.analysis = Some((game, thread::spawn(move || Solver::new(&board).search())));
+    }
+
+    /// Adds the solution of the board solved in the background to `solutions` once it is found,
+    /// waiting for it if `wait` is set.
+    fn collect_analysis(&mut self, wait: bool) {
+        let (game, handle) = match self.analysis.take() {
+            Some((game, handle)) if wait || handle.is_finished() => (game, handle),
+            analysis => {
+                self.analysis = analysis;
+                return;
+            }
+        };
+        // Only the shortest solutions say how far a board is from being complete
+        if let Ok(Some(solution)) = handle.join() {
+            if solution.optimal {
+                self.solutions.insert(&game, &solution.moves);
+            }
+        }
+    }
+
+    /// Shows a solution from the current board, which uses a hint. The boards solved in the
+    /// background since the start of the game are followed as far as they go, so the search only
+    /// starts from wherever the player left them, if at all.
+    fn solve(&mut self) -> io::Result<()> {
+        // The board still being solved is most often this one, so the search already under way
+        // is waited for rather than started again
+        let wait = self
+            .analysis
+            .as_ref()
+            .is_some_and(|(game, _)| game.state_key() == self.current_state.state_key());
+        self.collect_analysis(wait);
+        let solution = match self.solutions.solve(&self.current_state) {
+            Some(solution) => solution,
+            None => {
+                return writeln!(
+                    self.stdout,
+                    "The solver could not find a solution from this board."
+                )
+            }
+        };
+        if !self.current_state.use_hint() {
+            return writeln!(
+                self.stdout,
+                "You have used all of your hints for this puzzle."
+            );
+        }
+        let searched = match solution.states_searched {
+            0 => String::from("found from the analysis of earlier boards"),
+            states => format!("{} states searched", states),
+        };
+        writeln!(
+            self.stdout,
+            "A solution from here in {} moves ({}):",
+            solution.moves.len(),
+            searched
+        )?;
+        write!(self.stdout, "{}", numbered_moves_string(&solution.moves))
+    }
+
+    fn hint(&mut self) -> io::Result<()> {
+        // A board on a shortest solution found earlier in the session gets that solution's move
+        let hint = match self
+            .solutions
+            .best_move(&self.current_state)
+            .or_else(|| Solver::new(&self.current_state).hint())
+        {
+            Some(hint) => hint,
+            None => return writeln!(self.stdout, "There are no possible moves to suggest."),
+        };
+        if !self.current_state.use_hint() {
+            return writeln!(
+                self.stdout,
+                "You have used all of your hints for this puzzle."
+            );
+        }
+        // How promising the move is, by the share of random games after it that complete the game
+        let promise = format!(
+            "{:.0}% of random playouts after it finish the puzzle",
+            RolloutEvaluator::default().score_move(&self.current_state, &hint) * 100.0
+        );
+        match self.current_state.hints_remaining() {
+            Some(remaining) => writeln!(
+                self.stdout,
+                "Hint: try {} ({}, {} hints remaining)",
+                hint, promise, remaining
+            ),
+            None => writeln!(self.stdout, "Hint: try {} ({})", hint, promise),
+        }
+    }
+}
+
+enum SetupCommand {
+    Insert(usize),
+    Remove(usize),
+    RenameColour(String, String),
+}
+
+impl SetupCommand {
+    /// Parses an `insert <tube>`, `remove <tube>` or `rename-colour <old> <new>` command entered
+    /// in place of a tube's contents, when `entered` tubes have been entered so far. Returns None
+    /// if the input is not a command.
+    fn parse(input: &str, entered: usize) -> Option<Result<SetupCommand, String>> {
+        let (command, pos) = input.trim().split_once(' ')?;
+        let command = command.to_lowercase();
+        if command == "rename-colour" || command == "rename-color" {
+            return Some(
+                parse_rename_colour(pos).map(|(old, new)| SetupCommand::RenameColour(old, new)),
+            );
+        }
+        if command != "insert" && command != "remove" {
+            return None;
+        }
+        let pos = match pos.trim().parse::<usize>() {
+            Ok(pos) => pos,
+            Err(_) => return Some(Err(format!("Expected a tube number after '{}'", command))),
+        };
+        if pos < 1 || pos > entered {
+            return Some(Err(format!(
+                "Can only {} one of the tubes entered so far (1 to {})",
+                command, entered
+            )));
+        }
+        match command.as_str() {
+            "insert" => Some(Ok(SetupCommand::Insert(pos - 1))),
+            _ => Some(Ok(SetupCommand::Remove(pos - 1))),
+        }
+    }
+}
+
+/// Parses the arguments of a `rename-colour` command into the old and new names of the colour.
+fn parse_rename_colour(args: &str) -> Result<(String, String), String> {
+    match args.split_whitespace().collect::<Vec<&str>>()[..] {
+        [old, new] => Ok((normalise_colour(old), normalise_colour(new))),
+        _ => Err("Usage: rename-colour <old> <new>".to_string()),
+    }
+}
+
+/// Parses the arguments of a `note` command into the tube and its note, or None to clear the
+/// note. The note can be wrapped in quotes.
+fn parse_note(args: &str, num_of_tubes: usize) -> Result<(usize, Option<String>), String> {
+    let (tube, note) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+    let tube = match tube.parse::<usize>() {
+        Ok(tube) if tube >= 1 && tube <= num_of_tubes => tube - 1,
+        _ => {
+            return Err(format!(
+                "Expected 'note <tube> \"<text>\"' with a tube number from 1 to {}",
+                num_of_tubes
+            ))
+        }
+    };
+    let note = note.trim();
+    let note = note
+        .strip_prefix('"')
+        .and_then(|note| note.strip_suffix('"'))
+        .unwrap_or(note)
+        .trim();
+    Ok((tube, (!note.is_empty()).then(|| note.to_string())))
+}
+
+/// Parses the arguments of a `relabel` command into a new order of the tubes, where
+/// `order[new_position]` is the current position of the tube that moves there. The arguments are
+/// `reverse`, `<tube> <position>` to move one tube, or the full new order of the tubes.
+fn parse_relabel(args: &str, num_of_tubes: usize) -> Result<Vec<usize>, String> {
+    let args: Vec<&str> = args.split_whitespace().collect();
+    let mut order: Vec<usize> = (0..num_of_tubes).collect();
+    if args.len() == 1 && args[0].eq_ignore_ascii_case("reverse") {
+        order.reverse();
+        return Ok(order);
+    }
+    let mut numbers = Vec::with_capacity(args.len());
+    for arg in args.iter() {
+        match arg.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= num_of_tubes => numbers.push(number - 1),
+            _ => {
+                return Err(format!(
+                    "Expected a tube number from 1 to {}, got '{}'",
+                    num_of_tubes, arg
+                ))
+            }
+        }
+    }
+    match numbers.len() {
+        2 => {
+            let tube = order.remove(numbers[0]);
+            order.insert(numbers[1], tube);
+            Ok(order)
+        }
+        len if len == num_of_tubes => Ok(numbers),
+        _ => Err(
+            "Expected 'relabel reverse', 'relabel <tube> <position>' or the new order of every tube"
+                .to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rename_colour() {
+        let tests = [
+            ("teal cyan", Some(("teal", "cyan"))),
+            ("  Teal   #0FF ", Some(("teal", "#00ffff"))),
+            ("teal", None),
+            ("teal cyan blue", None),
+            ("", None),
+        ];
+        for test in tests {
+            let result = parse_rename_colour(test.0).ok();
+            let expected = test.1.map(|(old, new)| (old.to_string(), new.to_string()));
+            assert_eq!(result, expected, "wrong colours parsed from {:?}", test.0);
+        }
+    }
+
+    #[test]
+    fn test_parse_note() {
+        let tests = [
+            (
+                " 5 \"don't touch until green is free\"",
+                Some((4, Some("don't touch until green is free"))),
+            ),
+            (" 2   keep for red ", Some((1, Some("keep for red")))),
+            (" 3", Some((2, None))),
+            (" 3 \"\"", Some((2, None))),
+            ("", None),
+            (" 6 spare", None),
+            (" 0 spare", None),
+            (" first spare", None),
+        ];
+        for test in tests {
+            let result = parse_note(test.0, 5).ok();
+            let expected = test.1.map(|(tube, note)| (tube, note.map(str::to_string)));
+            assert_eq!(result, expected, "wrong note parsed from {:?}", test.0);
+        }
+    }
+
+    #[test]
+    fn test_parse_relabel() {
+        let tests: Vec<(&str, Option<Vec<usize>>)> = vec![
+            (" reverse", Some(vec![4, 3, 2, 1, 0])),
+            (" REVERSE", Some(vec![4, 3, 2, 1, 0])),
+            (" 4 1", Some(vec![3, 0, 1, 2, 4])),
+            (" 1 5", Some(vec![1, 2, 3, 4, 0])),
+            (" 2 3 1 5 4", Some(vec![1, 2, 0, 4, 3])),
+            ("", None),
+            (" 3", None),
+            (" 6 1", None),
+            (" 0 1", None),
+            (" first 1", None),
+            (" 1 2 3", None),
+        ];
+        for test in tests {
+            match (parse_relabel(test.0, 5), test.1) {
+                (Ok(order), Some(expected)) => assert_eq!(
+                    order, expected,
+                    "wrong order parsed from {:?}. Expected: {:?}, got: {:?}",
+                    test.0, expected, order
+                ),
+                (Err(_), None) => {}
+                (Ok(order), None) => {
+                    panic!("expected an error parsing {:?}, got {:?}", test.0, order)
+                }
+                (Err(err), Some(_)) => panic!("unexpected error parsing {:?}: {}", test.0, err),
+            }
+        }
     }
 }
@@ -0,0 +1,190 @@
+use crate::{
+    game::{Game, Move},
+    transpositions::TranspositionTable,
+};
+
+/// One of the player's moves, compared against the shortest solution from the board it was made
+/// on.
+#[derive(Clone)]
+pub struct MoveReview {
+    pub played: Move,
+    /// How many more moves this move added to the shortest possible finish, or None if the
+    /// shortest solution could not be found within the search limit.
+    pub cost: Option<usize>,
+    /// The first move of a shortest solution from the same board. Only kept when the played move
+    /// cost something.
+    pub best: Option<Move>,
+}
+
+/// Replays the player's moves from the start of the game, checking after each one how far the
+/// board is from being complete. A move that keeps to a shortest solution brings the finish one
+/// move closer, so anything else is where the player diverged, and the difference is what the
+/// divergence cost. Boards already in `solutions` are not searched again, and the ones searched are
+/// added to it.
+pub fn review_moves(
+    start: &Game,
+    moves: &[Move],
+    solutions: &mut TranspositionTable,
+) -> Vec<MoveReview> {
+    let mut state = start.clone();
+    let mut remaining = shortest_solution(&state, solutions);
+    let mut reviews = Vec::with_capacity(moves.len());
+    for played in moves {
+        let mut next = state.clone();
+        next.make_move(played);
+        let next_remaining = shortest_solution(&next, solutions);
+        let cost = match (&remaining, &next_remaining) {
+            (Some(before), Some(after)) => Some(after.len() + 1 - before.len()),
+            _ => None,
+        };
+        let best = match cost {
+            Some(0) => None,
+            _ => remaining
+                .as_ref()
+                .and_then(|solution| solution.first().cloned()),
+        };
+        reviews.push(MoveReview {
+            played: played.clone(),
+            cost,
+            best,
+        });
+        state = next;
+        remaining = next_remaining;
+    }
+    reviews
+}
+
+/// The shortest solution from a board. Solutions that might not be the shortest, found for large
+/// boards, cannot be compared against.
+fn shortest_solution(game: &Game, solutions: &mut TranspositionTable) -> Option<Vec<Move>> {
+    match solutions.solve(game) {
+        Some(solution) if solution.optimal => Some(solution.moves),
+        _ => None,
+    }
+}
+
+/// Lays the review out one move per line, with a total of what the divergences cost.
+pub fn review_string(reviews: &[MoveReview]) -> String {
+    let mut out = String::new();
+    let mut total = 0;
+    for (idx, review) in reviews.iter().enumerate() {
+        let verdict = match (review.cost, &review.best) {
+            (Some(0), _) => String::from("on a shortest line"),
+            (Some(cost), Some(best)) => {
+                total += cost;
+                format!(
+                    "diverged, costing {}. Best was ({})",
+                    plural_moves(cost),
+                    best
+                )
+            }
+            (Some(cost), None) => {
+                total += cost;
+                format!("diverged, costing {}", plural_moves(cost))
+            }
+            (None, _) => String::from("unknown, the board was too large to search"),
+        };
+        out.push_str(format!("{} : ({}) {}\n", idx + 1, review.played, verdict).as_str());
+    }
+    let divergences = reviews
+        .iter()
+        .filter(|review| review.cost.is_some_and(|cost| cost > 0))
+        .count();
+    out.push_str(
+        format!(
+            "{} divergence(s), costing {} in total\n",
+            divergences,
+            plural_moves(total)
+        )
+        .as_str(),
+    );
+    out
+}
+
+fn plural_moves(moves: usize) -> String {
+    match moves {
+        1 => String::from("1 move"),
+        _ => format!("{} moves", moves),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::initialise_game;
+
+    fn new_move(tube_from: usize, tube_to: usize, colour: &str, quantity: usize) -> Move {
+        Move {
+            tube_from,
+            tube_to,
+            colour: colour.to_string(),
+            quantity,
+            played_at: None,
+        }
+    }
+
+    #[test]
+    fn test_review_moves() {
+        let game = initialise_game(&["red, red, red, blue", "red, blue, blue, blue"], 4);
+        // The shortest solution is 3 moves. Once the three reds are in a tube, pouring the single
+        // red into the other empty tube leaves it needing to be merged back later.
+        let tests = [
+            (
+                vec![
+                    new_move(0, 2, "red", 3),
+                    new_move(1, 2, "red", 1),
+                    new_move(0, 1, "blue", 1),
+                ],
+                vec![Some(0), Some(0), Some(0)],
+            ),
+            (
+                vec![
+                    new_move(0, 2, "red", 3),
+                    new_move(1, 3, "red", 1),
+                    new_move(3, 2, "red", 1),
+                    new_move(0, 1, "blue", 1),
+                ],
+                vec![Some(0), Some(1), Some(0), Some(0)],
+            ),
+        ];
+        let mut solutions = TranspositionTable::default();
+        for (idx, test) in tests.iter().enumerate() {
+            let reviews = review_moves(&game, &test.0, &mut solutions);
+            let costs: Vec<Option<usize>> = reviews.iter().map(|review| review.cost).collect();
+            assert_eq!(
+                costs, test.1,
+                "wrong costs for line {}. Expected: {:?}, got: {:?}",
+                idx, test.1, costs
+            );
+            for review in reviews.iter() {
+                assert_eq!(
+                    review.best.is_some(),
+                    review.cost != Some(0),
+                    "best move should only be kept for a divergence"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_review_string() {
+        let reviews = [
+            MoveReview {
+                played: new_move(1, 3, "red", 1),
+                cost: Some(1),
+                best: Some(new_move(0, 2, "red", 3)),
+            },
+            MoveReview {
+                played: new_move(0, 2, "red", 3),
+                cost: Some(0),
+                best: None,
+            },
+        ];
+        assert_eq!(
+            review_string(&reviews),
+            "1 : (2 -> 4: red x 1) diverged, costing 1 move. Best was (1 -> 3: red x 3)\n\
+             2 : (1 -> 3: red x 3) on a shortest line\n\
+             1 divergence(s), costing 1 move in total\n"
+        );
+    }
+}
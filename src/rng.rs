@@ -0,0 +1,103 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// Where the random numbers of the board generator and the random playouts come from, so that
+/// any of them can be run either from a seed, to be repeated exactly, or from the operating
+/// system's randomness.
+pub trait RngSource {
+    fn next_u64(&mut self) -> u64;
+
+    /// A number below `bound`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// A small pseudo-random number generator (xorshift64*), so that the same seed always gives the
+/// same numbers and a run can be repeated after the search has been changed.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    seed: u64,
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> SeededRng {
+        SeededRng {
+            seed,
+            // Xorshift never leaves zero, so the seed is mixed with a constant first
+            state: (seed ^ 0x9e37_79b9_7f4a_7c15) | 1,
+        }
+    }
+
+    /// The seed the numbers come from, to record alongside anything made from them.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngSource for SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
+
+/// Numbers from the operating system's randomness, by way of the random keys the standard
+/// library draws from it for hash maps, so no two runs are the same.
+#[derive(Default, Debug)]
+pub struct OsRng {
+    count: u64,
+}
+
+impl RngSource for OsRng {
+    fn next_u64(&mut self) -> u64 {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(self.count);
+        self.count += 1;
+        hasher.finish()
+    }
+}
+
+/// A seed from the operating system's randomness, for something made at random that should still
+/// be possible to make again: make it from a `SeededRng` with this seed, and record the seed.
+pub fn os_seed() -> u64 {
+    OsRng::default().next_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_rng() {
+        let numbers = |seed: u64| -> Vec<u64> {
+            let mut rng = SeededRng::new(seed);
+            (0..5).map(|_| rng.next_u64()).collect()
+        };
+        assert_eq!(numbers(7), numbers(7), "the same seed should repeat");
+        assert_ne!(numbers(7), numbers(8), "another seed should differ");
+        assert_eq!(SeededRng::new(7).seed(), 7, "the seed should be kept");
+        let mut rng = SeededRng::new(0);
+        assert!(
+            (0..100).all(|_| rng.below(6) < 6),
+            "below should stay below its bound"
+        );
+    }
+
+    #[test]
+    fn test_os_rng() {
+        let mut rng = OsRng::default();
+        let numbers: Vec<u64> = (0..5).map(|_| rng.next_u64()).collect();
+        assert!(
+            numbers.windows(2).any(|pair| pair[0] != pair[1]),
+            "the numbers should not all be the same: {:?}",
+            numbers
+        );
+        assert_ne!(os_seed(), os_seed(), "two seeds should differ");
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashSet;
+
+use crate::{
+    game::{Game, Move},
+    rng::{RngSource, SeededRng},
+    solver::{state_hash, Solver},
+};
+
+/// How many random games a `RolloutEvaluator` plays by default.
+pub const DEFAULT_PLAYOUTS: usize = 50;
+
+/// How many moves a random game is played for by default before it is given up on.
+pub const DEFAULT_PLAYOUT_MOVES: usize = 100;
+
+/// Judges how promising a board is by playing random games on from it, and scoring it by the
+/// share of them that complete the game. Every random game picks one of the possible moves at
+/// random at each turn, never going back to a board it has already been on, until the game is
+/// complete, no move is left or `max_moves` have been made. The games are seeded from the board,
+/// so the same board always gets the same score.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RolloutEvaluator {
+    pub playouts: usize,
+    pub max_moves: usize,
+}
+
+impl Default for RolloutEvaluator {
+    fn default() -> Self {
+        RolloutEvaluator::new(DEFAULT_PLAYOUTS)
+    }
+}
+
+/// A possible move, with the share of random games that completed the game after it.
+#[derive(Clone)]
+pub struct RankedMove {
+    pub a_move: Move,
+    pub completion_rate: f64,
+}
+
+impl RolloutEvaluator {
+    pub fn new(playouts: usize) -> RolloutEvaluator {
+        RolloutEvaluator {
+            playouts,
+            max_moves: DEFAULT_PLAYOUT_MOVES,
+        }
+    }
+
+    /// The share of random games from the board that complete the game, from 0 to 1. A complete
+    /// board scores 1.
+    pub fn completion_rate(&self, game: &Game) -> f64 {
+        self.completion_rate_with(game, &mut SeededRng::new(state_hash(game)))
+    }
+
+    /// The share of random games from the board that complete the game, with the random moves
+    /// picked from any source, such as `OsRng` for games that differ on every call.
+    pub fn completion_rate_with(&self, game: &Game, random: &mut dyn RngSource) -> f64 {
+        if game.is_game_complete() {
+            return 1.0;
+        }
+        if self.playouts == 0 {
+            return 0.0;
+        }
+        let completed = (0..self.playouts)
+            .filter(|_| self.playout(game, random))
+            .count();
+        completed as f64 / self.playouts as f64
+    }
+
+    /// The share of random games that complete the game after the move is made.
+    pub fn score_move(&self, game: &Game, a_move: &Move) -> f64 {
+        let mut next = game.clone();
+        next.make_move(a_move);
+        self.completion_rate(&next)
+    }
+
+    /// Every possible move from the board, the most promising first. Moves with the same rate are
+    /// in the order the solver finds them.
+    pub fn rank_moves(&self, game: &Game) -> Vec<RankedMove> {
+        let mut ranked: Vec<RankedMove> = Solver::new(game)
+            .get_legal_moves()
+            .into_iter()
+            .map(|a_move| RankedMove {
+                completion_rate: self.score_move(game, &a_move),
+                a_move,
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.completion_rate.total_cmp(&a.completion_rate));
+        ranked
+    }
+
+    /// Plays one random game from the board, returning whether it completed the game.
+    fn playout(&self, game: &Game, random: &mut dyn RngSource) -> bool {
+        let mut state = game.clone();
+        let mut seen = HashSet::from([state_hash(&state)]);
+        for _ in 0..self.max_moves {
+            let moves: Vec<(Game, u64)> = Solver::new(&state)
+                .get_possible_moves()
+                .iter()
+                .filter_map(|a_move| {
+                    let mut next = state.clone();
+                    next.make_move(a_move);
+                    let hash = state_hash(&next);
+                    (!seen.contains(&hash)).then_some((next, hash))
+                })
+                .collect();
+            if moves.is_empty() {
+                return false;
+            }
+            let (next, hash) = moves[random.below(moves.len())].clone();
+            if next.is_game_complete() {
+                return true;
+            }
+            seen.insert(hash);
+            state = next;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_rate() {
+        let mut complete = Game::default();
+        complete.init_tubes(4);
+        complete.init_tube_contents(0, String::from("red, red, red, red"));
+        complete.init_tube_contents(1, String::from("blue, blue, blue, blue"));
+        let mut one_move = Game::default();
+        one_move.init_tubes(4);
+        one_move.init_tube_contents(0, String::from("red, red, red"));
+        one_move.init_tube_contents(1, String::from("blue, blue, blue, blue"));
+        one_move.init_tube_contents(2, String::from("red"));
+        let mut stuck = Game::default();
+        stuck.init_tubes(4);
+        stuck.init_tube_contents(0, String::from("red, blue, red, blue"));
+        stuck.init_tube_contents(1, String::from("blue, red, blue, red"));
+        stuck.init_tube_contents(2, String::from("green, yellow, green, yellow"));
+        stuck.init_tube_contents(3, String::from("yellow, green, yellow, green"));
+        // (board, completion rate)
+        let tests = [(complete, 1.0), (one_move, 1.0), (stuck, 0.0)];
+        let evaluator = RolloutEvaluator::default();
+        for test in tests {
+            assert_eq!(
+                evaluator.completion_rate(&test.0),
+                test.1,
+                "wrong completion rate for:\n{}",
+                test.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_rank_moves() {
+        let game = crate::puzzles::get_builtin_puzzle(1)
+            .expect("puzzle 1 should exist")
+            .to_game();
+        let evaluator = RolloutEvaluator::default();
+        let ranked = evaluator.rank_moves(&game);
+        assert_eq!(
+            ranked.len(),
+            Solver::new(&game).get_legal_moves().len(),
+            "every possible move should be ranked"
+        );
+        assert!(
+            ranked
+                .windows(2)
+                .all(|pair| pair[0].completion_rate >= pair[1].completion_rate),
+            "the moves should be ranked most promising first"
+        );
+        assert!(
+            ranked
+                .iter()
+                .all(|ranked| (0.0..=1.0).contains(&ranked.completion_rate)),
+            "a completion rate should be between 0 and 1"
+        );
+        let summary = |ranked: &[RankedMove]| -> Vec<(String, f64)> {
+            ranked
+                .iter()
+                .map(|ranked| (ranked.a_move.to_string(), ranked.completion_rate))
+                .collect()
+        };
+        assert_eq!(
+            summary(&ranked),
+            summary(&evaluator.rank_moves(&game)),
+            "the same board should be ranked the same way every time"
+        );
+    }
+}
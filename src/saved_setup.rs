@@ -0,0 +1,180 @@
+use std::{fmt::Display, fs};
+
+use crate::{config::home_dir, game::Ruleset};
+
+const SETUP_FILE_NAME: &str = "setup";
+const MIN_TUBES: usize = 4;
+
+/// A board left part way through being entered, so that a large level can be transcribed over
+/// more than one sitting. Stored as `key = value` lines alongside the settings, with every tube
+/// entered so far under its number.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SavedSetup {
+    pub num_of_tubes: usize,
+    pub ruleset: Ruleset,
+    /// The contents of the tubes entered so far, from tube 1.
+    pub tubes: Vec<String>,
+}
+
+impl SavedSetup {
+    pub fn parse(contents: &str) -> Result<SavedSetup, String> {
+        let mut num_of_tubes = None;
+        let mut ruleset = Ruleset::default();
+        let mut tubes = Vec::new();
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("Line {}: expected 'key = value'", line_num + 1)),
+            };
+            match key {
+                "tubes" => match value.parse::<usize>() {
+                    Ok(tubes) if tubes >= MIN_TUBES => num_of_tubes = Some(tubes),
+                    _ => {
+                        return Err(format!(
+                            "Line {}: unable to parse {} to a number of tubes",
+                            line_num + 1,
+                            value
+                        ))
+                    }
+                },
+                "ruleset" => {
+                    ruleset = value
+                        .parse::<Ruleset>()
+                        .map_err(|err| format!("Line {}: {}", line_num + 1, err))?
+                }
+                key => match key.parse::<usize>() {
+                    // The tubes are saved in order, so each must be the one after the last
+                    Ok(tube) if tube == tubes.len() + 1 => tubes.push(value.to_string()),
+                    Ok(tube) => {
+                        return Err(format!(
+                            "Line {}: expected tube {}, got tube {}",
+                            line_num + 1,
+                            tubes.len() + 1,
+                            tube
+                        ))
+                    }
+                    Err(_) => return Err(format!("Line {}: unknown key '{}'", line_num + 1, key)),
+                },
+            }
+        }
+        let num_of_tubes = match num_of_tubes {
+            Some(num_of_tubes) => num_of_tubes,
+            None => return Err("the number of tubes is missing".to_string()),
+        };
+        if tubes.len() > num_of_tubes {
+            return Err(format!(
+                "{} tubes were entered for a board of {} tubes",
+                tubes.len(),
+                num_of_tubes
+            ));
+        }
+        Ok(SavedSetup {
+            num_of_tubes,
+            ruleset,
+            tubes,
+        })
+    }
+
+    /// Loads the saved setup, or None if there is not one.
+    pub fn load() -> Result<Option<SavedSetup>, String> {
+        let path = match home_dir() {
+            Some(dir) => dir.join(SETUP_FILE_NAME),
+            None => return Ok(None),
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => SavedSetup::parse(&contents)
+                .map(Some)
+                .map_err(|err| format!("Unable to read {}: {}", path.display(), err)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let dir = match home_dir() {
+            Some(dir) => dir,
+            None => return Err("Unable to find a home directory to save the setup in".to_string()),
+        };
+        let path = dir.join(SETUP_FILE_NAME);
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, self.to_string()))
+            .map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+    }
+
+    /// Removes the saved setup once it has been resumed or given up on. There being none to
+    /// remove is not an error.
+    pub fn clear() -> Result<(), String> {
+        let path = match home_dir() {
+            Some(dir) => dir.join(SETUP_FILE_NAME),
+            None => return Ok(()),
+        };
+        match fs::remove_file(&path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Unable to remove {}: {}", path.display(), err))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Display for SavedSetup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "tubes = {}", self.num_of_tubes)?;
+        writeln!(f, "ruleset = {}", self.ruleset)?;
+        for (idx, tube) in self.tubes.iter().enumerate() {
+            writeln!(f, "{} = {}", idx + 1, tube)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_saved_setup() {
+        let tests = [
+            (
+                "tubes = 6\nruleset = no-split\n1 = red, red, blue, blue\n2 = empty, blue, red, red\n",
+                Some(SavedSetup {
+                    num_of_tubes: 6,
+                    ruleset: Ruleset::NoSplit,
+                    tubes: vec![
+                        String::from("red, red, blue, blue"),
+                        String::from("empty, blue, red, red"),
+                    ],
+                }),
+            ),
+            (
+                "# saved\ntubes = 4\n",
+                Some(SavedSetup {
+                    num_of_tubes: 4,
+                    ruleset: Ruleset::Standard,
+                    tubes: Vec::new(),
+                }),
+            ),
+            ("ruleset = standard\n1 = red\n", None),
+            ("tubes = 3\n", None),
+            ("tubes = 4\n2 = red\n", None),
+            ("tubes = 4\nruleset = two-unit\n", None),
+            ("tubes = 4\ncolour = red\n", None),
+            ("tubes = 4\n1 = a\n2 = b\n3 = c\n4 = d\n5 = e\n", None),
+            ("tubes 4\n", None),
+        ];
+        for test in tests {
+            let result = SavedSetup::parse(test.0).ok();
+            assert_eq!(result, test.1, "wrong setup parsed from {:?}", test.0);
+            if let Some(setup) = result {
+                assert_eq!(
+                    SavedSetup::parse(&setup.to_string()).ok(),
+                    Some(setup),
+                    "setup did not round trip"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,1037 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, VecDeque},
+};
+
+use crate::{
+    game::{Game, Move, Ruleset},
+    rollout::RolloutEvaluator,
+    solver::{state_hash, SearchSettings, Solver},
+    TUBE_SIZE,
+};
+
+/// A state waiting to be looked at by a search.
+#[derive(Clone)]
+pub struct SearchNode {
+    pub state: Game,
+    /// How many moves from the board being solved the state was reached in.
+    pub depth: usize,
+    /// The lower bound on the moves left to complete the game from the state.
+    pub bound: usize,
+    /// How many units of liquid were poured to reach the state.
+    pub volume: usize,
+    /// How promising the state looks by `evaluate`, lowest first.
+    pub score: i64,
+}
+
+impl SearchNode {
+    pub fn new(state: Game, depth: usize) -> SearchNode {
+        let bound = minimum_moves_lower_bound(&state);
+        let score = evaluate(&state);
+        SearchNode {
+            state,
+            depth,
+            bound,
+            volume: 0,
+            score,
+        }
+    }
+
+    /// The states one move on, in the order the moves are found.
+    pub fn children(&self) -> Vec<SearchNode> {
+        self.children_of(Solver::new(&self.state).get_possible_moves())
+    }
+
+    fn children_of(&self, moves: Vec<Move>) -> Vec<SearchNode> {
+        moves
+            .into_iter()
+            .map(|a_move| {
+                let mut next = self.state.clone();
+                next.make_move(&a_move);
+                SearchNode {
+                    volume: self.volume + a_move.quantity,
+                    ..SearchNode::new(next, self.depth + 1)
+                }
+            })
+            .collect()
+    }
+}
+
+/// An algorithm for the solver to search with, deciding which state to look at next and which are
+/// not worth looking at. The solver takes states from the strategy one at a time until one
+/// completes the game, and gives the strategy the states one move on from each that it does not
+/// prune. Any strategy can be run with `Solver::search_using`.
+pub trait SolverStrategy {
+    /// Adds a state to those waiting to be looked at.
+    fn push(&mut self, node: SearchNode);
+
+    /// Takes the state to look at next, or None once there are none left.
+    fn pop(&mut self) -> Option<SearchNode>;
+
+    /// The states waiting to be looked at, the next one first where the strategy can tell.
+    fn frontier(&self) -> Vec<&SearchNode>;
+
+    /// How many states are waiting to be looked at.
+    fn frontier_size(&self) -> usize {
+        self.frontier().len()
+    }
+
+    /// The states one move on from a state, in the order they should be pushed.
+    fn next_states(&self, node: &SearchNode) -> Vec<SearchNode> {
+        node.children()
+    }
+
+    /// The score the strategy gives a state. The most promising states score lowest.
+    fn evaluate(&self, node: &SearchNode) -> i64;
+
+    /// Whether a state is not worth looking at, given the fewest moves it has been reached in
+    /// before, if it has been seen.
+    fn should_prune(&mut self, node: &SearchNode, fewest: Option<usize>) -> bool;
+
+    /// Whether the first solution found is known to be the shortest.
+    fn finds_shortest(&self) -> bool;
+
+    /// How many states to look at before giving up.
+    fn budget(&self, settings: &SearchSettings) -> usize;
+
+    /// How many states the search may remember having seen, or None for every one. Once that
+    /// many are remembered they are all forgotten, and states seen before may be looked at again.
+    fn seen_limit(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Looks at the states in the order they were reached, so the first solution found is the
+/// shortest, but every state seen is kept.
+#[derive(Default)]
+pub struct BreadthFirstSearch {
+    queue: VecDeque<SearchNode>,
+}
+
+impl SolverStrategy for BreadthFirstSearch {
+    fn push(&mut self, node: SearchNode) {
+        self.queue.push_back(node);
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        self.queue.pop_front()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        self.queue.iter().collect()
+    }
+
+    fn frontier_size(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn evaluate(&self, node: &SearchNode) -> i64 {
+        node.depth as i64
+    }
+
+    fn should_prune(&mut self, _node: &SearchNode, fewest: Option<usize>) -> bool {
+        fewest.is_some()
+    }
+
+    fn finds_shortest(&self) -> bool {
+        true
+    }
+
+    fn budget(&self, settings: &SearchSettings) -> usize {
+        settings.max_breadth_first_states
+    }
+}
+
+/// Follows the most promising move from the latest state first, the one leaving the fewest
+/// blocks, so that memory stays bounded however large the board is. The solution is not always
+/// the shortest.
+#[derive(Default)]
+pub struct DepthFirstSearch {
+    stack: Vec<SearchNode>,
+}
+
+impl SolverStrategy for DepthFirstSearch {
+    fn push(&mut self, node: SearchNode) {
+        self.stack.push(node);
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        self.stack.pop()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        self.stack.iter().rev().collect()
+    }
+
+    fn frontier_size(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn next_states(&self, node: &SearchNode) -> Vec<SearchNode> {
+        node.children_of(ordered_moves(&node.state))
+    }
+
+    fn evaluate(&self, node: &SearchNode) -> i64 {
+        node.bound as i64
+    }
+
+    fn should_prune(&mut self, _node: &SearchNode, fewest: Option<usize>) -> bool {
+        fewest.is_some()
+    }
+
+    fn finds_shortest(&self) -> bool {
+        false
+    }
+
+    fn budget(&self, settings: &SearchSettings) -> usize {
+        settings.max_depth_first_states
+    }
+}
+
+/// The states waiting in a best first search, lowest key first, and in the order they were
+/// pushed among equal keys.
+#[derive(Default)]
+struct PriorityQueue {
+    heap: BinaryHeap<Reverse<(i64, i64, usize)>>,
+    nodes: Vec<Option<SearchNode>>,
+}
+
+impl PriorityQueue {
+    fn push(&mut self, key: (i64, i64), node: SearchNode) {
+        self.heap.push(Reverse((key.0, key.1, self.nodes.len())));
+        self.nodes.push(Some(node));
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        let Reverse((_, _, idx)) = self.heap.pop()?;
+        // A state is only looked at once, so it no longer needs to be kept
+        self.nodes[idx].take()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        let mut waiting = self.heap.clone().into_sorted_vec();
+        waiting.reverse();
+        waiting
+            .iter()
+            .filter_map(|Reverse((_, _, idx))| self.nodes[*idx].as_ref())
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+}
+
+/// Looks at the states in order of the moves made to reach them plus the lower bound on the moves
+/// left, fewest first, breaking ties by `evaluate`. The lower bound never overestimates and
+/// changes by at most one a move, so the first complete state found is at the end of a shortest
+/// solution.
+#[derive(Default)]
+pub struct AStarSearch {
+    queue: PriorityQueue,
+}
+
+impl SolverStrategy for AStarSearch {
+    fn push(&mut self, node: SearchNode) {
+        self.queue.push((self.evaluate(&node), node.score), node);
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        self.queue.pop()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        self.queue.frontier()
+    }
+
+    fn frontier_size(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn evaluate(&self, node: &SearchNode) -> i64 {
+        (node.depth + node.bound) as i64
+    }
+
+    fn should_prune(&mut self, node: &SearchNode, fewest: Option<usize>) -> bool {
+        // A state reached again in fewer moves is worth looking at again
+        fewest.is_some_and(|fewest| fewest <= node.depth)
+    }
+
+    fn finds_shortest(&self) -> bool {
+        true
+    }
+
+    fn budget(&self, settings: &SearchSettings) -> usize {
+        settings.max_a_star_states
+    }
+}
+
+/// Looks at the state that looks closest to complete by `evaluate` first, whatever it took to reach
+/// it, which heads for a solution quickly but not always the shortest.
+#[derive(Default)]
+pub struct GreedySearch {
+    queue: PriorityQueue,
+}
+
+impl SolverStrategy for GreedySearch {
+    fn push(&mut self, node: SearchNode) {
+        self.queue
+            .push((self.evaluate(&node), node.depth as i64), node);
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        self.queue.pop()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        self.queue.frontier()
+    }
+
+    fn frontier_size(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn evaluate(&self, node: &SearchNode) -> i64 {
+        node.score
+    }
+
+    fn should_prune(&mut self, _node: &SearchNode, fewest: Option<usize>) -> bool {
+        fewest.is_some()
+    }
+
+    fn finds_shortest(&self) -> bool {
+        false
+    }
+
+    fn budget(&self, settings: &SearchSettings) -> usize {
+        settings.max_a_star_states
+    }
+}
+
+/// Looks at the states a depth at a time, like a breadth first search, but keeps only the `width`
+/// most promising states of each depth, those that look closest to complete by `evaluate`, and
+/// drops the rest. Memory and time stay bounded by the width however large the board is, but a
+/// dropped state is never looked at again, so the solution is not always the shortest and a board
+/// that can be solved might not be.
+pub struct BeamSearch {
+    width: usize,
+    /// The states of the depth being looked at, the next one last.
+    level: Vec<SearchNode>,
+    /// The states one move on from those looked at, waiting to be cut down to the width.
+    next_level: Vec<SearchNode>,
+}
+
+impl BeamSearch {
+    pub fn new(width: usize) -> BeamSearch {
+        BeamSearch {
+            width,
+            level: Vec::new(),
+            next_level: Vec::new(),
+        }
+    }
+}
+
+impl SolverStrategy for BeamSearch {
+    fn push(&mut self, node: SearchNode) {
+        self.next_level.push(node);
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        if self.level.is_empty() {
+            // The sort is stable, so states with the same score keep the order they were reached in
+            let mut level = std::mem::take(&mut self.next_level);
+            level.sort_by_key(|node| node.score);
+            level.truncate(self.width);
+            level.reverse();
+            self.level = level;
+        }
+        self.level.pop()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        let mut next_level: Vec<&SearchNode> = self.next_level.iter().collect();
+        next_level.sort_by_key(|node| node.score);
+        next_level.truncate(self.width);
+        self.level.iter().rev().chain(next_level).collect()
+    }
+
+    fn frontier_size(&self) -> usize {
+        self.level.len() + self.next_level.len().min(self.width)
+    }
+
+    fn evaluate(&self, node: &SearchNode) -> i64 {
+        node.score
+    }
+
+    fn should_prune(&mut self, _node: &SearchNode, fewest: Option<usize>) -> bool {
+        fewest.is_some()
+    }
+
+    fn finds_shortest(&self) -> bool {
+        false
+    }
+
+    fn budget(&self, settings: &SearchSettings) -> usize {
+        settings.max_depth_first_states
+    }
+}
+
+/// Looks at the states in order of the liquid poured to reach them plus the lower bound on the
+/// moves left, least first, so the first solution found pours the least liquid, for apps that
+/// score by volume rather than by pours. It is not always the shortest. Every move pours at least
+/// one unit, so the lower bound on the moves left is also one on the liquid left to pour.
+#[derive(Default)]
+pub struct LeastVolumeSearch {
+    queue: PriorityQueue,
+    /// The least liquid every state seen has been reached with.
+    least: HashMap<u64, usize>,
+}
+
+impl SolverStrategy for LeastVolumeSearch {
+    fn push(&mut self, node: SearchNode) {
+        self.least.insert(state_hash(&node.state), node.volume);
+        self.queue
+            .push((self.evaluate(&node), node.bound as i64), node);
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        self.queue.pop()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        self.queue.frontier()
+    }
+
+    fn frontier_size(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn evaluate(&self, node: &SearchNode) -> i64 {
+        (node.volume + node.bound) as i64
+    }
+
+    fn should_prune(&mut self, node: &SearchNode, _fewest: Option<usize>) -> bool {
+        // The fewest moves do not matter, only whether the state was reached with less liquid
+        self.least
+            .get(&state_hash(&node.state))
+            .is_some_and(|least| *least <= node.volume)
+    }
+
+    fn finds_shortest(&self) -> bool {
+        false
+    }
+
+    fn budget(&self, settings: &SearchSettings) -> usize {
+        settings.max_a_star_states
+    }
+}
+
+/// Looks first at the state from which the most random games complete the game, as judged by a
+/// `RolloutEvaluator`, breaking ties by the lower bound on the moves left. Random games find the
+/// boards where the colours still fit together, which a lower bound cannot see, but every state
+/// reached is played on from many times, so each one costs far more to look at than in the other
+/// searches. The solution is not always the shortest.
+#[derive(Default)]
+pub struct RolloutSearch {
+    queue: PriorityQueue,
+    evaluator: RolloutEvaluator,
+}
+
+impl RolloutSearch {
+    pub fn new(evaluator: RolloutEvaluator) -> RolloutSearch {
+        RolloutSearch {
+            queue: PriorityQueue::default(),
+            evaluator,
+        }
+    }
+}
+
+impl SolverStrategy for RolloutSearch {
+    fn push(&mut self, node: SearchNode) {
+        self.queue
+            .push((self.evaluate(&node), node.bound as i64), node);
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        self.queue.pop()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        self.queue.frontier()
+    }
+
+    fn frontier_size(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// The share of random games that did not complete the game, in thousandths.
+    fn evaluate(&self, node: &SearchNode) -> i64 {
+        ((1.0 - self.evaluator.completion_rate(&node.state)) * 1000.0).round() as i64
+    }
+
+    fn should_prune(&mut self, _node: &SearchNode, fewest: Option<usize>) -> bool {
+        fewest.is_some()
+    }
+
+    fn finds_shortest(&self) -> bool {
+        false
+    }
+
+    fn budget(&self, settings: &SearchSettings) -> usize {
+        settings.max_a_star_states
+    }
+}
+
+/// How many states a pass of IDA* remembers having seen before forgetting them, so that its memory
+/// stays bounded however many states it looks at.
+pub const MAX_SEEN_STATES: usize = 1 << 20;
+
+/// One pass of an IDA* search: depth first, following only the states whose moves so far plus the
+/// lower bound on the moves left are within `bound`. The smallest total that went over the bound
+/// is kept as the bound of the next pass.
+pub struct BoundedDepthFirstSearch {
+    stack: Vec<SearchNode>,
+    bound: usize,
+    next_bound: Option<usize>,
+    budget: usize,
+    seen_limit: usize,
+}
+
+impl BoundedDepthFirstSearch {
+    pub fn new(bound: usize, budget: usize) -> BoundedDepthFirstSearch {
+        BoundedDepthFirstSearch {
+            stack: Vec::new(),
+            bound,
+            next_bound: None,
+            budget,
+            seen_limit: MAX_SEEN_STATES,
+        }
+    }
+
+    /// Remembers at most `seen_limit` states seen rather than `MAX_SEEN_STATES`.
+    pub fn with_seen_limit(mut self, seen_limit: usize) -> BoundedDepthFirstSearch {
+        self.seen_limit = seen_limit;
+        self
+    }
+
+    /// The bound for the next pass, or None if nothing went over this one, so that every line
+    /// has been followed to its end.
+    pub fn next_bound(&self) -> Option<usize> {
+        self.next_bound
+    }
+}
+
+impl SolverStrategy for BoundedDepthFirstSearch {
+    fn push(&mut self, node: SearchNode) {
+        self.stack.push(node);
+    }
+
+    fn pop(&mut self) -> Option<SearchNode> {
+        self.stack.pop()
+    }
+
+    fn frontier(&self) -> Vec<&SearchNode> {
+        self.stack.iter().rev().collect()
+    }
+
+    fn frontier_size(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn next_states(&self, node: &SearchNode) -> Vec<SearchNode> {
+        node.children_of(ordered_moves(&node.state))
+    }
+
+    fn evaluate(&self, node: &SearchNode) -> i64 {
+        (node.depth + node.bound) as i64
+    }
+
+    fn should_prune(&mut self, node: &SearchNode, fewest: Option<usize>) -> bool {
+        let total = node.depth + node.bound;
+        if total > self.bound {
+            self.next_bound = Some(self.next_bound.map_or(total, |next| next.min(total)));
+            return true;
+        }
+        // A state reached again in as many moves or more has nothing left to offer
+        fewest.is_some_and(|fewest| fewest <= node.depth)
+    }
+
+    fn finds_shortest(&self) -> bool {
+        true
+    }
+
+    fn budget(&self, _settings: &SearchSettings) -> usize {
+        self.budget
+    }
+
+    fn seen_limit(&self) -> Option<usize> {
+        Some(self.seen_limit)
+    }
+}
+
+/// The possible moves from a state in the order of `Solver::get_ordered_moves`, reversed so that
+/// the most promising move is last, to be pushed onto a stack last and looked at first.
+pub(crate) fn ordered_moves(state: &Game) -> Vec<Move> {
+    let mut moves = Solver::new(state).get_ordered_moves();
+    moves.reverse();
+    moves
+}
+
+/// The states one move before a state: every state with a move that the solver would make to
+/// reach it. `SearchNode::children` the other way round, for searching back from the completed
+/// board.
+pub(crate) fn predecessors(state: &Game) -> Vec<Game> {
+    let mut predecessors = Vec::new();
+    for (to_idx, to_tube) in state.tubes.iter().enumerate() {
+        let top = match to_tube.get_top_colour() {
+            Some(top) => top,
+            None => continue,
+        };
+        for (from_idx, from_tube) in state.tubes.iter().enumerate() {
+            if from_idx == to_idx {
+                continue;
+            }
+            let space = from_tube
+                .get_top_colour()
+                .map_or(TUBE_SIZE, |from_top| from_top.pos);
+            for quantity in 1..=top.block_size.min(space) {
+                let a_move = Move {
+                    tube_from: from_idx,
+                    tube_to: to_idx,
+                    colour: top.colour.clone(),
+                    quantity,
+                    played_at: None,
+                };
+                // Pour the liquid back, then check the pour is one the solver would make
+                let back = Move {
+                    tube_from: to_idx,
+                    tube_to: from_idx,
+                    ..a_move.clone()
+                };
+                let mut before = state.clone();
+                before.tubes[to_idx].pour_from(&back);
+                before.tubes[from_idx].pour_to(&back);
+                // The moves that reached the state did not reach the one before it, and the last
+                // of them would stop the solver from making moves that take it back
+                before.moves.clear();
+                let block_size = before.tubes[from_idx]
+                    .get_top_colour()
+                    .map_or(0, |from_top| from_top.block_size);
+                let space = before.tubes[to_idx]
+                    .get_top_colour()
+                    .map_or(TUBE_SIZE, |to_top| to_top.pos);
+                if before.validate_move(&a_move)
+                    && !before.is_null_progress(from_idx, to_idx)
+                    && before.ruleset.pour_quantity(block_size, space) == Some(quantity)
+                {
+                    predecessors.push(before);
+                }
+            }
+        }
+    }
+    predecessors
+}
+
+/// A lower bound on the number of moves left to complete the game, which never overestimates, so
+/// a search guided by it still finds the shortest solution. A block lying on another colour has
+/// to be poured off at least once, as its tube can only end up holding one colour. Of the blocks
+/// of a colour at the bottom of their tubes, at most one can stay where it is, in the tube that
+/// colour ends up in, so every other block has to move too. A pour moves at most one block that
+/// has not moved before, since two blocks of a colour only touch once one of them has been poured,
+/// so the game needs at least a move for every block that has to move, or for every segment of
+/// them under the one-unit rules. This is never less than the number of blocks over the number
+/// of colours, which counts only the pours that merge two blocks.
+pub fn minimum_moves_lower_bound(game: &Game) -> usize {
+    let cost = |size: usize| match game.ruleset {
+        Ruleset::OneUnit => size,
+        Ruleset::Standard | Ruleset::NoSplit => 1,
+    };
+    let mut moves = 0;
+    // The costliest block of each colour at the bottom of a tube, which can be the one kept
+    let mut kept: HashMap<&str, usize> = HashMap::new();
+    for tube in game.tubes.iter() {
+        let mut blocks: Vec<(&str, usize)> = Vec::new();
+        for colour in tube.contents.iter().rev().flatten() {
+            match blocks.last_mut() {
+                Some((last, size)) if *last == colour.as_str() => *size += 1,
+                _ => blocks.push((colour, 1)),
+            }
+        }
+        moves += blocks.iter().map(|(_, size)| cost(*size)).sum::<usize>();
+        if let Some((colour, size)) = blocks.first() {
+            let best = kept.entry(colour).or_default();
+            *best = (*best).max(cost(*size));
+        }
+    }
+    moves - kept.values().sum::<usize>()
+}
+
+/// How much `evaluate` counts every move of the lower bound, every tube holding more than one
+/// colour and every tube already complete.
+const BOUND_WEIGHT: i64 = 4;
+const MIXED_TUBE_WEIGHT: i64 = 2;
+const COMPLETE_TUBE_WEIGHT: i64 = 2;
+
+/// Scores how far a state looks from complete, the most promising lowest, for the informed
+/// searches to order states by. The number of blocks alone cannot tell a colour lying on top from
+/// one buried at the bottom of a tube, so to the lower bound on the moves left this adds, for
+/// every block under another colour, the segments above it that have to be poured off first, and
+/// the tubes holding more than one colour, whose bottom colour has to be dug out or covered, and
+/// takes off the tubes already complete. Unlike the lower bound this can overestimate, so A* only
+/// breaks ties with it and still finds the shortest solution.
+pub fn evaluate(game: &Game) -> i64 {
+    let mut score = BOUND_WEIGHT * minimum_moves_lower_bound(game) as i64;
+    for tube in game.tubes.iter() {
+        let segments: Vec<&String> = tube.contents.iter().flatten().collect();
+        if segments.len() == TUBE_SIZE && segments.iter().all(|colour| *colour == segments[0]) {
+            score -= COMPLETE_TUBE_WEIGHT;
+            continue;
+        }
+        let mut mixed = false;
+        for (depth, pair) in segments.windows(2).enumerate() {
+            if pair[0] != pair[1] {
+                // The block starting below this segment is buried under depth + 1 segments
+                score += depth as i64 + 1;
+                mixed = true;
+            }
+        }
+        if mixed {
+            score += MIXED_TUBE_WEIGHT;
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::Preset;
+
+    use super::*;
+
+    /// A node with no board, scored by its bound so that the order is easy to follow.
+    fn node(depth: usize, bound: usize) -> SearchNode {
+        SearchNode {
+            state: Game::default(),
+            depth,
+            bound,
+            volume: 0,
+            score: bound as i64,
+        }
+    }
+
+    /// The (depth, bound) of nodes, in the order a strategy gives them back.
+    type Order = Vec<(usize, usize)>;
+
+    fn pop_order(strategy: &mut dyn SolverStrategy, nodes: &[(usize, usize)]) -> Order {
+        for (depth, bound) in nodes {
+            strategy.push(node(*depth, *bound));
+        }
+        let waiting: Vec<(usize, usize)> = strategy
+            .frontier()
+            .iter()
+            .map(|node| (node.depth, node.bound))
+            .collect();
+        let mut order = Vec::new();
+        while let Some(node) = strategy.pop() {
+            order.push((node.depth, node.bound));
+        }
+        assert_eq!(
+            waiting, order,
+            "the frontier should list the nodes in order"
+        );
+        order
+    }
+
+    #[test]
+    fn test_pop_order() {
+        let nodes = [(1, 4), (2, 1), (1, 2), (3, 0)];
+        let tests: Vec<(Box<dyn SolverStrategy>, Order)> = vec![
+            (
+                Box::<BreadthFirstSearch>::default(),
+                vec![(1, 4), (2, 1), (1, 2), (3, 0)],
+            ),
+            (
+                Box::<DepthFirstSearch>::default(),
+                vec![(3, 0), (1, 2), (2, 1), (1, 4)],
+            ),
+            (
+                Box::<AStarSearch>::default(),
+                vec![(3, 0), (2, 1), (1, 2), (1, 4)],
+            ),
+            (
+                Box::<GreedySearch>::default(),
+                vec![(3, 0), (2, 1), (1, 2), (1, 4)],
+            ),
+            (
+                Box::new(BoundedDepthFirstSearch::new(5, 10)),
+                vec![(3, 0), (1, 2), (2, 1), (1, 4)],
+            ),
+        ];
+        for (mut strategy, expected) in tests {
+            assert_eq!(
+                pop_order(strategy.as_mut(), &nodes),
+                expected,
+                "wrong order"
+            );
+        }
+        // Ties are broken by the score in A*, and by the moves made in a greedy search
+        assert_eq!(
+            pop_order(&mut AStarSearch::default(), &[(2, 2), (3, 1)]),
+            vec![(3, 1), (2, 2)],
+            "wrong A* order"
+        );
+        assert_eq!(
+            pop_order(&mut GreedySearch::default(), &[(5, 1), (2, 1)]),
+            vec![(2, 1), (5, 1)],
+            "wrong greedy order"
+        );
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let tests: Vec<(Box<dyn SolverStrategy>, i64)> = vec![
+            (Box::<BreadthFirstSearch>::default(), 3),
+            (Box::<DepthFirstSearch>::default(), 2),
+            (Box::<AStarSearch>::default(), 5),
+            (Box::<GreedySearch>::default(), 2),
+            (Box::new(BoundedDepthFirstSearch::new(5, 10)), 5),
+        ];
+        for (strategy, expected) in tests {
+            assert_eq!(strategy.evaluate(&node(3, 2)), expected, "wrong score");
+        }
+    }
+
+    #[test]
+    fn test_evaluate_state() {
+        // (tubes, score): four per move of the lower bound, the segments above every buried
+        // block, two per mixed tube, less two per complete tube
+        let tests = [
+            (
+                vec!["red, red, red, red", "blue, blue, blue, blue", "", ""],
+                -4,
+            ),
+            (
+                vec!["blue, red, red, red", "red, blue, blue, blue", "", ""],
+                4 * 2 + 1 + 1 + 2 * 2,
+            ),
+            (
+                vec!["red, red, red, blue", "red, blue, blue, blue", "", ""],
+                4 * 3 + 3 + 1 + 2 * 2,
+            ),
+            (
+                vec!["red, red, blue", "red, blue, blue, blue", "red", ""],
+                4 * 3 + 2 + 1 + 2 * 2,
+            ),
+        ];
+        for test in tests {
+            let mut game = Game::default();
+            game.init_tubes(test.0.len());
+            for (idx, contents) in test.0.iter().enumerate() {
+                game.init_tube_contents(idx, contents.to_string());
+            }
+            assert_eq!(evaluate(&game), test.1, "wrong score for {:?}", test.0);
+        }
+    }
+
+    #[test]
+    fn test_should_prune() {
+        // (depth of the node, the fewest moves it was reached in before, whether each of
+        // breadth first, depth first, A* and greedy prunes it)
+        let tests = [
+            (3, None, [false, false, false, false]),
+            (3, Some(3), [true, true, true, true]),
+            (3, Some(4), [true, true, false, true]),
+            (3, Some(2), [true, true, true, true]),
+        ];
+        for (depth, fewest, expected) in tests {
+            let mut strategies: [Box<dyn SolverStrategy>; 4] = [
+                Box::<BreadthFirstSearch>::default(),
+                Box::<DepthFirstSearch>::default(),
+                Box::<AStarSearch>::default(),
+                Box::<GreedySearch>::default(),
+            ];
+            let pruned: Vec<bool> = strategies
+                .iter_mut()
+                .map(|strategy| strategy.should_prune(&node(depth, 1), fewest))
+                .collect();
+            assert_eq!(
+                pruned, expected,
+                "wrong pruning at depth {} reached before in {:?}",
+                depth, fewest
+            );
+        }
+    }
+
+    #[test]
+    fn test_bounded_depth_first() {
+        let mut pass = BoundedDepthFirstSearch::new(4, 10);
+        assert!(!pass.should_prune(&node(2, 2), None), "within the bound");
+        assert!(
+            pass.should_prune(&node(2, 2), Some(2)),
+            "seen in as few moves"
+        );
+        assert!(pass.should_prune(&node(3, 4), None), "over the bound");
+        assert!(pass.should_prune(&node(1, 5), None), "over the bound");
+        assert_eq!(pass.next_bound(), Some(6), "wrong next bound");
+        assert_eq!(
+            pass.budget(&Preset::Quick.settings()),
+            10,
+            "a pass has its own budget"
+        );
+        assert_eq!(
+            BoundedDepthFirstSearch::new(4, 10).next_bound(),
+            None,
+            "nothing has gone over the bound"
+        );
+    }
+
+    #[test]
+    fn test_next_states() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+        let start = SearchNode::new(game, 0);
+        assert_eq!(start.bound, 3, "wrong lower bound");
+        let children = BreadthFirstSearch::default().next_states(&start);
+        assert!(
+            children.iter().all(|child| child.depth == 1),
+            "every next state is one move on"
+        );
+        let ordered = DepthFirstSearch::default().next_states(&start);
+        assert_eq!(
+            children.len(),
+            ordered.len(),
+            "the same states in any order"
+        );
+        assert!(
+            ordered
+                .windows(2)
+                .all(|pair| pair[0].state.get_number_of_blocks()
+                    >= pair[1].state.get_number_of_blocks()),
+            "the most promising state should be pushed last"
+        );
+    }
+
+    #[test]
+    fn test_beam_search() {
+        assert_eq!(
+            pop_order(&mut BeamSearch::new(3), &[(1, 4), (1, 1), (1, 2), (1, 0)]),
+            vec![(1, 0), (1, 1), (1, 2)],
+            "only the most promising states of a depth should be kept"
+        );
+        for puzzle in crate::puzzles::builtin_puzzles().iter() {
+            let game = puzzle.to_game();
+            let solution = Solver::new(&game)
+                .search_using(&mut BeamSearch::new(100))
+                .unwrap_or_else(|| panic!("puzzle {} should be solved", puzzle.number));
+            assert!(
+                !solution.optimal,
+                "a beam search might not find the shortest solution"
+            );
+            let mut replay = game.clone();
+            for a_move in solution.moves.iter() {
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "solution does not complete the game"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rollout_search() {
+        for number in [1, 2, 3] {
+            let game = crate::puzzles::get_builtin_puzzle(number)
+                .unwrap_or_else(|| panic!("puzzle {} should exist", number))
+                .to_game();
+            let solution = Solver::new(&game)
+                .search_using(&mut RolloutSearch::new(RolloutEvaluator::new(10)))
+                .unwrap_or_else(|| panic!("puzzle {} should be solved", number));
+            assert!(
+                !solution.optimal,
+                "a rollout search might not find the shortest solution"
+            );
+            let mut replay = game.clone();
+            for a_move in solution.moves.iter() {
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "solution does not complete the game"
+            );
+        }
+    }
+
+    #[test]
+    fn test_predecessors() {
+        let game = crate::puzzles::get_builtin_puzzle(4)
+            .expect("puzzle 4 should exist")
+            .to_game();
+        let mut state = game.clone();
+        for a_move in Solver::new(&game)
+            .solve()
+            .expect("puzzle 4 should be solved")
+        {
+            for child in SearchNode::new(state.clone(), 0).children() {
+                assert!(
+                    predecessors(&child.state)
+                        .iter()
+                        .any(|before| state_hash(before) == state_hash(&state)),
+                    "a state should be one move before the states one move on from it"
+                );
+            }
+            state.make_move(&a_move);
+            for before in predecessors(&state) {
+                assert!(
+                    SearchNode::new(before, 0)
+                        .children()
+                        .iter()
+                        .any(|child| state_hash(&child.state) == state_hash(&state)),
+                    "a state one move before should have a move to the state"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_least_volume_search() {
+        let mut less = 0;
+        for puzzle in crate::puzzles::builtin_puzzles().iter() {
+            let game = puzzle.to_game();
+            let solver = Solver::new(&game);
+            let least = solver
+                .search_using(&mut LeastVolumeSearch::default())
+                .unwrap_or_else(|| panic!("puzzle {} should be solved", puzzle.number));
+            let shortest = solver
+                .search_using(&mut AStarSearch::default())
+                .unwrap_or_else(|| panic!("puzzle {} should be solved", puzzle.number));
+            assert!(
+                least.volume() <= shortest.volume(),
+                "puzzle {} was solved pouring {} units, more than the {} of the shortest solution",
+                puzzle.number,
+                least.volume(),
+                shortest.volume()
+            );
+            if least.volume() < shortest.volume() {
+                less += 1;
+            }
+            let mut replay = game.clone();
+            for a_move in least.moves.iter() {
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "solution does not complete the game"
+            );
+        }
+        assert!(
+            less > 0,
+            "some puzzle should have a solution pouring less than its shortest one"
+        );
+    }
+}
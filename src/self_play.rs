@@ -0,0 +1,243 @@
+use crate::{
+    analyze::format_table,
+    colour::LEVEL_COLOURS,
+    game::Game,
+    rng::{RngSource, SeededRng},
+    solver::{Preset, Solver, Strategy},
+    TUBE_SIZE,
+};
+
+/// The most colours a random board can have. Random boards are made of the colours of a real
+/// game, so that they read like one.
+pub const MAX_COLOURS: usize = LEVEL_COLOURS.len();
+
+/// Makes `count` boards of `colours` colours each, with every segment shuffled among full tubes
+/// and two empty tubes, as in a new level. Not every random board can be solved. The same seed
+/// always gives the same boards.
+pub fn random_boards(count: usize, colours: usize, seed: u64) -> Vec<Game> {
+    random_boards_from(count, colours, &mut SeededRng::new(seed))
+}
+
+/// Makes random boards as `random_boards` does, with the numbers from any source.
+pub fn random_boards_from(count: usize, colours: usize, random: &mut dyn RngSource) -> Vec<Game> {
+    let colours = &LEVEL_COLOURS[..colours.min(MAX_COLOURS)];
+    (0..count)
+        .map(|_| {
+            let mut segments: Vec<&str> = colours
+                .iter()
+                .flat_map(|colour| [*colour; TUBE_SIZE])
+                .collect();
+            // Fisher-Yates shuffle
+            for idx in (1..segments.len()).rev() {
+                let other = random.below(idx + 1);
+                segments.swap(idx, other);
+            }
+            let mut game = Game::default();
+            game.init_tubes(colours.len() + 2);
+            for (idx, tube) in segments.chunks(TUBE_SIZE).enumerate() {
+                game.init_tube_contents(idx, tube.join(", "));
+            }
+            game
+        })
+        .collect()
+}
+
+/// How one combination of strategy and preset did over a set of boards.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SelfPlayStats {
+    pub strategy: Strategy,
+    pub preset: Preset,
+    pub boards: usize,
+    /// How many boards were solved within the budgets of the preset.
+    pub solved: usize,
+    /// The states searched and the moves of the solutions, over the boards that were solved.
+    pub states_searched: usize,
+    pub moves: usize,
+}
+
+impl SelfPlayStats {
+    /// The share of the boards that were solved, from 0 to 1.
+    pub fn success_rate(&self) -> f64 {
+        match self.boards {
+            0 => 0.0,
+            boards => self.solved as f64 / boards as f64,
+        }
+    }
+
+    /// The average number of states searched for a solved board.
+    pub fn average_states(&self) -> Option<f64> {
+        match self.solved {
+            0 => None,
+            solved => Some(self.states_searched as f64 / solved as f64),
+        }
+    }
+
+    pub fn average_moves(&self) -> Option<f64> {
+        match self.solved {
+            0 => None,
+            solved => Some(self.moves as f64 / solved as f64),
+        }
+    }
+}
+
+/// Solves every board with every combination of strategy and preset, giving the statistics of
+/// each combination in the order of the presets and then of the strategies. This is the data
+/// for choosing the budgets of the presets: how often each one finds a solution and how much it
+/// searches to do so.
+pub fn self_play(
+    boards: &[Game],
+    strategies: &[Strategy],
+    presets: &[Preset],
+) -> Vec<SelfPlayStats> {
+    let mut all_stats = Vec::with_capacity(strategies.len() * presets.len());
+    for preset in presets {
+        for strategy in strategies {
+            let mut stats = SelfPlayStats {
+                strategy: *strategy,
+                preset: *preset,
+                boards: boards.len(),
+                solved: 0,
+                states_searched: 0,
+                moves: 0,
+            };
+            for board in boards {
+                let game = Game {
+                    preset: *preset,
+                    ..board.clone()
+                };
+                if let Some(solution) = Solver::new(&game).search_with(*strategy) {
+                    stats.solved += 1;
+                    stats.states_searched += solution.states_searched;
+                    stats.moves += solution.moves.len();
+                }
+            }
+            all_stats.push(stats);
+        }
+    }
+    all_stats
+}
+
+/// One row for every combination, with averages that could not be found, because no board was
+/// solved, shown as `-`.
+pub fn self_play_table(all_stats: &[SelfPlayStats]) -> String {
+    let average = |value: Option<f64>| match value {
+        Some(value) => format!("{:.1}", value),
+        None => String::from("-"),
+    };
+    let mut rows = vec![[
+        "Preset",
+        "Strategy",
+        "Solved",
+        "Success (%)",
+        "Avg states",
+        "Avg moves",
+    ]
+    .map(String::from)
+    .to_vec()];
+    for stats in all_stats {
+        rows.push(vec![
+            stats.preset.to_string(),
+            stats.strategy.to_string(),
+            format!("{}/{}", stats.solved, stats.boards),
+            format!("{:.1}", stats.success_rate() * 100.0),
+            average(stats.average_states()),
+            average(stats.average_moves()),
+        ]);
+    }
+    format_table(&rows, 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_boards() {
+        let boards = random_boards(5, 4, 7);
+        assert_eq!(boards.len(), 5, "wrong number of boards");
+        for board in &boards {
+            assert_eq!(board.tubes.len(), 6, "wrong number of tubes");
+            assert!(board.validate_setup(), "invalid board:\n{}", board);
+        }
+        assert_eq!(
+            random_boards(5, 4, 7)
+                .iter()
+                .map(|board| board.to_string())
+                .collect::<Vec<_>>(),
+            boards
+                .iter()
+                .map(|board| board.to_string())
+                .collect::<Vec<_>>(),
+            "the same seed should give the same boards"
+        );
+        assert_ne!(
+            random_boards(1, 4, 8)[0].to_string(),
+            boards[0].to_string(),
+            "a different seed should give a different board"
+        );
+    }
+
+    #[test]
+    fn test_self_play() {
+        let boards = random_boards(3, 3, 1);
+        let all_stats = self_play(
+            &boards,
+            &[Strategy::BreadthFirst, Strategy::DepthFirst],
+            &[Preset::Balanced, Preset::Quick],
+        );
+        let combinations: Vec<(Preset, Strategy)> = all_stats
+            .iter()
+            .map(|stats| (stats.preset, stats.strategy))
+            .collect();
+        assert_eq!(
+            combinations,
+            vec![
+                (Preset::Balanced, Strategy::BreadthFirst),
+                (Preset::Balanced, Strategy::DepthFirst),
+                (Preset::Quick, Strategy::BreadthFirst),
+                (Preset::Quick, Strategy::DepthFirst),
+            ],
+            "wrong combinations"
+        );
+        let breadth_first = &all_stats[0];
+        assert_eq!(breadth_first.boards, 3, "wrong number of boards");
+        assert!(
+            breadth_first.solved <= 3
+                && breadth_first.average_states().is_some() == (breadth_first.solved > 0),
+            "wrong statistics: {:?}",
+            breadth_first
+        );
+        assert!(
+            all_stats[1].moves >= all_stats[0].moves || all_stats[1].solved < all_stats[0].solved,
+            "breadth first should find the shortest solutions"
+        );
+    }
+
+    #[test]
+    fn test_self_play_table() {
+        let all_stats = [
+            SelfPlayStats {
+                strategy: Strategy::BreadthFirst,
+                preset: Preset::Balanced,
+                boards: 4,
+                solved: 3,
+                states_searched: 100,
+                moves: 30,
+            },
+            SelfPlayStats {
+                strategy: Strategy::DepthFirst,
+                preset: Preset::Quick,
+                boards: 4,
+                solved: 0,
+                states_searched: 0,
+                moves: 0,
+            },
+        ];
+        assert_eq!(
+            self_play_table(&all_stats),
+            "Preset    Strategy       Solved  Success (%)  Avg states  Avg moves\n\
+             balanced  breadth-first     3/4         75.0        33.3       10.0\n\
+             quick     depth-first       0/4          0.0           -          -\n"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::game::{Game, Move};
+
+/// Why a simulated game stopped.
+#[derive(Clone)]
+pub enum Outcome {
+    Completed,
+    /// No move can be made, so the game can never be completed from here.
+    Stuck,
+    /// The policy chose a move that cannot be made on the board it was given. The move is not
+    /// played.
+    InvalidMove(Move),
+    /// The policy used every move it was allowed without completing the game.
+    MoveLimit,
+}
+
+/// The moves a policy made in a simulated game, in order, and where they led.
+#[derive(Clone)]
+pub struct Trajectory {
+    pub moves: Vec<Move>,
+    /// The board after the last move. Every board on the way can be found by replaying the moves
+    /// from the start.
+    pub final_state: Game,
+    pub outcome: Outcome,
+}
+
+/// Plays a game without any input or output, asking `policy` for every move, until the game is
+/// completed or stuck, the policy chooses a move that cannot be made, or `max_moves` have been
+/// made. This lets any way of choosing moves, such as a heuristic or a learning agent, be played
+/// against the same rules as a person.
+pub fn simulate(
+    game: &Game,
+    mut policy: impl FnMut(&Game) -> Move,
+    max_moves: usize,
+) -> Trajectory {
+    let mut state = game.clone();
+    let mut moves = Vec::new();
+    let outcome = loop {
+        if state.is_game_complete() {
+            break Outcome::Completed;
+        }
+        if state.is_stuck() {
+            break Outcome::Stuck;
+        }
+        if moves.len() == max_moves {
+            break Outcome::MoveLimit;
+        }
+        let a_move = policy(&state);
+        if !state.validate_move(&a_move) {
+            break Outcome::InvalidMove(a_move);
+        }
+        state.make_move(&a_move);
+        moves.push(a_move);
+    };
+    Trajectory {
+        moves,
+        final_state: state,
+        outcome,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{solver::Solver, testing::initialise_game};
+
+    /// Plays the first move of a shortest solution from every board.
+    fn solver_policy(game: &Game) -> Move {
+        Solver::new(game).solve().expect("board should be solvable")[0].clone()
+    }
+
+    #[test]
+    fn test_simulate() {
+        let game = initialise_game(&["red, red, red, blue", "red, blue, blue, blue"], 4);
+        let trajectory = simulate(&game, solver_policy, 10);
+        assert!(
+            matches!(trajectory.outcome, Outcome::Completed),
+            "the solver policy should complete the game"
+        );
+        assert_eq!(trajectory.moves.len(), 3, "wrong number of moves");
+        assert!(
+            trajectory.final_state.is_game_complete(),
+            "the final state should be complete"
+        );
+
+        let limited = simulate(&game, solver_policy, 2);
+        assert!(
+            matches!(limited.outcome, Outcome::MoveLimit),
+            "expected the move limit to be reached"
+        );
+        assert_eq!(limited.moves.len(), 2, "wrong number of moves");
+
+        let invalid = simulate(
+            &game,
+            |_| Move {
+                tube_from: 2,
+                tube_to: 3,
+                colour: String::from("red"),
+                quantity: 1,
+                played_at: None,
+            },
+            10,
+        );
+        assert!(
+            matches!(invalid.outcome, Outcome::InvalidMove(ref a_move) if a_move.tube_from == 2),
+            "expected the invalid move to be reported"
+        );
+        assert!(
+            invalid.moves.is_empty(),
+            "the invalid move should not be played"
+        );
+
+        let stuck = initialise_game(
+            &[
+                "red, blue, red, blue",
+                "blue, red, blue, red",
+                "green, pink, green, pink",
+                "pink, green, pink, green",
+            ],
+            4,
+        );
+        let trajectory = simulate(&stuck, solver_policy, 10);
+        assert!(
+            matches!(trajectory.outcome, Outcome::Stuck),
+            "expected the game to be stuck"
+        );
+
+        // The only way on is a pour of one colour into an empty tube, which is not stuck
+        let mut forbidden =
+            initialise_game(&["green, green", "red, red, green, green", "red, red"], 5);
+        forbidden.forbidden_pours = vec![(1, 2), (1, 3), (1, 4)];
+        let trajectory = simulate(&forbidden, solver_policy, 10);
+        assert!(
+            matches!(trajectory.outcome, Outcome::Completed),
+            "the board with forbidden pours should be completed"
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{
+    game::{Game, Move},
+    solver::{Solver, Strategy},
+};
+
+/// A move as it is compared between solutions: the tube poured from, the tube poured into and the
+/// quantity.
+type Pour = (usize, usize, usize);
+
+/// The shortest solutions of a board, with those that are the same as one already found left out.
+#[derive(Clone)]
+pub struct SolutionSet {
+    /// The genuinely different solutions, in the order they were found.
+    pub solutions: Vec<Vec<Move>>,
+    /// How many of the solutions found were the same as one of `solutions`.
+    pub duplicates: usize,
+    /// Whether every shortest solution was looked at, rather than the search stopping at the
+    /// limit or at the budget of the game's preset.
+    pub complete: bool,
+}
+
+/// Finds up to `limit` genuinely different shortest solutions to the game, or None if no solution
+/// was found within the budget of the game's preset. Two solutions are the same if one is the
+/// other with tubes the board started with the same contents in, such as two empty tubes,
+/// interchanged, or with moves between different tubes made in another order.
+pub fn distinct_solutions(game: &Game, limit: usize) -> Option<SolutionSet> {
+    let relabellings = relabellings(game);
+    shortest_solutions(game, limit, &|moves| canonical_key(moves, &relabellings))
+}
+
+/// Finds up to `limit` shortest solutions to the game that are different sequences of moves, or
+/// None if no solution was found within the budget of the game's preset. Unlike
+/// `distinct_solutions`, solutions that only interchange tubes with the same contents or make
+/// moves in another order are all kept, so a level with exactly one solution here can only be
+/// solved in the fewest moves one way.
+pub fn all_solutions(game: &Game, limit: usize) -> Option<SolutionSet> {
+    shortest_solutions(game, limit, &|moves| {
+        vec![moves
+            .iter()
+            .map(|a_move| (a_move.tube_from, a_move.tube_to, a_move.quantity))
+            .collect()]
+    })
+}
+
+/// Finds up to `limit` shortest solutions, keeping only the first found of those with the same
+/// `solution_key`.
+fn shortest_solutions(
+    game: &Game,
+    limit: usize,
+    solution_key: &dyn Fn(&[Move]) -> Vec<Vec<Pour>>,
+) -> Option<SolutionSet> {
+    let length = Solver::new(game)
+        .search_with(Strategy::IdaStar)?
+        .moves
+        .len();
+    let max_states = game.preset.settings().max_depth_first_states;
+    let mut set = SolutionSet {
+        solutions: Vec::new(),
+        duplicates: 0,
+        complete: true,
+    };
+    let mut keys: HashSet<Vec<Vec<Pour>>> = HashSet::new();
+    if length == 0 {
+        set.solutions.push(Vec::new());
+        return Some(set);
+    }
+    // The fewest moves each state has been reached in. A state reached in more moves than that
+    // cannot be on a shortest solution, but one reached in as many can be on another
+    let mut fewest: HashMap<Vec<Vec<Option<String>>>, usize> =
+        HashMap::from([(game.state_key(), 0)]);
+    let mut states_searched = 0;
+    let mut line: Vec<Move> = Vec::new();
+    let mut frames = vec![(game.clone(), Solver::new(game).get_legal_moves())];
+    while let Some((state, moves)) = frames.last_mut() {
+        let a_move = match moves.pop() {
+            Some(a_move) => a_move,
+            None => {
+                frames.pop();
+                line.pop();
+                continue;
+            }
+        };
+        let mut next = state.clone();
+        next.make_move(&a_move);
+        let depth = line.len() + 1;
+        if depth + Solver::new(&next).lower_bound() > length {
+            continue;
+        }
+        let key = next.state_key();
+        if fewest.get(&key).is_some_and(|fewest| *fewest < depth) {
+            continue;
+        }
+        fewest.insert(key, depth);
+        states_searched += 1;
+        if next.is_game_complete() {
+            let mut solution = line.clone();
+            solution.push(a_move);
+            if keys.insert(solution_key(&solution)) {
+                set.solutions.push(solution);
+                if set.solutions.len() == limit {
+                    set.complete = false;
+                    break;
+                }
+            } else {
+                set.duplicates += 1;
+            }
+            continue;
+        }
+        if states_searched >= max_states {
+            set.complete = false;
+            break;
+        }
+        line.push(a_move);
+        let next_moves = Solver::new(&next).get_legal_moves();
+        frames.push((next, next_moves));
+    }
+    Some(set)
+}
+
+/// Every way of renumbering the tubes that leaves the starting board as it was: tubes with the
+/// same contents, such as the empty tubes, can be interchanged. A tube in a forbidden pour is
+/// never interchanged, as the pour sets it apart.
+fn relabellings(game: &Game) -> Vec<Vec<usize>> {
+    let forbidden = |idx: usize| {
+        game.forbidden_pours
+            .iter()
+            .any(|(from, to)| *from == idx || *to == idx)
+    };
+    let mut classes: Vec<Vec<usize>> = Vec::new();
+    for (idx, tube) in game.tubes.iter().enumerate() {
+        match classes.iter_mut().find(|class| {
+            !forbidden(idx)
+                && !forbidden(class[0])
+                && game.tubes[class[0]].contents == tube.contents
+        }) {
+            Some(class) => class.push(idx),
+            None => classes.push(vec![idx]),
+        }
+    }
+    classes
+        .iter()
+        .filter(|class| class.len() > 1)
+        .map(|class| class.iter().copied().permutations(class.len()))
+        .multi_cartesian_product()
+        .map(|orders| {
+            let mut relabelling: Vec<usize> = (0..game.tubes.len()).collect();
+            for order in orders {
+                let mut class = order.clone();
+                class.sort();
+                for (tube, new_tube) in class.into_iter().zip(order) {
+                    relabelling[tube] = new_tube;
+                }
+            }
+            relabelling
+        })
+        .collect()
+}
+
+/// The same key for every solution that is the same as this one. The moves are put in steps, each
+/// move in the step after the last move on either of its tubes, with the moves of a step in order
+/// of their tubes, so that moves between different tubes are in the same order whatever order they
+/// were made in. The smallest such form over every renumbering of interchangeable tubes is the key.
+fn canonical_key(moves: &[Move], relabellings: &[Vec<usize>]) -> Vec<Vec<Pour>> {
+    let normal_form = |relabelling: &[usize]| {
+        let mut steps: Vec<Vec<Pour>> = Vec::new();
+        let mut last_step: HashMap<usize, usize> = HashMap::new();
+        for a_move in moves {
+            let (from, to) = (relabelling[a_move.tube_from], relabelling[a_move.tube_to]);
+            let step = [from, to]
+                .iter()
+                .filter_map(|tube| last_step.get(tube).map(|step| step + 1))
+                .max()
+                .unwrap_or(0);
+            if step == steps.len() {
+                steps.push(Vec::new());
+            }
+            steps[step].push((from, to, a_move.quantity));
+            last_step.insert(from, step);
+            last_step.insert(to, step);
+        }
+        for step in steps.iter_mut() {
+            step.sort();
+        }
+        steps
+    };
+    let identity: Vec<usize> = (0..moves
+        .iter()
+        .map(|a_move| a_move.tube_from.max(a_move.tube_to) + 1)
+        .max()
+        .unwrap_or(0))
+        .collect();
+    relabellings
+        .iter()
+        .map(|relabelling| normal_form(relabelling))
+        .min()
+        .unwrap_or_else(|| normal_form(&identity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pours(moves: &[(usize, usize, usize)]) -> Vec<Move> {
+        moves
+            .iter()
+            .map(|(from, to, quantity)| Move {
+                tube_from: from - 1,
+                tube_to: to - 1,
+                colour: String::from("red"),
+                quantity: *quantity,
+                played_at: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let mut game = Game::default();
+        game.init_tubes(6);
+        game.init_tube_contents(0, String::from("red, red, blue, blue"));
+        game.init_tube_contents(1, String::from("blue, blue, red, red"));
+        game.init_tube_contents(2, String::from("green, green, yellow, yellow"));
+        game.init_tube_contents(3, String::from("yellow, yellow, green, green"));
+        let relabellings = relabellings(&game);
+        assert_eq!(
+            relabellings.len(),
+            2,
+            "only the two empty tubes can be interchanged"
+        );
+        // (first moves, second moves, whether they are the same solution)
+        let tests = [
+            (vec![(1, 5, 2), (3, 6, 2)], vec![(3, 6, 2), (1, 5, 2)], true),
+            (vec![(1, 5, 2), (3, 6, 2)], vec![(1, 6, 2), (3, 5, 2)], true),
+            (vec![(1, 5, 2), (2, 5, 2)], vec![(1, 6, 2), (2, 6, 2)], true),
+            (
+                vec![(1, 5, 2), (5, 2, 2)],
+                vec![(5, 2, 2), (1, 5, 2)],
+                false,
+            ),
+            (
+                vec![(1, 5, 2), (2, 5, 2)],
+                vec![(1, 5, 2), (2, 6, 2)],
+                false,
+            ),
+            (vec![(1, 5, 2)], vec![(2, 5, 2)], false),
+        ];
+        for test in tests {
+            assert_eq!(
+                canonical_key(&pours(&test.0), &relabellings)
+                    == canonical_key(&pours(&test.1), &relabellings),
+                test.2,
+                "wrong comparison of {:?} and {:?}",
+                test.0,
+                test.1
+            );
+        }
+    }
+
+    #[test]
+    fn test_distinct_solutions() {
+        let puzzle = crate::puzzles::get_builtin_puzzle(1).expect("puzzle 1 should exist");
+        let game = puzzle.to_game();
+        let set = distinct_solutions(&game, 10).expect("puzzle 1 should be solved");
+        assert!(set.complete, "every solution should have been looked at");
+        assert!(
+            set.duplicates > 0,
+            "the empty tubes should give duplicate solutions"
+        );
+        let relabellings = relabellings(&game);
+        let keys: HashSet<Vec<Vec<Pour>>> = set
+            .solutions
+            .iter()
+            .map(|solution| canonical_key(solution, &relabellings))
+            .collect();
+        assert_eq!(
+            keys.len(),
+            set.solutions.len(),
+            "the solutions should all be different"
+        );
+        for solution in set.solutions.iter() {
+            assert_eq!(
+                Some(solution.len()),
+                puzzle.par.moves,
+                "every solution should be a shortest one"
+            );
+            let mut replay = game.clone();
+            for a_move in solution {
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "solution does not complete the game"
+            );
+        }
+
+        let limited = distinct_solutions(&game, 1).expect("puzzle 1 should be solved");
+        assert_eq!(limited.solutions.len(), 1, "wrong number of solutions");
+        assert!(
+            !limited.complete || set.solutions.len() == 1,
+            "a search stopped at the limit is not complete"
+        );
+    }
+
+    #[test]
+    fn test_all_solutions() {
+        let puzzle = crate::puzzles::get_builtin_puzzle(1).expect("puzzle 1 should exist");
+        let game = puzzle.to_game();
+        let distinct = distinct_solutions(&game, 1_000).expect("puzzle 1 should be solved");
+        let all = all_solutions(&game, 1_000).expect("puzzle 1 should be solved");
+        assert!(all.complete, "every solution should have been looked at");
+        assert_eq!(all.duplicates, 0, "every path is a different sequence");
+        assert_eq!(
+            all.solutions.len(),
+            distinct.solutions.len() + distinct.duplicates,
+            "every solution found should be kept"
+        );
+        let sequences: HashSet<Vec<String>> = all
+            .solutions
+            .iter()
+            .map(|solution| solution.iter().map(Move::to_string).collect())
+            .collect();
+        assert_eq!(
+            sequences.len(),
+            all.solutions.len(),
+            "the solutions should all be different sequences"
+        );
+        assert!(
+            all.solutions
+                .iter()
+                .all(|solution| Some(solution.len()) == puzzle.par.moves),
+            "every solution should be a shortest one"
+        );
+
+        let limited = all_solutions(&game, 2).expect("puzzle 1 should be solved");
+        assert_eq!(limited.solutions.len(), 2, "wrong number of solutions");
+        assert!(!limited.complete, "the search should stop at the limit");
+    }
+}
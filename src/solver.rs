@@ -1,73 +1,519 @@
-use std::cmp::min;
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fmt::Display,
+    hash::{Hash, Hasher},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use itertools::Itertools;
 
 use crate::{
     game::{Game, Move},
+    opening_book::book_move,
+    search::{
+        self, predecessors, AStarSearch, BeamSearch, BoundedDepthFirstSearch, BreadthFirstSearch,
+        DepthFirstSearch, GreedySearch, SearchNode, SolverStrategy,
+    },
+    solutions::{all_solutions, SolutionSet},
+    tube::Tube,
     TUBE_SIZE,
 };
 
+/// A named bundle of search settings, so that the search can be made faster or more thorough
+/// without knowing how it works. The depth first search always tries the most promising moves
+/// first.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Preset {
+    /// Searches every board depth first with small budgets, for an answer straight away. The
+    /// solution is not always the shortest.
+    Quick,
+    #[default]
+    Balanced,
+    /// Searches larger boards breadth first, with budgets large enough for the hardest puzzles,
+    /// at the cost of time and memory.
+    Thorough,
+}
+
+/// How a preset searches.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SearchSettings {
+    /// Boards with more tubes than this are searched depth first. A breadth first search keeps
+    /// every state it has seen, which is more than fits in memory for a large board, whereas a
+    /// depth first search only keeps the current line and a hash of each state seen. The
+    /// solution it finds is not always the shortest.
+    pub breadth_first_tubes: usize,
+    /// How many states the breadth first search looks through before giving up, so that large
+    /// boards do not stall.
+    pub max_breadth_first_states: usize,
+    /// How many states the depth first search looks through before giving up.
+    pub max_depth_first_states: usize,
+    /// How many states the A* search looks through before giving up. Like a breadth first
+    /// search it keeps every state it has seen, but it looks at far fewer of them.
+    pub max_a_star_states: usize,
+}
+
+impl Preset {
+    pub fn settings(&self) -> SearchSettings {
+        match self {
+            Preset::Quick => SearchSettings {
+                breadth_first_tubes: 0,
+                max_breadth_first_states: 0,
+                max_depth_first_states: 100_000,
+                max_a_star_states: 50_000,
+            },
+            Preset::Balanced => SearchSettings {
+                breadth_first_tubes: 16,
+                max_breadth_first_states: 100_000,
+                max_depth_first_states: 1_000_000,
+                max_a_star_states: 500_000,
+            },
+            Preset::Thorough => SearchSettings {
+                breadth_first_tubes: 24,
+                max_breadth_first_states: 1_000_000,
+                max_depth_first_states: 10_000_000,
+                max_a_star_states: 2_000_000,
+            },
+        }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "quick" => Ok(Preset::Quick),
+            "balanced" => Ok(Preset::Balanced),
+            "thorough" => Ok(Preset::Thorough),
+            other => Err(format!(
+                "Unknown preset '{}'. Expected one of: quick, balanced, thorough",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Preset::Quick => "quick",
+            Preset::Balanced => "balanced",
+            Preset::Thorough => "thorough",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How the solver looks through the moves of a board. Which one is used is normally decided by the
+/// preset, from the size of the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Strategy {
+    /// Finds the shortest solution, but keeps every state it has seen.
+    BreadthFirst,
+    /// Tries the moves that leave the fewest blocks first, keeping only the current line.
+    DepthFirst,
+    /// Finds the shortest solution by looking at the states that could lead to the shortest
+    /// solution first, by the moves made so far and the fewest moves that could be left. This
+    /// looks at far fewer states than a breadth first search on a large board.
+    AStar,
+    /// Looks at the states with the fewest moves that could be left first, whatever it took to
+    /// reach them. This heads for a solution quickly, but it is not always the shortest.
+    Greedy,
+    /// Finds the shortest solution like A*, but depth first, with the lower bound on the moves
+    /// left raised a move at a time. Besides the current line, a hash of each state seen is kept
+    /// to skip states reached again, but only up to `search::MAX_SEEN_STATES` of them, after which
+    /// they are forgotten and some states are looked at again. Memory stays bounded however large
+    /// the board is, at the cost of time on boards with more states than that.
+    IdaStar,
+    /// Looks at the boards a depth at a time, keeping only this many of the boards with the fewest
+    /// moves that could be left at each depth. Time and memory stay bounded on boards of 20 tubes
+    /// or more, where no search for the shortest solution can finish, but the solution is not
+    /// always the shortest, and a board that can be solved might not be.
+    Beam(usize),
+    /// Finds the shortest solution by searching breadth first both from the board and back from
+    /// the completed board, until the two searches meet. Each only has to go about half as deep
+    /// as a breadth first search, so it looks at far fewer states on a hard puzzle. A board with
+    /// forbidden pours is searched breadth first from the board alone, as which colour ends up in
+    /// which of its tubes matters.
+    Bidirectional,
+}
+
+/// How many boards of each depth a beam search keeps, unless another width is given.
+pub const DEFAULT_BEAM_WIDTH: usize = 500;
+
+impl Strategy {
+    pub const ALL: [Strategy; 7] = [
+        Strategy::BreadthFirst,
+        Strategy::DepthFirst,
+        Strategy::AStar,
+        Strategy::Greedy,
+        Strategy::IdaStar,
+        Strategy::Beam(DEFAULT_BEAM_WIDTH),
+        Strategy::Bidirectional,
+    ];
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "breadth-first" => Ok(Strategy::BreadthFirst),
+            "depth-first" => Ok(Strategy::DepthFirst),
+            "a-star" | "astar" => Ok(Strategy::AStar),
+            "greedy" => Ok(Strategy::Greedy),
+            "ida-star" | "idastar" => Ok(Strategy::IdaStar),
+            "beam" => Ok(Strategy::Beam(DEFAULT_BEAM_WIDTH)),
+            "bidirectional" => Ok(Strategy::Bidirectional),
+            other => Err(format!(
+                "Unknown strategy '{}'. Expected one of: breadth-first, depth-first, a-star, greedy, ida-star, beam, bidirectional",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Strategy::BreadthFirst => "breadth-first",
+            Strategy::DepthFirst => "depth-first",
+            Strategy::AStar => "a-star",
+            Strategy::Greedy => "greedy",
+            Strategy::IdaStar => "ida-star",
+            Strategy::Beam(_) => "beam",
+            Strategy::Bidirectional => "bidirectional",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How far a search had got when it finished with one depth, for watching it work.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TraceStep {
+    /// How many moves from the start the search has reached.
+    pub depth: usize,
+    /// How many states have been looked at so far.
+    pub states_searched: usize,
+    /// The lower bound on the moves left from the most promising board at this depth. A depth
+    /// first search only gives the bound of the board that first reached the depth.
+    pub best_bound: usize,
+    /// How many moves so far were not followed, because they led to a board already seen or, in an
+    /// IDA* search, went over the bound.
+    pub pruned: usize,
+}
+
+/// How a search is getting on, reported every `PROGRESS_INTERVAL` states while it runs so that a
+/// long search can show it is still working.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Progress {
+    /// How many states have been looked at so far.
+    pub states_searched: usize,
+    /// How many moves from the start the state just looked at is.
+    pub depth: usize,
+    /// The lowest lower bound on the moves left of any state looked at so far.
+    pub best_bound: usize,
+}
+
+/// How many states a search looks at between reports of its `Progress`.
+pub const PROGRESS_INTERVAL: usize = 1_000;
+
+impl Display for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} states searched, at depth {}, best bound {}",
+            self.states_searched, self.depth, self.best_bound
+        )
+    }
+}
+
+impl Display for TraceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Depth {}: {} states searched, best bound {}, {} pruned",
+            self.depth, self.states_searched, self.best_bound, self.pruned
+        )
+    }
+}
+
+/// A solution to a game, the shortest unless `optimal` says otherwise.
+pub struct Solution {
+    pub moves: Vec<Move>,
+    /// How many states were looked at before the solution was found.
+    pub states_searched: usize,
+    /// How many states were not looked at again because the search had already reached them.
+    pub duplicates: usize,
+    /// False if the board was searched depth first, greedily or with a beam, so this might not be
+    /// the shortest solution.
+    pub optimal: bool,
+}
+
+impl Solution {
+    /// Grades how hard the game is to solve from 1 to 5, by how much searching it took.
+    pub fn difficulty(&self) -> usize {
+        match self.states_searched {
+            0..=99 => 1,
+            100..=999 => 2,
+            1_000..=9_999 => 3,
+            10_000..=49_999 => 4,
+            _ => 5,
+        }
+    }
+
+    /// How many units of liquid the moves pour in total.
+    pub fn volume(&self) -> usize {
+        self.moves.iter().map(|a_move| a_move.quantity).sum()
+    }
+
+    /// The difficulty in words, such as "Hard".
+    pub fn difficulty_name(&self) -> &'static str {
+        match self.difficulty() {
+            1 => "Very easy",
+            2 => "Easy",
+            3 => "Medium",
+            4 => "Hard",
+            _ => "Very hard",
+        }
+    }
+}
+
+/// Measures of how a search went, for comparing puzzles and strategies. See
+/// `Solver::search_with_stats`.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SolveStats {
+    /// How many states were looked at.
+    pub states_expanded: usize,
+    /// How many states one move on from those looked at were found, whether or not they were
+    /// pruned.
+    pub states_generated: usize,
+    /// The most states waiting to be looked at at any one time.
+    pub peak_frontier: usize,
+    pub duration: Duration,
+    /// How many moves the solution found takes, if one was found.
+    pub solution_length: Option<usize>,
+}
+
+impl SolveStats {
+    /// The average number of states one move on from each state looked at, or 0 if none was.
+    pub fn branching_factor(&self) -> f64 {
+        match self.states_expanded {
+            0 => 0.0,
+            expanded => self.states_generated as f64 / expanded as f64,
+        }
+    }
+}
+
+impl Display for SolveStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} states expanded, {} generated, branching factor {:.2}, peak frontier {}, {:.1} ms",
+            self.states_expanded,
+            self.states_generated,
+            self.branching_factor(),
+            self.peak_frontier,
+            self.duration.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Limits a caller can put on every search of a `Solver`, on top of the budget the game's preset
+/// gives each strategy, so that a search of a pathological board always stops.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SearchLimits {
+    /// The longest a search may take.
+    pub max_time: Option<Duration>,
+    /// The most states a search may look at.
+    pub max_states: Option<usize>,
+}
+
+/// How a search with limits ended. See `Solver::try_solve`.
+#[derive(Clone)]
+pub enum SolveOutcome {
+    Solved(Vec<Move>),
+    /// The game cannot be completed, for the reason given.
+    Unsolvable(UnsolvableReason),
+    /// The search stopped at a limit, or at the preset's budget, before finding a solution.
+    LimitReached,
+}
+
+/// Why a game cannot be completed.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UnsolvableReason {
+    /// A colour has a number of segments that cannot fill a whole number of tubes.
+    ColourCount { colour: String, count: usize },
+    /// Every tube is full, so nothing can be poured.
+    NoSpace,
+    /// Every state that can be reached was looked at without completing the game.
+    Exhausted,
+}
+
+impl Display for UnsolvableReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnsolvableReason::ColourCount { colour, count } => write!(
+                f,
+                "there are {} {} segments, which cannot fill a whole number of tubes of {}",
+                count, colour, TUBE_SIZE
+            ),
+            UnsolvableReason::NoSpace => write!(f, "every tube is full, so nothing can be poured"),
+            UnsolvableReason::Exhausted => {
+                write!(f, "every board that can be reached has been searched")
+            }
+        }
+    }
+}
+
+/// The states a bidirectional search from the board has reached, by their hash: the depth each was
+/// first reached at, with the hash of the state it was reached from and the move that reached it.
+type Reached = HashMap<u64, (usize, Option<(u64, Move)>)>;
+
+/// How a search ended, with the solution if one was found.
+enum Ending {
+    Solved(Solution),
+    Exhausted,
+    Stopped,
+}
+
+impl Ending {
+    fn solution(self) -> Option<Solution> {
+        match self {
+            Ending::Solved(solution) => Some(solution),
+            Ending::Exhausted | Ending::Stopped => None,
+        }
+    }
+}
+
 pub struct Solver {
-    states: Vec<Vec<Game>>,
     current_state: Game,
     current_block_count: usize,
+    limits: SearchLimits,
 }
 
 impl Solver {
     pub fn new(current_state: &Game) -> Solver {
-        let number_of_blocks = current_state.get_number_of_blocks();
-        let mut states: Vec<Vec<Game>> = Vec::new();
-        if number_of_blocks + 2 == current_state.tubes.len() {
-            return Solver {
-                states,
-                current_state: current_state.clone(),
-                current_block_count: number_of_blocks,
-            };
-        }
-        for _ in 0..number_of_blocks - (current_state.tubes.len() - 2) {
-            states.push(Vec::new());
-        }
-        states[0].push(current_state.clone());
-
+        // A solution is read back from the moves of the state that completes the game, so the
+        // search keeps every move whatever the game's own limit
+        let current_state = Game {
+            history_limit: None,
+            ..current_state.clone()
+        };
         Solver {
-            states,
-            current_state: current_state.clone(),
-            current_block_count: number_of_blocks,
+            current_block_count: current_state.get_number_of_blocks(),
+            current_state,
+            limits: SearchLimits::default(),
         }
     }
 
-    fn get_possible_moves(&self) -> Vec<Move> {
+    /// Puts limits on every search the solver makes.
+    pub fn with_limits(mut self, limits: SearchLimits) -> Solver {
+        self.limits = limits;
+        self
+    }
+
+    /// The moves worth searching from the current state: every legal move, but of the pours from
+    /// a tube into empty tubes that are in no forbidden pour, which give the same board apart from
+    /// which tube is left empty, only the first, and never the pour that takes back the last move
+    /// made. A tube holding a single colour is never poured into an empty tube either, as the
+    /// board would be the same afterwards. Whether a game is stuck depends on `get_legal_moves`,
+    /// as taking back the last move is still a move.
+    pub fn get_possible_moves(&self) -> Vec<Move> {
+        let game = &self.current_state;
+        let last_move = game.moves.get(&game.current_move);
+        let interchangeable = |idx: usize| {
+            game.tubes[idx].get_top_colour().is_none() && !game.is_in_forbidden_pour(idx)
+        };
+        let mut poured_into_empty: HashSet<usize> = HashSet::new();
+        self.get_legal_moves()
+            .into_iter()
+            .filter(|a_move| {
+                let takes_back = last_move.is_some_and(|last| {
+                    last.tube_from == a_move.tube_to
+                        && last.tube_to == a_move.tube_from
+                        && last.quantity == a_move.quantity
+                });
+                !takes_back
+                    && (!interchangeable(a_move.tube_to)
+                        || poured_into_empty.insert(a_move.tube_from))
+            })
+            .collect()
+    }
+
+    /// The possible moves, the most promising first: the moves that complete a tube, then the
+    /// others that merge two blocks, then pours onto a colour, and last pours into an empty tube,
+    /// each in the order of `get_possible_moves`. Tried in this order, a depth first search finds
+    /// a solution far sooner.
+    pub fn get_ordered_moves(&self) -> Vec<Move> {
+        let mut moves: Vec<((bool, bool, bool), Move)> = self
+            .get_possible_moves()
+            .into_iter()
+            .map(|a_move| {
+                let to_tube = &self.peek_move(&a_move).tubes[a_move.tube_to];
+                let completes = to_tube.contents.iter().all(Option::is_some)
+                    && to_tube.is_tube_all_same_contents();
+                let onto_colour = self.current_state.tubes[a_move.tube_to]
+                    .get_top_colour()
+                    .is_some();
+                let merges = self.does_move_reduce_block_count(&a_move);
+                ((completes, merges, onto_colour), a_move)
+            })
+            .collect();
+        moves.sort_by_key(|(rank, _)| Reverse(*rank));
+        moves.into_iter().map(|(_, a_move)| a_move).collect()
+    }
+
+    /// Every move that can be made from the current state, apart from pouring a tube holding a
+    /// single colour into an empty tube.
+    pub fn get_legal_moves(&self) -> Vec<Move> {
         let mut moves = Vec::new();
+        let ruleset = self.current_state.ruleset;
         for (from_idx, from_tube) in self.current_state.tubes.iter().enumerate() {
             let from_top_colour = from_tube.get_top_colour();
             if from_top_colour.is_none() {
-                break;
+                continue;
             }
             let from_top_colour = from_top_colour.unwrap();
             for (to_idx, to_tube) in self.current_state.tubes.iter().enumerate() {
-                if from_idx == to_idx {
+                if from_idx == to_idx || self.current_state.is_pour_forbidden(from_idx, to_idx) {
                     continue;
                 }
                 let to_top_colour = to_tube.get_top_colour();
                 if to_top_colour.is_none() {
-                    // Do not allow moves where you are emptying a tube and the destination tube is already empty.
-                    if TUBE_SIZE - from_top_colour.block_size == from_top_colour.pos {
+                    // Moving a tube's only block into another empty tube never helps
+                    if self.current_state.is_null_progress(from_idx, to_idx) {
                         continue;
                     }
 
-                    moves.push(Move {
-                        tube_from: from_idx,
-                        tube_to: to_idx,
-                        colour: from_top_colour.colour.clone(),
-                        quantity: from_top_colour.block_size,
-                    });
+                    if let Some(quantity) =
+                        ruleset.pour_quantity(from_top_colour.block_size, TUBE_SIZE)
+                    {
+                        moves.push(Move {
+                            tube_from: from_idx,
+                            tube_to: to_idx,
+                            colour: from_top_colour.colour.clone(),
+                            quantity,
+                            played_at: None,
+                        });
+                    }
                     continue;
                 }
                 let to_top_colour = to_top_colour.unwrap();
-                if to_top_colour.colour == from_top_colour.colour {
+                if to_top_colour.colour != from_top_colour.colour {
+                    continue;
+                }
+                if let Some(quantity) =
+                    ruleset.pour_quantity(from_top_colour.block_size, to_top_colour.pos)
+                {
                     moves.push(Move {
                         tube_from: from_idx,
                         tube_to: to_idx,
                         colour: from_top_colour.colour.clone(),
-                        quantity: min(from_top_colour.block_size, to_top_colour.pos),
+                        quantity,
+                        played_at: None,
                     })
                 }
             }
@@ -76,6 +522,632 @@ impl Solver {
         moves
     }
 
+    /// Searches for the shortest list of moves that completes the game, or None if the game cannot
+    /// be completed or no solution was found within the search limit of the game's preset. The
+    /// moves are every one still to make from the current state, in the order to make them, so
+    /// they can be played straight onto the board. On a board that is searched depth first the
+    /// solution is not always the shortest.
+    pub fn solve(&self) -> Option<Vec<Move>> {
+        self.search().map(|solution| solution.moves)
+    }
+
+    /// Like `solve`, but tells a board that cannot be completed apart from one the search stopped
+    /// on at its limits before finding a solution. A board that `why_unsolvable` can tell is
+    /// impossible is not searched at all.
+    pub fn try_solve(&self) -> SolveOutcome {
+        if let Some(reason) = self.why_unsolvable() {
+            return SolveOutcome::Unsolvable(reason);
+        }
+        match self.run_search(self.strategy(), None, None).0 {
+            Ending::Solved(solution) => SolveOutcome::Solved(solution.moves),
+            Ending::Exhausted => SolveOutcome::Unsolvable(UnsolvableReason::Exhausted),
+            Ending::Stopped => SolveOutcome::LimitReached,
+        }
+    }
+
+    /// Up to `max` of the shortest solutions, every one a different sequence of moves, so that a
+    /// generated level can be checked for having a unique solution. See
+    /// `solutions::all_solutions`, and `solutions::distinct_solutions` for leaving out those that
+    /// are the same solution written another way.
+    pub fn find_all_solutions(&self, max: usize) -> Option<SolutionSet> {
+        all_solutions(&self.current_state, max)
+    }
+
+    /// Why the game cannot be completed, if that can be told from the board without searching:
+    /// a colour whose segments cannot fill whole tubes, or tubes too full for anything to be
+    /// poured. None does not mean there is a solution, only that one has to be searched for.
+    pub fn why_unsolvable(&self) -> Option<UnsolvableReason> {
+        if self.current_state.is_game_complete() {
+            return None;
+        }
+        let mut counts: HashMap<&String, usize> = HashMap::new();
+        for colour in self
+            .current_state
+            .tubes
+            .iter()
+            .flat_map(|tube| tube.contents.iter().flatten())
+        {
+            *counts.entry(colour).or_insert(0) += 1;
+        }
+        if let Some((colour, count)) = counts
+            .into_iter()
+            .sorted()
+            .find(|(_, count)| !count.is_multiple_of(TUBE_SIZE))
+        {
+            return Some(UnsolvableReason::ColourCount {
+                colour: colour.clone(),
+                count,
+            });
+        }
+        let full = |tube: &Tube| tube.contents.iter().all(Option::is_some);
+        if self.current_state.tubes.iter().all(full) {
+            return Some(UnsolvableReason::NoSpace);
+        }
+        None
+    }
+
+    /// Like `solve`, but also reports how much searching it took to find the solution.
+    pub fn search(&self) -> Option<Solution> {
+        self.search_with(self.strategy())
+    }
+
+    /// The strategy the game's preset searches the board with: breadth first, unless the board is
+    /// too large.
+    pub fn strategy(&self) -> Strategy {
+        match self.searches_depth_first() {
+            true => Strategy::DepthFirst,
+            false => Strategy::BreadthFirst,
+        }
+    }
+
+    /// Whether the board is too large for the game's preset to search breadth first, so that a
+    /// solution is not always the shortest.
+    pub fn searches_depth_first(&self) -> bool {
+        self.current_state.tubes.len() > self.current_state.preset.settings().breadth_first_tubes
+    }
+
+    /// Searches with the given strategy whatever the size of the board, within the budget the
+    /// game's preset gives that strategy.
+    pub fn search_with(&self, strategy: Strategy) -> Option<Solution> {
+        self.run_search(strategy, None, None).0.solution()
+    }
+
+    /// Searches like `search`, also returning how the search went, whether or not it found a
+    /// solution.
+    pub fn solve_with_stats(&self) -> (Option<Solution>, SolveStats) {
+        self.search_with_stats(self.strategy())
+    }
+
+    /// Searches like `search_with`, returning how the search went like `solve_with_stats`.
+    pub fn search_with_stats(&self, strategy: Strategy) -> (Option<Solution>, SolveStats) {
+        let (ending, stats) = self.run_search(strategy, None, None);
+        (ending.solution(), stats)
+    }
+
+    /// Searches like `search`, calling `progress` every `PROGRESS_INTERVAL` states, so that a long
+    /// search can be shown to be getting on.
+    pub fn solve_with_progress(&self, progress: &mut dyn FnMut(&Progress)) -> Option<Solution> {
+        self.search_with_progress(self.strategy(), progress)
+    }
+
+    /// Searches like `search_with`, calling `progress` like `solve_with_progress`.
+    pub fn search_with_progress(
+        &self,
+        strategy: Strategy,
+        progress: &mut dyn FnMut(&Progress),
+    ) -> Option<Solution> {
+        self.run_search(strategy, None, Some(progress)).0.solution()
+    }
+
+    /// Searches like `search`, calling `trace` every time the search finishes with a depth, so
+    /// that how it goes about a board can be watched.
+    pub fn search_traced(&self, trace: &mut dyn FnMut(&TraceStep)) -> Option<Solution> {
+        self.search_traced_with(self.strategy(), trace)
+    }
+
+    /// Searches like `search_with`, calling `trace` like `search_traced`.
+    pub fn search_traced_with(
+        &self,
+        strategy: Strategy,
+        trace: &mut dyn FnMut(&TraceStep),
+    ) -> Option<Solution> {
+        self.run_search(strategy, Some(trace), None).0.solution()
+    }
+
+    /// Searches with any strategy, within the budget it takes from the game's preset.
+    pub fn search_using(&self, strategy: &mut dyn SolverStrategy) -> Option<Solution> {
+        self.solve_using(strategy, None, None).0.solution()
+    }
+
+    fn run_search(
+        &self,
+        strategy: Strategy,
+        trace: Option<&mut dyn FnMut(&TraceStep)>,
+        progress: Option<&mut dyn FnMut(&Progress)>,
+    ) -> (Ending, SolveStats) {
+        let started = Instant::now();
+        if self.current_state.is_game_complete() {
+            let solution = Solution {
+                moves: Vec::new(),
+                states_searched: 0,
+                duplicates: 0,
+                optimal: true,
+            };
+            let stats = SolveStats {
+                duration: started.elapsed(),
+                solution_length: Some(0),
+                ..Default::default()
+            };
+            return (Ending::Solved(solution), stats);
+        }
+        let (ending, mut stats) = match strategy {
+            Strategy::BreadthFirst => {
+                self.solve_using(&mut BreadthFirstSearch::default(), trace, progress)
+            }
+            Strategy::DepthFirst => {
+                self.solve_using(&mut DepthFirstSearch::default(), trace, progress)
+            }
+            Strategy::AStar => self.solve_using(&mut AStarSearch::default(), trace, progress),
+            Strategy::Greedy => self.solve_using(&mut GreedySearch::default(), trace, progress),
+            Strategy::Beam(width) => self.solve_using(&mut BeamSearch::new(width), trace, progress),
+            Strategy::Bidirectional => match self.current_state.forbidden_pours.is_empty() {
+                true => self.search_bidirectional(trace, progress),
+                false => self.solve_using(&mut BreadthFirstSearch::default(), trace, progress),
+            },
+            Strategy::IdaStar => self.search_ida_star(
+                self.current_state.preset.settings().max_depth_first_states,
+                trace,
+                progress,
+            ),
+        };
+        stats.duration = started.elapsed();
+        if let Ending::Solved(solution) = &ending {
+            stats.solution_length = Some(solution.moves.len());
+        }
+        (ending, stats)
+    }
+
+    fn solve_using(
+        &self,
+        strategy: &mut dyn SolverStrategy,
+        trace: Option<&mut dyn FnMut(&TraceStep)>,
+        progress: Option<&mut dyn FnMut(&Progress)>,
+    ) -> (Ending, SolveStats) {
+        let search = self.explore(strategy, trace, progress, self.deadline());
+        let stats = search.stats();
+        let ending = match search.solved {
+            Some(solved) => Ending::Solved(Solution {
+                moves: self.moves_to(&solved),
+                states_searched: search.states_searched,
+                duplicates: search.duplicates,
+                optimal: strategy.finds_shortest(),
+            }),
+            None if search.over_budget => Ending::Stopped,
+            None => Ending::Exhausted,
+        };
+        (ending, stats)
+    }
+
+    /// When a search started now must stop by, under the solver's limits.
+    fn deadline(&self) -> Option<Instant> {
+        self.limits
+            .max_time
+            .map(|max_time| Instant::now() + max_time)
+    }
+
+    /// The moves the search made to reach a state.
+    fn moves_to(&self, state: &Game) -> Vec<Move> {
+        (self.current_state.current_move + 1..=state.current_move)
+            .filter_map(|idx| state.moves.get(&idx).cloned())
+            .collect()
+    }
+
+    /// Takes states from the strategy until one completes the game, the strategy has none left,
+    /// or its budget, the solver's limit on states or the deadline has been reached. The states
+    /// seen are remembered up to the strategy's `seen_limit`. `trace` is
+    /// called every time the search reaches a depth it has not reached before, for the depth
+    /// before, and once more at the end. `progress` is called every `PROGRESS_INTERVAL` states.
+    fn explore(
+        &self,
+        strategy: &mut dyn SolverStrategy,
+        mut trace: Option<&mut dyn FnMut(&TraceStep)>,
+        mut progress: Option<&mut dyn FnMut(&Progress)>,
+        deadline: Option<Instant>,
+    ) -> Exploration {
+        let max_states = self.max_states(strategy.budget(&self.current_state.preset.settings()));
+        let start = SearchNode::new(self.current_state.clone(), 0);
+        let mut search = Exploration {
+            solved: None,
+            states_searched: 0,
+            pruned: 0,
+            duplicates: 0,
+            states_generated: 0,
+            peak_frontier: 1,
+            best_bound: start.bound,
+            over_budget: false,
+        };
+        // The fewest moves each state seen has been reached in
+        let mut seen: HashMap<u64, usize> = HashMap::from([(state_hash(&start.state), 0)]);
+        // The deepest depth reached, reported once the search goes deeper or ends
+        let mut step: Option<TraceStep> = None;
+        strategy.push(start);
+        while let Some(node) = strategy.pop() {
+            if search.states_searched == max_states
+                || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                search.over_budget = true;
+                break;
+            }
+            // The state has been reached in fewer moves since it was pushed
+            if seen
+                .get(&state_hash(&node.state))
+                .is_some_and(|fewest| *fewest < node.depth)
+            {
+                search.duplicates += 1;
+                continue;
+            }
+            search.states_searched += 1;
+            search.best_bound = search.best_bound.min(node.bound);
+            if let Some(progress) = progress.as_mut() {
+                if search.states_searched.is_multiple_of(PROGRESS_INTERVAL) {
+                    progress(&Progress {
+                        states_searched: search.states_searched,
+                        depth: node.depth,
+                        best_bound: search.best_bound,
+                    });
+                }
+            }
+            if trace.is_some() && node.depth > 0 {
+                match step.as_mut() {
+                    Some(step) if step.depth >= node.depth => {
+                        if step.depth == node.depth {
+                            step.best_bound = step.best_bound.min(node.bound);
+                        }
+                    }
+                    _ => {
+                        if let (Some(trace), Some(step)) = (trace.as_mut(), step) {
+                            trace(&step);
+                        }
+                        step = Some(TraceStep {
+                            depth: node.depth,
+                            states_searched: 0,
+                            best_bound: node.bound,
+                            pruned: 0,
+                        });
+                    }
+                }
+                if let Some(step) = step.as_mut() {
+                    step.states_searched = search.states_searched;
+                    step.pruned = search.pruned;
+                }
+            }
+            if node.state.is_game_complete() {
+                search.solved = Some(node.state);
+                break;
+            }
+            for next in strategy.next_states(&node) {
+                search.states_generated += 1;
+                let hash = state_hash(&next.state);
+                let fewest = seen.get(&hash).copied();
+                if strategy.should_prune(&next, fewest) {
+                    search.pruned += 1;
+                    if fewest.is_some() {
+                        search.duplicates += 1;
+                    }
+                    continue;
+                }
+                // Forgetting every state seen only costs looking at some of them again
+                if strategy
+                    .seen_limit()
+                    .is_some_and(|limit| fewest.is_none() && seen.len() >= limit)
+                {
+                    seen.clear();
+                }
+                seen.insert(hash, next.depth);
+                strategy.push(next);
+            }
+            search.peak_frontier = search.peak_frontier.max(strategy.frontier_size());
+        }
+        if let (Some(trace), Some(step)) = (trace, step) {
+            trace(&step);
+        }
+        search
+    }
+
+    /// Searches with IDA*: passes of a depth first search, each following only the lines whose
+    /// moves so far plus the lower bound on the moves left are within a bound. The bound starts at
+    /// the lower bound of the board and is raised to the smallest total that went over it, until
+    /// a solution is found, so the first solution found is a shortest one. Each pass is reported
+    /// to `trace` once it is finished, with the bound as the depth. The budget of `max_states` is
+    /// shared by every pass.
+    fn search_ida_star(
+        &self,
+        max_states: usize,
+        mut trace: Option<&mut dyn FnMut(&TraceStep)>,
+        mut progress: Option<&mut dyn FnMut(&Progress)>,
+    ) -> (Ending, SolveStats) {
+        let max_states = self.max_states(max_states);
+        let deadline = self.deadline();
+        let mut bound = self.lower_bound();
+        let mut states_searched = 0;
+        let mut pruned = 0;
+        let mut duplicates = 0;
+        let mut stats = SolveStats::default();
+        loop {
+            let mut pass = BoundedDepthFirstSearch::new(bound, max_states - states_searched);
+            // Every pass counts its states from 0, so they are reported as a total of every pass
+            let before = states_searched;
+            let mut report = |step: &Progress| {
+                if let Some(progress) = progress.as_mut() {
+                    progress(&Progress {
+                        states_searched: before + step.states_searched,
+                        ..*step
+                    });
+                }
+            };
+            let search = self.explore(&mut pass, None, Some(&mut report), deadline);
+            states_searched += search.states_searched;
+            pruned += search.pruned;
+            duplicates += search.duplicates;
+            let pass_stats = search.stats();
+            stats.states_expanded += pass_stats.states_expanded;
+            stats.states_generated += pass_stats.states_generated;
+            stats.peak_frontier = stats.peak_frontier.max(pass_stats.peak_frontier);
+            if let Some(solved) = search.solved {
+                let solution = Solution {
+                    moves: self.moves_to(&solved),
+                    states_searched,
+                    duplicates,
+                    optimal: true,
+                };
+                return (Ending::Solved(solution), stats);
+            }
+            if search.over_budget {
+                return (Ending::Stopped, stats);
+            }
+            if let Some(trace) = trace.as_mut() {
+                trace(&TraceStep {
+                    depth: bound,
+                    states_searched,
+                    best_bound: search.best_bound,
+                    pruned,
+                });
+            }
+            bound = match pass.next_bound() {
+                Some(bound) => bound,
+                None => return (Ending::Exhausted, stats),
+            };
+        }
+    }
+
+    /// Searches breadth first from the board and back from the completed board a depth at a time,
+    /// always taking the next depth of whichever search has fewer states waiting, until a state
+    /// one of them reaches has been reached by the other. The moves to that state and on from it
+    /// to the completed board are then a shortest solution. States are compared whatever the
+    /// order of their tubes, so which colour ends up in which tube does not matter, but that
+    /// needs a board without forbidden pours. Each depth is reported to `trace` once it is
+    /// finished, with the depths of both searches added together as the depth, the length of the
+    /// shortest solution there could still be. The budget is that of a breadth first search.
+    fn search_bidirectional(
+        &self,
+        mut trace: Option<&mut dyn FnMut(&TraceStep)>,
+        mut progress: Option<&mut dyn FnMut(&Progress)>,
+    ) -> (Ending, SolveStats) {
+        let settings = self.current_state.preset.settings();
+        let max_states = self.max_states(settings.max_breadth_first_states);
+        let deadline = self.deadline();
+        let mut stats = SolveStats {
+            peak_frontier: 2,
+            ..Default::default()
+        };
+        let goal = match self.completed_board() {
+            Some(goal) => goal,
+            None => return (Ending::Exhausted, stats),
+        };
+        let start = self.current_state.clone();
+        let mut forward: Reached = HashMap::from([(state_hash(&start), (0, None))]);
+        // The depth each state was first reached at back from the completed board
+        let mut backward: HashMap<u64, usize> = HashMap::from([(state_hash(&goal), 0)]);
+        let mut forward_layer = vec![start];
+        let mut backward_layer = vec![goal];
+        let (mut forward_depth, mut backward_depth) = (0, 0);
+        let mut duplicates = 0;
+        let mut best_bound = self.lower_bound();
+        let mut meeting = None;
+        while meeting.is_none() {
+            if forward_layer.is_empty() || backward_layer.is_empty() {
+                return (Ending::Exhausted, stats);
+            }
+            let forwards = forward_layer.len() <= backward_layer.len();
+            let (layer, depth) = match forwards {
+                true => (std::mem::take(&mut forward_layer), forward_depth),
+                false => (std::mem::take(&mut backward_layer), backward_depth),
+            };
+            let mut next_layer = Vec::new();
+            'layer: for state in layer {
+                if stats.states_expanded == max_states
+                    || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+                {
+                    return (Ending::Stopped, stats);
+                }
+                stats.states_expanded += 1;
+                if forwards {
+                    best_bound = best_bound.min(search::minimum_moves_lower_bound(&state));
+                }
+                if let Some(progress) = progress.as_mut() {
+                    if stats.states_expanded.is_multiple_of(PROGRESS_INTERVAL) {
+                        progress(&Progress {
+                            states_searched: stats.states_expanded,
+                            depth,
+                            best_bound,
+                        });
+                    }
+                }
+                let hash = state_hash(&state);
+                let next_states: Vec<(Game, Option<Move>)> = match forwards {
+                    true => Solver::new(&state)
+                        .get_possible_moves()
+                        .into_iter()
+                        .map(|a_move| {
+                            let mut next = state.clone();
+                            next.make_move(&a_move);
+                            (next, Some(a_move))
+                        })
+                        .collect(),
+                    false => predecessors(&state)
+                        .into_iter()
+                        .map(|before| (before, None))
+                        .collect(),
+                };
+                for (next, a_move) in next_states {
+                    stats.states_generated += 1;
+                    let next_hash = state_hash(&next);
+                    let seen = match forwards {
+                        true => forward.contains_key(&next_hash),
+                        false => backward.contains_key(&next_hash),
+                    };
+                    if seen {
+                        duplicates += 1;
+                        continue;
+                    }
+                    match a_move {
+                        Some(a_move) => {
+                            forward.insert(next_hash, (depth + 1, Some((hash, a_move))));
+                        }
+                        None => {
+                            backward.insert(next_hash, depth + 1);
+                        }
+                    }
+                    if forward.contains_key(&next_hash) && backward.contains_key(&next_hash) {
+                        meeting = Some(next_hash);
+                        break 'layer;
+                    }
+                    next_layer.push(next);
+                }
+            }
+            match forwards {
+                true => {
+                    forward_layer = next_layer;
+                    forward_depth += 1;
+                }
+                false => {
+                    backward_layer = next_layer;
+                    backward_depth += 1;
+                }
+            }
+            stats.peak_frontier = stats
+                .peak_frontier
+                .max(forward_layer.len() + backward_layer.len());
+            if let Some(trace) = trace.as_mut() {
+                trace(&TraceStep {
+                    depth: forward_depth + backward_depth,
+                    states_searched: stats.states_expanded,
+                    best_bound,
+                    pruned: duplicates,
+                });
+            }
+        }
+        let meeting = meeting.expect("the searches should have met");
+        let solution = Solution {
+            moves: self.moves_through(meeting, &forward, &backward),
+            states_searched: stats.states_expanded,
+            duplicates,
+            optimal: true,
+        };
+        (Ending::Solved(solution), stats)
+    }
+
+    /// The moves from the board through a state both searches of `search_bidirectional` reached
+    /// to the completed board: the moves the search from the board made to reach it, then moves
+    /// to states ever fewer moves back from the completed board.
+    fn moves_through(
+        &self,
+        meeting: u64,
+        forward: &Reached,
+        backward: &HashMap<u64, usize>,
+    ) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut hash = meeting;
+        while let Some((_, Some((parent, a_move)))) = forward.get(&hash) {
+            moves.push(a_move.clone());
+            hash = *parent;
+        }
+        moves.reverse();
+        let mut state = self.current_state.clone();
+        for a_move in moves.iter() {
+            state.make_move(a_move);
+        }
+        for left in (0..backward[&meeting]).rev() {
+            let a_move = Solver::new(&state)
+                .get_possible_moves()
+                .into_iter()
+                .find(|a_move| {
+                    let mut next = state.clone();
+                    next.make_move(a_move);
+                    backward.get(&state_hash(&next)) == Some(&left)
+                })
+                .expect("a state reached back from the completed board has a move towards it");
+            state.make_move(&a_move);
+            moves.push(a_move);
+        }
+        moves
+    }
+
+    /// The completed board with the colours of the board: each colour in full tubes, in the order
+    /// the colours are first found, and the rest of the tubes empty. None if the colours cannot
+    /// fill whole tubes, or need more tubes than there are.
+    fn completed_board(&self) -> Option<Game> {
+        if self.why_unsolvable().is_some() {
+            return None;
+        }
+        let mut counts: Vec<(&String, usize)> = Vec::new();
+        for colour in self
+            .current_state
+            .tubes
+            .iter()
+            .flat_map(|tube| tube.contents.iter().flatten())
+        {
+            match counts.iter_mut().find(|(known, _)| *known == colour) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((colour, 1)),
+            }
+        }
+        let mut full_tubes = counts
+            .into_iter()
+            .flat_map(|(colour, count)| std::iter::repeat_n(colour, count / TUBE_SIZE));
+        let mut goal = self.current_state.clone();
+        for tube in goal.tubes.iter_mut() {
+            tube.contents = vec![full_tubes.next().cloned(); TUBE_SIZE];
+        }
+        match full_tubes.next() {
+            Some(_) => None,
+            None => Some(goal),
+        }
+    }
+
+    /// The budget of a search, lowered to the solver's limit on states if it has one.
+    fn max_states(&self, budget: usize) -> usize {
+        self.limits
+            .max_states
+            .map_or(budget, |max_states| max_states.min(budget))
+    }
+
+    /// Suggests a move from the current state. Boards in the opening book get the book's move.
+    /// Otherwise this is a simple heuristic: the first of `get_ordered_moves`, so a move that
+    /// completes a tube or reduces the number of blocks is preferred.
+    pub fn hint(&self) -> Option<Move> {
+        if let Some(book_move) = book_move(&self.current_state) {
+            return Some(book_move);
+        }
+        self.get_ordered_moves().into_iter().next()
+    }
+
+    /// A lower bound on the number of moves left to complete the game, as
+    /// `minimum_moves_lower_bound` gives.
+    pub fn lower_bound(&self) -> usize {
+        search::minimum_moves_lower_bound(&self.current_state)
+    }
+
     fn does_move_reduce_block_count(&self, possible_move: &Move) -> bool {
         self.current_block_count > self.peek_move(possible_move).get_number_of_blocks()
     }
@@ -90,11 +1162,111 @@ impl Solver {
     }
 }
 
+/// Finds a shortest solution to any board, however long that takes. The presets of `Solver` give
+/// up after a budget of states and search large boards depth first, so a solution they find might
+/// not be the shortest. This searches with IDA* and no budget at all, so that memory use stays
+/// bounded and every solution found is known to be the shortest, for comparing a game played
+/// against the true optimum. Only the limits given with `with_limits` stop it sooner.
+pub struct OptimalSolver {
+    solver: Solver,
+}
+
+impl OptimalSolver {
+    pub fn new(current_state: &Game) -> OptimalSolver {
+        OptimalSolver {
+            solver: Solver::new(current_state),
+        }
+    }
+
+    /// Puts limits on the search, which otherwise goes on until it has an answer.
+    pub fn with_limits(mut self, limits: SearchLimits) -> OptimalSolver {
+        self.solver = self.solver.with_limits(limits);
+        self
+    }
+
+    /// A shortest solution, or None if the game cannot be completed or a limit was reached.
+    pub fn search(&self) -> Option<Solution> {
+        self.run_search(None).solution()
+    }
+
+    /// Searches like `search`, calling `trace` after every pass of the search.
+    pub fn search_traced(&self, trace: &mut dyn FnMut(&TraceStep)) -> Option<Solution> {
+        self.run_search(Some(trace)).solution()
+    }
+
+    /// Like `search`, but tells a board that cannot be completed apart from one the search
+    /// stopped on at its limits. See `Solver::try_solve`.
+    pub fn try_search(&self) -> SolveOutcome {
+        if let Some(reason) = self.solver.why_unsolvable() {
+            return SolveOutcome::Unsolvable(reason);
+        }
+        match self.run_search(None) {
+            Ending::Solved(solution) => SolveOutcome::Solved(solution.moves),
+            Ending::Exhausted => SolveOutcome::Unsolvable(UnsolvableReason::Exhausted),
+            Ending::Stopped => SolveOutcome::LimitReached,
+        }
+    }
+
+    fn run_search(&self, trace: Option<&mut dyn FnMut(&TraceStep)>) -> Ending {
+        if self.solver.current_state.is_game_complete() {
+            return Ending::Solved(Solution {
+                moves: Vec::new(),
+                states_searched: 0,
+                duplicates: 0,
+                optimal: true,
+            });
+        }
+        self.solver.search_ida_star(usize::MAX, trace, None).0
+    }
+}
+
+/// How far a search with one strategy got. See `Solver::explore`.
+struct Exploration {
+    /// The state that completes the game, if one was found.
+    solved: Option<Game>,
+    states_searched: usize,
+    pruned: usize,
+    /// How many of the states pruned had been reached before, with those taken from the strategy
+    /// and not looked at because they had since been reached in fewer moves.
+    duplicates: usize,
+    /// How many states one move on from those looked at were found, pruned or not.
+    states_generated: usize,
+    /// The most states waiting in the strategy at any one time.
+    peak_frontier: usize,
+    /// The lowest lower bound on the moves left of any state looked at.
+    best_bound: usize,
+    /// Whether the search stopped because it had used its budget.
+    over_budget: bool,
+}
+
+impl Exploration {
+    /// The measures of the search, leaving the duration and solution to the caller.
+    fn stats(&self) -> SolveStats {
+        SolveStats {
+            states_expanded: self.states_searched,
+            states_generated: self.states_generated,
+            peak_frontier: self.peak_frontier,
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) fn state_hash(state: &Game) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.canonical_key().hash(&mut hasher);
+    hasher.finish()
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::{HashMap, HashSet};
 
-    use crate::tube::Tube;
+    use crate::{
+        game::Ruleset,
+        search::MAX_SEEN_STATES,
+        testing::{assert_move_eq, assert_tubes_eq, initialise_game},
+        tube::Tube,
+    };
 
     use super::*;
 
@@ -129,7 +1301,7 @@ mod tests {
         ];
 
         for test in tests {
-            let game = initialise_game(test.0, num_of_tubes);
+            let game = initialise_game(&test.0, num_of_tubes);
             let num_of_blocks = game.get_number_of_blocks();
             assert_eq!(
                 num_of_blocks, test.1,
@@ -143,15 +1315,15 @@ mod tests {
     fn test_solver_init() {
         let num_of_tubes = 4;
         // Vec<String> = initial tube setup
-        // usize = x = initial list of number of moves which do decrease the number of blocks
-        // Game = initial status of the game in states[0][0]
+        // usize = number of blocks on the board
+        // Game = the state the solver starts from
         let tests: Vec<(Vec<String>, usize, Game)> = vec![
             (
                 vec![
                     String::from("red,red,blue,blue"),
                     String::from("blue,blue,red,red"),
                 ],
-                2,
+                4,
                 Game {
                     tubes: vec![
                         Tube::from_string(String::from("red,red,blue,blue"), 0),
@@ -162,6 +1334,7 @@ mod tests {
                     moves: HashMap::new(),
                     current_move: 0,
                     colours: HashSet::from(["red".to_string(), "blue".to_string()]),
+                    ..Default::default()
                 },
             ),
             (
@@ -169,7 +1342,7 @@ mod tests {
                     String::from("red, blue, green"),
                     String::from("red, blue, green"),
                 ],
-                4,
+                6,
                 Game {
                     tubes: vec![
                         Tube::from_string(String::from("red, blue, green"), 0),
@@ -184,39 +1357,31 @@ mod tests {
                         "blue".to_string(),
                         "green".to_string(),
                     ]),
+                    ..Default::default()
                 },
             ),
         ];
         for test in tests {
-            let game = initialise_game(test.0, num_of_tubes);
+            let game = initialise_game(&test.0, num_of_tubes);
             let solver = Solver::new(&game);
             assert_eq!(
-                solver.states.len(),
-                test.1,
-                "solver has incorrect size in x direction. Expected: {}, got: {}",
-                test.1,
-                solver.states.len()
+                solver.current_block_count, test.1,
+                "solver has incorrect block count. Expected: {}, got: {}",
+                test.1, solver.current_block_count
+            );
+            let state = &solver.current_state;
+            assert_tubes_eq(&state.tubes, &test.2.tubes);
+            assert_eq!(
+                state.current_move, 0,
+                "current move wrong value. Expected = {}, got = {}",
+                0, state.current_move
+            );
+            assert!(state.moves.is_empty(), "moves are not empty");
+            assert_eq!(
+                state.colours, test.2.colours,
+                "Colours hashset is not the same. Expected = {:?}, got = {:?}",
+                test.2.colours, state.colours
             );
-            for (idx, state) in solver.states.iter().enumerate() {
-                if idx == 0 {
-                    assert_eq!(state.len(), 1, "solver states for x = {} has incorrect size in y direction. Expected: 1, got: {}", idx, state.len());
-                    let state_0_0 = &state[idx];
-                    test_all_tubes(&state_0_0.tubes, &test.2.tubes);
-                    assert_eq!(
-                        state_0_0.current_move, 0,
-                        "current move wrong value. Expected = {}, got = {}",
-                        0, state_0_0.current_move
-                    );
-                    assert!(state_0_0.moves.is_empty(), "moves are not empty");
-                    assert_eq!(
-                        state_0_0.colours, test.2.colours,
-                        "Colours hashset is not the same. Expected = {:?}, got = {:?}",
-                        test.2.colours, state_0_0.colours
-                    );
-                } else {
-                    assert_eq!(state.len(), 0, "solver states for x = {} has incorrect size in y direction. Expected: 0, got: {}", idx, state.len());
-                }
-            }
         }
     }
 
@@ -236,12 +1401,14 @@ mod tests {
                         tube_to: 2,
                         colour: String::from("red"),
                         quantity: 3,
+                        played_at: None,
                     },
                     Move {
                         tube_from: 2,
                         tube_to: 0,
                         colour: String::from("red"),
                         quantity: 1,
+                        played_at: None,
                     },
                 ],
             ),
@@ -265,24 +1432,28 @@ mod tests {
                         tube_to: 2,
                         colour: String::from("red"),
                         quantity: 2,
+                        played_at: None,
                     },
                     Move {
                         tube_from: 2,
                         tube_to: 0,
                         colour: String::from("red"),
                         quantity: 2,
+                        played_at: None,
                     },
                     Move {
                         tube_from: 1,
                         tube_to: 3,
                         colour: String::from("blue"),
                         quantity: 3,
+                        played_at: None,
                     },
                     Move {
                         tube_from: 3,
                         tube_to: 1,
                         colour: String::from("blue"),
                         quantity: 1,
+                        played_at: None,
                     },
                 ],
             ),
@@ -298,24 +1469,27 @@ mod tests {
                         tube_to: 1,
                         colour: String::from("red"),
                         quantity: 1,
+                        played_at: None,
                     },
                     Move {
                         tube_from: 1,
                         tube_to: 0,
                         colour: String::from("red"),
                         quantity: 1,
+                        played_at: None,
                     },
                     Move {
                         tube_from: 1,
                         tube_to: 3,
                         colour: String::from("red"),
                         quantity: 1,
+                        played_at: None,
                     },
                 ],
             ),
         ];
         for test in tests {
-            let game = initialise_game(test.0, num_of_tubes);
+            let game = initialise_game(&test.0, num_of_tubes);
             let solver = Solver::new(&game);
             let possible_moves = solver.get_possible_moves();
             assert_eq!(
@@ -331,7 +1505,7 @@ mod tests {
                     if possible_move.tube_from == expected_move.tube_from
                         && possible_move.tube_to == expected_move.tube_to
                     {
-                        test_move(possible_move, expected_move);
+                        assert_move_eq(possible_move, expected_move);
                         found = true;
                         break;
                     }
@@ -343,6 +1517,1058 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_legal_moves_skips_unpourable_tubes() {
+        // (tubes, expected moves as (tube_from, tube_to, colour, quantity))
+        let tests = [
+            // Tubes after an empty one are still poured from
+            (
+                vec!["", "red, red, blue, blue", "blue, blue, red, red"],
+                vec![
+                    (1, 0, "red", 2),
+                    (1, 3, "red", 2),
+                    (2, 0, "blue", 2),
+                    (2, 3, "blue", 2),
+                ],
+            ),
+            // A full tube is never poured into, even onto the same colour
+            (
+                vec!["red, blue, blue, blue", "red, red, red, blue"],
+                vec![
+                    (0, 2, "red", 1),
+                    (0, 3, "red", 1),
+                    (1, 2, "red", 3),
+                    (1, 3, "red", 3),
+                ],
+            ),
+        ];
+        for test in tests {
+            let game = initialise_game(&test.0, 4);
+            let legal_moves = Solver::new(&game).get_legal_moves();
+            let moves: Vec<(usize, usize, &str, usize)> = legal_moves
+                .iter()
+                .map(|a_move| {
+                    (
+                        a_move.tube_from,
+                        a_move.tube_to,
+                        a_move.colour.as_str(),
+                        a_move.quantity,
+                    )
+                })
+                .sorted()
+                .collect();
+            assert_eq!(moves, test.1, "wrong legal moves for {:?}", test.0);
+        }
+    }
+
+    #[test]
+    fn test_null_progress_with_forbidden_pours() {
+        // Tube 2 can only be emptied into tube 1, so tube 1 has to be poured into an empty tube
+        // first, which is not a mere relabelling once tube 1 is in a forbidden pour
+        let mut game = initialise_game(&["green, green", "red, red, green, green", "red, red"], 5);
+        game.forbidden_pours = vec![(1, 2), (1, 3), (1, 4)];
+        assert!(
+            Solver::new(&game)
+                .get_legal_moves()
+                .iter()
+                .any(|a_move| a_move.tube_from == 0 && a_move.tube_to == 3),
+            "pouring tube 1 into an empty tube should be a legal move"
+        );
+        let solution = Solver::new(&game)
+            .search()
+            .expect("the board should be solvable");
+        assert_eq!(
+            solution.moves.len(),
+            4,
+            "wrong solution length: {:?}",
+            solution
+                .moves
+                .iter()
+                .map(Move::to_string)
+                .collect::<Vec<_>>()
+        );
+        let optimal = OptimalSolver::new(&game)
+            .search()
+            .expect("the board should be solvable by the optimal solver");
+        assert_eq!(optimal.moves.len(), 4, "wrong optimal solution length");
+        let mut replay = game.clone();
+        for a_move in solution.moves.iter() {
+            assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+            replay.make_move(a_move);
+        }
+        assert!(
+            replay.is_game_complete(),
+            "solution does not complete the game"
+        );
+    }
+
+    #[test]
+    fn test_get_possible_moves_one_unit() {
+        let mut game = initialise_game(
+            &[
+                String::from(""),
+                String::from("red, red, blue, blue"),
+                String::from("red, red, blue, blue"),
+            ],
+            4,
+        );
+        game.ruleset = Ruleset::OneUnit;
+        let solver = Solver::new(&game);
+        let possible_moves = solver.get_legal_moves();
+        let expected = [(1, 0), (1, 3), (2, 0), (2, 3)];
+        assert_eq!(
+            possible_moves.len(),
+            expected.len(),
+            "possible moves wrong length. Expected: {}, got: {}",
+            expected.len(),
+            possible_moves.len()
+        );
+        for (tube_from, tube_to) in expected {
+            let expected_move = Move {
+                tube_from,
+                tube_to,
+                colour: String::from("red"),
+                quantity: 1,
+                played_at: None,
+            };
+            match possible_moves
+                .iter()
+                .find(|x| x.tube_from == tube_from && x.tube_to == tube_to)
+            {
+                Some(possible_move) => assert_move_eq(possible_move, &expected_move),
+                None => panic!("expected move cannot be found: {}", expected_move),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_possible_moves_no_split() {
+        let mut game = initialise_game(
+            &[
+                String::from("red, red, blue, blue"),
+                String::from("red, red, blue"),
+                String::from("red, blue, blue, blue"),
+                String::from("red, red, blue, blue"),
+            ],
+            6,
+        );
+        game.ruleset = Ruleset::NoSplit;
+        let solver = Solver::new(&game);
+        let possible_moves = solver.get_legal_moves();
+        // Tube 2 only has space for one segment, so only the single red from tube 3 can go there.
+        let mut expected = vec![(2, 1, 1)];
+        for tube_to in [4, 5] {
+            expected.extend([
+                (0, tube_to, 2),
+                (1, tube_to, 2),
+                (2, tube_to, 1),
+                (3, tube_to, 2),
+            ]);
+        }
+        assert_eq!(
+            possible_moves.len(),
+            expected.len(),
+            "possible moves wrong length. Expected: {}, got: {}",
+            expected.len(),
+            possible_moves.len()
+        );
+        for (tube_from, tube_to, quantity) in expected {
+            let expected_move = Move {
+                tube_from,
+                tube_to,
+                colour: String::from("red"),
+                quantity,
+                played_at: None,
+            };
+            match possible_moves
+                .iter()
+                .find(|x| x.tube_from == tube_from && x.tube_to == tube_to)
+            {
+                Some(possible_move) => assert_move_eq(possible_move, &expected_move),
+                None => panic!("expected move cannot be found: {}", expected_move),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_possible_moves_pruned() {
+        let mut game = initialise_game(
+            &[
+                String::from("red, red, red, blue"),
+                String::from("red, green, green"),
+                String::from("blue, blue, blue"),
+            ],
+            5,
+        );
+        let pours = |game: &Game, legal: bool| -> Vec<(usize, usize)> {
+            let solver = Solver::new(game);
+            let moves = match legal {
+                true => solver.get_legal_moves(),
+                false => solver.get_possible_moves(),
+            };
+            moves.iter().map(|x| (x.tube_from, x.tube_to)).collect()
+        };
+        assert_eq!(
+            pours(&game, true),
+            vec![(0, 1), (0, 3), (0, 4), (1, 3), (1, 4)],
+            "every pour into an empty tube should be legal"
+        );
+        assert_eq!(
+            pours(&game, false),
+            vec![(0, 1), (0, 3), (1, 3)],
+            "only the first of the empty tubes should be poured into"
+        );
+
+        game.make_move(&Solver::new(&game).get_possible_moves()[0]);
+        assert!(
+            pours(&game, true).contains(&(1, 0)),
+            "taking the last move back should be legal"
+        );
+        assert!(
+            !pours(&game, false).contains(&(1, 0)),
+            "taking the last move back should not be searched"
+        );
+
+        game.forbidden_pours = vec![(1, 4)];
+        game.undo_move();
+        assert_eq!(
+            pours(&game, false),
+            vec![(0, 1), (0, 3), (0, 4), (1, 3), (2, 4)],
+            "an empty tube in a forbidden pour is not the same as the others, even for a tube of one colour"
+        );
+    }
+
+    #[test]
+    fn test_get_ordered_moves() {
+        let game = initialise_game(
+            &[
+                String::from("red, blue, blue, blue"),
+                String::from("red, red"),
+                String::from("green, red, blue"),
+                String::from("green, green, green"),
+            ],
+            5,
+        );
+        let solver = Solver::new(&game);
+        let result: Vec<(usize, usize)> = solver
+            .get_ordered_moves()
+            .iter()
+            .map(|x| (x.tube_from, x.tube_to))
+            .collect();
+        // A completed tube, a merge, a pour onto a colour and then the pours into the empty tube
+        assert_eq!(
+            result,
+            vec![(2, 3), (0, 1), (3, 2), (0, 4), (2, 4)],
+            "wrong order of moves"
+        );
+        assert_eq!(
+            solver.hint().map(|x| (x.tube_from, x.tube_to)),
+            Some((2, 3)),
+            "the hint should be the most promising move"
+        );
+    }
+
+    #[test]
+    fn test_get_possible_moves_forbidden() {
+        let mut game = initialise_game(
+            &[
+                String::from("red, red, blue, blue"),
+                String::from("blue, blue, red, red"),
+            ],
+            4,
+        );
+        game.forbidden_pours = vec![(0, 2), (1, 3)];
+        let possible_moves = Solver::new(&game).get_possible_moves();
+        let result: Vec<(usize, usize)> = possible_moves
+            .iter()
+            .map(|x| (x.tube_from, x.tube_to))
+            .collect();
+        assert_eq!(
+            result,
+            vec![(0, 3), (1, 2)],
+            "wrong possible moves with pours {:?} forbidden",
+            game.forbidden_pours
+        );
+    }
+
+    #[test]
+    fn test_search_large_board() {
+        // Built-in puzzles 5 to 10 side by side, sharing one pair of empty tubes, make a board of
+        // 32 tubes and 30 colours that can be solved one puzzle at a time
+        let mut tube_strings: Vec<String> = Vec::new();
+        for puzzle in crate::puzzles::builtin_puzzles()[4..10].iter() {
+            for tube in puzzle.tubes.iter().filter(|tube| tube.as_str() != "empty") {
+                let renamed: Vec<String> = tube
+                    .split(',')
+                    .map(|colour| format!("{}{}", colour.trim(), puzzle.number))
+                    .collect();
+                tube_strings.push(renamed.join(","));
+            }
+        }
+        let game = initialise_game(&tube_strings, 32);
+        assert!(game.validate_setup(), "large board is not a valid setup");
+        assert_eq!(game.colours.len(), 30, "wrong number of colours");
+        let solution = Solver::new(&game)
+            .search()
+            .expect("large board should be solved");
+        assert!(
+            !solution.optimal,
+            "a large board solution is not known to be the shortest"
+        );
+        let mut replay = game.clone();
+        for a_move in solution.moves.iter() {
+            assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+            replay.make_move(a_move);
+        }
+        assert!(
+            replay.is_game_complete(),
+            "solution does not complete the game"
+        );
+    }
+
+    #[test]
+    fn test_search_presets() {
+        let puzzle = &crate::puzzles::builtin_puzzles()[2];
+        let shortest = Solver::new(&puzzle.to_game())
+            .solve()
+            .expect("puzzle should be solved")
+            .len();
+        let tests = [
+            (Preset::Quick, false),
+            (Preset::Balanced, true),
+            (Preset::Thorough, true),
+        ];
+        for (preset, optimal) in tests {
+            let mut game = puzzle.to_game();
+            game.preset = preset;
+            let solution = Solver::new(&game)
+                .search()
+                .unwrap_or_else(|| panic!("puzzle should be solved with the {} preset", preset));
+            assert_eq!(
+                solution.optimal, optimal,
+                "wrong optimal flag with the {} preset",
+                preset
+            );
+            assert!(
+                solution.moves.len() >= shortest,
+                "the {} preset found a solution shorter than the shortest",
+                preset
+            );
+            if optimal {
+                assert_eq!(
+                    solution.moves.len(),
+                    shortest,
+                    "the {} preset should find the shortest solution",
+                    preset
+                );
+            }
+        }
+        assert!(
+            "fast".parse::<Preset>().is_err(),
+            "expected an error for an unknown preset"
+        );
+    }
+
+    #[test]
+    fn test_search_a_star() {
+        // Par is the length of the shortest solution, so every built-in puzzle can be checked
+        // without a slow breadth first search of the larger ones
+        for puzzle in crate::puzzles::builtin_puzzles().iter() {
+            let game = puzzle.to_game();
+            let solver = Solver::new(&game);
+            let a_star = solver
+                .search_with(Strategy::AStar)
+                .unwrap_or_else(|| panic!("puzzle {} should be solved by A*", puzzle.number));
+            assert!(a_star.optimal, "an A* solution is the shortest");
+            assert_eq!(
+                Some(a_star.moves.len()),
+                puzzle.par.moves,
+                "A* should find the shortest solution to puzzle {}",
+                puzzle.number
+            );
+            let mut replay = game.clone();
+            for a_move in a_star.moves.iter() {
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "solution does not complete the game"
+            );
+            if puzzle.number <= 5 {
+                let breadth_first = solver
+                    .search_with(Strategy::BreadthFirst)
+                    .expect("puzzle should be solved breadth first");
+                assert!(
+                    a_star.states_searched <= breadth_first.states_searched,
+                    "A* searched {} states of puzzle {}, more than breadth first's {}",
+                    a_star.states_searched,
+                    puzzle.number,
+                    breadth_first.states_searched
+                );
+            }
+        }
+        let stuck = initialise_game(
+            &[
+                String::from("red, red, red, blue"),
+                String::from("blue, blue, blue, red"),
+                String::from("green, green, green, yellow"),
+                String::from("yellow, yellow, yellow, green"),
+            ],
+            4,
+        );
+        assert!(
+            Solver::new(&stuck).search_with(Strategy::AStar).is_none(),
+            "a stuck board has no solution"
+        );
+    }
+
+    #[test]
+    fn test_search_ida_star() {
+        for puzzle in crate::puzzles::builtin_puzzles().iter() {
+            let game = puzzle.to_game();
+            let mut bounds = Vec::new();
+            let solution = Solver::new(&game)
+                .search_traced_with(Strategy::IdaStar, &mut |step| bounds.push(step.depth))
+                .unwrap_or_else(|| panic!("puzzle {} should be solved by IDA*", puzzle.number));
+            assert!(solution.optimal, "an IDA* solution is the shortest");
+            assert_eq!(
+                Some(solution.moves.len()),
+                puzzle.par.moves,
+                "IDA* should find the shortest solution to puzzle {}",
+                puzzle.number
+            );
+            assert!(
+                bounds.windows(2).all(|pair| pair[0] < pair[1])
+                    && bounds.iter().all(|bound| *bound < solution.moves.len()),
+                "every pass before the solution should have a larger bound: {:?}",
+                bounds
+            );
+            let mut replay = game.clone();
+            for a_move in solution.moves.iter() {
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "solution does not complete the game"
+            );
+        }
+        let stuck = initialise_game(
+            &[
+                String::from("red, red, red, blue"),
+                String::from("blue, blue, blue, red"),
+                String::from("green, green, green, yellow"),
+                String::from("yellow, yellow, yellow, green"),
+            ],
+            4,
+        );
+        assert!(
+            Solver::new(&stuck).search_with(Strategy::IdaStar).is_none(),
+            "a stuck board has no solution"
+        );
+    }
+
+    #[test]
+    fn test_ida_star_pass_with_seen_limit() {
+        // A pass that keeps forgetting the states it has seen still finds a shortest solution
+        for puzzle in crate::puzzles::builtin_puzzles().iter() {
+            let game = puzzle.to_game();
+            let par = puzzle.par.moves.expect("builtin puzzles have a par");
+            let solver = Solver::new(&game);
+            for seen_limit in [1, 16, MAX_SEEN_STATES] {
+                let mut pass =
+                    BoundedDepthFirstSearch::new(par, usize::MAX).with_seen_limit(seen_limit);
+                let solved = solver
+                    .explore(&mut pass, None, None, None)
+                    .solved
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "puzzle {} should be solved remembering {} states",
+                            puzzle.number, seen_limit
+                        )
+                    });
+                assert_eq!(
+                    solver.moves_to(&solved).len(),
+                    par,
+                    "puzzle {} remembering {} states should take the fewest moves",
+                    puzzle.number,
+                    seen_limit
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_search_bidirectional() {
+        for puzzle in crate::puzzles::builtin_puzzles().iter() {
+            let game = puzzle.to_game();
+            let solver = Solver::new(&game);
+            let mut depths = Vec::new();
+            let solution = solver
+                .search_traced_with(Strategy::Bidirectional, &mut |step| depths.push(step.depth))
+                .unwrap_or_else(|| {
+                    panic!("puzzle {} should be solved bidirectionally", puzzle.number)
+                });
+            assert!(solution.optimal, "a bidirectional solution is the shortest");
+            assert_eq!(
+                Some(solution.moves.len()),
+                puzzle.par.moves,
+                "a bidirectional search should find the shortest solution to puzzle {}",
+                puzzle.number
+            );
+            assert!(
+                depths.windows(2).all(|pair| pair[0] < pair[1]),
+                "every depth should go further than the one before: {:?}",
+                depths
+            );
+            let mut replay = game.clone();
+            for a_move in solution.moves.iter() {
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "solution does not complete the game"
+            );
+            if puzzle.number <= 8 {
+                let breadth_first = solver
+                    .search_with(Strategy::BreadthFirst)
+                    .expect("puzzle should be solved breadth first");
+                assert!(
+                    solution.states_searched <= breadth_first.states_searched,
+                    "a bidirectional search looked at {} states of puzzle {}, more than breadth first's {}",
+                    solution.states_searched,
+                    puzzle.number,
+                    breadth_first.states_searched
+                );
+            }
+        }
+        // The search back from the completed board has to follow the rules as well
+        let mut one_unit = initialise_game(
+            &[
+                String::from("red, red, blue, blue"),
+                String::from("blue, red, red, blue"),
+                String::from("empty"),
+                String::from("empty"),
+            ],
+            4,
+        );
+        one_unit.ruleset = Ruleset::OneUnit;
+        let solver = Solver::new(&one_unit);
+        let bidirectional = solver
+            .search_with(Strategy::Bidirectional)
+            .expect("the one unit board should be solved");
+        let breadth_first = solver
+            .search_with(Strategy::BreadthFirst)
+            .expect("the one unit board should be solved");
+        assert_eq!(
+            bidirectional.moves.len(),
+            breadth_first.moves.len(),
+            "wrong length of the one unit solution"
+        );
+        assert!(
+            bidirectional
+                .moves
+                .iter()
+                .all(|a_move| a_move.quantity == 1),
+            "every move should pour one unit"
+        );
+        for tubes in [
+            [
+                "red, red, red, blue",
+                "blue, blue, blue, red",
+                "green, green, green, yellow",
+                "yellow, yellow, yellow, green",
+            ],
+            [
+                "red, red, red, blue",
+                "green, green, green, blue",
+                "empty, green, red, blue",
+                "empty, empty, empty, blue",
+            ],
+        ] {
+            let game = initialise_game(&tubes, 4);
+            assert!(
+                Solver::new(&game)
+                    .search_with(Strategy::Bidirectional)
+                    .is_none(),
+                "{:?} has no solution",
+                tubes
+            );
+        }
+    }
+
+    #[test]
+    fn test_optimal_solver() {
+        for puzzle in crate::puzzles::builtin_puzzles().iter() {
+            // The quick preset searches every board depth first, which the optimal solver ignores
+            let game = Game {
+                preset: Preset::Quick,
+                ..puzzle.to_game()
+            };
+            let solution = OptimalSolver::new(&game)
+                .search()
+                .unwrap_or_else(|| panic!("puzzle {} should be solved", puzzle.number));
+            assert!(solution.optimal, "an optimal solution is the shortest");
+            assert_eq!(
+                Some(solution.moves.len()),
+                puzzle.par.moves,
+                "the optimal solver should find the shortest solution to puzzle {}",
+                puzzle.number
+            );
+            if let Some(quick) = Solver::new(&game).search() {
+                assert!(
+                    quick.moves.len() >= solution.moves.len(),
+                    "puzzle {} was solved in {} moves, fewer than the optimum of {}",
+                    puzzle.number,
+                    quick.moves.len(),
+                    solution.moves.len()
+                );
+            }
+            let mut replay = game.clone();
+            for a_move in solution.moves.iter() {
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "solution does not complete the game"
+            );
+        }
+        let complete = initialise_game(
+            &[
+                String::from("red, red, red, red"),
+                String::from("blue, blue, blue, blue"),
+            ],
+            4,
+        );
+        assert_eq!(
+            OptimalSolver::new(&complete)
+                .search()
+                .map(|solution| solution.moves.len()),
+            Some(0),
+            "a complete game needs no moves"
+        );
+        let stuck = initialise_game(
+            &[
+                String::from("red, red, red, blue"),
+                String::from("blue, blue, blue, red"),
+                String::from("green, green, green, yellow"),
+                String::from("yellow, yellow, yellow, green"),
+            ],
+            4,
+        );
+        assert!(
+            OptimalSolver::new(&stuck).search().is_none(),
+            "a stuck board has no solution"
+        );
+    }
+
+    #[test]
+    fn test_optimal_solver_with_limits() {
+        let game = crate::puzzles::get_builtin_puzzle(9)
+            .expect("puzzle 9 should exist")
+            .to_game();
+        let stuck = initialise_game(
+            &[
+                "red, red, red, blue",
+                "blue, blue, blue, red",
+                "green, green, green, yellow",
+                "yellow, yellow, yellow, green",
+            ],
+            4,
+        );
+        let few_states = SearchLimits {
+            max_states: Some(10),
+            ..Default::default()
+        };
+        let no_time = SearchLimits {
+            max_time: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        // (board, limits, outcome)
+        let tests = [
+            (&game, SearchLimits::default(), "solved"),
+            (&game, few_states, "limit"),
+            (&game, no_time, "limit"),
+            (&stuck, SearchLimits::default(), "unsolvable"),
+        ];
+        for test in tests {
+            let outcome = match OptimalSolver::new(test.0).with_limits(test.1).try_search() {
+                SolveOutcome::Solved(_) => "solved",
+                SolveOutcome::Unsolvable(_) => "unsolvable",
+                SolveOutcome::LimitReached => "limit",
+            };
+            assert_eq!(outcome, test.2, "wrong outcome with limits {:?}", test.1);
+        }
+        assert!(
+            OptimalSolver::new(&game)
+                .with_limits(few_states)
+                .search()
+                .is_none(),
+            "a search stopped at its limit has no solution"
+        );
+    }
+
+    #[test]
+    fn test_solve_with_progress() {
+        let game = crate::puzzles::get_builtin_puzzle(9)
+            .expect("puzzle 9 should exist")
+            .to_game();
+        let mut reports: Vec<Progress> = Vec::new();
+        let solution = Solver::new(&game)
+            .solve_with_progress(&mut |progress| reports.push(*progress))
+            .expect("puzzle 9 should be solved");
+        assert_eq!(
+            reports.len(),
+            solution.states_searched / PROGRESS_INTERVAL,
+            "progress should be reported every {} states",
+            PROGRESS_INTERVAL
+        );
+        for (idx, report) in reports.iter().enumerate() {
+            assert_eq!(
+                report.states_searched,
+                (idx + 1) * PROGRESS_INTERVAL,
+                "wrong states searched in report {}",
+                idx + 1
+            );
+        }
+        assert!(
+            reports
+                .windows(2)
+                .all(|pair| pair[0].best_bound >= pair[1].best_bound
+                    && pair[0].depth <= pair[1].depth),
+            "a breadth first search only goes deeper and finds better bounds: {:?}",
+            reports
+        );
+
+        // IDA* reports the states of every pass so far. The bound is close enough on the built-in
+        // puzzles that IDA* solves them without a report, so this is a larger random board
+        let game = crate::self_play::random_boards(30, 12, 1).swap_remove(29);
+        let mut reports: Vec<Progress> = Vec::new();
+        let solution = Solver::new(&game)
+            .search_with_progress(Strategy::IdaStar, &mut |progress| reports.push(*progress))
+            .expect("the random board should be solved");
+        assert!(!reports.is_empty(), "a long IDA* search should report");
+        assert!(
+            reports
+                .windows(2)
+                .all(|pair| pair[0].states_searched < pair[1].states_searched)
+                && reports
+                    .iter()
+                    .all(|report| report.states_searched <= solution.states_searched),
+            "the states searched should only grow: {:?}",
+            reports
+        );
+    }
+
+    #[test]
+    fn test_try_solve() {
+        let puzzle = crate::puzzles::get_builtin_puzzle(1).expect("puzzle 1 should exist");
+        let game = puzzle.to_game();
+        let stuck = initialise_game(
+            &[
+                String::from("red, red, red, blue"),
+                String::from("blue, blue, blue, red"),
+                String::from("green, green, green, yellow"),
+                String::from("yellow, yellow, yellow, green"),
+            ],
+            4,
+        );
+        // (board, limits, the outcome expected as solved, unsolvable or limit reached)
+        let tests = [
+            (&game, SearchLimits::default(), "solved"),
+            (
+                &game,
+                SearchLimits {
+                    max_time: Some(Duration::from_secs(60)),
+                    max_states: Some(1_000_000),
+                },
+                "solved",
+            ),
+            (&stuck, SearchLimits::default(), "unsolvable"),
+            (
+                &game,
+                SearchLimits {
+                    max_states: Some(1),
+                    ..SearchLimits::default()
+                },
+                "limit reached",
+            ),
+            (
+                &game,
+                SearchLimits {
+                    max_time: Some(Duration::ZERO),
+                    ..SearchLimits::default()
+                },
+                "limit reached",
+            ),
+        ];
+        for (board, limits, expected) in tests {
+            let outcome = match Solver::new(board).with_limits(limits).try_solve() {
+                SolveOutcome::Solved(moves) => {
+                    let mut replay = board.clone();
+                    for a_move in moves.iter() {
+                        assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                        replay.make_move(a_move);
+                    }
+                    assert!(
+                        replay.is_game_complete(),
+                        "solution does not complete the game"
+                    );
+                    "solved"
+                }
+                SolveOutcome::Unsolvable(_) => "unsolvable",
+                SolveOutcome::LimitReached => "limit reached",
+            };
+            assert_eq!(outcome, expected, "wrong outcome with {:?}", limits);
+        }
+        let limited = Solver::new(&game).with_limits(SearchLimits {
+            max_states: Some(1),
+            ..SearchLimits::default()
+        });
+        for strategy in Strategy::ALL {
+            assert!(
+                limited.search_with(strategy).is_none(),
+                "{} should stop at the limit on states",
+                strategy
+            );
+        }
+    }
+
+    #[test]
+    fn test_why_unsolvable() {
+        // (tubes, the reason the board cannot be completed, if it cannot)
+        let tests = [
+            (
+                vec![
+                    "red, red, red, blue",
+                    "red, blue, blue, blue",
+                    "empty",
+                    "empty",
+                ],
+                None,
+            ),
+            (
+                vec![
+                    "red, red, red, red",
+                    "blue, blue, blue, blue",
+                    "empty",
+                    "empty",
+                ],
+                None,
+            ),
+            (
+                vec![
+                    "red, red, red, blue",
+                    "blue, blue, blue, blue",
+                    "empty",
+                    "empty",
+                ],
+                Some(UnsolvableReason::ColourCount {
+                    colour: String::from("blue"),
+                    count: 5,
+                }),
+            ),
+            (
+                vec![
+                    "red, red, red, blue",
+                    "blue, blue, blue, red",
+                    "green, green, green, yellow",
+                    "yellow, yellow, yellow, green",
+                ],
+                Some(UnsolvableReason::NoSpace),
+            ),
+            (
+                vec![
+                    "red, red, red, blue",
+                    "green, green, green, blue",
+                    "empty, green, red, blue",
+                    "empty, empty, empty, blue",
+                ],
+                Some(UnsolvableReason::Exhausted),
+            ),
+        ];
+        for (tubes, expected) in tests {
+            let game = initialise_game(&tubes, 4);
+            let solver = Solver::new(&game).with_limits(SearchLimits {
+                max_states: Some(100_000),
+                ..SearchLimits::default()
+            });
+            let reason = match solver.try_solve() {
+                SolveOutcome::Unsolvable(reason) => Some(reason),
+                _ => None,
+            };
+            assert_eq!(reason, expected, "wrong reason for {:?}", tubes);
+            if expected != Some(UnsolvableReason::Exhausted) {
+                assert_eq!(
+                    solver.why_unsolvable(),
+                    expected,
+                    "the reason for {:?} should be found without searching",
+                    tubes
+                );
+            }
+        }
+        let limited = Solver::new(&initialise_game(
+            &[
+                String::from("red, red, red, blue"),
+                String::from("blue, blue, blue, red"),
+                String::from("green, green, green, yellow"),
+                String::from("yellow, yellow, yellow, green"),
+            ],
+            4,
+        ))
+        .with_limits(SearchLimits {
+            max_states: Some(0),
+            ..SearchLimits::default()
+        });
+        assert!(
+            matches!(
+                limited.try_solve(),
+                SolveOutcome::Unsolvable(UnsolvableReason::NoSpace)
+            ),
+            "a board with no space should be unsolvable whatever the limits"
+        );
+    }
+
+    #[test]
+    fn test_search_with_stats() {
+        let game = crate::puzzles::get_builtin_puzzle(1)
+            .expect("puzzle 1 should exist")
+            .to_game();
+        let solver = Solver::new(&game);
+        for strategy in Strategy::ALL {
+            let (solution, stats) = solver.search_with_stats(strategy);
+            let solution = solution.unwrap_or_else(|| panic!("{} should solve puzzle 1", strategy));
+            assert_eq!(
+                stats.solution_length,
+                Some(solution.moves.len()),
+                "wrong solution length for {}",
+                strategy
+            );
+            assert_eq!(
+                stats.states_expanded, solution.states_searched,
+                "wrong states expanded for {}",
+                strategy
+            );
+            assert!(
+                stats.states_generated >= stats.states_expanded && stats.branching_factor() >= 1.0,
+                "{} should find at least one state one move on from each state: {}",
+                strategy,
+                stats
+            );
+            assert!(
+                stats.peak_frontier >= 1,
+                "{} should have had states waiting: {}",
+                strategy,
+                stats
+            );
+        }
+
+        let stuck = initialise_game(
+            &[
+                String::from("red, red, red, blue"),
+                String::from("blue, blue, blue, red"),
+                String::from("green, green, green, yellow"),
+                String::from("yellow, yellow, yellow, green"),
+            ],
+            4,
+        );
+        let (solution, stats) = Solver::new(&stuck).solve_with_stats();
+        assert!(solution.is_none(), "the stuck board should not be solved");
+        assert_eq!(
+            stats,
+            SolveStats {
+                states_expanded: 1,
+                states_generated: 0,
+                peak_frontier: 1,
+                duration: stats.duration,
+                solution_length: None,
+            },
+            "wrong stats for a board without moves"
+        );
+        assert_eq!(stats.branching_factor(), 0.0, "no moves can be made");
+    }
+
+    #[test]
+    fn test_duplicates_skipped() {
+        let game = crate::puzzles::get_builtin_puzzle(1)
+            .expect("puzzle 1 should exist")
+            .to_game();
+        // Breadth first, pouring back reaches a board already seen
+        let solution = Solver::new(&game)
+            .search_with(Strategy::BreadthFirst)
+            .expect("puzzle 1 should be solved");
+        assert!(
+            solution.duplicates > 0,
+            "the search should skip the boards it had already reached"
+        );
+    }
+
+    #[test]
+    fn test_lower_bound() {
+        let num_of_tubes = 4;
+        // (tubes, rules, lower bound)
+        let tests: Vec<(Vec<String>, Ruleset, usize)> = vec![
+            (
+                vec![
+                    String::from("red, red, red, red"),
+                    String::from("blue, blue, blue, blue"),
+                ],
+                Ruleset::Standard,
+                0,
+            ),
+            (
+                vec![
+                    String::from("red, red, red, blue"),
+                    String::from("red, blue, blue, blue"),
+                ],
+                Ruleset::Standard,
+                3,
+            ),
+            (
+                vec![
+                    String::from("red, red, red, blue"),
+                    String::from("red, blue, blue, blue"),
+                ],
+                Ruleset::OneUnit,
+                5,
+            ),
+            (
+                vec![
+                    String::from("red, blue, red, blue"),
+                    String::from("blue, red, blue, red"),
+                ],
+                Ruleset::Standard,
+                6,
+            ),
+        ];
+        for test in tests {
+            let mut game = initialise_game(&test.0, num_of_tubes);
+            game.ruleset = test.1;
+            let solver = Solver::new(&game);
+            let lower_bound = solver.lower_bound();
+            assert_eq!(
+                lower_bound, test.2,
+                "wrong lower bound. Expected: {}, got: {}",
+                test.2, lower_bound
+            );
+            assert!(
+                lower_bound >= game.get_number_of_blocks() - game.colours.len(),
+                "the lower bound should be at least the blocks over the colours"
+            );
+            if let Some(solution) = solver.solve() {
+                assert!(
+                    lower_bound <= solution.len(),
+                    "lower bound {} is more than the shortest solution of {} moves",
+                    lower_bound,
+                    solution.len()
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_does_move_reduce_block_count() {
         let num_of_tubes = 4;
@@ -358,6 +2584,7 @@ mod tests {
                     tube_to: 1,
                     colour: String::from("red"),
                     quantity: 2,
+                    played_at: None,
                 },
                 true,
             ),
@@ -372,6 +2599,7 @@ mod tests {
                     tube_to: 1,
                     colour: String::from("red"),
                     quantity: 1,
+                    played_at: None,
                 },
                 false,
             ),
@@ -386,6 +2614,7 @@ mod tests {
                     tube_to: 0,
                     colour: String::from("red"),
                     quantity: 1,
+                    played_at: None,
                 },
                 true,
             ),
@@ -400,73 +2629,262 @@ mod tests {
                     tube_to: 3,
                     colour: String::from("red"),
                     quantity: 2,
+                    played_at: None,
                 },
                 false,
             ),
         ];
         for test in tests {
-            let game = initialise_game(test.0, num_of_tubes);
+            let game = initialise_game(&test.0, num_of_tubes);
             let solver = Solver::new(&game);
             let result = solver.does_move_reduce_block_count(&test.1);
             assert_eq!(result, test.2, "does move reduce block count gives incorrect return value. Expected: {}, got: {} for move: {}", test.2, result, test.1);
         }
     }
 
-    fn initialise_game(tube_strings: Vec<String>, num_of_tubes: usize) -> Game {
-        let mut game = Game::default();
-        game.init_tubes(num_of_tubes);
-        for (idx, tube_string) in tube_strings.into_iter().enumerate() {
-            game.init_tube_contents(idx, tube_string);
+    #[test]
+    fn test_hint() {
+        let num_of_tubes = 4;
+        let tests: Vec<(Vec<String>, Option<Move>)> = vec![
+            (
+                vec![
+                    String::from("blue, red, red, red"),
+                    String::from("blue, blue, blue"),
+                    String::from("red"),
+                ],
+                Some(Move {
+                    tube_from: 0,
+                    tube_to: 1,
+                    colour: String::from("blue"),
+                    quantity: 1,
+                    played_at: None,
+                }),
+            ),
+            (
+                vec![
+                    String::from("red, blue, red, blue"),
+                    String::from("blue, red, blue, red"),
+                ],
+                Some(Move {
+                    tube_from: 0,
+                    tube_to: 2,
+                    colour: String::from("red"),
+                    quantity: 1,
+                    played_at: None,
+                }),
+            ),
+            (
+                vec![
+                    String::from("red, red, red, red"),
+                    String::from("blue, blue, blue, blue"),
+                ],
+                None,
+            ),
+        ];
+        for test in tests {
+            let game = initialise_game(&test.0, num_of_tubes);
+            let solver = Solver::new(&game);
+            match (solver.hint(), test.1) {
+                (Some(hint), Some(expected)) => assert_move_eq(&hint, &expected),
+                (None, None) => {}
+                (Some(hint), None) => panic!("expected no hint, got: {}", hint),
+                (None, Some(expected)) => panic!("expected hint {}, got nothing", expected),
+            }
         }
-        game
     }
 
-    fn test_all_tubes(result: &[Tube], expected: &[Tube]) {
-        assert_eq!(
-            result.len(),
-            expected.len(),
-            "different number of tubes. Expected = {}, got = {}",
-            result.len(),
-            expected.len()
-        );
-        for (idx, expected_tube) in expected.iter().enumerate() {
-            test_tube(&result[idx], expected_tube);
+    #[test]
+    fn test_solve() {
+        let num_of_tubes = 4;
+        let tests: Vec<(Vec<String>, Option<usize>, usize)> = vec![
+            (
+                vec![
+                    String::from("red, red, red, red"),
+                    String::from("blue, blue, blue, blue"),
+                ],
+                Some(0),
+                1,
+            ),
+            (
+                vec![
+                    String::from("red, red, red, blue"),
+                    String::from("red, blue, blue, blue"),
+                ],
+                Some(3),
+                1,
+            ),
+            (
+                vec![
+                    String::from("red, blue, red, blue"),
+                    String::from("blue, red, blue, red"),
+                ],
+                Some(7),
+                1,
+            ),
+            (
+                vec![
+                    String::from("red, red, red, blue"),
+                    String::from("blue, blue, blue, red"),
+                    String::from("green, green, green, yellow"),
+                    String::from("yellow, yellow, yellow, green"),
+                ],
+                None,
+                0,
+            ),
+        ];
+        for test in tests {
+            let game = initialise_game(&test.0, num_of_tubes);
+            let solution = Solver::new(&game).search();
+            match (&solution, test.1) {
+                (Some(solution), Some(expected)) => {
+                    assert_eq!(
+                        solution.moves.len(),
+                        expected,
+                        "wrong solution length. Expected: {}, got: {}",
+                        expected,
+                        solution.moves.len()
+                    );
+                    assert_eq!(
+                        solution.difficulty(),
+                        test.2,
+                        "wrong difficulty. Expected: {}, got: {}",
+                        test.2,
+                        solution.difficulty()
+                    );
+                    assert!(
+                        !solution.difficulty_name().is_empty(),
+                        "difficulty {} has no name",
+                        test.2
+                    );
+                    let mut solved = game.clone();
+                    for a_move in solution.moves.iter() {
+                        assert!(solved.validate_move(a_move), "invalid move {}", a_move);
+                        solved.make_move(a_move);
+                    }
+                    assert!(
+                        solved.is_game_complete(),
+                        "solution does not complete the game"
+                    );
+                }
+                (None, None) => {}
+                (Some(solution), None) => panic!(
+                    "expected no solution, got one of {} moves",
+                    solution.moves.len()
+                ),
+                (None, Some(expected)) => {
+                    panic!("expected a solution of {} moves, got nothing", expected)
+                }
+            }
         }
     }
 
-    fn test_tube(test_result: &Tube, expected: &Tube) {
-        assert_eq!(
-            test_result.contents, expected.contents,
-            "tube contents are not the same. Expected = {}, got = {}",
-            expected, test_result
+    #[test]
+    fn test_solve_full_move_sequence() {
+        let two_colours = initialise_game(&["red, blue, red, blue", "blue, red, blue, red"], 4);
+        // A game part way through is solved from where it is, with only the moves still to make
+        let mut mid_game = two_colours.clone();
+        mid_game.make_move(
+            &Solver::new(&two_colours)
+                .solve()
+                .expect("board should be solvable")[0],
         );
-        assert_eq!(
-            test_result.tube_number, expected.tube_number,
-            "tube number not the same. Expected = {}, got = {}",
-            expected.tube_number, test_result.tube_number
+        let complete = initialise_game(&["red, red, red, red", "blue, blue, blue, blue"], 4);
+        let stuck = initialise_game(
+            &[
+                "red, red, red, blue",
+                "blue, blue, blue, red",
+                "green, green, green, yellow",
+                "yellow, yellow, yellow, green",
+            ],
+            4,
         );
+        // (board, length of the shortest solution)
+        let tests = [
+            (&two_colours, Some(7)),
+            (&mid_game, Some(6)),
+            (&complete, Some(0)),
+            (&stuck, None),
+        ];
+        for test in tests {
+            let moves = Solver::new(test.0).solve();
+            assert_eq!(
+                moves.as_ref().map(Vec::len),
+                test.1,
+                "wrong solution length for {:?}",
+                test.0.state_key()
+            );
+            let Some(moves) = moves else { continue };
+            // Every move is made on the board the ones before it leave, and the last completes it
+            let mut replay = test.0.clone();
+            for (idx, a_move) in moves.iter().enumerate() {
+                assert!(
+                    !replay.is_game_complete(),
+                    "the game is complete before move {}",
+                    idx + 1
+                );
+                assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+                replay.make_move(a_move);
+            }
+            assert!(
+                replay.is_game_complete(),
+                "the moves do not complete the game"
+            );
+            assert_eq!(
+                replay.current_move,
+                test.0.current_move + moves.len(),
+                "every move should be played in order"
+            );
+        }
     }
 
-    fn test_move(test_result: &Move, expected: &Move) {
-        assert_eq!(
-            test_result.tube_from, expected.tube_from,
-            "Move.tube_from different. Expected = {}, got = {}",
-            expected.tube_from, test_result.tube_from
-        );
-        assert_eq!(
-            test_result.tube_to, expected.tube_to,
-            "Move.tube_to different. Expected = {}, got = {}",
-            expected.tube_to, test_result.tube_to
-        );
-        assert_eq!(
-            test_result.colour, expected.colour,
-            "Move.colour different. Expected = {}, got = {}",
-            expected.colour, test_result.colour
+    #[test]
+    fn test_search_traced() {
+        let mut game = initialise_game(
+            &[
+                String::from("red, blue, red, blue"),
+                String::from("blue, red, blue, red"),
+            ],
+            4,
         );
+        for preset in [Preset::Balanced, Preset::Quick] {
+            game.preset = preset;
+            let solver = Solver::new(&game);
+            let mut steps = Vec::new();
+            let solution = solver
+                .search_traced(&mut |step| steps.push(*step))
+                .expect("puzzle should be solvable");
+            let depths: Vec<usize> = steps.iter().map(|step| step.depth).collect();
+            assert_eq!(
+                depths,
+                (1..=solution.moves.len()).collect::<Vec<usize>>(),
+                "every depth should be reported once with the {} preset",
+                preset
+            );
+            let last = steps.last().unwrap();
+            assert_eq!(
+                last.states_searched, solution.states_searched,
+                "the last step should count every state searched with the {} preset",
+                preset
+            );
+            assert_eq!(
+                last.best_bound, 0,
+                "the solved board has nothing left to do"
+            );
+            assert!(
+                steps
+                    .windows(2)
+                    .all(|pair| pair[0].states_searched <= pair[1].states_searched
+                        && pair[0].pruned <= pair[1].pruned),
+                "the counts should only go up with the {} preset",
+                preset
+            );
+        }
         assert_eq!(
-            test_result.quantity, expected.quantity,
-            "Move.colour different. Expected = {}, got = {}",
-            expected.quantity, test_result.quantity
+            Solver::new(&game)
+                .search_traced(&mut |_| {})
+                .map(|s| s.moves.len()),
+            Solver::new(&game).search().map(|s| s.moves.len()),
+            "tracing should not change the solution"
         );
     }
 }
@@ -0,0 +1,169 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    config::Config,
+    game::{parse_move_sequence, Game, Move},
+    render::{renderer, Renderer},
+    transpositions::TranspositionTable,
+};
+
+/// Steps through a recorded game, such as a transcript read by `analyze <file>`, for reviewing it
+/// afterwards. The board can be shown at any move of the record, with hints, and moves entered
+/// try out a what-if line from the board shown. Nothing entered changes the record.
+pub struct Spectator<R: BufRead, W: Write> {
+    stdin: R,
+    stdout: W,
+    /// The board before the first move of the record and after every move.
+    boards: Vec<Game>,
+    moves: Vec<Move>,
+    /// How many moves of the record have been made on the board shown.
+    position: usize,
+    /// A line tried out from the board at `position`, off the record.
+    what_if: Option<Game>,
+    renderer: Box<dyn Renderer>,
+    solutions: TranspositionTable,
+}
+
+impl<R: BufRead, W: Write> Spectator<R, W> {
+    pub fn new(stdin: R, stdout: W, start: Game, moves: Vec<Move>, config: &Config) -> Self {
+        let mut boards = vec![start];
+        for a_move in moves.iter() {
+            let mut next = boards[boards.len() - 1].clone();
+            next.make_move(a_move);
+            boards.push(next);
+        }
+        Spectator {
+            stdin,
+            stdout,
+            boards,
+            moves,
+            position: 0,
+            what_if: None,
+            renderer: renderer(config.render_mode, config),
+            solutions: TranspositionTable::default(),
+        }
+    }
+
+    /// Reads commands until `quit` or the end of the input.
+    pub fn run(&mut self) -> io::Result<()> {
+        writeln!(
+            self.stdout,
+            "Reviewing a game of {} moves. Enter next (or nothing), prev, goto <move>, start or end to step through it, hint for the best move, or a move to try a what-if line. quit stops.",
+            self.moves.len()
+        )?;
+        self.show()?;
+        loop {
+            write!(self.stdout, "> ")?;
+            self.stdout.flush()?;
+            let mut input = String::new();
+            if self.stdin.read_line(&mut input)? == 0 {
+                return Ok(());
+            }
+            let input = input.trim();
+            match input {
+                "" | "next" | "n" => self.go_to(self.position + 1)?,
+                "prev" | "p" => match self.position {
+                    0 => writeln!(self.stdout, "This is the start of the game")?,
+                    position => self.go_to(position - 1)?,
+                },
+                "start" => self.go_to(0)?,
+                "end" => self.go_to(self.moves.len())?,
+                "hint" => self.hint()?,
+                "back" => match self.what_if.take() {
+                    Some(_) => self.show()?,
+                    None => writeln!(self.stdout, "There is no what-if line to go back from")?,
+                },
+                "quit" => return Ok(()),
+                command if command.starts_with("goto") => {
+                    match command["goto".len()..].trim().parse::<usize>() {
+                        Ok(position) => self.go_to(position)?,
+                        Err(_) => writeln!(self.stdout, "Usage: goto <move>")?,
+                    }
+                }
+                input => self.try_moves(input)?,
+            }
+        }
+    }
+
+    /// Shows the board after `position` moves of the record, leaving any what-if line.
+    fn go_to(&mut self, position: usize) -> io::Result<()> {
+        if position > self.moves.len() {
+            return writeln!(self.stdout, "The game ends after move {}", self.moves.len());
+        }
+        self.position = position;
+        self.what_if = None;
+        self.show()
+    }
+
+    fn show(&mut self) -> io::Result<()> {
+        match (&self.what_if, self.position) {
+            (Some(_), position) => writeln!(
+                self.stdout,
+                "What if, from move {} (enter back to return to the game):",
+                position
+            )?,
+            (None, 0) => writeln!(self.stdout, "The start of the game:")?,
+            (None, position) => writeln!(
+                self.stdout,
+                "Move {} of {}: {}",
+                position,
+                self.moves.len(),
+                self.moves[position - 1]
+            )?,
+        }
+        let board = self.renderer.render(self.board(), None);
+        writeln!(self.stdout, "{}", board)
+    }
+
+    /// The board shown: the what-if line if one is being tried, or else the record.
+    fn board(&self) -> &Game {
+        self.what_if.as_ref().unwrap_or(&self.boards[self.position])
+    }
+
+    /// Suggests the first move of a shortest solution from the board shown, and on the record,
+    /// the move the game went on with.
+    fn hint(&mut self) -> io::Result<()> {
+        let board = self.board().clone();
+        if board.is_game_complete() {
+            return writeln!(self.stdout, "The game is complete here");
+        }
+        match self.solutions.solve(&board) {
+            Some(solution) => writeln!(
+                self.stdout,
+                "Hint: {} ({} moves to finish)",
+                solution.moves[0],
+                solution.moves.len()
+            )?,
+            None => writeln!(self.stdout, "No solution was found from here")?,
+        }
+        if self.what_if.is_none() && self.position < self.moves.len() {
+            writeln!(
+                self.stdout,
+                "The game went on with {}",
+                self.moves[self.position]
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Makes moves on a what-if line from the board shown. Like during play, several moves can be
+    /// entered together, and if any of them cannot be made none of them are.
+    fn try_moves(&mut self, input: &str) -> io::Result<()> {
+        let requests = match parse_move_sequence(input) {
+            Ok(requests) => requests,
+            Err(err) => return writeln!(self.stdout, "Unable to parse {}", err),
+        };
+        let mut board = self.board().clone();
+        let moves = match board.resolve_sequence(&requests) {
+            Ok(moves) => moves,
+            Err((idx, err)) => {
+                return writeln!(self.stdout, "Move {} is invalid: {}", idx + 1, err)
+            }
+        };
+        for a_move in moves.iter() {
+            board.make_move(a_move);
+        }
+        self.what_if = Some(board);
+        self.show()
+    }
+}
@@ -0,0 +1,431 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::config::home_dir;
+
+const STATS_FILE_NAME: &str = "stats";
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// A completed puzzle, as recorded in the lifetime statistics.
+pub struct SolveRecord {
+    pub moves: usize,
+    /// The length of the shortest solution, if it could be found.
+    pub optimal_moves: Option<usize>,
+    pub hints_used: usize,
+    /// The number of tubes on the board.
+    pub tubes: usize,
+    pub duration: Duration,
+    /// The day the puzzle was solved on, counted in days since the Unix epoch.
+    pub day: u64,
+}
+
+/// The best a puzzle has been solved in. The fewest moves and the fastest time are kept apart, as
+/// they need not come from the same solve.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PersonalBest {
+    pub moves: usize,
+    pub time: Duration,
+}
+
+impl Display for PersonalBest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} moves, {}", self.moves, format_duration(self.time))
+    }
+}
+
+/// Statistics kept across every game a user has played, stored as `key = value` lines alongside
+/// the settings.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct LifetimeStats {
+    pub puzzles_solved: usize,
+    /// How many of the solved puzzles had a known shortest solution to compare against.
+    pub solves_compared: usize,
+    pub moves_over_optimal: usize,
+    pub fastest_solve: Option<Duration>,
+    /// The number of consecutive days, ending on `last_solve_day`, with at least one solve.
+    pub current_streak: usize,
+    pub best_streak: usize,
+    pub last_solve_day: Option<u64>,
+    /// Blitz mode puzzles that were not completed before the time ran out.
+    pub blitz_losses: usize,
+    /// Puzzles ended on a board with no legal moves left.
+    pub stuck_losses: usize,
+    /// The best solve of every puzzle, by the fingerprint of its starting board. Stored as
+    /// `best.<fingerprint> = <moves> <seconds>` lines.
+    pub personal_bests: HashMap<String, PersonalBest>,
+}
+
+impl LifetimeStats {
+    pub fn parse(contents: &str) -> Result<LifetimeStats, String> {
+        let mut stats = LifetimeStats::default();
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("Line {}: expected 'key = value'", line_num + 1)),
+            };
+            if let Some(fingerprint) = key.strip_prefix("best.") {
+                let best = parse_personal_best(value)
+                    .map_err(|err| format!("Line {}: {}", line_num + 1, err))?;
+                stats.personal_bests.insert(fingerprint.to_string(), best);
+                continue;
+            }
+            let number = match value.parse::<u64>() {
+                Ok(number) => number,
+                Err(_) => {
+                    return Err(format!(
+                        "Line {}: unable to parse {} to a number",
+                        line_num + 1,
+                        value
+                    ))
+                }
+            };
+            match key {
+                "puzzles_solved" => stats.puzzles_solved = number as usize,
+                "solves_compared" => stats.solves_compared = number as usize,
+                "moves_over_optimal" => stats.moves_over_optimal = number as usize,
+                "fastest_solve" => stats.fastest_solve = Some(Duration::from_secs(number)),
+                "current_streak" => stats.current_streak = number as usize,
+                "best_streak" => stats.best_streak = number as usize,
+                "last_solve_day" => stats.last_solve_day = Some(number),
+                "blitz_losses" => stats.blitz_losses = number as usize,
+                "stuck_losses" => stats.stuck_losses = number as usize,
+                _ => {
+                    return Err(format!(
+                        "Line {}: unknown statistic '{}'",
+                        line_num + 1,
+                        key
+                    ))
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Loads the user's statistics. A missing statistics file means nothing has been solved yet.
+    pub fn load() -> Result<LifetimeStats, String> {
+        let path = match home_dir() {
+            Some(dir) => dir.join(STATS_FILE_NAME),
+            None => return Ok(LifetimeStats::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => LifetimeStats::parse(&contents)
+                .map_err(|err| format!("Unable to read {}: {}", path.display(), err)),
+            Err(_) => Ok(LifetimeStats::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let dir = match home_dir() {
+            Some(dir) => dir,
+            None => return Err("Unable to find a home directory to save statistics in".to_string()),
+        };
+        let path = dir.join(STATS_FILE_NAME);
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, self.to_string()))
+            .map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+    }
+
+    pub fn record_solve(&mut self, solve: &SolveRecord) {
+        self.puzzles_solved += 1;
+        if let Some(optimal_moves) = solve.optimal_moves {
+            self.solves_compared += 1;
+            self.moves_over_optimal += solve.moves.saturating_sub(optimal_moves);
+        }
+        if self
+            .fastest_solve
+            .is_none_or(|fastest| solve.duration < fastest)
+        {
+            self.fastest_solve = Some(solve.duration);
+        }
+        self.current_streak = match self.last_solve_day {
+            Some(last) if last == solve.day => self.current_streak,
+            Some(last) if last + 1 == solve.day => self.current_streak + 1,
+            _ => 1,
+        };
+        self.best_streak = self.best_streak.max(self.current_streak);
+        self.last_solve_day = Some(solve.day);
+    }
+
+    /// Keeps the moves and time of a solve of the puzzle with the given fingerprint if either is
+    /// better than before. Returns the personal best from before the solve, if the puzzle had been
+    /// solved before.
+    pub fn record_personal_best(
+        &mut self,
+        fingerprint: &str,
+        moves: usize,
+        time: Duration,
+    ) -> Option<PersonalBest> {
+        let previous = self.personal_bests.get(fingerprint).copied();
+        let best = match previous {
+            Some(best) => PersonalBest {
+                moves: best.moves.min(moves),
+                time: best.time.min(time),
+            },
+            None => PersonalBest { moves, time },
+        };
+        self.personal_bests.insert(fingerprint.to_string(), best);
+        previous
+    }
+
+    pub fn record_blitz_loss(&mut self) {
+        self.blitz_losses += 1;
+    }
+
+    pub fn record_stuck_loss(&mut self) {
+        self.stuck_losses += 1;
+    }
+
+    pub fn average_moves_over_optimal(&self) -> Option<f64> {
+        match self.solves_compared {
+            0 => None,
+            solves => Some(self.moves_over_optimal as f64 / solves as f64),
+        }
+    }
+
+    /// A summary of the statistics for the `stats --lifetime` command.
+    pub fn summary(&self) -> String {
+        let average = match self.average_moves_over_optimal() {
+            Some(average) => format!("{:.1}", average),
+            None => String::from("-"),
+        };
+        let fastest = match self.fastest_solve {
+            Some(fastest) => format_duration(fastest),
+            None => String::from("-"),
+        };
+        format!(
+            "Puzzles solved: {}\nAverage moves over optimal: {}\nFastest solve: {}\nCurrent streak: {}\nBest streak: {}\nBlitz puzzles lost: {}\nPuzzles lost when stuck: {}\n",
+            self.puzzles_solved,
+            average,
+            fastest,
+            plural_days(self.current_streak),
+            plural_days(self.best_streak),
+            self.blitz_losses,
+            self.stuck_losses
+        )
+    }
+}
+
+impl Display for LifetimeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut out = format!(
+            "puzzles_solved = {}\nsolves_compared = {}\nmoves_over_optimal = {}\ncurrent_streak = {}\nbest_streak = {}\nblitz_losses = {}\nstuck_losses = {}\n",
+            self.puzzles_solved,
+            self.solves_compared,
+            self.moves_over_optimal,
+            self.current_streak,
+            self.best_streak,
+            self.blitz_losses,
+            self.stuck_losses
+        );
+        if let Some(fastest) = self.fastest_solve {
+            out.push_str(format!("fastest_solve = {}\n", fastest.as_secs()).as_str());
+        }
+        if let Some(day) = self.last_solve_day {
+            out.push_str(format!("last_solve_day = {}\n", day).as_str());
+        }
+        let mut fingerprints: Vec<&String> = self.personal_bests.keys().collect();
+        fingerprints.sort();
+        for fingerprint in fingerprints {
+            let best = &self.personal_bests[fingerprint];
+            out.push_str(
+                format!(
+                    "best.{} = {} {}\n",
+                    fingerprint,
+                    best.moves,
+                    best.time.as_secs()
+                )
+                .as_str(),
+            );
+        }
+        write!(f, "{}", out)
+    }
+}
+
+fn parse_personal_best(value: &str) -> Result<PersonalBest, String> {
+    let best = value.split_once(' ').and_then(|(moves, seconds)| {
+        Some(PersonalBest {
+            moves: moves.trim().parse().ok()?,
+            time: Duration::from_secs(seconds.trim().parse().ok()?),
+        })
+    });
+    best.ok_or_else(|| format!("unable to parse {} to moves and seconds", value))
+}
+
+/// Today, counted in days since the Unix epoch.
+pub fn current_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or(0)
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        _ => format!("{}m {:02}s", seconds / 60, seconds % 60),
+    }
+}
+
+fn plural_days(days: usize) -> String {
+    match days {
+        1 => String::from("1 day"),
+        _ => format!("{} days", days),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_solve() {
+        // (moves, optimal moves, seconds taken, day), then the expected solves compared, moves
+        // over optimal, fastest solve in seconds, current streak and best streak
+        let tests = [
+            (12, Some(10), 90, 100, [1, 2, 90, 1, 1]),
+            (8, Some(8), 120, 100, [2, 2, 90, 1, 1]),
+            (20, None, 45, 101, [2, 2, 45, 2, 2]),
+            (15, Some(12), 300, 102, [3, 5, 45, 3, 3]),
+            (10, Some(10), 60, 110, [4, 5, 45, 1, 3]),
+        ];
+        let mut stats = LifetimeStats::default();
+        for (idx, test) in tests.iter().enumerate() {
+            stats.record_solve(&SolveRecord {
+                moves: test.0,
+                optimal_moves: test.1,
+                hints_used: 0,
+                tubes: 4,
+                duration: Duration::from_secs(test.2),
+                day: test.3,
+            });
+            let result = [
+                stats.solves_compared as u64,
+                stats.moves_over_optimal as u64,
+                stats.fastest_solve.unwrap().as_secs(),
+                stats.current_streak as u64,
+                stats.best_streak as u64,
+            ];
+            assert_eq!(
+                stats.puzzles_solved,
+                idx + 1,
+                "wrong number of puzzles solved"
+            );
+            assert_eq!(
+                result, test.4,
+                "wrong statistics after solve {}. Expected: {:?}, got: {:?}",
+                idx, test.4, result
+            );
+        }
+        assert_eq!(stats.average_moves_over_optimal(), Some(1.25));
+    }
+
+    #[test]
+    fn test_record_personal_best() {
+        // (moves, seconds taken), then the expected previous best and the best after the solve
+        let tests = [
+            (20, 90, None, (20, 90)),
+            (18, 120, Some((20, 90)), (18, 90)),
+            (25, 60, Some((18, 90)), (18, 60)),
+            (18, 60, Some((18, 60)), (18, 60)),
+        ];
+        let mut stats = LifetimeStats::default();
+        let best = |(moves, seconds): (usize, u64)| PersonalBest {
+            moves,
+            time: Duration::from_secs(seconds),
+        };
+        for (idx, test) in tests.iter().enumerate() {
+            let previous =
+                stats.record_personal_best("fingerprint", test.0, Duration::from_secs(test.1));
+            assert_eq!(
+                previous,
+                test.2.map(best),
+                "wrong previous best for solve {}",
+                idx
+            );
+            assert_eq!(
+                stats.personal_bests.get("fingerprint"),
+                Some(&best(test.3)),
+                "wrong best after solve {}",
+                idx
+            );
+        }
+        assert_eq!(
+            stats.personal_bests["fingerprint"].to_string(),
+            "18 moves, 1m 00s"
+        );
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let mut stats = LifetimeStats::default();
+        assert_eq!(
+            LifetimeStats::parse(&stats.to_string()),
+            Ok(stats.clone()),
+            "empty statistics did not round trip"
+        );
+        stats.record_solve(&SolveRecord {
+            moves: 14,
+            optimal_moves: Some(11),
+            hints_used: 1,
+            tubes: 6,
+            duration: Duration::from_secs(75),
+            day: 19000,
+        });
+        stats.record_blitz_loss();
+        stats.record_stuck_loss();
+        stats.record_personal_best("0123456789abcdef", 14, Duration::from_secs(75));
+        assert_eq!(
+            LifetimeStats::parse(&stats.to_string()),
+            Ok(stats.clone()),
+            "statistics did not round trip"
+        );
+
+        let errors = [
+            "puzzles_solved 3",
+            "puzzles_solved = three",
+            "hints_used = 2",
+            "best.0123456789abcdef = 14",
+            "best.0123456789abcdef = 14 fast",
+        ];
+        for contents in errors {
+            assert!(
+                LifetimeStats::parse(contents).is_err(),
+                "expected an error parsing {:?}",
+                contents
+            );
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        assert_eq!(
+            LifetimeStats::default().summary(),
+            "Puzzles solved: 0\nAverage moves over optimal: -\nFastest solve: -\nCurrent streak: 0 days\nBest streak: 0 days\nBlitz puzzles lost: 0\nPuzzles lost when stuck: 0\n"
+        );
+        let stats = LifetimeStats {
+            puzzles_solved: 3,
+            solves_compared: 2,
+            moves_over_optimal: 3,
+            fastest_solve: Some(Duration::from_secs(65)),
+            current_streak: 1,
+            best_streak: 4,
+            last_solve_day: Some(19000),
+            blitz_losses: 2,
+            stuck_losses: 1,
+            personal_bests: HashMap::new(),
+        };
+        assert_eq!(
+            stats.summary(),
+            "Puzzles solved: 3\nAverage moves over optimal: 1.5\nFastest solve: 1m 05s\nCurrent streak: 1 day\nBest streak: 4 days\nBlitz puzzles lost: 2\nPuzzles lost when stuck: 1\n"
+        );
+    }
+}
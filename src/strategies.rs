@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use crate::{
+    analyze::format_table,
+    puzzles::Puzzle,
+    solver::{Preset, SolveStats, Solver, Strategy},
+};
+
+/// How one strategy did on one puzzle.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StrategyRun {
+    pub puzzle: String,
+    pub strategy: Strategy,
+    /// How the search went, with the length of the solution found if one was found within the
+    /// search limit.
+    pub stats: SolveStats,
+}
+
+/// Solves every puzzle with every strategy, within the budgets of `preset`, so that a change to
+/// the search can be measured against the one before it. The runs are in the order of the
+/// puzzles, and then of the strategies.
+pub fn compare_strategies(
+    puzzles: &[Puzzle],
+    strategies: &[Strategy],
+    preset: Preset,
+) -> Vec<StrategyRun> {
+    let mut runs = Vec::with_capacity(puzzles.len() * strategies.len());
+    for puzzle in puzzles {
+        let mut game = puzzle.to_game();
+        game.preset = preset;
+        let solver = Solver::new(&game);
+        for strategy in strategies {
+            let (_, stats) = solver.search_with_stats(*strategy);
+            runs.push(StrategyRun {
+                puzzle: format!("[{}] {}", puzzle.number, puzzle.name),
+                strategy: *strategy,
+                stats,
+            });
+        }
+    }
+    runs
+}
+
+/// One row per run, followed by the totals of every strategy. The moves of a puzzle that was not
+/// solved are shown as `-`, and only the solved puzzles count towards the totals of moves and
+/// states.
+pub fn strategy_table(runs: &[StrategyRun], strategies: &[Strategy]) -> String {
+    let mut rows = vec![[
+        "Puzzle",
+        "Strategy",
+        "Moves",
+        "States",
+        "Peak frontier",
+        "Branching",
+        "Time (ms)",
+    ]
+    .map(String::from)
+    .to_vec()];
+    for run in runs {
+        rows.push(vec![
+            run.puzzle.clone(),
+            run.strategy.to_string(),
+            match run.stats.solution_length {
+                Some(moves) => moves.to_string(),
+                None => String::from("-"),
+            },
+            run.stats.states_expanded.to_string(),
+            run.stats.peak_frontier.to_string(),
+            format!("{:.2}", run.stats.branching_factor()),
+            milliseconds(run.stats.duration),
+        ]);
+    }
+    let mut out = format_table(&rows, 2);
+    for strategy in strategies {
+        let runs: Vec<&StrategyRun> = runs
+            .iter()
+            .filter(|run| run.strategy == *strategy)
+            .collect();
+        let solved: Vec<&&StrategyRun> = runs
+            .iter()
+            .filter(|run| run.stats.solution_length.is_some())
+            .collect();
+        out.push_str(
+            format!(
+                "{}: solved {} of {}, {} moves and {} states in total, {} ms\n",
+                strategy,
+                solved.len(),
+                runs.len(),
+                solved
+                    .iter()
+                    .filter_map(|run| run.stats.solution_length)
+                    .sum::<usize>(),
+                solved
+                    .iter()
+                    .map(|run| run.stats.states_expanded)
+                    .sum::<usize>(),
+                milliseconds(runs.iter().map(|run| run.stats.duration).sum())
+            )
+            .as_str(),
+        );
+    }
+    out
+}
+
+fn milliseconds(time: Duration) -> String {
+    format!("{:.1}", time.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::puzzles::parse_catalogue;
+
+    use super::*;
+
+    #[test]
+    fn test_compare_strategies() {
+        let puzzles =
+            parse_catalogue("[1] Good\nred, red, red, blue\nred, blue, blue, blue\nempty\nempty\n")
+                .expect("catalogue should parse");
+        let runs = compare_strategies(&puzzles, &Strategy::ALL, Preset::default());
+        let strategies: Vec<Strategy> = runs.iter().map(|run| run.strategy).collect();
+        assert_eq!(strategies, Strategy::ALL, "wrong strategies run");
+        assert_eq!(
+            runs[0].stats.solution_length,
+            Some(3),
+            "breadth first should find the shortest"
+        );
+        assert!(
+            runs[1]
+                .stats
+                .solution_length
+                .is_some_and(|moves| moves >= 3),
+            "depth first should find a solution"
+        );
+        assert!(
+            runs.iter().all(|run| run.puzzle == "[1] Good"),
+            "wrong puzzle name"
+        );
+    }
+
+    #[test]
+    fn test_strategy_table() {
+        let runs = [
+            StrategyRun {
+                puzzle: String::from("[1] Good"),
+                strategy: Strategy::BreadthFirst,
+                stats: SolveStats {
+                    states_expanded: 12,
+                    states_generated: 30,
+                    peak_frontier: 9,
+                    duration: Duration::from_micros(1500),
+                    solution_length: Some(3),
+                },
+            },
+            StrategyRun {
+                puzzle: String::from("[1] Good"),
+                strategy: Strategy::DepthFirst,
+                stats: SolveStats {
+                    states_expanded: 40,
+                    states_generated: 80,
+                    peak_frontier: 25,
+                    duration: Duration::from_millis(20),
+                    solution_length: None,
+                },
+            },
+        ];
+        assert_eq!(
+            strategy_table(&runs, &[Strategy::BreadthFirst, Strategy::DepthFirst]),
+            "Puzzle    Strategy       Moves  States  Peak frontier  Branching  Time (ms)\n\
+             [1] Good  breadth-first      3      12              9       2.50        1.5\n\
+             [1] Good  depth-first        -      40             25       2.00       20.0\n\
+             breadth-first: solved 1 of 1, 3 moves and 12 states in total, 1.5 ms\n\
+             depth-first: solved 0 of 1, 0 moves and 0 states in total, 20.0 ms\n"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+//! Boards and assertions comparing tubes and moves, for tests of the crate and of programs using
+//! it. Every difference is listed in the panic message, rather than only the first field that
+//! differs.
+//! Other crates get the module with the `testing` feature.
+
+use crate::{
+    game::{Game, Move},
+    tube::Tube,
+};
+
+/// A board of `num_of_tubes` tubes, the first of them filled from `tube_strings` in the format
+/// `Game::init_tube_contents` reads, top segment first, and the rest left empty.
+pub fn initialise_game<S: AsRef<str>>(tube_strings: &[S], num_of_tubes: usize) -> Game {
+    let mut game = Game::default();
+    game.init_tubes(num_of_tubes);
+    for (idx, tube_string) in tube_strings.iter().enumerate() {
+        game.init_tube_contents(idx, tube_string.as_ref().to_string());
+    }
+    game
+}
+
+/// Asserts that two lists of tubes are the same, listing every tube that differs.
+pub fn assert_tubes_eq(result: &[Tube], expected: &[Tube]) {
+    let mut differences = Vec::new();
+    if result.len() != expected.len() {
+        differences.push(format!(
+            "expected {} tubes, got {}",
+            expected.len(),
+            result.len()
+        ));
+    }
+    for (idx, (result, expected)) in result.iter().zip(expected).enumerate() {
+        differences.extend(
+            tube_differences(result, expected)
+                .into_iter()
+                .map(|difference| format!("tube {}: {}", idx + 1, difference)),
+        );
+    }
+    if !differences.is_empty() {
+        panic!("tubes are not the same:\n  {}", differences.join("\n  "));
+    }
+}
+
+/// Asserts that two tubes are the same, listing every segment that differs.
+pub fn assert_tube_eq(result: &Tube, expected: &Tube) {
+    let differences = tube_differences(result, expected);
+    if !differences.is_empty() {
+        panic!(
+            "tube is not the same. Expected = {}, got = {}:\n  {}",
+            expected,
+            result,
+            differences.join("\n  ")
+        );
+    }
+}
+
+/// Asserts that two moves are the same, listing every field that differs.
+pub fn assert_move_eq(result: &Move, expected: &Move) {
+    let mut differences = Vec::new();
+    if result.tube_from != expected.tube_from {
+        differences.push(format!(
+            "tube_from: expected {}, got {}",
+            expected.tube_from, result.tube_from
+        ));
+    }
+    if result.tube_to != expected.tube_to {
+        differences.push(format!(
+            "tube_to: expected {}, got {}",
+            expected.tube_to, result.tube_to
+        ));
+    }
+    if result.colour != expected.colour {
+        differences.push(format!(
+            "colour: expected {}, got {}",
+            expected.colour, result.colour
+        ));
+    }
+    if result.quantity != expected.quantity {
+        differences.push(format!(
+            "quantity: expected {}, got {}",
+            expected.quantity, result.quantity
+        ));
+    }
+    if !differences.is_empty() {
+        panic!(
+            "move is not the same. Expected = {}, got = {}:\n  {}",
+            expected,
+            result,
+            differences.join("\n  ")
+        );
+    }
+}
+
+fn tube_differences(result: &Tube, expected: &Tube) -> Vec<String> {
+    let segment = |segment: Option<&Option<String>>| match segment {
+        Some(Some(colour)) => colour.clone(),
+        Some(None) => String::from("empty"),
+        None => String::from("nothing"),
+    };
+    let mut differences = Vec::new();
+    for idx in 0..result.contents.len().max(expected.contents.len()) {
+        let (result, expected) = (result.contents.get(idx), expected.contents.get(idx));
+        if result != expected {
+            differences.push(format!(
+                "segment {} from the top: expected {}, got {}",
+                idx + 1,
+                segment(expected),
+                segment(result)
+            ));
+        }
+    }
+    if result.tube_number != expected.tube_number {
+        differences.push(format!(
+            "tube number: expected {}, got {}",
+            expected.tube_number, result.tube_number
+        ));
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic;
+
+    use super::*;
+
+    type Assertion = Box<dyn FnOnce() + panic::UnwindSafe>;
+
+    fn panic_message(assertion: impl FnOnce() + panic::UnwindSafe) -> Option<String> {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = panic::catch_unwind(assertion);
+        panic::set_hook(hook);
+        result.err().map(|err| match err.downcast::<String>() {
+            Ok(message) => *message,
+            Err(_) => String::new(),
+        })
+    }
+
+    fn tube(contents: &str, tube_number: usize) -> Tube {
+        Tube::from_string(contents.to_string(), tube_number)
+    }
+
+    fn a_move(tube_to: usize, quantity: usize) -> Move {
+        Move {
+            tube_from: 0,
+            tube_to,
+            colour: String::from("red"),
+            quantity,
+            played_at: None,
+        }
+    }
+
+    #[test]
+    fn test_assertions() {
+        assert_eq!(
+            panic_message(|| assert_tube_eq(&tube("red, blue", 0), &tube("red, blue", 0))),
+            None,
+            "the same tubes should pass"
+        );
+        assert_eq!(
+            panic_message(|| assert_move_eq(&a_move(2, 1), &a_move(2, 1))),
+            None,
+            "the same moves should pass"
+        );
+        // (assertion, text expected in its panic message)
+        let tests: Vec<(Assertion, &str)> = vec![
+            (
+                Box::new(|| assert_tube_eq(&tube("red, blue", 0), &tube("green, blue", 0))),
+                "segment 3 from the top: expected green, got red",
+            ),
+            (
+                Box::new(|| assert_tube_eq(&tube("red", 1), &tube("red", 0))),
+                "tube number: expected 0, got 1",
+            ),
+            (
+                Box::new(|| {
+                    assert_tubes_eq(
+                        &[tube("red", 0), tube("blue", 1)],
+                        &[tube("red", 0), tube("green", 1)],
+                    )
+                }),
+                "tube 2: segment 4 from the top: expected green, got blue",
+            ),
+            (
+                Box::new(|| assert_tubes_eq(&[tube("red", 0)], &[])),
+                "expected 0 tubes, got 1",
+            ),
+            (
+                Box::new(|| assert_move_eq(&a_move(2, 1), &a_move(3, 2))),
+                "tube_to: expected 3, got 2\n  quantity: expected 2, got 1",
+            ),
+        ];
+        for (assertion, expected) in tests {
+            let message = panic_message(assertion);
+            assert!(
+                message
+                    .as_deref()
+                    .is_some_and(|message| message.contains(expected)),
+                "expected {:?} in the panic message, got {:?}",
+                expected,
+                message
+            );
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::{collections::HashMap, fmt::Display, fs, path::Path};
+
+use crate::{
+    game::{Game, Move},
+    known_puzzles::{fingerprint, parse_stored_move, positions, stored_move},
+    solver::{Solution, Solver},
+};
+
+/// A board on a shortest solution found before, with how far it was from being complete.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Transposition {
+    pub distance: usize,
+    /// The first move of a shortest solution from the board, as `(tube_from, tube_to, quantity)`
+    /// against the tubes in fingerprint order.
+    pub best: (usize, usize, usize),
+}
+
+/// What the solver has learned from earlier searches: every board along the shortest solutions it
+/// found, looked up by fingerprint. Boards reached again, from the same puzzle or a similar one,
+/// are finished from the table rather than searched. Stored as `fingerprint = distance: move`
+/// lines in a file of the user's choosing.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct TranspositionTable {
+    pub boards: HashMap<String, Transposition>,
+}
+
+impl TranspositionTable {
+    pub fn parse(contents: &str) -> Result<TranspositionTable, String> {
+        let mut table = TranspositionTable::default();
+        for (line_num, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(format!("Line {}: expected 'key = value'", line_num + 1)),
+            };
+            let transposition = parse_transposition(value)
+                .map_err(|err| format!("Line {}: {}", line_num + 1, err))?;
+            table.boards.insert(key.to_string(), transposition);
+        }
+        Ok(table)
+    }
+
+    /// Loads the table from a file. A missing file just means nothing has been learned yet.
+    pub fn load(path: &Path) -> Result<TranspositionTable, String> {
+        match fs::read_to_string(path) {
+            Ok(contents) => TranspositionTable::parse(&contents)
+                .map_err(|err| format!("Unable to read {}: {}", path.display(), err)),
+            Err(_) => Ok(TranspositionTable::default()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_string())
+            .map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+    }
+
+    /// Adds every board along a shortest solution from the game. A board already in the table
+    /// keeps whichever distance is shorter.
+    pub fn insert(&mut self, game: &Game, moves: &[Move]) {
+        let mut state = game.clone();
+        for (idx, a_move) in moves.iter().enumerate() {
+            let (key, order) = fingerprint(&state);
+            let position = positions(&order);
+            let transposition = Transposition {
+                distance: moves.len() - idx,
+                best: (
+                    position[a_move.tube_from],
+                    position[a_move.tube_to],
+                    a_move.quantity,
+                ),
+            };
+            match self.boards.get(&key) {
+                Some(known) if known.distance <= transposition.distance => {}
+                _ => {
+                    self.boards.insert(key, transposition);
+                }
+            }
+            state.make_move(a_move);
+        }
+    }
+
+    /// The first move of a shortest solution from the game, if its board is in the table.
+    pub fn best_move(&self, game: &Game) -> Option<Move> {
+        let (key, order) = fingerprint(game);
+        stored_move(game, &order, &self.boards.get(&key)?.best)
+    }
+
+    /// Solves the game, following the table for as long as it knows the board and searching from
+    /// wherever it runs out. The moves taken from the table are not counted in the states
+    /// searched. Shortest solutions are added to the table.
+    pub fn solve(&mut self, game: &Game) -> Option<Solution> {
+        let mut state = game.clone();
+        let mut moves = Vec::new();
+        let mut previous: Option<usize> = None;
+        while !state.is_game_complete() {
+            let (key, order) = fingerprint(&state);
+            let a_move = match self.boards.get(&key) {
+                // The distance must come down by one every move, or the stored line is broken
+                Some(known) if previous.is_none_or(|previous| known.distance + 1 == previous) => {
+                    previous = Some(known.distance);
+                    stored_move(&state, &order, &known.best)
+                }
+                _ => None,
+            };
+            match a_move {
+                Some(a_move) => {
+                    state.make_move(&a_move);
+                    moves.push(a_move);
+                }
+                None => break,
+            }
+        }
+        let rest = match state.is_game_complete() {
+            true => Solution {
+                moves: Vec::new(),
+                states_searched: 0,
+                duplicates: 0,
+                optimal: true,
+            },
+            false => Solver::new(&state).search()?,
+        };
+        moves.extend(rest.moves);
+        let solution = Solution {
+            moves,
+            states_searched: rest.states_searched,
+            duplicates: rest.duplicates,
+            optimal: rest.optimal,
+        };
+        // Only the shortest solutions say how far a board is from being complete
+        if solution.optimal {
+            self.insert(game, &solution.moves);
+        }
+        Some(solution)
+    }
+}
+
+fn parse_transposition(value: &str) -> Result<Transposition, String> {
+    let (distance, best) = match value.split_once(':') {
+        Some((distance, best)) => (distance.trim(), best.trim()),
+        None => return Err("expected 'distance: move'".to_string()),
+    };
+    let distance = match distance.parse::<usize>() {
+        Ok(distance) if distance > 0 => distance,
+        _ => return Err(format!("unable to parse {} to a distance", distance)),
+    };
+    Ok(Transposition {
+        distance,
+        best: parse_stored_move(best)?,
+    })
+}
+
+impl Display for TranspositionTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys: Vec<&String> = self.boards.keys().collect();
+        keys.sort();
+        for key in keys {
+            let known = &self.boards[key];
+            let (from, to, quantity) = known.best;
+            writeln!(
+                f,
+                "{} = {}: {}>{}x{}",
+                key, known.distance, from, to, quantity
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::initialise_game;
+
+    #[test]
+    fn test_solve() {
+        let game = initialise_game(&["red, red, red, blue", "red, blue, blue, blue"], 4);
+        let mut table = TranspositionTable::default();
+        let solution = table.solve(&game).expect("puzzle should be solvable");
+        assert_eq!(solution.moves.len(), 3, "wrong solution length");
+        assert!(solution.states_searched > 0, "the board should be searched");
+        assert_eq!(
+            table.boards.len(),
+            3,
+            "every board along the solution should be kept"
+        );
+
+        // A board on the way, with its tubes in a different order, is finished from the table
+        let mut played = game.clone();
+        played.make_move(&solution.moves[0]);
+        let reversed: Vec<String> = played
+            .tubes
+            .iter()
+            .rev()
+            .map(|tube| tube.contents_string())
+            .collect();
+        let reversed: Vec<&str> = reversed.iter().map(String::as_str).collect();
+        let next = initialise_game(&reversed, 4);
+        let known = table.solve(&next).expect("board should be solvable");
+        assert_eq!(known.moves.len(), 2, "wrong solution length from the table");
+        assert_eq!(
+            known.states_searched, 0,
+            "a known board should not be searched"
+        );
+        assert!(known.optimal, "a solution from the table is the shortest");
+        let mut replay = next.clone();
+        for a_move in known.moves.iter() {
+            assert!(replay.validate_move(a_move), "invalid move {}", a_move);
+            replay.make_move(a_move);
+        }
+        assert!(
+            replay.is_game_complete(),
+            "table does not complete the game"
+        );
+
+        let best = table
+            .best_move(&next)
+            .expect("board should be in the table");
+        assert!(next.validate_move(&best), "invalid best move {}", best);
+        assert!(
+            table.best_move(&game).is_some(),
+            "the starting board should be in the table"
+        );
+        assert!(
+            TranspositionTable::default().best_move(&game).is_none(),
+            "an empty table knows no moves"
+        );
+
+        // A stored move that cannot be made falls back to searching
+        let mut broken = table.clone();
+        for transposition in broken.boards.values_mut() {
+            transposition.best = (0, 0, 1);
+        }
+        let searched = broken.solve(&game).expect("puzzle should be solvable");
+        assert_eq!(
+            searched.moves.len(),
+            3,
+            "wrong solution length after a broken line"
+        );
+        assert!(searched.states_searched > 0, "the board should be searched");
+
+        let reloaded = TranspositionTable::parse(&table.to_string()).expect("should parse");
+        assert_eq!(reloaded, table, "table did not round trip");
+    }
+
+    #[test]
+    fn test_parse_transposition_table() {
+        let tests = [
+            ("# learned\n0123456789abcdef = 2: 0>2x1\n", true),
+            ("0123456789abcdef = 0: 0>2x1\n", false),
+            ("0123456789abcdef = 2:\n", false),
+            ("0123456789abcdef = 2 0>2x1\n", false),
+            ("0123456789abcdef\n", false),
+            ("", true),
+        ];
+        for test in tests {
+            assert_eq!(
+                TranspositionTable::parse(test.0).is_ok(),
+                test.1,
+                "wrong result parsing {:?}",
+                test.0
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
+use itertools::Itertools;
 use std::fmt::Display;
 
-use crate::{game::Move, TUBE_SIZE};
+use crate::{colour::normalise_colour, game::Move, TUBE_SIZE};
 
 #[derive(Clone)]
 pub struct ColourPos {
@@ -18,10 +19,8 @@ pub struct Tube {
 impl Tube {
     pub fn from_string(string_colours: String, tube_number: usize) -> Tube {
         let mut colours = Vec::with_capacity(TUBE_SIZE);
-        let vec_string_colours: Vec<String> = string_colours
-            .split(',')
-            .map(|x| x.trim().to_lowercase())
-            .collect();
+        let vec_string_colours: Vec<String> =
+            string_colours.split(',').map(normalise_colour).collect();
         // Add empty cells where there is no string colour supplied
         for _ in vec_string_colours.len()..TUBE_SIZE {
             colours.push(None);
@@ -47,7 +46,7 @@ impl Tube {
             match colour {
                 None => contents.push(None),
                 Some(colour) => {
-                    let colour = colour.trim().to_lowercase();
+                    let colour = normalise_colour(colour);
                     if colour == *"empty" {
                         contents.push(None);
                     } else {
@@ -142,15 +141,12 @@ impl Tube {
 
     pub fn get_top_colour(&self) -> Option<ColourPos> {
         for (pos, colour) in self.contents.iter().enumerate() {
-            match colour {
-                Some(col) => {
-                    return Some(ColourPos {
-                        colour: col.to_string(),
-                        pos,
-                        block_size: self.get_block_size(pos, col),
-                    })
-                }
-                None => {}
+            if let Some(col) = colour {
+                return Some(ColourPos {
+                    colour: col.to_string(),
+                    pos,
+                    block_size: self.get_block_size(pos, col),
+                });
             }
         }
         None
@@ -165,6 +161,37 @@ impl Tube {
         .is_some()
     }
 
+    /// Whether a colour sits above an empty segment. Liquid settles to the bottom, so the empty
+    /// segments of a real tube are always at the top.
+    pub fn has_gaps(&self) -> bool {
+        match self.contents.iter().position(Option::is_some) {
+            Some(first) => self.contents[first..].iter().any(Option::is_none),
+            None => false,
+        }
+    }
+
+    /// Lets the colours settle to the bottom of the tube, keeping them in the same order.
+    pub fn settle(&mut self) {
+        let size = self.contents.len();
+        let mut settled: Vec<Option<String>> =
+            self.contents.drain(..).flatten().map(Some).collect();
+        while settled.len() < size {
+            settled.insert(0, None);
+        }
+        self.contents = settled;
+    }
+
+    /// The contents in the form they are entered in, top first.
+    pub fn contents_string(&self) -> String {
+        self.contents
+            .iter()
+            .map(|colour| match colour {
+                Some(colour) => colour.as_str(),
+                None => "empty",
+            })
+            .join(", ")
+    }
+
     fn get_block_size(&self, start: usize, colour: &String) -> usize {
         let mut block_size = 0;
         for idx in start..self.contents.len() {
@@ -207,6 +234,8 @@ impl Display for Tube {
 
 #[cfg(test)]
 mod tests {
+    use crate::testing::assert_tube_eq;
+
     use super::*;
 
     #[test]
@@ -286,11 +315,23 @@ mod tests {
                     tube_number: 7,
                 },
             ),
+            (
+                String::from("#FF8800, #f80, red"),
+                Tube {
+                    contents: vec![
+                        None,
+                        Some("#ff8800".to_string()),
+                        Some("#ff8800".to_string()),
+                        Some("red".to_string()),
+                    ],
+                    tube_number: 8,
+                },
+            ),
         ];
 
         for (idx, test) in string_tests.into_iter().enumerate() {
             let result = Tube::from_string(test.0, idx + 1);
-            test_tube(&result, &test.1);
+            assert_tube_eq(&result, &test.1);
         }
     }
 
@@ -341,7 +382,7 @@ mod tests {
         ];
         for (idx, test) in tests.into_iter().enumerate() {
             let result = Tube::from_string_vec(test.0, idx + 1);
-            test_tube(&result, &test.1);
+            assert_tube_eq(&result, &test.1);
         }
     }
 
@@ -414,7 +455,7 @@ mod tests {
 
     #[test]
     fn test_pour_from() {
-        let tests = vec![
+        let tests = [
             (
                 String::from("red, purple, blue, green"),
                 Move {
@@ -422,6 +463,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 Tube::from_string(String::from("purple, blue, green"), 0),
             ),
@@ -432,6 +474,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 Tube::from_string(String::from("red, blue, green"), 1),
             ),
@@ -442,6 +485,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 Tube::from_string(String::from("blue, green"), 2),
             ),
@@ -452,6 +496,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 3,
+                    played_at: None,
                 },
                 Tube::from_string(String::from(""), 3),
             ),
@@ -462,6 +507,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 Tube::from_string(String::from("empty, empty, empty, blue"), 4),
             ),
@@ -470,13 +516,13 @@ mod tests {
         for (idx, test) in tests.iter().enumerate() {
             let mut result = Tube::from_string(test.0.to_owned(), idx);
             result.pour_from(&test.1);
-            test_tube(&result, &test.2);
+            assert_tube_eq(&result, &test.2);
         }
     }
 
     #[test]
     fn test_pour_to() {
-        let tests = vec![
+        let tests = [
             (
                 String::from(""),
                 Move {
@@ -484,6 +530,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 Tube::from_string(String::from("empty, empty, empty, red"), 0),
             ),
@@ -494,6 +541,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 Tube::from_string(String::from("empty, empty, red, red"), 1),
             ),
@@ -504,6 +552,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 Tube::from_string(String::from("empty, red, blue, red"), 2),
             ),
@@ -514,6 +563,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 Tube::from_string(String::from("red, red, blue, red"), 3),
             ),
@@ -522,7 +572,7 @@ mod tests {
         for (idx, test) in tests.iter().enumerate() {
             let mut result = Tube::from_string(test.0.to_owned(), idx);
             result.pour_to(&test.1);
-            test_tube(&result, &test.2);
+            assert_tube_eq(&result, &test.2);
         }
     }
 
@@ -536,6 +586,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 true,
             ),
@@ -546,6 +597,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 false,
             ),
@@ -556,6 +608,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 false,
             ),
@@ -566,6 +619,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 true,
             ),
@@ -576,6 +630,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 true,
             ),
@@ -586,6 +641,7 @@ mod tests {
                     tube_to: 1,
                     colour: "red".to_string(),
                     quantity: 4,
+                    played_at: None,
                 },
                 true,
             ),
@@ -612,6 +668,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 true,
             ),
@@ -622,6 +679,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 false,
             ),
@@ -632,6 +690,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 true,
             ),
@@ -642,6 +701,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 3,
+                    played_at: None,
                 },
                 true,
             ),
@@ -652,6 +712,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 4,
+                    played_at: None,
                 },
                 false,
             ),
@@ -662,6 +723,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 true,
             ),
@@ -672,6 +734,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 false,
             ),
@@ -682,6 +745,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 4,
+                    played_at: None,
                 },
                 false,
             ),
@@ -692,6 +756,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 2,
+                    played_at: None,
                 },
                 false,
             ),
@@ -702,6 +767,7 @@ mod tests {
                     tube_to: 0,
                     colour: "red".to_string(),
                     quantity: 1,
+                    played_at: None,
                 },
                 false,
             ),
@@ -718,16 +784,32 @@ mod tests {
         }
     }
 
-    fn test_tube(test_result: &Tube, expected: &Tube) {
-        assert_eq!(
-            test_result.contents, expected.contents,
-            "tube contents are not the same. Expected = {}, got = {}",
-            expected, test_result
-        );
-        assert_eq!(
-            test_result.tube_number, expected.tube_number,
-            "tube number not the same. Expected = {}, got = {}",
-            expected.tube_number, test_result.tube_number
-        );
+    #[test]
+    fn test_settle() {
+        let tests = [
+            ("red, empty, blue, blue", true, "empty, red, blue, blue"),
+            ("red, empty, empty, blue", true, "empty, empty, red, blue"),
+            ("red, blue, empty", true, "empty, empty, red, blue"),
+            ("empty, red, blue, blue", false, "empty, red, blue, blue"),
+            ("empty", false, "empty, empty, empty, empty"),
+        ];
+        for test in tests {
+            let mut tube = Tube::from_string(test.0.to_string(), 0);
+            assert_eq!(
+                tube.has_gaps(),
+                test.1,
+                "wrong gaps for {}. Expected: {}",
+                test.0,
+                test.1
+            );
+            tube.settle();
+            assert_eq!(
+                tube.contents_string(),
+                test.2,
+                "wrong settled contents for {}",
+                test.0
+            );
+            assert!(!tube.has_gaps(), "a settled tube has no gaps");
+        }
     }
 }
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    known_puzzles::fingerprint,
+    puzzles::{parse_catalogue, Puzzle},
+    solver::Preset,
+    transpositions::TranspositionTable,
+    TUBE_SIZE,
+};
+
+/// The result of checking one puzzle of a catalogue.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PuzzleReport {
+    pub number: usize,
+    pub name: String,
+    /// The length of the shortest solution, if one was found.
+    pub moves: Option<usize>,
+    pub problems: Vec<String>,
+}
+
+/// Checks every puzzle in a catalogue before it is published: that its tubes are well formed,
+/// that it has the right number of every colour, that it can be solved, and that no other puzzle
+/// in the catalogue is the same board. The puzzles are solved with the settings of `preset`, and
+/// boards already in `table` are not searched again. A catalogue that cannot be parsed at all is an
+/// error.
+pub fn validate_catalogue(
+    catalogue: &str,
+    preset: Preset,
+    table: &mut TranspositionTable,
+) -> Result<Vec<PuzzleReport>, String> {
+    let puzzles = parse_catalogue(catalogue)?;
+    let mut reports = Vec::with_capacity(puzzles.len());
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for puzzle in puzzles.iter() {
+        let mut report = PuzzleReport {
+            number: puzzle.number,
+            name: puzzle.name.clone(),
+            moves: None,
+            problems: shape_problems(puzzle),
+        };
+        if report.problems.is_empty() {
+            let mut game = puzzle.to_game();
+            game.preset = preset;
+            let solution = table.solve(&game);
+            match &solution {
+                Some(solution) => report.moves = Some(solution.moves.len()),
+                None => report
+                    .problems
+                    .push("no solution was found within the search limit".to_string()),
+            }
+            if let (Some(par), Some(solution)) = (puzzle.par.moves, &solution) {
+                // Compared only against a solution known to be the shortest
+                if solution.optimal && par < solution.moves.len() {
+                    report.problems.push(format!(
+                        "par of {} moves is below the shortest solution of {}",
+                        par,
+                        solution.moves.len()
+                    ));
+                }
+            }
+            let (key, _) = fingerprint(&game);
+            match seen.get(&key) {
+                Some(first) => report
+                    .problems
+                    .push(format!("same board as puzzle {}", first)),
+                None => {
+                    seen.insert(key, puzzle.number);
+                }
+            }
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+/// Checks the tubes and colours of a puzzle, which must be right before it can be solved.
+fn shape_problems(puzzle: &Puzzle) -> Vec<String> {
+    if puzzle.tubes.len() < 4 {
+        return vec![format!(
+            "has {} tubes, at least 4 are needed",
+            puzzle.tubes.len()
+        )];
+    }
+    let game = puzzle.to_game();
+    let mut problems = game.check_integrity();
+    let mut counts: HashMap<&String, usize> = HashMap::new();
+    for colour in game
+        .tubes
+        .iter()
+        .flat_map(|tube| tube.contents.iter().flatten())
+    {
+        *counts.entry(colour).or_insert(0) += 1;
+    }
+    if counts.len() + 2 != puzzle.tubes.len() {
+        problems.push(format!(
+            "has {} colours for {} tubes, expected {}",
+            counts.len(),
+            puzzle.tubes.len(),
+            puzzle.tubes.len() - 2
+        ));
+    }
+    // Counts that are not a whole number of tubes are already reported by the integrity check
+    for (colour, count) in counts.iter().sorted() {
+        if *count != TUBE_SIZE && count % TUBE_SIZE == 0 {
+            problems.push(format!(
+                "has {} {} segments, expected {}",
+                count, colour, TUBE_SIZE
+            ));
+        }
+    }
+    problems
+}
+
+/// One line per puzzle, followed by its problems, and a count of the puzzles that passed.
+pub fn report_string(reports: &[PuzzleReport]) -> String {
+    let mut out = String::new();
+    for report in reports {
+        let result = match (report.problems.is_empty(), report.moves) {
+            (true, Some(moves)) => format!("ok, solvable in {} moves", moves),
+            _ => String::from("FAILED"),
+        };
+        out.push_str(format!("[{}] {}: {}\n", report.number, report.name, result).as_str());
+        for problem in report.problems.iter() {
+            out.push_str(format!("    - {}\n", problem).as_str());
+        }
+    }
+    let passed = reports
+        .iter()
+        .filter(|report| report.problems.is_empty())
+        .count();
+    out.push_str(format!("{} of {} puzzles passed\n", passed, reports.len()).as_str());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_catalogue() {
+        let catalogue = "\
+[1] Good
+red, red, red, blue
+red, blue, blue, blue
+empty
+empty
+
+[2] Reordered
+empty
+red, blue, blue, blue
+red, red, red, blue
+empty
+
+[3] Short
+red, red, red, blue
+red, blue, blue
+empty
+empty
+
+[4] Overfull
+red, red, red, red, blue
+blue, blue, blue
+empty
+empty
+
+[5] Extra tube
+red, blue, red, blue
+blue, red, blue, red
+empty
+empty
+empty
+
+[6] Tiny
+red, red, red, red
+empty
+
+[7] Floating
+red, red, red, blue
+red, empty, blue, blue
+blue
+empty
+
+[8] Low par
+par moves 2
+blue, blue, red, red
+red, blue, blue, red
+empty
+empty
+
+[9] Stuck
+red, blue, red, blue
+blue, red, blue, red
+empty
+empty
+forbid 1 -> 3
+forbid 1 -> 4
+forbid 2 -> 3
+forbid 2 -> 4
+";
+        let tests: [(Option<usize>, Vec<&str>); 9] = [
+            (Some(3), vec![]),
+            (Some(3), vec!["same board as puzzle 1"]),
+            (
+                None,
+                vec!["colour blue has 3 segments, which is not a multiple of 4"],
+            ),
+            (None, vec!["tube 1 has 5 segments, a tube holds 4"]),
+            (None, vec!["has 2 colours for 5 tubes, expected 3"]),
+            (None, vec!["has 2 tubes, at least 4 are needed"]),
+            (None, vec!["tube 2 has red floating above an empty segment"]),
+            (
+                Some(4),
+                vec!["par of 2 moves is below the shortest solution of 4"],
+            ),
+            (None, vec!["no solution was found within the search limit"]),
+        ];
+        let mut table = TranspositionTable::default();
+        let reports = validate_catalogue(catalogue, Preset::default(), &mut table)
+            .expect("catalogue should parse");
+        assert_eq!(reports.len(), tests.len(), "wrong number of reports");
+        for (report, test) in reports.iter().zip(tests.iter()) {
+            assert_eq!(
+                report.moves, test.0,
+                "wrong solution length for puzzle {}",
+                report.number
+            );
+            assert_eq!(
+                report.problems, test.1,
+                "wrong problems for puzzle {}. Expected: {:?}, got: {:?}",
+                report.number, test.1, report.problems
+            );
+        }
+        assert!(
+            validate_catalogue("red, red\n", Preset::default(), &mut table).is_err(),
+            "expected an error for a catalogue that cannot be parsed"
+        );
+    }
+
+    #[test]
+    fn test_report_string() {
+        let reports = [
+            PuzzleReport {
+                number: 1,
+                name: String::from("Good"),
+                moves: Some(3),
+                problems: vec![],
+            },
+            PuzzleReport {
+                number: 2,
+                name: String::from("Copy"),
+                moves: Some(3),
+                problems: vec![String::from("same board as puzzle 1")],
+            },
+        ];
+        assert_eq!(
+            report_string(&reports),
+            "[1] Good: ok, solvable in 3 moves\n\
+             [2] Copy: FAILED\n    - same board as puzzle 1\n\
+             1 of 2 puzzles passed\n"
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+use crate::game::{Game, Move, MoveError, MoveRequest};
+
+/// Why a list of moves is not a solution of a board. Moves are counted from 0.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum VerifyError {
+    /// The move cannot be made on the board the moves before it leave.
+    IllegalMove(usize, MoveError),
+    /// The game was already complete after this many moves, before the rest were made.
+    CompletedEarly(usize),
+    /// Every move can be made, but the game is not complete after the last of them.
+    Incomplete,
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::IllegalMove(idx, err) => {
+                write!(f, "move {} can not be made: {}", idx + 1, err)
+            }
+            VerifyError::CompletedEarly(moves) => write!(
+                f,
+                "the game is complete after {} moves, before the rest are made",
+                moves
+            ),
+            VerifyError::Incomplete => write!(f, "the game is not complete after the last move"),
+        }
+    }
+}
+
+/// Checks that `moves` solve the board: that every move can be made, with the colour and
+/// quantity it gives, on the board the moves before it leave, and that the game is complete after
+/// the last of them and not before. This validates a solution from anywhere, such as another
+/// solver or a saved game, against the same rules as a person playing.
+pub fn verify_solution(game: &Game, moves: &[Move]) -> Result<(), VerifyError> {
+    let mut state = game.clone();
+    for (idx, a_move) in moves.iter().enumerate() {
+        if state.is_game_complete() {
+            return Err(VerifyError::CompletedEarly(idx));
+        }
+        let resolved = state
+            .resolve(MoveRequest {
+                tube_from: a_move.tube_from,
+                tube_to: a_move.tube_to,
+                quantity: a_move.quantity,
+                colour: Some(a_move.colour.clone()),
+            })
+            .map_err(|err| VerifyError::IllegalMove(idx, err))?;
+        // A request for more than can be poured is resolved to less, which is not the move given
+        if resolved.quantity != a_move.quantity {
+            return Err(VerifyError::IllegalMove(
+                idx,
+                MoveError::Quantity(state.ruleset),
+            ));
+        }
+        state.make_move(&resolved);
+    }
+    match state.is_game_complete() {
+        true => Ok(()),
+        false => Err(VerifyError::Incomplete),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::Ruleset, solver::Solver};
+
+    fn pour(tube_from: usize, tube_to: usize, colour: &str, quantity: usize) -> Move {
+        Move {
+            tube_from,
+            tube_to,
+            colour: colour.to_string(),
+            quantity,
+            played_at: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_solution() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+        let solution = [
+            pour(0, 2, "red", 3),
+            pour(1, 2, "red", 1),
+            pour(0, 1, "blue", 1),
+        ];
+        // (moves, result)
+        let tests = [
+            (solution.to_vec(), Ok(())),
+            (solution[..2].to_vec(), Err(VerifyError::Incomplete)),
+            (vec![], Err(VerifyError::Incomplete)),
+            (
+                vec![pour(0, 2, "red", 3), pour(1, 2, "blue", 1)],
+                Err(VerifyError::IllegalMove(
+                    1,
+                    MoveError::NotOnTop(String::from("blue"), 1),
+                )),
+            ),
+            (
+                vec![pour(0, 2, "red", 4)],
+                Err(VerifyError::IllegalMove(
+                    0,
+                    MoveError::Quantity(Ruleset::Standard),
+                )),
+            ),
+            (
+                vec![pour(0, 7, "red", 3)],
+                Err(VerifyError::IllegalMove(0, MoveError::NoSuchTube(7))),
+            ),
+            (
+                [&solution[..], &[pour(1, 3, "blue", 4)]].concat(),
+                Err(VerifyError::CompletedEarly(3)),
+            ),
+        ];
+        for test in tests {
+            assert_eq!(
+                verify_solution(&game, &test.0),
+                test.1,
+                "wrong result verifying {:?}",
+                test.0.iter().map(Move::to_string).collect::<Vec<_>>()
+            );
+        }
+
+        let found = Solver::new(&game)
+            .solve()
+            .expect("board should be solvable");
+        assert_eq!(
+            verify_solution(&game, &found),
+            Ok(()),
+            "the solver's solution should verify"
+        );
+    }
+}
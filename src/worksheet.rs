@@ -0,0 +1,127 @@
+use crate::{
+    colour::Theme,
+    game::{Game, Move},
+    render::{escape_xml, Renderer, SvgRenderer},
+};
+
+/// How the worksheet is laid out on screen and on paper: the steps in a grid of small diagrams,
+/// none of them split across two pages.
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+h1 { font-size: 1.4em; }
+.start svg { max-width: 100%; height: auto; }
+ol.steps { list-style: none; padding: 0; display: grid; grid-template-columns: repeat(auto-fill, minmax(14em, 1fr)); gap: 1em; }
+ol.steps li { border: 1px solid #999; border-radius: 4px; padding: 0.5em; break-inside: avoid; }
+ol.steps svg { width: 100%; height: auto; }
+.tick { display: inline-block; width: 0.9em; height: 0.9em; border: 1px solid black; margin-right: 0.4em; vertical-align: middle; }
+@media print { body { margin: 0; } }
+";
+
+/// A printable page of a board and a solution of it, to follow along on paper: the starting board,
+/// then every move of the solution, numbered, with a box to tick and a small diagram of the board
+/// after it with the colour poured outlined. Consecutive pours between the same two tubes are one
+/// step, as a solution is listed. The page is a single HTML file, with its pictures drawn inline
+/// as SVG, so it can be opened or printed, or saved as a PDF from a browser, without anything
+/// alongside it.
+pub fn worksheet_html(game: &Game, moves: &[Move], theme: Theme) -> String {
+    let renderer = SvgRenderer { theme };
+    let mut out =
+        String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>Water sort worksheet</title>\n");
+    out.push_str(&format!("<style>\n{}</style>\n</head>\n<body>\n", STYLE));
+    out.push_str(&format!(
+        "<h1>Water sort: {} tubes, {} colours, solved in {} moves</h1>\n",
+        game.tubes.len(),
+        game.colours.len(),
+        moves.len()
+    ));
+    out.push_str("<h2>Starting board</h2>\n<div class=\"start\">\n");
+    out.push_str(&renderer.render(game, None));
+    out.push_str("</div>\n");
+    if let Some(pours) = game.forbidden_pours_string() {
+        out.push_str(&format!("<p>{}</p>\n", escape_xml(&pours)));
+    }
+    out.push_str("<h2>Solution</h2>\n<ol class=\"steps\">\n");
+    let mut state = game.clone();
+    let mut first = 1;
+    let mut idx = 0;
+    while idx < moves.len() {
+        // A run of pours between the same two tubes is one step, drawn once it is all poured
+        let mut step = moves[idx].clone();
+        state.make_move(&moves[idx]);
+        let mut last = idx;
+        while last + 1 < moves.len() && moves[last].continues(&moves[last + 1]) {
+            last += 1;
+            step.quantity += moves[last].quantity;
+            state.make_move(&moves[last]);
+        }
+        let numbers = match last - idx {
+            0 => first.to_string(),
+            more => format!("{}-{}", first, first + more),
+        };
+        out.push_str(&format!(
+            "<li><p><span class=\"tick\"></span><strong>{}.</strong> {}</p>\n",
+            numbers,
+            escape_xml(&step.to_string())
+        ));
+        out.push_str(&renderer.render(&state, Some(&step.colour)));
+        out.push_str("</li>\n");
+        first += last - idx + 1;
+        idx = last + 1;
+    }
+    out.push_str("</ol>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::Ruleset, solver::Solver};
+
+    #[test]
+    fn test_worksheet_html() {
+        let mut game = Game::default();
+        game.init_tubes(4);
+        game.init_tube_contents(0, String::from("red, red, red, blue"));
+        game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+        let moves = Solver::new(&game)
+            .solve()
+            .expect("board should be solvable");
+        let html = worksheet_html(&game, &moves, Theme::Classic);
+        let tests = [
+            ("<!DOCTYPE html>", 1),
+            ("solved in 3 moves", 1),
+            ("<svg ", 4),
+            ("<li>", 3),
+            ("<strong>1.</strong> 1 -&gt; 3: red x 3", 1),
+            ("class=\"tick\"", 3),
+            ("</html>", 1),
+        ];
+        for test in tests {
+            assert_eq!(
+                html.matches(test.0).count(),
+                test.1,
+                "wrong number of {:?} in:\n{}",
+                test.0,
+                html
+            );
+        }
+
+        // Pours of one unit at a time between the same tubes are one step
+        game.ruleset = Ruleset::OneUnit;
+        let moves = Solver::new(&game)
+            .solve()
+            .expect("board should be solvable");
+        let html = worksheet_html(&game, &moves, Theme::Classic);
+        assert!(
+            html.contains("<strong>1-3.</strong> 1 -&gt; 3: red x 3"),
+            "the pours of red should be one step:\n{}",
+            html
+        );
+        assert_eq!(
+            html.matches("<li>").count(),
+            3,
+            "wrong number of steps in:\n{}",
+            html
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Stepping through a search with `solve --interactive`.
+
+use water_sort_solver::{
+    analyze::parse_transcript, debugger::SearchDebugger, game::Game, solver::Strategy,
+};
+
+fn board() -> Game {
+    let (start, _) = parse_transcript(
+        r#"{"tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []], "moves": []}"#,
+    )
+    .expect("the board should parse");
+    start
+}
+
+fn step_through(strategy: Strategy, input: &str) -> String {
+    let mut output = Vec::new();
+    let mut debugger = SearchDebugger::new(input.as_bytes(), &mut output, &board(), strategy)
+        .expect("the strategy should be stepped through");
+    debugger.run().expect("the debugger should run");
+    drop(debugger);
+    String::from_utf8(output).expect("output should be UTF-8")
+}
+
+fn assert_shown(output: &str, expected: &[&str]) {
+    for text in expected {
+        assert!(
+            output.contains(text),
+            "expected {:?} in the output:\n{}",
+            text,
+            output
+        );
+    }
+}
+
+#[test]
+fn test_expand_and_inspect() {
+    let output = step_through(
+        Strategy::BreadthFirst,
+        "best\nexpand\nfrontier\nwhy 1\nwhy 0\nwhy 99\nwhy\nexpand x\nfly\nquit\n",
+    );
+    assert_shown(
+        &output,
+        &[
+            "State 0 is the board being solved:\n1: (red, red, red, blue)\n",
+            "Next is state 0 (depth 0, lower bound",
+            "Expanded state 0 (depth 0,",
+            "  Added state 1 after ",
+            "states waiting, after 1 looked at:\n  state 1 after ",
+            "State 1 was reached in 1 moves: ",
+            "It is waiting to be looked at, number 1 in the frontier.",
+            "It was the state looked at number 1.",
+            "There is no state 99",
+            "Usage: why <state>",
+            "Usage: expand [<count>]",
+            "Unknown command 'fly'",
+        ],
+    );
+}
+
+#[test]
+fn test_run_to_solution() {
+    for strategy in [
+        Strategy::BreadthFirst,
+        Strategy::DepthFirst,
+        Strategy::AStar,
+        Strategy::Greedy,
+    ] {
+        let output = step_through(strategy, "run\nexpand\n");
+        assert_shown(
+            &output,
+            &["completes the game in ", "The search has finished"],
+        );
+    }
+    let output = step_through(Strategy::BreadthFirst, "run\n");
+    assert_shown(&output, &["Pruned "]);
+}
+
+#[test]
+fn test_ida_star_not_stepped_through() {
+    let mut output = Vec::new();
+    assert!(
+        SearchDebugger::new("".as_bytes(), &mut output, &board(), Strategy::IdaStar).is_none(),
+        "ida-star searches in passes, which cannot be stepped through"
+    );
+}
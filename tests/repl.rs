@@ -0,0 +1,480 @@
+//! Complete sessions of the interactive game, scripted from setup to the end of play, checking what
+//! the player is shown.
+
+use std::{env, fs, time::Duration};
+
+use water_sort_solver::{
+    analyze::load_transcript,
+    colour::{ColourNames, Locale},
+    config::Config,
+    game::Game,
+    repl::Repl,
+};
+
+const SETUP: &str = "4\n\nred, red, red, blue\nred, blue, blue, blue\nempty\nempty\n";
+
+/// Plays a session from the given input, entering the tubes first if `setup` is set, and returns
+/// everything written to the player. Statistics are kept in a directory of their own, away from
+/// the player's.
+fn run_session(game: Game, input: &str, setup: bool) -> String {
+    env::set_var(
+        "WATER_SORT_SOLVER_HOME",
+        env::temp_dir().join("water_sort_solver_repl_tests"),
+    );
+    let mut output = Vec::new();
+    let mut repl = Repl::new(input.as_bytes(), &mut output, game, Config::default());
+    if setup {
+        assert!(repl.start(), "setup should succeed");
+    }
+    repl.play();
+    drop(repl);
+    String::from_utf8(output).expect("output should be UTF-8")
+}
+
+fn entered_game() -> Game {
+    let mut game = Game::default();
+    game.init_tubes(4);
+    game.init_tube_contents(0, String::from("red, red, red, blue"));
+    game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+    game
+}
+
+fn assert_shown(output: &str, expected: &[&str]) {
+    for text in expected {
+        assert!(
+            output.contains(text),
+            "expected {:?} in the output:\n{}",
+            text,
+            output
+        );
+    }
+}
+
+#[test]
+fn test_setup_and_win() {
+    let input = format!("{}1 3 3\n2 3 1\n1 2 1\n\n", SETUP);
+    let output = run_session(Game::default(), &input, true);
+    assert_shown(
+        &output,
+        &[
+            "Enter the initial state of tube 4: ",
+            "Starting state of the game:\n1: (red, red, red, blue)\n",
+            "This looks like a Very easy puzzle (optimal \u{2248} 3 moves)",
+            "After move: 1 -> 3: red x 3:",
+            "Congratulations! You have completed the game! The moves were:",
+            "3 : (1 -> 2: blue x 1)",
+            "Rating: ★★★ (0 hints used)",
+            "You took 3 moves. The shortest solution takes 3.",
+        ],
+    );
+}
+
+#[test]
+fn test_invalid_setup_input() {
+    let input = "abc\n2\n4\ndouble\n4\n\nred, red, red, blue\nred, empty, blue, blue\nr\n\
+                 red, blue, blue, blue\nempty\nempty\nquit\n";
+    let output = run_session(Game::default(), input, true);
+    assert_shown(
+        &output,
+        &[
+            "Unable to parse abc to a number",
+            "A game needs at least 4 tubes",
+            "Unknown ruleset",
+            "A colour is above an empty segment",
+            "Starting state of the game:",
+        ],
+    );
+}
+
+#[test]
+fn test_incorrect_setup() {
+    let input = "4\n\nred, red, red, blue\nred, blue, blue, blue\nred\nempty\na\n";
+    let mut output = Vec::new();
+    let mut repl = Repl::new(
+        input.as_bytes(),
+        &mut output,
+        Game::default(),
+        Config::default(),
+    );
+    assert!(!repl.start(), "setup should fail with too much red");
+    drop(repl);
+    let output = String::from_utf8(output).expect("output should be UTF-8");
+    assert_shown(
+        &output,
+        &["Error: Initial setup is incorrect. Please try again."],
+    );
+}
+
+#[test]
+fn test_several_moves_on_one_line() {
+    let output = run_session(entered_game(), "1>3, 2>1, 2>3\n1>3, 2>3, 1>2\n\n", false);
+    assert_shown(
+        &output,
+        &[
+            "Move 2 is invalid: the colour on top of tube 1 does not match. None of the moves were made",
+            "After move: 1 -> 3: red x 3:\nAfter move: 2 -> 3: red x 1:\nAfter move: 1 -> 2: blue x 1:\n",
+            "Congratulations! You have completed the game!",
+        ],
+    );
+}
+
+#[test]
+fn test_invalid_moves() {
+    let output = run_session(
+        entered_game(),
+        "1\n1 1 1\n5 1 1\n3 1 1\n1 2 3\nquit\n",
+        false,
+    );
+    assert_shown(
+        &output,
+        &[
+            "Unable to parse move:",
+            "Move is invalid: a tube cannot pour into itself",
+            "Move is invalid: there is no tube 5",
+            "Move is invalid: tube 3 is empty",
+            "Move is invalid: tube 2 is full",
+        ],
+    );
+    assert!(
+        !output.contains("After move"),
+        "no invalid move should be made:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_null_progress_moves() {
+    let output = run_session(entered_game(), "1 3 3\n3 4 3\nquit\n", false);
+    assert_shown(
+        &output,
+        &[
+            "Warning: 3 -> 4: red x 3 only moves the red into another empty tube, which makes no progress",
+            "After move: 3 -> 4: red x 3:",
+        ],
+    );
+
+    let input = "1 3 3\n3 4 3\nquit\n";
+    let mut output = Vec::new();
+    let config = Config {
+        reject_null_moves: true,
+        ..Default::default()
+    };
+    let mut repl = Repl::new(input.as_bytes(), &mut output, entered_game(), config);
+    repl.play();
+    drop(repl);
+    let output = String::from_utf8(output).expect("output should be UTF-8");
+    assert_shown(
+        &output,
+        &["Move rejected: 3 -> 4: red x 3 only moves the red into another empty tube"],
+    );
+    assert!(
+        !output.contains("After move: 3 -> 4"),
+        "the move should be rejected:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_hints() {
+    let mut game = entered_game();
+    game.hint_limit = Some(1);
+    let output = run_session(game, "hint\nhint\nquit\n", false);
+    assert_shown(
+        &output,
+        &[
+            "Hint: try ",
+            "% of random playouts after it finish the puzzle, 0 hints remaining)",
+            "You have used all of your hints for this puzzle.",
+        ],
+    );
+}
+
+#[test]
+fn test_empty_tube() {
+    let output = run_session(
+        entered_game(),
+        "empty-tube\n1 3 3\nempty-tube 1\nempty-tube none\nquit\n",
+        false,
+    );
+    assert_shown(
+        &output,
+        &[
+            "The next move needs an empty tube\n",
+            "The next move needs no empty tube: 2 -> 3: red x 1\n",
+            "Usage: empty-tube [<moves>]",
+        ],
+    );
+}
+
+#[test]
+fn test_branch_and_back() {
+    let output = run_session(
+        entered_game(),
+        "back\nbranch\n1 3 3\nback\n1 4 3\nquit\n",
+        false,
+    );
+    assert_shown(
+        &output,
+        &[
+            "There is no branch to go back from",
+            "Exploring branch 1 from move 0. Enter back to return.",
+            "After move: 1 -> 3: red x 3:",
+            "Back on the main line at move 0:\n1: (red, red, red, blue)\n",
+            "After move: 1 -> 4: red x 3:",
+        ],
+    );
+}
+
+#[test]
+fn test_stuck() {
+    let mut game = Game::default();
+    game.init_tubes(4);
+    game.init_tube_contents(0, String::from("empty, green, red, blue"));
+    game.init_tube_contents(1, String::from("empty, empty, blue, red"));
+    game.init_tube_contents(2, String::from("empty, green, red, blue"));
+    game.init_tube_contents(3, String::from("green, green, red, blue"));
+    let output = run_session(
+        game,
+        "undo
+1 3 1
+undo
+1 3 1
+
+",
+        false,
+    );
+    assert_shown(
+        &output,
+        &[
+            "There is no move to take back",
+            "After move: 1 -> 3: green x 1:",
+            "Stuck: no legal moves remain.\nEnter 'undo' to take back the last move, or press Enter to end the game: ",
+            "Took back 1 -> 3: green x 1:\n1: (empty, green, red, blue)\n",
+            "The puzzle is lost.",
+        ],
+    );
+    assert_eq!(
+        output.matches("Stuck: no legal moves remain.").count(),
+        2,
+        "the game should be stuck after each time the move is made:\n{}",
+        output
+    );
+    assert!(
+        !output.contains("Congratulations"),
+        "a stuck game is not won:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_theme() {
+    let output = run_session(
+        entered_game(),
+        "theme\ntheme dark\ntheme pastel\nquit\n",
+        false,
+    );
+    assert_shown(
+        &output,
+        &[
+            "The theme is classic. Themes: classic, pastel, neon",
+            "Unknown theme 'dark'. Expected one of: classic, pastel, neon",
+            "Using the pastel theme\n1: (red, red, red, blue)\n",
+        ],
+    );
+}
+
+#[test]
+fn test_strict_input() {
+    let input = "4\n\nred, red, red, blue\nred, bleu, blue, blue\nempty\nempty\n";
+    let mut output = Vec::new();
+    let config = Config {
+        strict_input: true,
+        ..Default::default()
+    };
+    let mut repl = Repl::new(input.as_bytes(), &mut output, Game::default(), config);
+    assert!(!repl.start(), "setup should fail with an unknown colour");
+    assert!(repl.setup_rejected(), "the setup should be rejected");
+    drop(repl);
+    let output = String::from_utf8(output).expect("output should be UTF-8");
+    assert_shown(
+        &output,
+        &["Error: unknown colour 'bleu' (did you mean 'blue'?). Only the known colour names and hex codes can be used with strict input."],
+    );
+    assert!(
+        !output.contains("Enter the initial state of tube 3"),
+        "setup should stop at the unknown colour:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_localised_colours() {
+    let input = "4\n\nrouge, rouge, rouge, bleu\nrouge, bleu, bleu, blue\nempty\nempty\nquit\n";
+    let mut output = Vec::new();
+    let config = Config {
+        strict_input: true,
+        colour_names: ColourNames {
+            locales: vec![Locale::French],
+            aliases: Vec::new(),
+        },
+        ..Default::default()
+    };
+    let mut repl = Repl::new(input.as_bytes(), &mut output, Game::default(), config);
+    assert!(repl.start(), "the French colour names should be known");
+    drop(repl);
+    let output = String::from_utf8(output).expect("output should be UTF-8");
+    assert_shown(
+        &output,
+        &["1: (red, red, red, blue)", "2: (red, blue, blue, blue)"],
+    );
+}
+
+#[test]
+fn test_difficulty_can_be_hidden() {
+    let input = format!("{}quit\n", SETUP);
+    let mut output = Vec::new();
+    let config = Config {
+        show_difficulty: Some(false),
+        ..Default::default()
+    };
+    let mut repl = Repl::new(input.as_bytes(), &mut output, Game::default(), config);
+    assert!(repl.start(), "setup should succeed");
+    drop(repl);
+    let output = String::from_utf8(output).expect("output should be UTF-8");
+    assert_shown(&output, &["Starting state of the game:"]);
+    assert!(
+        !output.contains("This looks like"),
+        "the difficulty should be hidden:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_setstate() {
+    let input = "setstate RRR/RBBB/-/-\nsetstate RRRB/RBBB/-/-/-\nsetstate BBBR/BRRR/-/-\n1 3 3\n2 3 1\n1 2 1\n\n";
+    let output = run_session(entered_game(), input, false);
+    assert_shown(
+        &output,
+        &[
+            "Unable to set the board: it needs 4 of every colour and two more tubes than colours",
+            "Board set:\n1: (red, red, red, blue)\n",
+            "After move: 1 -> 3: red x 3:",
+            "Congratulations! You have completed the game! The moves were:",
+        ],
+    );
+    assert!(
+        !output.contains("You took 3 moves."),
+        "a board set with setstate should not count as a solve:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_solve_mid_game() {
+    let output = run_session(entered_game(), "1 3 3\nsolve\n2 3 1\n1 2 1\n\n", false);
+    assert_shown(
+        &output,
+        &[
+            "A solution from here in 2 moves (found from the analysis of earlier boards):\n  1: 2 -> 3: red x 1\n  2: 1 -> 2: blue x 1\n",
+            "Congratulations! You have completed the game!",
+        ],
+    );
+}
+
+#[test]
+fn test_notes() {
+    let path = env::temp_dir().join("water_sort_solver_notes_test.json");
+    let input = format!(
+        "note 3 \"keep for red\"\nnote 9 spare\nrelabel reverse\nsave-game {}\nnote 2\nquit\n",
+        path.display()
+    );
+    let output = run_session(entered_game(), &input, false);
+    assert_shown(
+        &output,
+        &[
+            "Note added to tube 3:\n",
+            "Notes:\n  3: keep for red\n",
+            "Unable to add the note: Expected 'note <tube> \"<text>\"' with a tube number from 1 to 4",
+            "Notes:\n  2: keep for red\n",
+            "Note on tube 2 cleared:",
+        ],
+    );
+    let saved = fs::read_to_string(&path).expect("the game should have been saved");
+    fs::remove_file(&path).expect("the saved game should be removed");
+    assert!(
+        saved.contains(r#""notes": [[2, "keep for red"]]"#),
+        "the notes should be saved with the game:\n{}",
+        saved
+    );
+}
+
+#[test]
+fn test_pack_progress() {
+    let input = "progress\n1 3 3\n2 3 1\n1 2 1\n\n";
+    env::set_var(
+        "WATER_SORT_SOLVER_HOME",
+        env::temp_dir().join("water_sort_solver_repl_tests"),
+    );
+    let mut output = Vec::new();
+    let mut repl = Repl::new(
+        input.as_bytes(),
+        &mut output,
+        entered_game(),
+        Config::default(),
+    );
+    repl.set_pack_report(String::from("0 of 2 levels completed (0%), on level 1\n"));
+    repl.play();
+    assert_eq!(
+        repl.completed_game().map(|game| game.current_move),
+        Some(3),
+        "the level should be completed in 3 moves"
+    );
+    drop(repl);
+    let output = String::from_utf8(output).expect("output should be UTF-8");
+    assert_shown(&output, &["0 of 2 levels completed (0%), on level 1\n"]);
+
+    let output = run_session(entered_game(), "progress\nquit\n", false);
+    assert_shown(&output, &["This board is not part of a pack."]);
+}
+
+#[test]
+fn test_blitz_clock_expired() {
+    let game = Game {
+        time_limit: Some(Duration::ZERO),
+        ..entered_game()
+    };
+    let output = run_session(game, "1 3 3\n2 3 1\n1 2 1\n", false);
+    assert_shown(&output, &["Time is up! The puzzle is lost."]);
+    assert!(
+        !output.contains("Enter a move") && !output.contains("Congratulations"),
+        "no move should be asked for or played once the time is up:\n{}",
+        output
+    );
+}
+
+#[test]
+fn test_relabel_then_save_game() {
+    let path = env::temp_dir().join("water_sort_solver_relabel_test.json");
+    // A move made after relabelling, and a relabelling on a branch that is then left
+    let input = format!(
+        "relabel reverse\n4 2 3\nbranch\nrelabel reverse\nback\nsave-game {}\nquit\n",
+        path.display()
+    );
+    run_session(entered_game(), &input, false);
+    let loaded = load_transcript(&path);
+    fs::remove_file(&path).expect("the saved game should be removed");
+    let (start, moves) = loaded.expect("the saved game should load");
+    assert_eq!(
+        start.tubes[0].to_string(),
+        entered_game().tubes[0].to_string(),
+        "the board should be saved in the order it was relabelled back to"
+    );
+    assert_eq!(
+        moves
+            .iter()
+            .map(|a_move| (a_move.tube_from, a_move.tube_to, a_move.quantity))
+            .collect::<Vec<_>>(),
+        vec![(0, 2, 3)],
+        "the move should be renumbered with the board"
+    );
+}
@@ -0,0 +1,73 @@
+//! Entering a board over two sessions, saving the tubes entered part way through and carrying on
+//! from them. Kept apart from the other sessions, as the saved setup is shared by every session
+//! using the same directory.
+
+use std::env;
+
+use water_sort_solver::{config::Config, game::Game, repl::Repl, saved_setup::SavedSetup};
+
+fn start_session(input: &str) -> (bool, bool, String) {
+    let mut output = Vec::new();
+    let mut repl = Repl::new(
+        input.as_bytes(),
+        &mut output,
+        Game::default(),
+        Config::default(),
+    );
+    let started = repl.start();
+    let saved = repl.setup_saved();
+    drop(repl);
+    let output = String::from_utf8(output).expect("output should be UTF-8");
+    (started, saved, output)
+}
+
+#[test]
+fn test_save_and_resume_setup() {
+    env::set_var(
+        "WATER_SORT_SOLVER_HOME",
+        env::temp_dir().join("water_sort_solver_saved_setup_tests"),
+    );
+    SavedSetup::clear().expect("should clear an earlier saved setup");
+
+    let (started, saved, output) =
+        start_session("6\nno-split\nred, red, blue, blue\nblue, blue, red, red\nsave\n");
+    assert!(!started, "saving should end setup without a board");
+    assert!(saved, "the setup should be saved");
+    assert!(
+        output.contains("Saved the first 2 of 6 tubes. Start a new game to carry on from tube 3."),
+        "expected the save to be confirmed:\n{}",
+        output
+    );
+
+    let (started, saved, output) = start_session(
+        "\ngreen, green, yellow, yellow\nyellow, yellow, green, green\nempty\nempty\n",
+    );
+    assert!(
+        started,
+        "the resumed setup should be completed:\n{}",
+        output
+    );
+    assert!(!saved, "the setup should not be saved again");
+    for text in [
+        "A board of 6 tubes was saved with 2 entered. Carry on entering it? [Y/n]: ",
+        "Carrying on from tube 3 with the no-split rules.",
+        "Starting state of the game:\n1: (red, red, blue, blue)\n2: (blue, blue, red, red)\n",
+    ] {
+        assert!(
+            output.contains(text),
+            "expected {:?} in the output:\n{}",
+            text,
+            output
+        );
+    }
+    assert!(
+        !output.contains("Enter the total number of tubes"),
+        "the number of tubes should come from the saved setup:\n{}",
+        output
+    );
+    assert_eq!(
+        SavedSetup::load(),
+        Ok(None),
+        "the saved setup should be removed once the board is entered"
+    );
+}
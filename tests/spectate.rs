@@ -0,0 +1,123 @@
+//! Reviews of saved games, from the file written by `save-game` to stepping through it.
+
+use std::{env, fs};
+
+use water_sort_solver::{
+    analyze::{load_transcript, parse_transcript},
+    config::Config,
+    game::Game,
+    repl::Repl,
+    spectate::Spectator,
+};
+
+const TRANSCRIPT: &str = r#"{
+    "tubes": [["red", "red", "red", "blue"], ["red", "blue", "blue", "blue"], [], []],
+    "moves": [[1, 3, 3], [2, 3, 1]]
+}"#;
+
+fn review(transcript: &str, input: &str) -> String {
+    let (start, moves) = parse_transcript(transcript).expect("the transcript should parse");
+    let mut output = Vec::new();
+    let mut spectator = Spectator::new(
+        input.as_bytes(),
+        &mut output,
+        start,
+        moves,
+        &Config::default(),
+    );
+    spectator.run().expect("the review should run");
+    drop(spectator);
+    String::from_utf8(output).expect("output should be UTF-8")
+}
+
+fn assert_shown(output: &str, expected: &[&str]) {
+    for text in expected {
+        assert!(
+            output.contains(text),
+            "expected {:?} in the output:\n{}",
+            text,
+            output
+        );
+    }
+}
+
+#[test]
+fn test_step_through_game() {
+    let output = review(
+        TRANSCRIPT,
+        "next\n\nnext\nprev\nstart\nprev\ngoto 2\ngoto 5\nend\nquit\n",
+    );
+    assert_shown(
+        &output,
+        &[
+            "Reviewing a game of 2 moves.",
+            "The start of the game:\n1: (red, red, red, blue)\n",
+            "Move 1 of 2: 1 -> 3: red x 3\n1: (empty, empty, empty, blue)\n",
+            "Move 2 of 2: 2 -> 3: red x 1\n",
+            "The game ends after move 2",
+            "This is the start of the game",
+        ],
+    );
+}
+
+#[test]
+fn test_hints_and_what_if() {
+    let output = review(TRANSCRIPT, "hint\n1 4 3\nhint\n1 2 1\nback\nback\n");
+    assert_shown(
+        &output,
+        &[
+            "Hint: ",
+            "(3 moves to finish)",
+            "The game went on with 1 -> 3: red x 3",
+            "What if, from move 0 (enter back to return to the game):\n1: (empty, empty, empty, blue)\n",
+            "Move 1 is invalid: ",
+            "The start of the game:",
+            "There is no what-if line to go back from",
+        ],
+    );
+    // The review ends with the input, and the record is as it was
+    assert_eq!(
+        parse_transcript(TRANSCRIPT)
+            .expect("the transcript should parse")
+            .1
+            .len(),
+        2,
+        "the record should not change"
+    );
+}
+
+#[test]
+fn test_save_game() {
+    let path = env::temp_dir().join("water_sort_solver_saved_game.json");
+    let _ = fs::remove_file(&path);
+    env::set_var(
+        "WATER_SORT_SOLVER_HOME",
+        env::temp_dir().join("water_sort_solver_spectate_tests"),
+    );
+    let mut game = Game::default();
+    game.init_tubes(4);
+    game.init_tube_contents(0, String::from("red, red, red, blue"));
+    game.init_tube_contents(1, String::from("red, blue, blue, blue"));
+    let input = format!("save-game\n1 3 3\nsave-game {}\nquit\n", path.display());
+    let mut output = Vec::new();
+    let mut repl = Repl::new(input.as_bytes(), &mut output, game, Config::default());
+    repl.play();
+    drop(repl);
+    let output = String::from_utf8(output).expect("output should be UTF-8");
+    assert_shown(
+        &output,
+        &[
+            "Usage: save-game <path>",
+            &format!("Game saved to {}", path.display()),
+        ],
+    );
+    let (start, moves) = load_transcript(&path).expect("the saved game should load");
+    assert_eq!(
+        start.tubes[0].contents_string(),
+        "red, red, red, blue",
+        "wrong board saved"
+    );
+    assert_eq!(moves.len(), 1, "wrong moves saved");
+    assert_eq!(moves[0].to_string(), "1 -> 3: red x 3", "wrong move saved");
+    let _ = fs::remove_file(&path);
+}